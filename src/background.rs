@@ -0,0 +1,131 @@
+use crate::color::Color;
+use crate::object::texture::Texture;
+use crate::vec3d::{Vec3d, Float, consts};
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+
+/// Light arriving from infinitely far away along rays that leave the scene.
+///
+/// The camera keeps one for its own rays and optionally another for every bounce, see
+/// ``Camera::set_lighting_background``, so a studio backdrop can be shown while an
+/// environment lights the scene. A ``Color`` is the same in every direction.
+pub trait Background: Send + Sync + Debug {
+    /// Radiance arriving from ``direction``, which need not be normalized.
+    fn value(&self, direction: &Vec3d) -> Color;
+}
+
+
+impl Background for Color {
+    fn value(&self, _direction: &Vec3d) -> Color {
+        *self
+    }
+}
+
+
+/// The sky of *Ray Tracing in One Weekend*, blending linearly with the height of the
+/// direction from ``bottom`` straight down to ``top`` straight up.
+///
+/// The default is the book's, from white to light blue.
+/// # Examples
+/// ```
+/// use ray_tracing::background::{Background, SkyGradient};
+/// use ray_tracing::color::Color;
+/// use ray_tracing::vec3d::Vec3d;
+/// let sky = SkyGradient::default();
+/// assert_eq!(sky.value(&Vec3d::new(0.0, 3.0, 0.0)), Color::new(0.5, 0.7, 1.0));
+/// assert_eq!(sky.value(&Vec3d::new(1.0, 0.0, 0.0)), Color::new(0.75, 0.85, 1.0));
+/// assert_eq!(sky.value(&Vec3d::new(0.0, -1.0, 0.0)), Color::splat(1.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyGradient {
+    bottom: Color,
+    top: Color,
+}
+
+
+impl SkyGradient {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+}
+
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self::new(Color::splat(1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+
+impl Background for SkyGradient {
+    fn value(&self, direction: &Vec3d) -> Color {
+        let t = 0.5 * (direction.unit_vector().y() + 1.0);
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
+
+
+/// A panorama around the scene, e.g. a photographed environment, in the equirectangular
+/// layout: ``u`` goes once around the horizon starting towards ``-z``, and ``v`` from
+/// straight down at ``0`` to straight up at ``1``.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    texture: Arc<dyn Texture>,
+    intensity: Float,
+}
+
+
+impl EnvironmentMap {
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self { texture, intensity: 1.0 }
+    }
+
+    /// Scales the radiance of the panorama, ``1.0`` by default, e.g. to light a scene with
+    /// an 8-bit image brighter than white.
+    pub fn with_intensity(mut self, intensity: Float) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+
+impl Background for EnvironmentMap {
+    fn value(&self, direction: &Vec3d) -> Color {
+        let direction = direction.unit_vector();
+        let u = (direction.x().atan2(-direction.z()) / (2.0 * consts::PI)).rem_euclid(1.0);
+        let v = 0.5 + direction.y().clamp(-1.0, 1.0).asin() / consts::PI;
+        self.texture.value(u, v, &direction) * self.intensity
+    }
+}
+
+
+#[cfg(test)]
+mod test_background {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Returns its ``u`` and ``v`` as red and green.
+    #[derive(Debug)]
+    struct Coordinates;
+
+    impl Texture for Coordinates {
+        fn value(&self, u: Float, v: Float, _p: &Vec3d) -> Color {
+            Color::new(u, v, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_environment_map_layout() {
+        let map = EnvironmentMap::new(Arc::new(Coordinates)).with_intensity(2.0);
+        let at = |x, y, z| map.value(&Vec3d::new(x, y, z));
+        assert_approx_eq!(at(0.0, 0.0, -1.0).r(), 0.0);
+        assert_approx_eq!(at(1.0, 0.0, 0.0).r(), 2.0 * 0.25);
+        assert_approx_eq!(at(0.0, 0.0, 1.0).r(), 2.0 * 0.5);
+        assert_approx_eq!(at(-1.0, 0.0, 0.0).r(), 2.0 * 0.75);
+        assert_approx_eq!(at(0.0, 0.0, -5.0).g(), 2.0 * 0.5);
+        assert_approx_eq!(at(0.0, 2.0, 0.0).g(), 2.0);
+        assert_approx_eq!(at(0.0, -1.0, 0.0).g(), 0.0);
+    }
+}
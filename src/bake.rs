@@ -0,0 +1,406 @@
+//! Baking of lightmaps: the light arriving at the surface of a mesh, traced offline by the
+//! path tracer with all of its global illumination and stored in a texture over the UV layout
+//! of the mesh, for real-time renderers to show. Objects moving through the scene take their
+//! light from ``LightProbe``s instead, baked at points of it.
+//!
+//! Every texel covered by a triangle in UV space gathers the irradiance at the point of the
+//! triangle under its center, from the front of the triangle. Texels around the islands of the
+//! layout are filled in from their neighbors, so filtering the texture at the seams does not
+//! bleed in black.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::bake::bake_lightmap;
+//! use ray_tracing::camera::Camera;
+//! use ray_tracing::color::Color;
+//! use ray_tracing::object::{HittableVec, MeshData};
+//! use ray_tracing::vec3d::{Point3d, consts};
+//! // A floor facing up under a white sky, mapped onto the whole texture.
+//! let floor = MeshData {
+//!     positions: vec![Point3d::new(0.0, 0.0, 0.0), Point3d::new(1.0, 0.0, 0.0), Point3d::new(1.0, 0.0, -1.0), Point3d::new(0.0, 0.0, -1.0)],
+//!     uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+//!     triangles: vec![[0, 1, 2], [0, 2, 3]],
+//!     ..Default::default()
+//! };
+//! let mut camera = Camera::new();
+//! camera.set_background_color(Color::splat(1.0));
+//! let lightmap = bake_lightmap(&camera, &HittableVec::new(), &floor, 4, 4, 8).unwrap();
+//! // The whole sky shines on it, with an irradiance of pi.
+//! assert!(lightmap.texels().iter().all(|texel| (texel.r() - consts::PI).abs() < 1e-4));
+//! ```
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::image::{write_image, write_pfm};
+use crate::object::{Hittable, MeshData};
+use crate::ray::{Ray, RayKind};
+use crate::sampler::{self, Pcg32};
+use crate::vec3d::{Vec3d, Point3d, Float, consts, cross};
+
+
+/// Rounds of filling in the texels around the islands of the UV layout.
+const PADDING: usize = 2;
+
+/// Rays traced at once while baking, to bound the memory they take.
+const BATCH_SIZE: usize = 1 << 16;
+
+
+/// Irradiance over the UV layout of a mesh, see ``bake_lightmap``.
+///
+/// Texels are in row-major order from the top left, where ``v`` is ``1``, the way the
+/// ``ImageTexture`` reads images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lightmap {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    covered: Vec<bool>,
+}
+
+
+impl Lightmap {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Irradiance of every texel, the light arriving per unit of area.
+    pub fn texels(&self) -> &[Color] {
+        &self.texels
+    }
+
+    /// Whether a triangle covers the center of the texel in column ``x`` and row ``y``, as
+    /// opposed to it being filled in around an island or left black.
+    pub fn is_covered(&self, x: usize, y: usize) -> bool {
+        self.covered[y * self.width + x]
+    }
+
+    /// The texels as the radiance a white diffuse surface sends out under their irradiance,
+    /// i.e. divided by ``pi``, which multiplied by the albedo gives the lit color.
+    pub fn radiance(&self) -> Vec<Color> {
+        self.texels.iter().map(|&texel| texel / consts::PI).collect()
+    }
+
+    /// Writes the ``radiance`` like ``write_image``.
+    pub fn write(&self, path: &str) {
+        write_image(path, &self.radiance(), self.width as i32, self.height as i32);
+    }
+
+    /// Writes the ``radiance`` without clipping like ``write_pfm``.
+    pub fn write_pfm(&self, path: &str) -> Result<()> {
+        write_pfm(path, &self.radiance(), self.width as i32, self.height as i32)
+    }
+}
+
+
+/// Coefficients of the real spherical harmonics up to the second band, in the order ``Y00``,
+/// ``Y1-1``, ``Y10``, ``Y11``, ``Y2-2``, ``Y2-1``, ``Y20``, ``Y21`` and ``Y22``, at the unit
+/// ``direction``.
+fn sh_basis(direction: &Vec3d) -> [Float; 9] {
+    let (x, y, z) = (direction.x(), direction.y(), direction.z());
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+
+/// The light arriving at a point from all directions, as the projection of its radiance onto
+/// the spherical harmonics of the first three bands, the nine "L2" coefficients of the
+/// irradiance probes of real-time engines, see ``bake_light_probes``.
+///
+/// The coefficients are of the radiance, in the order of ``Y00``, ``Y1-1``, ``Y10``, ``Y11``,
+/// ``Y2-2``, ``Y2-1``, ``Y20``, ``Y21`` and ``Y22``. Engines convolve them with the cosine
+/// lobe for the irradiance, as ``irradiance`` does after Ramamoorthi and Hanrahan, which keeps
+/// the light of all but the sharpest shadows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightProbe {
+    pub position: Point3d,
+    pub coefficients: [Color; 9],
+}
+
+
+impl LightProbe {
+    /// The irradiance reaching a surface facing the unit ``normal`` at the probe.
+    pub fn irradiance(&self, normal: &Vec3d) -> Color {
+        // The cosine lobe scales each band: pi, 2 pi / 3 and pi / 4.
+        let bands = [consts::PI, 2.0 * consts::PI / 3.0, consts::PI / 4.0];
+        let basis = sh_basis(normal);
+        (0..9).map(|i| {
+            let band = if i == 0 { 0 } else if i < 4 { 1 } else { 2 };
+            self.coefficients[i] * (bands[band] * basis[i])
+        }).sum()
+    }
+}
+
+
+/// Bakes a ``LightProbe`` at each of the ``points``, from ``samples`` rays in directions all
+/// around it traced through ``world`` with the settings of ``camera``, like
+/// ``bake_lightmap``.
+/// # Examples
+/// ```
+/// use ray_tracing::bake::bake_light_probes;
+/// use ray_tracing::camera::Camera;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::HittableVec;
+/// use ray_tracing::vec3d::{Point3d, Vec3d, consts};
+/// let mut camera = Camera::new();
+/// camera.set_background_color(Color::splat(1.0));
+/// let probes = bake_light_probes(&camera, &HittableVec::new(), &[Point3d::zero()], 64);
+/// // Under a uniform sky, all the light is in the first coefficient.
+/// assert!((probes[0].coefficients[0].r() - 2.0 * consts::PI.sqrt()).abs() < 1e-4);
+/// assert!((probes[0].irradiance(&Vec3d::new(0.0, 1.0, 0.0)).r() - consts::PI).abs() < 0.5);
+/// ```
+pub fn bake_light_probes<H: Hittable>(camera: &Camera, world: &H, points: &[Point3d], samples: u32) -> Vec<LightProbe> {
+    let samples = samples.max(1) as usize;
+    points.iter().enumerate().map(|(probe, &position)| {
+        let directions: Vec<Vec3d> = (0..samples).map(|sample| {
+            let mut rng = Pcg32::for_sample(camera.seed() ^ 0x9b0e, probe as u64, sample as u64);
+            sampler::with_stream(&mut rng, Vec3d::random_unit_vector)
+        }).collect();
+        let rays: Vec<Ray> = directions.iter()
+            .map(|&direction| Ray::new(position, direction, 0.0).with_kind(RayKind::Reflection).with_depth(1))
+            .collect();
+        let radiance = camera.trace_rays(world, &rays, camera.seed() ^ probe as u64);
+
+        // Uniform directions over the sphere each stand for 4 pi / samples of solid angle.
+        let mut coefficients = [Color::zero(); 9];
+        for (direction, radiance) in directions.iter().zip(radiance) {
+            for (coefficient, basis) in coefficients.iter_mut().zip(sh_basis(direction)) {
+                *coefficient += radiance * (basis * 4.0 * consts::PI / samples as Float);
+            }
+        }
+        LightProbe { position, coefficients }
+    }).collect()
+}
+
+
+/// Writes ``probes`` to ``path`` as JSON, a list of objects with the ``position`` of the
+/// probe and its nine ``coefficients``, each an RGB triple, for engines to load.
+pub fn write_light_probes(path: &str, probes: &[LightProbe]) -> Result<()> {
+    let triple = |x: Float, y: Float, z: Float| format!("[{},{},{}]", x, y, z);
+    let entries: Vec<String> = probes.iter().map(|probe| {
+        let coefficients: Vec<String> = probe.coefficients.iter().map(|c| triple(c.r(), c.g(), c.b())).collect();
+        format!(
+            "{{\"position\":{},\"coefficients\":[{}]}}",
+            triple(probe.position.x(), probe.position.y(), probe.position.z()), coefficients.join(","),
+        )
+    }).collect();
+    let json = format!("[{}]\n", entries.join(","));
+    std::fs::write(path, json).map_err(|source| Error::Io { file: path.to_string(), source })
+}
+
+
+/// A texel and the point on the mesh it gathers the light at.
+struct TexelPoint {
+    texel: usize,
+    point: Point3d,
+    normal: Vec3d,
+}
+
+
+/// Bakes the irradiance over the UV layout of ``mesh`` into a lightmap of ``width x height``
+/// texels, with ``samples`` rays per texel traced through ``world`` with the settings of
+/// ``camera``, e.g. its background, lights, depth and seed.
+///
+/// The mesh only places the texels, so the ``world`` should contain it too for it to shadow
+/// itself. Rays leave the front of every triangle, or of its shading normal, by the hit
+/// epsilon of the camera.
+/// # Errors
+/// If the mesh has no texture coordinates, or invalid data, see ``Mesh::try_new``.
+pub fn bake_lightmap<H: Hittable>(camera: &Camera, world: &H, mesh: &MeshData, width: usize, height: usize, samples: u32) -> Result<Lightmap> {
+    mesh.validate()?;
+    if mesh.uvs.is_empty() {
+        return Err(Error::AttributeCount { attribute: "uv", expected: mesh.positions.len(), found: 0 });
+    }
+
+    let points = texel_points(mesh, width, height);
+    let mut texels = vec![Color::zero(); width * height];
+    let mut covered = vec![false; width * height];
+    for texel in &points {
+        covered[texel.texel] = true;
+    }
+
+    let samples = samples.max(1) as usize;
+    for batch in points.chunks((BATCH_SIZE / samples).max(1)) {
+        let rays: Vec<Ray> = batch.iter().flat_map(|texel| {
+            (0..samples).map(move |sample| {
+                let mut rng = Pcg32::for_sample(camera.seed() ^ 0xba4e, texel.texel as u64, sample as u64);
+                // A normal plus a random unit vector is distributed by the cosine to it.
+                let direction = texel.normal + sampler::with_stream(&mut rng, Vec3d::random_unit_vector);
+                let direction = if direction.near_zero() { texel.normal } else { direction };
+                Ray::new(texel.point + texel.normal * camera.hit_epsilon(), direction, 0.0)
+                    .with_kind(RayKind::Reflection)
+                    .with_depth(1)
+            })
+        }).collect();
+        // Rays take the streams of their index in the batch, so batches, starting at different
+        // texels, need seeds of their own.
+        let seed = camera.seed() ^ batch[0].texel as u64;
+        let radiance = camera.trace_rays(world, &rays, seed);
+        for (texel, radiance) in batch.iter().zip(radiance.chunks(samples)) {
+            // Cosine weighted samples estimate the irradiance as pi times their mean.
+            texels[texel.texel] = radiance.iter().copied().sum::<Color>() * (consts::PI / samples as Float);
+        }
+    }
+
+    let mut lightmap = Lightmap { width, height, texels, covered };
+    dilate(&mut lightmap);
+    Ok(lightmap)
+}
+
+
+/// The texels whose center a triangle of ``mesh`` covers in UV space, with the point and
+/// normal under it, the first triangle taking texels several overlap.
+fn texel_points(mesh: &MeshData, width: usize, height: usize) -> Vec<TexelPoint> {
+    let mut taken = vec![false; width * height];
+    let mut points = Vec::new();
+    for &[i0, i1, i2] in &mesh.triangles {
+        let [p0, p1, p2] = [i0, i1, i2].map(|i| mesh.positions[i]);
+        // Texel coordinates, rows counted from the top.
+        let [a, b, c] = [i0, i1, i2].map(|i| (mesh.uvs[i].0 * width as Float, (1.0 - mesh.uvs[i].1) * height as Float));
+        let area = (b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1);
+        let geometric = cross(&(p1 - p0), &(p2 - p0));
+        if area == 0.0 || geometric.near_zero() { continue; }
+
+        let range = |low: Float, high: Float, size: usize| {
+            (low.floor().max(0.0) as usize)..(high.ceil().max(0.0) as usize).min(size)
+        };
+        for y in range(a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1), height) {
+            for x in range(a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0), width) {
+                let (px, py) = (x as Float + 0.5, y as Float + 0.5);
+                let beta = ((px - a.0) * (c.1 - a.1) - (c.0 - a.0) * (py - a.1)) / area;
+                let gamma = ((b.0 - a.0) * (py - a.1) - (px - a.0) * (b.1 - a.1)) / area;
+                let alpha = 1.0 - beta - gamma;
+                if alpha < 0.0 || beta < 0.0 || gamma < 0.0 || taken[y * width + x] { continue; }
+
+                let normal = if mesh.normals.is_empty() || mesh.flat_shading {
+                    geometric
+                } else {
+                    mesh.normals[i0] * alpha + mesh.normals[i1] * beta + mesh.normals[i2] * gamma
+                };
+                let normal = if normal.near_zero() { geometric } else { normal };
+                taken[y * width + x] = true;
+                points.push(TexelPoint {
+                    texel: y * width + x,
+                    point: p0 * alpha + p1 * beta + p2 * gamma,
+                    normal: normal.unit_vector(),
+                });
+            }
+        }
+    }
+    points
+}
+
+
+/// Fills every empty texel next to a filled one with the mean of its filled neighbors, for
+/// ``PADDING`` rounds.
+fn dilate(lightmap: &mut Lightmap) {
+    let (width, height) = (lightmap.width, lightmap.height);
+    let mut filled = lightmap.covered.clone();
+    for _ in 0..PADDING {
+        let previous = filled.clone();
+        for texel in 0..width * height {
+            if previous[texel] { continue; }
+            let (x, y) = (texel % width, texel / width);
+            let neighbors = [
+                (x > 0).then(|| texel - 1),
+                (x + 1 < width).then(|| texel + 1),
+                (y > 0).then(|| texel - width),
+                (y + 1 < height).then(|| texel + width),
+            ];
+            let sources: Vec<usize> = neighbors.into_iter().flatten().filter(|&neighbor| previous[neighbor]).collect();
+            if sources.is_empty() { continue; }
+            lightmap.texels[texel] = sources.iter().map(|&neighbor| lightmap.texels[neighbor]).sum::<Color>() / sources.len() as Float;
+            filled[texel] = true;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test_bake {
+    use super::*;
+    use crate::object::{HittableVec, Quad};
+    use crate::object::material::{Material, Lambertian};
+
+    /// A unit floor facing up, mapped onto the left half of the texture.
+    fn floor() -> MeshData {
+        MeshData {
+            positions: vec![Point3d::new(0.0, 0.0, 0.0), Point3d::new(1.0, 0.0, 0.0), Point3d::new(1.0, 0.0, -1.0), Point3d::new(0.0, 0.0, -1.0)],
+            uvs: vec![(0.0, 0.0), (0.5, 0.0), (0.5, 1.0), (0.0, 1.0)],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_light_probe_of_the_sky_above() {
+        /// White above the horizon, black below.
+        #[derive(Debug)]
+        struct Sky;
+
+        impl crate::background::Background for Sky {
+            fn value(&self, direction: &Vec3d) -> Color {
+                if direction.y() > 0.0 { Color::splat(1.0) } else { Color::zero() }
+            }
+        }
+
+        let mut camera = Camera::new();
+        camera.set_background(Sky);
+        let probes = bake_light_probes(&camera, &HittableVec::new(), &[Point3d::zero(), Point3d::new(0.0, 5.0, 0.0)], 16384);
+        // The second band takes a hemisphere in whole: all of it from above, none from below.
+        let up = probes[0].irradiance(&Vec3d::new(0.0, 1.0, 0.0));
+        let down = probes[0].irradiance(&Vec3d::new(0.0, -1.0, 0.0));
+        let side = probes[0].irradiance(&Vec3d::new(1.0, 0.0, 0.0));
+        assert!((up.g() - consts::PI).abs() < 0.15, "{}", up);
+        assert!(down.g().abs() < 0.15, "{}", down);
+        assert!((side.g() - consts::PI / 2.0).abs() < 0.15, "{}", side);
+        assert_eq!(probes[1].position, Point3d::new(0.0, 5.0, 0.0));
+
+        let path = std::env::temp_dir().join("ray_tracing_test_probes.json");
+        let path = path.to_str().unwrap();
+        write_light_probes(path, &probes[..1]).unwrap();
+        let json = std::fs::read_to_string(path).unwrap();
+        assert!(json.starts_with("[{\"position\":[0,0,0],\"coefficients\":[["), "{}", json);
+        assert_eq!(json.matches('[').count(), 1 + 1 + 1 + 9);
+    }
+
+    #[test]
+    fn test_shadowed_texels_and_padding() {
+        // A black roof over the half of the floor with x above 0.5.
+        let mut world = HittableVec::new();
+        world.add(std::sync::Arc::new(Quad::new(
+            Point3d::new(0.5, 0.01, 5.0), Vec3d::new(50.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, -10.0),
+            Material::Lambertian(Lambertian::new(Color::zero())),
+        )));
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::splat(1.0));
+        let lightmap = bake_lightmap(&camera, &world, &floor(), 8, 4, 64).unwrap();
+
+        // Texel columns 0 to 3 hold the floor, 0 and 1 left of the roof, 2 and 3 under it.
+        let texel = |x: usize, y: usize| lightmap.texels()[y * 8 + x].g() / consts::PI;
+        assert!(lightmap.is_covered(0, 0) && lightmap.is_covered(3, 3) && !lightmap.is_covered(4, 0));
+        assert!(texel(0, 1) > 0.6, "{}", texel(0, 1));
+        assert!(texel(3, 1) < 0.1, "{}", texel(3, 1));
+        // The padding takes the light of the border, two texels wide.
+        assert_eq!(lightmap.texels()[8 + 4], lightmap.texels()[8 + 3]);
+        assert_eq!(lightmap.texels()[8 + 5], lightmap.texels()[8 + 4]);
+        assert_eq!(lightmap.texels()[8 + 6], Color::zero());
+        assert_eq!(bake_lightmap(&camera, &world, &floor(), 8, 4, 64).unwrap(), lightmap);
+
+        let mut bare = floor();
+        bare.uvs.clear();
+        assert!(matches!(bake_lightmap(&camera, &world, &bare, 8, 4, 1), Err(Error::AttributeCount { attribute: "uv", .. })));
+    }
+}
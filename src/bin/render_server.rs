@@ -0,0 +1,12 @@
+use ray_tracing::server::RenderServer;
+use std::sync::Arc;
+
+fn main() {
+    // The address to listen on can be given as the only argument.
+    let address = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    println!("Rendering on http://{}", address);
+    if let Err(error) = Arc::new(RenderServer::new()).serve(&address) {
+        eprintln!("Could not serve on {}: {}", address, error);
+        std::process::exit(1);
+    }
+}
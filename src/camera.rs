@@ -1,42 +1,249 @@
-use crate::vec3d::{Vec3d, Color, Point3d, cross};
-use crate::object::Hittable;
-use crate::ray::{Ray, Interval};
+use crate::vec3d::{Vec3d, Point3d, Float, consts, cross, dot};
+use crate::color::Color;
+use crate::object::{AABB, Atmosphere, BVHNode, Emitter, HitRecord, Hittable, Portal};
+use crate::ray::{Ray, RayKind, Interval};
 use rand::Rng;
-use crate::object::material::Scatterable;
-use indicatif::ProgressBar;
+use crate::object::material::{BackFace, Material, Scatterable};
+use crate::stats::{self, Counter, RenderStats};
+use crate::pdf::{LightPdf, MaterialPdf, MixturePdf, Pdf, PortalPdf};
+use crate::tile::{self, Tile, TileOrder};
+use crate::film::Film;
+use crate::error::{Error, Result};
+use crate::background::Background;
+use crate::sampler::{self, Pcg32, PixelSampler};
+use crate::trace;
+use crate::raylog::{RayLog, RecordedPath, Termination};
+use crate::image::Preview;
+use crate::cubemap::{CubeFace, Cubemap};
+use indicatif::{ProgressBar, ProgressDrawTarget};
 
 use std::thread;
 use rayon;
-use std::sync::mpsc;
+use rayon::prelude::*;
+use std::ops::Range;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
 
-#[derive(Copy, Clone)]
+/// Color used to flag pixels with invalid radiance in diagnostics mode.
+const DIAGNOSTICS_COLOR: Color = Color::new(1.0, 0.0, 1.0);
+
+
+/// Whether every component of the radiance is finite and non-negative.
+fn is_valid_radiance(color: &Color) -> bool {
+    (0..3).all(|i| color[i].is_finite() && color[i] >= 0.0)
+}
+
+
+/// Draws a line between two points given in pixels onto a square ``image`` of ``size``
+/// pixels a side, leaving out the parts off the image.
+fn draw_line(image: &mut [Color], size: usize, from: (Float, Float), to: (Float, Float), color: Color) {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().min(4.0 * size as Float) as usize;
+    for step in 0..=steps {
+        let t = if steps == 0 { 0.0 } else { step as Float / steps as Float };
+        let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+        if x >= 0.0 && y >= 0.0 && x < size as Float && y < size as Float {
+            image[y as usize * size + x as usize] = color;
+        }
+    }
+}
+
+
+/// ``v`` rotated by ``angle`` radians around the unit vector ``axis``, counter-clockwise when
+/// the axis points at the viewer.
+fn rotate_about(v: &Vec3d, axis: &Vec3d, angle: Float) -> Vec3d {
+    let (sin, cos) = angle.sin_cos();
+    *v * cos + cross(axis, v) * sin + *axis * dot(axis, v) * (1.0 - cos)
+}
+
+
+/// Weight of a sample drawn with density ``pdf`` among two strategies, the other one having
+/// density ``other_pdf`` for the same sample.
+fn power_heuristic(pdf: Float, other_pdf: Float) -> Float {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b <= 0.0 { 1.0 } else { a / (a + b) }
+}
+
+
+/// A hit on the inner side of a ``Dielectric`` filled with a medium, or the scattering inside
+/// the medium on the way there, see ``Medium::scatter_before``.
+fn through_interior<'m>(ray: &Ray, start: Float, hit_record: HitRecord<'m>) -> HitRecord<'m> {
+    match hit_record.material {
+        Material::Dielectric(dielectric) if !hit_record.front_face => match dielectric.interior() {
+            Some(medium) => medium.scatter_before(ray, start, hit_record),
+            None => hit_record,
+        },
+        _ => hit_record,
+    }
+}
+
+
+/// Bounces along a path so far, per material type.
+///
+/// Bounces off materials without their own limit are also counted together, as they share
+/// the camera's maximum depth.
+#[derive(Debug, Clone, Copy, Default)]
+struct Bounces {
+    shared: i32,
+    per_material: [i32; Material::TYPES],
+}
+
+
+impl Bounces {
+    /// Returns the bounces after scattering off ``material``.
+    fn after(mut self, material: &Material) -> Self {
+        if material.max_bounces().is_none() {
+            self.shared += 1;
+        }
+        self.per_material[material.type_index()] += 1;
+        self
+    }
+}
+
+
+/// Number of camera paths traced together in one wavefront.
+const WAVEFRONT_SIZE: usize = 4096;
+
+
+/// A camera path in flight, advanced one bounce per round of wavefront stages.
+#[derive(Debug, Clone)]
+struct Path {
+    ray: Ray,
+    /// Product of the attenuations and weights of all bounces so far.
+    throughput: Color,
+    radiance: Color,
+    bounces: Bounces,
+    /// Index of the pixel the path contributes to.
+    pixel: usize,
+    /// Density the direction of ``ray`` was scattered with, when light sampling also covers
+    /// it and emission it hits needs weighting against that.
+    scatter_pdf: Option<Float>,
+    /// Random numbers of this sample, see ``sampler``.
+    rng: Pcg32,
+    /// Part of ``radiance`` from the lights of each light group, as far as any has been added.
+    groups: Vec<Color>,
+    /// ``0`` once the camera ray missed the world or hit a holdout, see ``RenderOutput::alpha``.
+    alpha: Float,
+    /// Points the path went through so far, only kept for ``Camera::record_paths``.
+    vertices: Option<Vec<Point3d>>,
+    /// Why the path ended, once it has.
+    termination: Termination,
+    /// Rays traced along the path so far, including those passing through culled back faces
+    /// and scattering in media, see ``RenderOutput::path_lengths``.
+    segments: u32,
+}
+
+
+impl Path {
+    fn new(ray: Ray, pixel: usize, rng: Pcg32) -> Self {
+        Self {
+            ray, throughput: Color::splat(1.0), radiance: Color::zero(), bounces: Bounces::default(), pixel,
+            scatter_pdf: None, rng, groups: Vec::new(), alpha: 1.0, vertices: None, termination: Termination::Absorbed,
+            segments: 0,
+        }
+    }
+
+    /// Adds ``light`` to the radiance, and to the light group with the index ``group``.
+    fn add_light(&mut self, light: Color, group: Option<usize>) {
+        self.radiance += light;
+        if let Some(group) = group {
+            if self.groups.len() <= group {
+                self.groups.resize(group + 1, Color::zero());
+            }
+            self.groups[group] += light;
+        }
+    }
+
+    /// Light of the light group with the index ``group``.
+    fn group(&self, group: usize) -> Color {
+        self.groups.get(group).copied().unwrap_or(Color::zero())
+    }
+}
+
+
+/// Callback of ``Camera::set_progress``.
+type ProgressCallback = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+
+/// Everything ``Camera::render_output`` gathers for the pixels, each buffer in the same
+/// row-major order as ``image``.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOutput {
+    pub image: Vec<Color>,
+    /// Samples traced for each pixel.
+    pub sample_counts: Vec<u32>,
+    /// Estimated variance of each pixel's color per channel, i.e. how far the image is from
+    /// converged, see ``Film::variance``. Denoisers accepting a confidence map take it as an AOV, see
+    /// ``image::write_pfm``.
+    pub variance: Vec<Color>,
+    /// Name and image of every group of ``Camera::add_light_group``, lit only by its lights.
+    /// Together with the light of no group, e.g. the background, they sum up to ``image``.
+    pub light_groups: Vec<(String, Vec<Color>)>,
+    /// Share of the samples of each pixel whose camera ray hit the world, as opposed to
+    /// missing it or hitting a holdout, see ``VisibilityFlags::holdout``. Holdouts render
+    /// black, while the background stays in ``image``; set it to black too for premultiplied
+    /// compositing, see ``image::write_image_with_alpha``.
+    pub alpha: Vec<Float>,
+    /// Mean number of rays traced per sample of each pixel, however its paths ended. Deep
+    /// stacks of transparent surfaces or dense fog stand out as long paths, the places to
+    /// lower the depth of, see ``Material::max_bounces``; write them with
+    /// ``image::write_heatmap``.
+    pub path_lengths: Vec<Float>,
+}
+
+
+#[derive(Clone)]
 pub struct Camera {
     center: Point3d,
-    aspect_ratio: f64,
+    aspect_ratio: Float,
 
     resolution: (i32, i32),
-    viewport_dims: (f64, f64),
+    viewport_dims: (Float, Float),
 
     viewport_u: Vec3d,
     viewport_v: Vec3d,
 
     samples_per_pixel: i32,
-    samples_scale: f64,
 
     max_depth: i32,
 
-    v_fov: f64, // Vertical field of view in degrees.
+    v_fov: Float, // Vertical field of view in degrees.
 
     look_from: Point3d,   // Point camera is looking from
     look_at: Vec3d,     // Point camera is looking at
     v_up: Vec3d,        // Camera-relative up vector
 
-    defocus_angle: f64,
-    defocus_radius: f64,
-    focus_dist: f64,
+    defocus_angle: Float,
+    defocus_radius: Float,
+    focus_dist: Float,
+
+    sensor_height_mm: Float, // Height of the film back focal lengths are relative to.
+    f_number: Option<Float>, // Aperture as a ratio of the focal length, overrides defocus_angle.
+
+    background: Arc<dyn Background>, // Seen by camera rays, and by all rays without a lighting background.
+    lighting_background: Option<Arc<dyn Background>>, // Seen by every ray after the first bounce.
+
+    diagnostics: bool, // Flag and log samples with NaN, infinite or negative radiance.
+
+    hit_epsilon: Float, // Minimum hit distance, and offset of secondary rays along the normal.
+
+    portals: Vec<Arc<Portal>>, // Openings diffuse bounces sample the background through.
+
+    lights: Vec<Arc<dyn Emitter>>, // Emitters diffuse bounces send shadow rays to.
+    light_groups: Vec<String>,
 
-    background_color: Color,
+    atmosphere: Option<Atmosphere>, // Fog every ray segment passes through.
 
+    clipping: Option<(Float, Float)>, // Near and far distances along the view axis camera rays see between.
+
+    tile_size: usize, // Side length in pixels of the squares rendered as one task.
+    tile_order: TileOrder,
+
+    seed: u64, // Seed the random numbers of every pixel and sample are derived from.
+    pixel_sampler: PixelSampler,
+
+    progress: Option<ProgressCallback>, // Told about finished pixels instead of the progress bar.
+
+    preview: Option<Preview>, // Image of the render in progress, written from time to time.
 }
 
 
@@ -45,7 +252,7 @@ impl Camera {
         let center = Point3d::zero();
         let aspect_ratio = 16.0 / 9.0;
         let image_width = 1080;
-        let v_fov: f64 = 90.0;
+        let v_fov: Float = 90.0;
 
         let look_from = Point3d::new(0.0, 0.0, 0.0);
         let look_at = Vec3d::new(0.0, 0.0, -1.0);
@@ -56,8 +263,8 @@ impl Camera {
         let h = (theta / 2.0).tan();
         let viewport_height = 2.0 * h * focal_length;
 
-        let image_height: i32 = (image_width as f64 / aspect_ratio) as i32;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+        let image_height: i32 = (image_width as Float / aspect_ratio) as i32;
+        let viewport_width = viewport_height * (image_width as Float / image_height as Float);
 
         let viewport_u = Vec3d::new(viewport_width, 0.0, 0.0);
         let viewport_v = Vec3d::new(0.0, -viewport_height, 0.0);
@@ -70,7 +277,6 @@ impl Camera {
             viewport_u,
             viewport_v,
             samples_per_pixel: 1,
-            samples_scale: 1.0,
             max_depth: 10,
             v_fov,
             look_from,
@@ -79,7 +285,23 @@ impl Camera {
             defocus_angle: 0.0,
             defocus_radius: 0.0,
             focus_dist: 10.0,
-            background_color: Color::zero(),
+            sensor_height_mm: 24.0,
+            f_number: None,
+            background: Arc::new(Color::zero()),
+            lighting_background: None,
+            diagnostics: false,
+            hit_epsilon: 0.0001,
+            portals: Vec::new(),
+            lights: Vec::new(),
+            light_groups: Vec::new(),
+            atmosphere: None,
+            clipping: None,
+            tile_size: 16,
+            tile_order: TileOrder::Scanline,
+            seed: 0,
+            pixel_sampler: PixelSampler::Random,
+            progress: None,
+            preview: None,
         }
     }
 
@@ -89,16 +311,21 @@ impl Camera {
 
         let h = (self.theta() / 2.0).tan();
         let viewport_height = 2.0 * h * self.focus_dist;
-        let viewport_width = viewport_height * (self.resolution_width() as f64 / self.resolution_height() as f64);
+        let viewport_width = viewport_height * (self.resolution_width() as Float / self.resolution_height() as Float);
         self.viewport_dims = (viewport_width, viewport_height);
 
         self.viewport_u = self.u() * self.viewport_width();
         self.viewport_v = -self.v() * self.viewport_height();
 
+        if let Some(f_number) = self.f_number {
+            // Scene units are meters, so the aperture diameter is converted from millimeters.
+            let aperture_radius = self.focal_length_mm() / f_number / 2.0 / 1000.0;
+            self.defocus_angle = 2.0 * (aperture_radius / self.focus_dist).atan().to_degrees();
+        }
         self.defocus_radius = (self.defocus_angle / 2.0).to_radians().tan() * self.focus_dist;
     }
 
-    fn theta(&self) -> f64 { self.v_fov.to_radians() }
+    fn theta(&self) -> Float { self.v_fov.to_radians() }
 
     fn w(&self) -> Vec3d { (self.look_from - self.look_at).unit_vector() }
 
@@ -110,33 +337,297 @@ impl Camera {
     pub fn set_look_at(&mut self, look_at: Vec3d) -> () { self.look_at = look_at; }
     pub fn set_v_up(&mut self, v_up: Vec3d) -> () { self.v_up = v_up; }
 
-    pub fn focal_length(&self) -> f64 { (self.look_from - self.look_at).length() }
+    pub fn look_from(&self) -> Point3d { self.look_from }
+    pub fn look_at(&self) -> Point3d { self.look_at }
+
+    pub fn focal_length(&self) -> Float { (self.look_from - self.look_at).length() }
+
+    /// Orbits the camera around the point it looks at, by ``yaw`` degrees around the up
+    /// vector, counter-clockwise seen from above, and ``pitch`` degrees upwards. The camera
+    /// stops just short of straight above or below, where the up vector would flip.
+    ///
+    /// Together with ``pan`` and ``zoom`` this is what mouse drags in an interactive preview
+    /// map to; render again afterwards to see the new view.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let mut camera = Camera::new();
+    /// camera.set_look_from(Point3d::new(0.0, 0.0, 2.0));
+    /// camera.set_look_at(Point3d::zero());
+    /// camera.orbit(90.0, 0.0);
+    /// assert!((camera.look_from() - Point3d::new(2.0, 0.0, 0.0)).length() < 1e-9);
+    /// ```
+    pub fn orbit(&mut self, yaw: Float, pitch: Float) {
+        const MARGIN: Float = 0.01;
+        let up = self.v_up.unit_vector();
+        let mut offset = self.look_from - self.look_at;
+
+        let side = cross(&offset, &up);
+        if side.length_squared() > 0.0 {
+            let polar = dot(&offset.unit_vector(), &up).clamp(-1.0, 1.0).acos();
+            let target = (polar - pitch.to_radians()).clamp(MARGIN, consts::PI - MARGIN);
+            offset = rotate_about(&offset, &side.unit_vector(), polar - target);
+        }
+        offset = rotate_about(&offset, &up, yaw.to_radians());
+        self.look_from = self.look_at + offset;
+    }
+
+    /// Moves the camera and the point it looks at alike, ``dx`` to the right and ``dy`` up in
+    /// the view, both as fractions of the height of the view at the point looked at.
+    pub fn pan(&mut self, dx: Float, dy: Float) {
+        let view_height = 2.0 * (self.theta() / 2.0).tan() * self.focal_length();
+        let shift = (self.u() * dx + self.v() * dy) * view_height;
+        self.look_from += shift;
+        self.look_at += shift;
+    }
+
+    /// Moves the camera towards the point it looks at, dividing the distance by ``factor``, so
+    /// a factor above ``1.0`` zooms in. The point looked at is never passed.
+    pub fn zoom(&mut self, factor: Float) {
+        if factor <= 0.0 { return; }
+        let offset = self.look_from - self.look_at;
+        let distance = (offset.length() / factor).max(1e-3);
+        self.look_from = self.look_at + offset.unit_vector() * distance;
+    }
+
+    /// Points the camera at ``world`` from as far as it takes for the whole of it to be in
+    /// view with a vertical field of view of ``fov`` degrees, keeping the direction it looks
+    /// from. ``margin`` widens the view by that share of the size of the world, e.g. ``0.1``
+    /// for a tenth around it.
+    ///
+    /// The world is framed by the sphere around its bounding box, so it fits at any aspect
+    /// ratio and from any direction, and the focus is set to its center.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// use std::sync::Arc;
+    /// let mut world = HittableVec::new();
+    /// world.add(Arc::new(Sphere::static_sphere(Point3d::new(5.0, 0.0, 0.0), 1.0, Material::Empty(Empty {}))));
+    /// let mut camera = Camera::new();
+    /// camera.set_look_from(Point3d::new(0.0, 0.0, 10.0));
+    /// camera.frame(&BVHNode::from_hittable_vec(Arc::new(world)), 40.0, 0.1);
+    /// assert_eq!(camera.look_at(), Point3d::new(5.0, 0.0, 0.0));
+    /// assert!(camera.look_from().z() > 5.0 && camera.look_from().x() == 5.0);
+    /// ```
+    pub fn frame<H: Hittable + ?Sized>(&mut self, world: &H, fov: Float, margin: Float) {
+        self.v_fov = fov;
+        let (center, radius) = world.bounding_box().bounding_sphere();
+        if radius <= 0.0 { return; }
+
+        // The narrower of the vertical and horizontal view has to take the sphere in.
+        let half_height = (fov.to_radians() / 2.0).tan();
+        let half_angle = half_height.min(half_height * self.aspect_ratio).atan();
+        let distance = radius * (1.0 + margin) / half_angle.sin();
+
+        let offset = self.look_from - self.look_at;
+        let direction = if offset.length_squared() > 0.0 { offset.unit_vector() } else { Vec3d::new(0.0, 0.0, 1.0) };
+        self.look_at = center;
+        self.look_from = center + direction * distance;
+        self.focus_dist = distance;
+    }
 
     fn set_center(&mut self, center: Vec3d) -> () { self.center = center; }
 
     pub fn set_samples_per_pixel(&mut self, samples_per_pixel: i32) -> () {
         self.samples_per_pixel = samples_per_pixel;
-        self.samples_scale = 1.0 / (samples_per_pixel as f64);
     }
 
-    pub fn set_v_fov(&mut self, v_fov: f64) -> () { self.v_fov = v_fov; }
+    pub fn set_v_fov(&mut self, v_fov: Float) -> () { self.v_fov = v_fov; }
 
+    /// Sets the maximum number of bounces of a path, for materials without their own limit,
+    /// see ``Scatterable::max_bounces``.
     pub fn set_depth(&mut self, max_depth: i32) -> () { self.max_depth = max_depth; }
 
-    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) -> () { self.aspect_ratio = aspect_ratio; }
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: Float) -> () { self.aspect_ratio = aspect_ratio; }
 
     pub fn set_resolution_width(&mut self, width: i32) -> () { self.resolution.0 = width; }
 
     fn update_resolution_height(&mut self) -> () {
-        let height = (self.resolution_width() as f64 / self.aspect_ratio) as i32;
+        let height = (self.resolution_width() as Float / self.aspect_ratio) as i32;
         self.resolution.1 = height.max(1);
     }
 
-    pub fn set_defocus_angle(&mut self, angle: f64) -> () { self.defocus_angle = angle; }
+    pub fn set_defocus_angle(&mut self, angle: Float) -> () {
+        self.defocus_angle = angle;
+        self.f_number = None;
+    }
+
+    /// Sets the height of the sensor in millimeters, ``24.0`` (full frame) by default. Focal
+    /// lengths set afterwards are relative to it.
+    pub fn set_sensor_height_mm(&mut self, height: Float) { self.sensor_height_mm = height; }
+
+    /// Sets the vertical field of view from a lens focal length in millimeters, e.g. ``50.0``
+    /// for a normal lens or ``24.0`` for a wide angle one.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// let mut camera = Camera::new();
+    /// camera.set_focal_length_mm(12.0);
+    /// assert!((camera.focal_length_mm() - 12.0).abs() < 1e-9);
+    /// ```
+    pub fn set_focal_length_mm(&mut self, focal_length: Float) {
+        self.v_fov = 2.0 * (self.sensor_height_mm / (2.0 * focal_length)).atan().to_degrees();
+    }
+
+    /// Returns the focal length in millimeters matching the vertical field of view.
+    pub fn focal_length_mm(&self) -> Float {
+        self.sensor_height_mm / (2.0 * (self.theta() / 2.0).tan())
+    }
+
+    /// Sets the aperture as an f-number, e.g. ``1.4`` for a shallow or ``16.0`` for a deep
+    /// depth of field, replacing the defocus angle.
+    ///
+    /// The aperture diameter is the focal length divided by ``f_number``, with the scene in
+    /// meters. The matching defocus angle is derived from the focus distance when rendering,
+    /// so the blur stays consistent when refocusing.
+    pub fn set_f_number(&mut self, f_number: Float) { self.f_number = Some(f_number); }
+
+    pub fn f_number(&self) -> Option<Float> { self.f_number }
+
+    pub fn set_focus_dist(&mut self, focus_dist: Float) -> () { self.focus_dist = focus_dist; }
+
+    pub fn set_background_color(&mut self, color: Color) -> () { self.background = Arc::new(color); }
+
+    /// Sets what rays leaving the scene see, e.g. a ``SkyGradient`` or an ``EnvironmentMap``.
+    pub fn set_background(&mut self, background: impl Background + 'static) { self.background = Arc::new(background); }
+
+    /// Sets a separate background for rays that bounced at least once, which therefore
+    /// lights the scene, while camera rays keep seeing the one of ``set_background``. This is
+    /// the studio setup of a plain backdrop in the picture and an environment in reflections
+    /// and lighting.
+    pub fn set_lighting_background(&mut self, background: impl Background + 'static) {
+        self.lighting_background = Some(Arc::new(background));
+    }
+
+    /// Background ``ray`` sees when it leaves the scene.
+    fn background_for(&self, ray: &Ray) -> &dyn Background {
+        match (&self.lighting_background, ray.kind) {
+            (Some(lighting), kind) if kind != RayKind::Camera => lighting.as_ref(),
+            _ => self.background.as_ref(),
+        }
+    }
+
+    /// Enables the radiance diagnostics mode.
+    ///
+    /// Every sample is checked for NaN, infinite or negative radiance. Offending pixels are
+    /// painted magenta in the output, and both the pixel and the material that first produced
+    /// the invalid value are logged to stderr.
+    pub fn set_diagnostics(&mut self, enabled: bool) { self.diagnostics = enabled; }
+
+    /// Sets the epsilon used to avoid self-intersection of secondary rays.
+    ///
+    /// Hits closer than ``epsilon`` along a ray are ignored, and scattered rays start
+    /// ``epsilon`` away from the surface along its normal. The default of ``0.0001`` suits
+    /// scenes of roughly unit scale; scale it with the scene's extent.
+    pub fn set_hit_epsilon(&mut self, epsilon: Float) { self.hit_epsilon = epsilon; }
+
+    pub fn hit_epsilon(&self) -> Float { self.hit_epsilon }
+
+    /// Registers an opening the background light enters the scene through, see ``Portal``.
+    pub fn add_portal(&mut self, portal: Portal) { self.portals.push(Arc::new(portal)); }
+
+    /// Registers an emitter of the world to sample directly on diffuse bounces, see ``Emitter``.
+    pub fn add_light(&mut self, light: Arc<dyn Emitter>) { self.lights.push(light); }
+
+    /// Renders the light of the lights put into the group ``name``, with ``Light::with_group``
+    /// or ``PointLight::with_group``, into an image of its own in ``RenderOutput::light_groups``.
+    ///
+    /// All groups are rendered in the same pass as the image, so key, fill and rim lights can
+    /// be rebalanced in compositing without rendering again.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Quad};
+    /// use ray_tracing::object::material::{Material, Light};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// use std::sync::Arc;
+    /// // A wall of two lights, the left half in the ``key`` group.
+    /// let mut world = HittableVec::new();
+    /// for (x, light) in [(-10.0, Light::from_color(Color::splat(1.0)).with_group("key")), (0.0, Light::from_color(Color::splat(0.5)))] {
+    ///     let wall = Quad::new(Point3d::new(x, -10.0, -1.0), Vec3d::new(10.0, 0.0, 0.0), Vec3d::new(0.0, 20.0, 0.0), Material::Light(light));
+    ///     world.add(Arc::new(wall));
+    /// }
+    /// let mut camera = Camera::new();
+    /// camera.set_resolution_width(4);
+    /// camera.set_aspect_ratio(1.0);
+    /// camera.set_samples_per_pixel(1);
+    /// camera.set_look_from(Point3d::zero());
+    /// camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+    /// camera.add_light_group("key");
+    /// let output = camera.render_output(&BVHNode::from_hittable_vec(Arc::new(world)));
+    /// let (name, key) = &output.light_groups[0];
+    /// assert_eq!(name, "key");
+    /// assert_eq!(key[0], output.image[0]);
+    /// assert_eq!(key[3], Color::zero());
+    /// ```
+    pub fn add_light_group(&mut self, name: &str) {
+        if !self.light_groups.iter().any(|group| group == name) {
+            self.light_groups.push(name.to_string());
+        }
+    }
+
+    /// Writes the image rendered so far to ``preview`` from time to time while rendering, and
+    /// once more when it is done. Pixels not rendered yet are black.
+    pub fn set_preview(&mut self, preview: Preview) { self.preview = Some(preview); }
+
+    /// Index of the light group ``name`` among those of ``add_light_group``.
+    fn light_group(&self, name: Option<&str>) -> Option<usize> {
+        name.and_then(|name| self.light_groups.iter().position(|group| group == name))
+    }
+
+    /// Sets the side length in pixels of the tiles the image is rendered in, ``16`` by default.
+    pub fn set_tile_size(&mut self, tile_size: usize) { self.tile_size = tile_size.max(1); }
+
+    /// Sets the order tiles are rendered in, see ``TileOrder``.
+    pub fn set_tile_order(&mut self, tile_order: TileOrder) { self.tile_order = tile_order; }
 
-    pub fn set_focus_dist(&mut self, focus_dist: f64) -> () { self.focus_dist = focus_dist; }
+    /// Sets the seed of the render, ``0`` by default. Every sample of every pixel draws its
+    /// random numbers from its own stream of this seed, see ``sampler``, so renders with the
+    /// same seed match whatever the tiles, threads or interruptions; a different seed gives
+    /// independent noise.
+    pub fn set_seed(&mut self, seed: u64) { self.seed = seed; }
 
-    pub fn set_background_color(&mut self, color: Color) -> () { self.background_color = color; }
+    /// Sets how the samples are placed within each pixel, see ``PixelSampler``.
+    pub fn set_pixel_sampler(&mut self, pixel_sampler: PixelSampler) { self.pixel_sampler = pixel_sampler; }
+
+    pub fn seed(&self) -> u64 { self.seed }
+
+    /// Reports progress to ``progress`` instead of drawing a progress bar. It is called from
+    /// the rendering thread with the number of finished pixels and the total after each pixel.
+    pub fn set_progress(&mut self, progress: impl Fn(u64, u64) + Send + Sync + 'static) {
+        self.progress = Some(Arc::new(progress));
+    }
+
+    /// Hides everything nearer than ``near`` or farther than ``far`` along the view axis from
+    /// camera rays, e.g. to cut away the front of a box or a mesh and look inside without
+    /// changing the geometry. Reflected and shadow rays still see the whole scene, so what is
+    /// cut away keeps casting shadows.
+    pub fn set_clipping(&mut self, near: Float, far: Float) { self.clipping = Some((near, far)); }
+
+    /// Removes the clipping distances of ``set_clipping``.
+    pub fn clear_clipping(&mut self) { self.clipping = None; }
+
+    /// Narrows ``interval`` to the part of ``ray`` between the clipping distances, for camera
+    /// rays, which may also start at a surface they carried on past.
+    fn clipped(&self, ray: &Ray, interval: Interval) -> Interval {
+        let Some((near, far)) = self.clipping else { return interval; };
+        if ray.kind != RayKind::Camera { return interval; }
+        let forward = -self.w();
+        let (origin_depth, speed) = (dot(&(ray.origin - self.look_from), &forward), dot(&ray.direction, &forward));
+        if speed <= 0.0 { return Interval { min: interval.min, max: interval.min }; }
+        Interval {
+            min: interval.min.max((near - origin_depth) / speed),
+            max: interval.max.min((far - origin_depth) / speed),
+        }
+    }
+
+    /// Fills the scene with fog, see ``Atmosphere``.
+    pub fn set_atmosphere(&mut self, atmosphere: Atmosphere) { self.atmosphere = Some(atmosphere); }
 
     fn defocus_disk_u(&self) -> Vec3d { self.u() * self.defocus_radius }
 
@@ -146,18 +637,18 @@ impl Camera {
 
     pub fn resolution_height(&self) -> i32 { self.resolution.1 }
 
-    pub fn viewport_width(&self) -> f64 { self.viewport_dims.0 }
+    pub fn viewport_width(&self) -> Float { self.viewport_dims.0 }
 
-    pub fn viewport_height(&self) -> f64 { self.viewport_dims.1 }
+    pub fn viewport_height(&self) -> Float { self.viewport_dims.1 }
 
     pub fn pixel_delta_u(&self) -> Vec3d {
         // The pixel delta in the u (x) direction.
-        self.viewport_u / self.resolution_width() as f64
+        self.viewport_u / self.resolution_width() as Float
     }
 
     pub fn pixel_delta_v(&self) -> Vec3d {
         // The pixel delta in the v (y) direction.
-        self.viewport_v / self.resolution_height() as f64
+        self.viewport_v / self.resolution_height() as Float
     }
 
     pub fn viewport_upper_left(&self) -> Point3d {
@@ -172,41 +663,272 @@ impl Camera {
     /// # Arguments
     /// * `w` - The width coordinate of the pixel.
     /// * `h` - The height coordinate of the pixel.
-    pub fn pixel_coords(&self, w: f64, h: f64) -> Point3d {
+    pub fn pixel_coords(&self, w: Float, h: Float) -> Point3d {
         self.pixel_upper_left() + self.pixel_delta_u() * w + self.pixel_delta_v() * h
     }
 
-    fn ray_color<H: Hittable>(ray: &Ray, world: &H, depth: i32, background: &Color) -> Color {
-        if depth <= 0 { return Color::zero(); }
+    /// Moves the origin of a scattered ray off the surface along the geometric normal, to the
+    /// side the ray leaves towards.
+    fn offset_ray_origin(&self, ray: Ray, normal: &Vec3d) -> Ray {
+        let offset = if dot(&ray.direction, normal) >= 0.0 {
+            *normal * self.hit_epsilon
+        } else {
+            -*normal * self.hit_epsilon
+        };
+        Ray::new(ray.origin + offset, ray.direction, ray.time).with_kind(ray.kind).with_depth(ray.depth)
+    }
+
+    /// Whether a path with the given bounces may not scatter off ``material`` any further.
+    fn exhausted(&self, bounces: &Bounces, material: &Material) -> bool {
+        match material.max_bounces() {
+            Some(limit) => bounces.per_material[material.type_index()] >= limit,
+            None => bounces.shared >= self.max_depth,
+        }
+    }
 
-        if let Some(hit_record) = world.hit(ray, &Interval { min: 0.0001, max: f64::INFINITY }) {
-            let emitted = hit_record.material.emitted(hit_record.u, hit_record.v, &hit_record.point);
+    /// Traces a wavefront of paths to completion, returning them with their radiance.
+    ///
+    /// Instead of recursing along one path at a time, each bounce runs in stages over all
+    /// paths still in flight: every ray is intersected first, then every hit is shaded and
+    /// finally every path that goes on scatters into its next ray. Each stage walks over a
+    /// flat buffer with the same work for every entry, which keeps the caches warm and maps
+    /// directly onto compute kernels. Finished paths are returned in no particular order.
+    fn trace_wavefront<H: Hittable>(&self, mut active: Vec<Path>, world: &H) -> Vec<Path> {
+        if self.max_depth <= 0 { return active; }
+
+        let interval = Interval { min: self.hit_epsilon, max: Float::INFINITY };
+        let mut finished = Vec::with_capacity(active.len());
+        while !active.is_empty() {
+            // Intersect.
+            // Media decide where rays scatter while hitting, which draws from the path's stream.
+            let hits: Vec<_> = active.iter_mut().map(|path| {
+                path.segments += 1;
+                let ray = path.ray;
+                let interval = self.clipped(&ray, interval);
+                sampler::with_stream(&mut path.rng, || {
+                    world.hit(&ray, &interval).map(|hit_record| through_interior(&ray, interval.min, hit_record))
+                })
+            }).collect();
+
+            // Shade: add the background or the emission, and handle back faces.
+            let mut next = Vec::with_capacity(active.len());
+            let mut scattering = Vec::with_capacity(active.len());
+            for (mut path, hit) in active.into_iter().zip(hits) {
+                if let Some(atmosphere) = &self.atmosphere {
+                    let t_max = hit.as_ref().map_or(Float::INFINITY, |hit_record| hit_record.t);
+                    let transmittance = atmosphere.transmittance(&path.ray, t_max);
+                    path.radiance += path.throughput * atmosphere.color() * (1.0 - transmittance);
+                    path.throughput *= transmittance;
+                }
 
-            if let Some((scattered_ray, attenuation)) = hit_record.material.scatter(ray, &hit_record) {
-                let color = attenuation * Self::ray_color(
-                    &scattered_ray, world, depth - 1, background
+                let Some(mut hit_record) = hit else {
+                    if path.ray.kind == RayKind::Camera { path.alpha = 0.0; }
+                    path.termination = Termination::Escaped(path.ray.direction.unit_vector());
+                    path.radiance += path.throughput * self.background_for(&path.ray).value(&path.ray.direction);
+                    finished.push(path);
+                    continue;
+                };
+                hit_record.time = path.ray.time;
+                if let Some(vertices) = &mut path.vertices {
+                    vertices.push(hit_record.point);
+                }
+                if hit_record.holdout {
+                    path.alpha = 0.0;
+                    finished.push(path);
+                    continue;
+                }
+
+                if !hit_record.front_face {
+                    match hit_record.material.back_face() {
+                        BackFace::TwoSided => {}
+                        BackFace::Culled => {
+                            // Carry on past the surface, without using up a bounce.
+                            let continued = Ray::new(hit_record.point, path.ray.direction, path.ray.time)
+                                .with_kind(path.ray.kind)
+                                .with_depth(path.ray.depth);
+                            path.ray = self.offset_ray_origin(continued, &hit_record.normal);
+                            next.push(path);
+                            continue;
+                        }
+                        BackFace::Black => {
+                            finished.push(path);
+                            continue;
+                        }
+                    }
+                }
+
+                let valid = is_valid_radiance(&path.radiance) && is_valid_radiance(&path.throughput);
+                let emitted = hit_record.material.emitted_at(&hit_record);
+                let weight = match path.scatter_pdf {
+                    Some(scatter_pdf) => {
+                        let light_pdf = self.light_pdf(&path.ray.origin, &path.ray.direction);
+                        power_heuristic(scatter_pdf, light_pdf)
+                    }
+                    None => 1.0,
+                };
+                path.add_light(path.throughput * emitted * weight, self.light_group(hit_record.material.light_group()));
+                scattering.push((path, hit_record, valid));
+            }
+
+            // Scatter.
+            for (mut path, hit_record, valid) in scattering {
+                let mut rng = path.rng;
+                let continues = sampler::with_stream(&mut rng, || self.scatter_path(&mut path, &hit_record, world));
+                path.rng = rng;
+
+                // Only report the bounce introducing the invalid value, not every bounce it
+                // propagates through afterwards.
+                if self.diagnostics && valid
+                    && !(is_valid_radiance(&path.radiance) && is_valid_radiance(&path.throughput)) {
+                    eprintln!(
+                        "Invalid radiance {} at {} on {} from material {:?}",
+                        path.radiance, hit_record.point, hit_record.name.unwrap_or("unnamed object"),
+                        hit_record.material,
+                    );
+                }
+
+                if continues { next.push(path) } else { finished.push(path) }
+            }
+            active = next;
+        }
+        finished
+    }
+
+    /// Scatters ``path`` off ``hit_record``, adding the light sampled there, and returns
+    /// whether it carries on.
+    fn scatter_path<H: Hittable>(&self, path: &mut Path, hit_record: &HitRecord, world: &H) -> bool {
+        match hit_record.material.scatter(&path.ray, hit_record) {
+            Some((scattered_ray, attenuation)) => {
+                let ray_in = path.ray;
+                let bounces = path.bounces.after(hit_record.material);
+                let continues = !self.exhausted(&bounces, hit_record.material);
+                let (direct, group) = self.sample_light(&ray_in, hit_record, world, continues);
+                path.add_light(path.throughput * attenuation * direct, group);
+
+                let (scattered_ray, weight) = self.guide_through_portals(&ray_in, hit_record, scattered_ray);
+                path.scatter_pdf = if self.lights.is_empty()
+                    || hit_record.material.scattering_pdf(&ray_in, hit_record, &scattered_ray).is_none() {
+                    None
+                } else {
+                    Some(self.scattering_pdf(&ray_in, hit_record, &scattered_ray))
+                };
+                path.ray = self.offset_ray_origin(
+                    scattered_ray.with_kind(RayKind::Reflection).with_depth(path.ray.depth + 1),
+                    &hit_record.normal,
                 );
-                return color + emitted;
+                path.bounces = bounces;
+                if !continues {
+                    path.termination = Termination::MaxDepth;
+                    false
+                } else {
+                    stats::count(Counter::SecondaryRays);
+                    path.throughput = path.throughput * attenuation * weight;
+                    true
+                }
             }
-            emitted
+            None => false,
+        }
+    }
+
+    /// Replaces, half of the time, a diffuse scattered ray by one through a random portal.
+    ///
+    /// Returns the ray to trace and the weight of its contribution, the material's density of
+    /// its direction over the density of the mix of both strategies. Specular scattering, or a
+    /// camera without portals, keeps the ray with a weight of ``1.0``.
+    fn guide_through_portals(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: Ray) -> (Ray, Float) {
+        let material = hit_record.material;
+        if self.portals.is_empty() || material.scattering_pdf(ray_in, hit_record, &scattered).is_none() {
+            return (scattered, 1.0);
+        }
+
+        let scattered = if sampler::rng().random_bool(0.5) {
+            let portals = PortalPdf::new(&self.portals, hit_record.point);
+            Ray::new(hit_record.point, portals.generate().unwrap_or(scattered.direction), ray_in.time)
         } else {
-            // hits nothing.
-            *background
+            scattered
+        };
+
+        let material_pdf = MaterialPdf::new(ray_in, hit_record).value(&scattered.direction);
+        let mixture_pdf = self.scattering_pdf(ray_in, hit_record, &scattered);
+        if mixture_pdf <= 0.0 { return (scattered, 0.0); }
+        (scattered, material_pdf / mixture_pdf)
+    }
+
+    /// Density with which a diffuse bounce scatters into the direction of ``scattered``,
+    /// including rays guided through portals.
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Float {
+        let material = MaterialPdf::new(ray_in, hit_record);
+        let portals = PortalPdf::new(&self.portals, hit_record.point);
+        let weight = if self.portals.is_empty() { 0.0 } else { 0.5 };
+        MixturePdf::new()
+            .with(1.0 - weight, &material)
+            .with(weight, &portals)
+            .value(&scattered.direction)
+    }
+
+    /// Density with which light sampling picks ``direction`` from ``origin``.
+    fn light_pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        LightPdf::new(&self.lights, *origin).value(direction)
+    }
+
+    /// Light reaching a diffuse bounce straight from a random light, to be multiplied by the
+    /// attenuation of the bounce, with the light group of the light. Zero for specular
+    /// materials, or if the light is occluded.
+    ///
+    /// The contribution is weighted against scattered rays hitting the same light with the
+    /// power heuristic, so each strategy counts most where it samples best. A path that does
+    /// not ``continue`` past this bounce traces no scattered ray, so its light sample counts
+    /// in full.
+    fn sample_light<H: Hittable>(&self, ray_in: &Ray, hit_record: &HitRecord, world: &H, continues: bool) -> (Color, Option<usize>) {
+        if self.lights.is_empty() { return (Color::zero(), None); }
+
+        let light = &self.lights[sampler::rng().random_range(0..self.lights.len())];
+        let Some(sample) = light.sample(&hit_record.point) else { return (Color::zero(), None); };
+        let group = self.light_group(light.group());
+        let shadow_ray = Ray::new(hit_record.point, sample.direction, ray_in.time)
+            .with_kind(RayKind::Shadow)
+            .with_depth(ray_in.depth + 1);
+        let material_pdf = match hit_record.material.scattering_pdf(ray_in, hit_record, &shadow_ray) {
+            Some(pdf) if pdf > 0.0 => pdf,
+            _ => return (Color::zero(), None),
+        };
+
+        stats::count(Counter::ShadowRays);
+        // The direction reaches the light at ``t = 1``, so the interval leaves out both ends.
+        let distance = sample.direction.length();
+        let interval = Interval { min: self.hit_epsilon / distance, max: 1.0 - self.hit_epsilon / distance };
+        if world.hit_any(&shadow_ray, &interval) { return (Color::zero(), None); }
+        let radiance = match &self.atmosphere {
+            Some(atmosphere) => sample.radiance * atmosphere.transmittance(&shadow_ray, 1.0),
+            None => sample.radiance,
+        };
+
+        // Scattered rays never reach delta lights, so there is nothing to weight against.
+        if light.is_delta() {
+            let light_pdf = sample.pdf / self.lights.len() as Float;
+            return (radiance * (material_pdf / light_pdf), group);
         }
+        let light_pdf = self.light_pdf(&hit_record.point, &sample.direction);
+        if light_pdf <= 0.0 { return (Color::zero(), None); }
+        let weight = if continues {
+            power_heuristic(light_pdf, self.scattering_pdf(ray_in, hit_record, &shadow_ray))
+        } else {
+            1.0
+        };
+        (radiance * (material_pdf * weight / light_pdf), group)
     }
 
     /// Random sample a ray through the pixel at the given width and height coordinate.
     /// # Arguments
     /// * `i` - The width coordinate of the pixel.
     /// * `j` - The height coordinate of the pixel.
-    fn sample_ray(&self, i: i32, j: i32) -> Ray {
-        let mut rng = rand::thread_rng();
-
-        let (offset_i, offset_j) = rng.random::<(f64, f64)>();
+    /// * `offset` - The point across the pixel the ray goes through, in pixels.
+    fn sample_ray(&self, i: i32, j: i32, (offset_i, offset_j): (Float, Float)) -> Ray {
+        let mut rng = sampler::rng();
 
         let pixel_sample = self.pixel_coords(
-            i as f64 + offset_i,
-            j as f64 + offset_j,
+            i as Float + offset_i,
+            j as Float + offset_j,
         );
 
         let ray_origin = if self.defocus_angle <= 0.0 {
@@ -217,7 +939,7 @@ impl Camera {
 
         let direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, direction, rng.random::<f64>())
+        Ray::new(ray_origin, direction, rng.random::<Float>())
     }
 
     fn defocus_disk_sample(&self) -> Vec3d {
@@ -225,51 +947,1428 @@ impl Camera {
         self.center + self.defocus_disk_u() * p.x() + self.defocus_disk_v() * p.y()
     }
 
-    pub fn render<H: Hittable>(&mut self, world: &'static H) -> Vec<Vec3d> {
+    /// Renders the world like ``render``, also returning the statistics gathered during this
+    /// render. The statistics are only collected with the ``stats`` feature enabled, and also
+    /// include any other render running concurrently in the process.
+    pub fn render_with_stats<H: Hittable>(&mut self, world: &H) -> (Vec<Color>, RenderStats) {
+        let before = stats::snapshot();
+        let image = self.render(world);
+        (image, stats::snapshot() - before)
+    }
+
+    pub fn render<H: Hittable>(&mut self, world: &H) -> Vec<Color> {
+        self.render_output(world).image
+    }
+
+    /// Renders like ``render``, also returning how many samples were traced for every pixel,
+    /// in the same order as the pixels. Write them with ``image::write_heatmap`` to see where
+    /// the sampler spent its effort.
+    pub fn render_with_sample_counts<H: Hittable>(&mut self, world: &H) -> (Vec<Color>, Vec<u32>) {
+        let output = self.render_output(world);
+        (output.image, output.sample_counts)
+    }
+
+    /// Renders like ``render``, returning the image along with the per-pixel buffers gathered
+    /// while sampling, see ``RenderOutput``.
+    pub fn render_output<H: Hittable>(&mut self, world: &H) -> RenderOutput {
+        let (film, group_films, alpha_film, length_film) = self.render_films(world, 0..self.samples_per_pixel.max(0) as u32);
+        let light_groups = self.light_groups.iter().cloned().zip(group_films.iter().map(Film::image)).collect();
+        let alpha = alpha_film.image().iter().map(|alpha| alpha[0]).collect();
+        let path_lengths = length_film.image().iter().map(|length| length[0]).collect();
+        RenderOutput {
+            image: film.image(), sample_counts: film.sample_counts().to_vec(), variance: film.variance(), light_groups, alpha,
+            path_lengths,
+        }
+    }
+
+    /// Renders a quick preview of the depth of field, with one ray through every pixel instead
+    /// of full path tracing, to set ``focus_dist`` and ``defocus_angle`` by.
+    ///
+    /// Surfaces are shaded grey by how directly they face the camera. Those blurred by less than
+    /// a pixel are tinted green, those nearer blue and those farther red, the more the blurrier,
+    /// and the focus plane is drawn in yellow where it cuts through the scene.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Quad};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// use std::sync::Arc;
+    /// // A wall at the focus distance, straight ahead.
+    /// let mut world = HittableVec::new();
+    /// world.add(Arc::new(Quad::new(
+    ///     Point3d::new(-10.0, -10.0, -4.0), Vec3d::new(20.0, 0.0, 0.0), Vec3d::new(0.0, 20.0, 0.0), Material::Empty(Empty {}),
+    /// )));
+    /// let mut camera = Camera::new();
+    /// camera.set_resolution_width(8);
+    /// camera.set_aspect_ratio(1.0);
+    /// camera.set_look_from(Point3d::zero());
+    /// camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+    /// camera.set_focus_dist(4.0);
+    /// camera.set_defocus_angle(2.0);
+    /// let preview = camera.render_focus_preview(&BVHNode::from_hittable_vec(Arc::new(world)));
+    /// assert!(preview.iter().all(|pixel| pixel.g() > pixel.r() && pixel.g() > pixel.b()));
+    /// ```
+    pub fn render_focus_preview<H: Hittable>(&mut self, world: &H) -> Vec<Color> {
+        const BACKGROUND: Color = Color::new(0.05, 0.05, 0.05);
+        const IN_FOCUS: Color = Color::new(0.2, 1.0, 0.2);
+        const NEAR: Color = Color::new(0.2, 0.4, 1.0);
+        const FAR: Color = Color::new(1.0, 0.3, 0.2);
+        const FOCUS_PLANE: Color = Color::new(1.0, 0.9, 0.1);
+        // Blur in pixels at which the tint of blurred surfaces is strongest.
+        const FULL_TINT_BLUR: Float = 20.0;
+
+        self.initialize();
+        let (width, height) = (self.resolution_width() as usize, self.resolution_height() as usize);
+        let interval = Interval { min: self.hit_epsilon, max: Float::INFINITY };
+        let pixel_size = self.pixel_delta_u().length();
+
+        // Depth along the view axis and shading of every pixel, ``None`` for misses.
+        let hits: Vec<Option<(Float, Float)>> = (0..width * height).map(|pixel| {
+            let target = self.pixel_coords((pixel % width) as Float, (pixel / width) as Float);
+            let ray = Ray::new(self.center, target - self.center, 0.0);
+            world.hit(&ray, &self.clipped(&ray, interval)).map(|hit_record| {
+                let facing = dot(&hit_record.normal, &ray.direction.unit_vector()).abs();
+                // The direction reaches the focus plane at ``t = 1``.
+                (hit_record.t * self.focus_dist, 0.2 + 0.8 * facing)
+            })
+        }).collect();
+
+        let side = |pixel: usize| hits[pixel].map(|(depth, _)| depth > self.focus_dist);
+        (0..width * height).map(|pixel| {
+            let Some((depth, shade)) = hits[pixel] else { return BACKGROUND; };
+            let (x, y, far) = (pixel % width, pixel / width, depth > self.focus_dist);
+            let crosses = (x + 1 < width && side(pixel + 1) == Some(!far))
+                || (y + 1 < height && side(pixel + width) == Some(!far));
+            if crosses { return FOCUS_PLANE; }
+
+            let grey = Color::splat(shade);
+            let blur = 2.0 * self.defocus_radius * (depth - self.focus_dist).abs() / depth / pixel_size;
+            if blur <= 1.0 {
+                grey * 0.5 + IN_FOCUS * shade * 0.5
+            } else {
+                let tint = (blur / FULL_TINT_BLUR).min(1.0) * 0.6;
+                let color = if far { FAR } else { NEAR };
+                grey * (1.0 - tint) + color * shade * tint
+            }
+        }).collect()
+    }
+
+    /// Draws a schematic of the view from above, into a square image ``size`` pixels a side:
+    /// the bounding boxes of the objects of ``world`` in grey, the camera as a red dot and
+    /// the edges of its view in yellow, out to the far clipping distance if there is one.
+    ///
+    /// This tells at a glance why a render comes out empty, e.g. the camera looking away from
+    /// the objects or clipping them off. The map covers the world and the camera, with ``x``
+    /// to the right and ``z`` downwards, and is quick to make as it traces no rays. Objects
+    /// of infinite extent are left out.
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::vec3d::Point3d;
+    /// let mut world = HittableVec::new();
+    /// world.add(Arc::new(Sphere::static_sphere(Point3d::new(0.0, 0.0, -5.0), 1.0, Material::Empty(Empty {}))));
+    /// let mut camera = Camera::new();
+    /// camera.set_look_from(Point3d::new(0.0, 0.0, 5.0));
+    /// camera.set_look_at(Point3d::zero());
+    /// let minimap = camera.render_minimap(&BVHNode::from_hittable_vec(Arc::new(world)), 64);
+    /// assert_eq!(minimap.len(), 64 * 64);
+    /// assert!(minimap.contains(&Color::new(1.0, 0.2, 0.2)));
+    /// ```
+    pub fn render_minimap(&mut self, world: &BVHNode, size: usize) -> Vec<Color> {
+        const BACKGROUND: Color = Color::new(0.05, 0.05, 0.05);
+        const FILL: Color = Color::new(0.15, 0.15, 0.2);
+        const OUTLINE: Color = Color::new(0.6, 0.6, 0.6);
+        const VIEW: Color = Color::new(1.0, 0.9, 0.1);
+        const CAMERA: Color = Color::new(1.0, 0.2, 0.2);
+        // Share of the map left around the world and the camera.
+        const MARGIN: Float = 0.1;
+
+        self.initialize();
+        let boxes: Vec<AABB> = world.objects().iter()
+            .map(|object| object.bounding_box())
+            .filter(|bbox| !bbox.is_empty() && (0..3).all(|axis| bbox.axis_interval(axis).size().is_finite()))
+            .collect();
+        let bounds = boxes.iter().fold(AABB::EMPTY.grow(&self.look_from), |bounds, bbox| AABB::surrounding_box(&bounds, bbox));
+        let (x, z) = (bounds.axis_interval(0), bounds.axis_interval(2));
+        let extent = x.size().max(z.size()).max(Float::EPSILON) * (1.0 + 2.0 * MARGIN);
+        let scale = size as Float / extent;
+        let center = ((x.min + x.max) / 2.0, (z.min + z.max) / 2.0);
+        let to_pixel = |point: Point3d| {
+            ((point.x() - center.0) * scale + size as Float / 2.0, (point.z() - center.1) * scale + size as Float / 2.0)
+        };
+
+        let mut image = vec![BACKGROUND; size * size];
+        for bbox in &boxes {
+            let (x, z) = (bbox.axis_interval(0), bbox.axis_interval(2));
+            let (left, top) = to_pixel(Point3d::new(x.min, 0.0, z.min));
+            let (right, bottom) = to_pixel(Point3d::new(x.max, 0.0, z.max));
+            let clamp = |value: Float| (value.max(0.0) as usize).min(size);
+            for row in clamp(top)..clamp(bottom) {
+                image[row * size + clamp(left)..row * size + clamp(right)].fill(FILL);
+            }
+        }
+        for bbox in &boxes {
+            let (x, z) = (bbox.axis_interval(0), bbox.axis_interval(2));
+            let corners = [(x.min, z.min), (x.max, z.min), (x.max, z.max), (x.min, z.max)]
+                .map(|(x, z)| to_pixel(Point3d::new(x, 0.0, z)));
+            for i in 0..4 {
+                draw_line(&mut image, size, corners[i], corners[(i + 1) % 4], OUTLINE);
+            }
+        }
+
+        // The edges through the corners of the viewport, from the near to the far distance.
+        let forward = -self.w();
+        let (near, far) = self.clipping.unwrap_or((0.0, extent * 2.0));
+        let corners = [Vec3d::zero(), self.viewport_u, self.viewport_u + self.viewport_v, self.viewport_v]
+            .map(|corner| self.viewport_upper_left() + corner - self.center);
+        let ends = corners.map(|direction| {
+            let speed = dot(&direction, &forward);
+            (to_pixel(self.center + direction * (near / speed)), to_pixel(self.center + direction * (far / speed)))
+        });
+        for i in 0..4 {
+            draw_line(&mut image, size, ends[i].0, ends[i].1, VIEW);
+            if self.clipping.is_some() {
+                draw_line(&mut image, size, ends[i].0, ends[(i + 1) % 4].0, VIEW);
+                draw_line(&mut image, size, ends[i].1, ends[(i + 1) % 4].1, VIEW);
+            }
+        }
+        let (eye_x, eye_y) = to_pixel(self.center);
+        for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx as Float, dy as Float))) {
+            draw_line(&mut image, size, (eye_x + dx, eye_y + dy), (eye_x + dx, eye_y + dy), CAMERA);
+        }
+        image
+    }
+
+    /// Renders the six faces of a cubemap around ``center``, each ``size`` pixels a side, in
+    /// one call, e.g. to bake an environment map of the scene, see ``cubemap``.
+    ///
+    /// Each face is seen through a pinhole with a field of view of ``90`` degrees, with every
+    /// other setting of the camera, e.g. its samples and background, kept as it is.
+    pub fn render_cubemap<H: Hittable>(&self, world: &H, center: Point3d, size: i32) -> Cubemap {
+        let faces = CubeFace::ALL.map(|face| {
+            let mut camera = self.clone();
+            camera.set_look_from(center);
+            camera.set_look_at(center + face.direction());
+            camera.set_v_up(face.up());
+            camera.set_v_fov(90.0);
+            camera.set_aspect_ratio(1.0);
+            camera.set_resolution_width(size);
+            camera.set_defocus_angle(0.0);
+            camera.render(world)
+        });
+        Cubemap::new(size.max(0) as usize, faces)
+    }
+
+    /// Renders ``samples`` samples of the pixel in column ``x`` and row ``y`` alone, drawing
+    /// their random numbers from ``seed`` instead of the camera's, and returns the mean.
+    ///
+    /// This is cheap enough to check the radiance of a material or light in unit tests, and
+    /// gives the pixel's value in ``render_output`` for the same seed, see ``set_seed``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::Quad;
+    /// use ray_tracing::object::material::{Material, Lambertian};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// // A white furnace: a diffuse wall under a uniform sky of 1 reflects its albedo.
+    /// let wall = Quad::new(
+    ///     Point3d::new(-10.0, -10.0, -1.0), Vec3d::new(20.0, 0.0, 0.0), Vec3d::new(0.0, 20.0, 0.0),
+    ///     Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+    /// );
+    /// let mut camera = Camera::new();
+    /// camera.set_background_color(Color::splat(1.0));
+    /// camera.set_look_from(Point3d::zero());
+    /// camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+    /// let pixel = camera.render_pixel(&wall, 10, 10, 8, 42);
+    /// assert_eq!(pixel, Color::splat(0.5));
+    /// assert_eq!(camera.render_pixel(&wall, 10, 10, 8, 42), pixel);
+    /// ```
+    pub fn render_pixel<H: Hittable>(&mut self, world: &H, x: usize, y: usize, samples: u32, seed: u64) -> Color {
         self.initialize();
+        let paths = (0..samples).map(|index| self.primary_path(seed, x, y, index)).collect();
+        let total = self.trace_wavefront(paths, world).iter()
+            .fold(Color::zero(), |total, path| total + path.radiance);
+        if samples == 0 { total } else { total / samples as Float }
+    }
 
-        let mut image = vec![
-            Vec3d::new(0.0, 0.0, 0.0);
-            (self.resolution_width() * self.resolution_height()) as usize
-        ];
+    /// The path of sample ``index`` of the pixel in column ``w`` and row ``h``, with the
+    /// random numbers ``seed`` gives it.
+    fn primary_path(&self, seed: u64, w: usize, h: usize, index: u32) -> Path {
+        let pixel = h * self.resolution_width() as usize + w;
+        let mut rng = Pcg32::for_sample(seed, pixel as u64, index as u64);
+        let ray = sampler::with_stream(&mut rng, || {
+            let offset = match self.pixel_sampler {
+                PixelSampler::Random => sampler::rng().random(),
+                PixelSampler::Sobol => sampler::sobol_2d(seed, pixel as u64, index),
+            };
+            self.sample_ray(w as i32, h as i32, offset)
+        });
+        Path::new(ray, pixel, rng)
+    }
 
-        let bar = ProgressBar::new(
-            self.resolution_height() as u64 * self.resolution_width() as u64
+    /// Returns the radiance arriving back along each of ``rays`` out of ``world``, traced like
+    /// the light of camera paths, e.g. to gather the light reaching points of a surface, see
+    /// ``bake``. Rays are traced as they are, so scattered rays should have the kind
+    /// ``RayKind::Reflection`` to see the lighting background.
+    ///
+    /// Ray ``i`` draws its random numbers from the stream of pixel ``i`` of ``seed``, so the
+    /// result only depends on the rays and the seed.
+    pub fn trace_rays<H: Hittable>(&self, world: &H, rays: &[Ray], seed: u64) -> Vec<Color> {
+        let wavefronts: Vec<Vec<Path>> = rays.par_chunks(WAVEFRONT_SIZE).enumerate().map(|(chunk, rays)| {
+            let paths = rays.iter().enumerate().map(|(i, ray)| {
+                let index = chunk * WAVEFRONT_SIZE + i;
+                Path::new(*ray, index, Pcg32::for_sample(seed, index as u64, 0))
+            }).collect();
+            self.trace_wavefront(paths, world)
+        }).collect();
+
+        let mut radiance = vec![Color::zero(); rays.len()];
+        for path in wavefronts.into_iter().flatten() {
+            radiance[path.pixel] = path.radiance;
+        }
+        radiance
+    }
+
+    /// Traces the first ``samples`` samples of each of the ``pixels``, given by column and row,
+    /// and records the points their paths went through, to look at how light gets around the
+    /// scene, see ``raylog``.
+    ///
+    /// The samples draw the same random numbers as when rendering, so the paths are those
+    /// behind the pixels of the image.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::Quad;
+    /// use ray_tracing::object::material::{Material, Metal};
+    /// use ray_tracing::raylog::Termination;
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// // A mirror straight ahead sends every ray back out.
+    /// let mirror = Quad::new(
+    ///     Point3d::new(-10.0, -10.0, -2.0), Vec3d::new(20.0, 0.0, 0.0), Vec3d::new(0.0, 20.0, 0.0),
+    ///     Material::Metal(Metal::new(Color::splat(1.0), 0.0)),
+    /// );
+    /// let mut camera = Camera::new();
+    /// camera.set_resolution_width(4);
+    /// camera.set_aspect_ratio(1.0);
+    /// camera.set_look_from(Point3d::zero());
+    /// camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+    /// let log = camera.record_paths(&mirror, &[(1, 1), (2, 2)], 3);
+    /// assert_eq!(log.paths().len(), 6);
+    /// for path in log.paths() {
+    ///     assert_eq!(path.vertices.len(), 2);
+    ///     assert!((path.vertices[1].z() + 2.0).abs() < 1e-9);
+    ///     assert!(matches!(path.termination, Termination::Escaped(direction) if direction.z() > 0.0));
+    /// }
+    /// ```
+    pub fn record_paths<H: Hittable>(&mut self, world: &H, pixels: &[(usize, usize)], samples: u32) -> RayLog {
+        self.initialize();
+        let width = self.resolution_width() as usize;
+        let paths = pixels.iter()
+            .flat_map(|&(w, h)| (0..samples).map(move |index| (w, h, index)))
+            .map(|(w, h, index)| {
+                let mut path = self.primary_path(self.seed, w, h, index);
+                path.vertices = Some(vec![path.ray.origin]);
+                path
+            })
+            .collect();
+        let mut recorded: Vec<RecordedPath> = self.trace_wavefront(paths, world).into_iter()
+            .map(|path| RecordedPath {
+                pixel: (path.pixel % width, path.pixel / width),
+                vertices: path.vertices.unwrap_or_default(),
+                termination: path.termination,
+            })
+            .collect();
+        // Paths finish in no particular order.
+        recorded.sort_by_key(|path| (path.pixel.1, path.pixel.0));
+        RayLog::new(recorded)
+    }
+
+    /// Renders only the samples numbered ``samples`` out of those of every pixel, e.g.
+    /// ``0..16`` in one process and ``16..32`` in another, into a partial ``Film``.
+    ///
+    /// Every sample draws its own random numbers, see ``set_seed``, so merging the films of
+    /// ranges covering ``0..n`` gives the same image as rendering ``n`` samples per pixel at
+    /// once with the same seed.
+    pub fn render_film<H: Hittable>(&mut self, world: &H, samples: Range<u32>) -> Film {
+        self.render_films(world, samples).0
+    }
+
+    /// Renders like ``render_film``, along with a film for every light group and ones of the
+    /// alpha and the path length of the samples.
+    fn render_films<H: Hittable>(&mut self, world: &H, samples: Range<u32>) -> (Film, Vec<Film>, Film, Film) {
+        self.initialize();
+        let tiles = tile::tiles(
+            self.resolution_width() as usize, self.resolution_height() as usize, self.tile_size, self.tile_order,
         );
+        self.render_tiles(world, samples, tiles)
+    }
+
+    /// Renders like ``render_films``, only the pixels of ``tiles`` though, leaving the rest of
+    /// the films without samples.
+    fn render_tiles<H: Hittable>(&mut self, world: &H, samples: Range<u32>, tiles: Vec<Tile>) -> (Film, Vec<Film>, Film, Film) {
+        let _span = trace::span("camera::render");
+        let start = Instant::now();
+        self.initialize();
+
+        let mut film = Film::new(self.resolution_width() as usize, self.resolution_height() as usize);
+        let mut group_films = vec![film.clone(); self.light_groups.len()];
+        let mut alpha_film = film.clone();
+        let mut length_film = film.clone();
+
+        let total = tiles.iter().map(|tile| (tile.width * tile.height) as u64).sum();
+        let bar = ProgressBar::new(total);
+        if self.progress.is_some() {
+            bar.set_draw_target(ProgressDrawTarget::hidden());
+        }
 
         // Multi threading computation
         let available_threads = thread::available_parallelism().unwrap().get();
         let num_threads = (available_threads as f32 * 0.75) as usize;
 
         let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build().unwrap();
+        trace::event!(
+            info,
+            "Rendering {}x{} pixels, samples {:?} of each, max depth {}, on {} threads",
+            self.resolution_width(), self.resolution_height(), samples,
+            self.max_depth, num_threads,
+        );
         let (tx, rx) = mpsc::channel();
 
-        rayon::scope(|s| {
-            for h in 0..self.resolution_height() {
-                for w in 0..self.resolution_width() {
+        let tile_count = tiles.len();
+        thread::scope(|scope| {
+            // The tasks borrow the world, so they are queued from a scoped thread while this one
+            // collects the tiles as they finish.
+            scope.spawn(|| thread_pool.scope_fifo(|s| {
+                // Tasks are queued first in, first out, so tiles start in the requested order.
+                for tile in tiles {
                     let tx_clone = tx.clone();
                     let camera = self.clone();
+                    let samples = samples.clone();
+
+                    s.spawn_fifo(move |_| {
+                        let pixels: Vec<(usize, usize)> = tile.pixels().collect();
+                        let samples_per_pixel = samples.len();
+                        let mut tile_film = Film::new(tile.width, tile.height);
+                        let mut tile_group_films = vec![tile_film.clone(); camera.light_groups.len()];
+                        let mut tile_alpha_film = tile_film.clone();
+                        let mut tile_length_film = tile_film.clone();
+                        let mut invalid_samples = vec![0; pixels.len()];
+
+                        // Generate the samples of the tile a wavefront at a time.
+                        let sample_count = pixels.len() * samples_per_pixel;
+                        for first in (0..sample_count).step_by(WAVEFRONT_SIZE) {
+                            let paths = (first..sample_count.min(first + WAVEFRONT_SIZE))
+                                .map(|sample| {
+                                    let pixel = sample / samples_per_pixel;
+                                    let (w, h) = pixels[pixel];
+                                    stats::count(Counter::PrimaryRays);
+                                    let index = samples.start + (sample % samples_per_pixel) as u32;
+                                    let mut path = camera.primary_path(camera.seed, w, h, index);
+                                    path.pixel = pixel;
+                                    path
+                                })
+                                .collect();
+                            for path in camera.trace_wavefront(paths, world) {
+                                if camera.diagnostics && !is_valid_radiance(&path.radiance) {
+                                    invalid_samples[path.pixel] += 1;
+                                }
+                                tile_film.add_sample(path.pixel, path.radiance);
+                                for (group, group_film) in tile_group_films.iter_mut().enumerate() {
+                                    group_film.add_sample(path.pixel, path.group(group));
+                                }
+                                tile_alpha_film.add_sample(path.pixel, Color::splat(path.alpha));
+                                tile_length_film.add_sample(path.pixel, Color::splat(path.segments as Float));
+                            }
+                        }
 
-                    thread_pool.spawn(move || {
-                        let mut color = Vec3d::zero();
-                        for _ in 0..camera.samples_per_pixel {
-                            let ray = camera.sample_ray(w, h);
-                            color += Self::ray_color(&ray, world, camera.max_depth, &camera.background_color);
+                        for (pixel, &invalid) in invalid_samples.iter().enumerate() {
+                            if invalid > 0 {
+                                let (w, h) = pixels[pixel];
+                                eprintln!("Pixel ({}, {}) has {} invalid radiance sample(s)", w, h, invalid);
+                                tile_film.fill(pixel, DIAGNOSTICS_COLOR, tile_film.sample_counts()[pixel]);
+                            }
                         }
-                        tx_clone.send((w, h, color * camera.samples_scale)).unwrap();
+                        tx_clone.send((tile, tile_film, tile_group_films, tile_alpha_film, tile_length_film)).unwrap();
+                        stats::flush();
                     })
                 }
+            }));
+
+            let mut done = 0;
+            let mut last_preview = Instant::now();
+            for _ in 0..tile_count {
+                let (tile, tile_film, tile_group_films, tile_alpha_film, tile_length_film) = rx.recv().unwrap();
+                film.paste(&tile_film, tile.x, tile.y);
+                alpha_film.paste(&tile_alpha_film, tile.x, tile.y);
+                length_film.paste(&tile_length_film, tile.x, tile.y);
+                for (group_film, tile_group_film) in group_films.iter_mut().zip(&tile_group_films) {
+                    group_film.paste(tile_group_film, tile.x, tile.y);
+                }
+                let pixels = (tile.width * tile.height) as u64;
+                done += pixels;
+                bar.inc(pixels);
+                if let Some(progress) = &self.progress {
+                    progress(done, total);
+                }
+                if let Some(preview) = &self.preview {
+                    if done == total || last_preview.elapsed() >= preview.interval() {
+                        // A preview failing to write is no reason to give up on the render.
+                        if let Err(error) = preview.write(&film.image(), self.resolution_width(), self.resolution_height()) {
+                            eprintln!("Could not write the preview: {}", error);
+                        }
+                        last_preview = Instant::now();
+                    }
+                }
             }
         });
+        bar.finish_and_clear();
+        stats::add_time(Counter::RenderNanos, start.elapsed());
+        trace::event!(info, "Rendered image in {:?}", start.elapsed());
+        stats::flush();
+        (film, group_films, alpha_film, length_film)
+    }
 
+    /// Where ``point`` shows in the image, as the column and row from its top left corner in
+    /// pixels, or ``None`` behind the camera.
+    fn project(&self, point: &Point3d) -> Option<(Float, Float)> {
+        let offset = *point - self.center;
+        let depth = -dot(&offset, &self.w());
+        if depth <= 0.0 { return None; }
+        let on_viewport = self.center + offset * (self.focus_dist / depth) - self.viewport_upper_left();
+        Some((
+            dot(&on_viewport, &self.viewport_u) / self.viewport_u.length_squared() * self.resolution_width() as Float,
+            dot(&on_viewport, &self.viewport_v) / self.viewport_v.length_squared() * self.resolution_height() as Float,
+        ))
+    }
 
-        for _ in 0..(self.resolution_height() * self.resolution_width()) {
-            let (w, h, color) = rx.recv().unwrap();
-            image[(h * self.resolution_width() + w) as usize] = color;
-            bar.inc(1);
+    /// The pixels ``bbox`` may show in, as a tile of the rectangle around it with a pixel to
+    /// spare, or ``None`` when it is all behind the camera. A box reaching behind the camera
+    /// may show anywhere.
+    fn screen_bounds(&self, bbox: &AABB) -> Option<Tile> {
+        let (width, height) = (self.resolution_width() as usize, self.resolution_height() as usize);
+        let whole = Tile { x: 0, y: 0, width, height };
+        let corners = (0..8).map(|corner| {
+            let bound = |axis: usize| {
+                let interval = bbox.axis_interval(axis);
+                if corner & (1 << axis) == 0 { interval.min } else { interval.max }
+            };
+            self.project(&Point3d::new(bound(0), bound(1), bound(2)))
+        });
+        let mut visible = 0;
+        let (mut min, mut max) = ((Float::INFINITY, Float::INFINITY), (Float::NEG_INFINITY, Float::NEG_INFINITY));
+        for corner in corners {
+            let Some((column, row)) = corner else { continue };
+            if !(column.is_finite() && row.is_finite()) { return Some(whole); }
+            visible += 1;
+            min = (min.0.min(column), min.1.min(row));
+            max = (max.0.max(column), max.1.max(row));
         }
-        bar.finish_and_clear();
-        image
+        match visible {
+            0 => None,
+            8 => {
+                // Samples of a pixel spread over the pixel after it, see ``sample_ray``.
+                let first = |at: Float, size: usize| ((at - 1.5).floor().max(0.0) as usize).min(size);
+                let last = |at: Float, size: usize| ((at + 0.5).ceil().max(0.0) as usize).min(size);
+                let (x, y) = (first(min.0, width), first(min.1, height));
+                let tile = Tile { x, y, width: last(max.0, width) - x, height: last(max.1, height) - y };
+                (tile.width > 0 && tile.height > 0).then_some(tile)
+            }
+            _ => Some(whole),
+        }
+    }
+
+    /// The tiles of the image that may look different in ``after`` than in ``before``: those
+    /// showing the bounding box of an object in one of the worlds but not in the other.
+    /// Objects are the same when the worlds share them, built once and cloned into both.
+    ///
+    /// This is conservative for what the camera sees directly, not for the light the objects
+    /// throw around: a changed object's shadow or reflection elsewhere stays as it was.
+    pub fn changed_tiles(&mut self, before: &BVHNode, after: &BVHNode) -> Vec<Tile> {
+        self.initialize();
+        let missing_from = |world: &BVHNode, object: &Arc<dyn Hittable>| {
+            !world.objects().iter().any(|other| Arc::ptr_eq(other, object))
+        };
+        let changed: Vec<Tile> = before.objects().iter().filter(|object| missing_from(after, object))
+            .chain(after.objects().iter().filter(|object| missing_from(before, object)))
+            .filter_map(|object| self.screen_bounds(&object.bounding_box()))
+            .collect();
+        tile::tiles(self.resolution_width() as usize, self.resolution_height() as usize, self.tile_size, self.tile_order)
+            .into_iter()
+            .filter(|tile| changed.iter().any(|region| region.overlaps(tile)))
+            .collect()
+    }
+
+    /// Updates ``film``, rendered from ``before`` with these settings, to show ``after`` by
+    /// rendering only the ``changed_tiles`` again, with all samples. Returns the tiles
+    /// rendered, or fails when the film is not of the size of the image.
+    ///
+    /// During interactive edits this keeps the loop short: moving one object only renders the
+    /// few tiles around where it was and where it is.
+    pub fn rerender_changed(&mut self, film: &mut Film, before: &BVHNode, after: &BVHNode) -> Result<Vec<Tile>> {
+        self.initialize();
+        let (width, height) = (self.resolution_width() as usize, self.resolution_height() as usize);
+        if (film.width(), film.height()) != (width, height) {
+            return Err(Error::SizeMismatch { expected: width * height, found: film.width() * film.height() });
+        }
+        let tiles = self.changed_tiles(before, after);
+        if tiles.is_empty() { return Ok(tiles); }
+        let (rendered, ..) = self.render_tiles(after, 0..self.samples_per_pixel.max(0) as u32, tiles.clone());
+        for tile in &tiles {
+            film.copy_tile(&rendered, tile);
+        }
+        Ok(tiles)
     }
 }
 
+
+#[cfg(test)]
+mod test_camera {
+    use super::*;
+
+    /// Traces a single path from ``ray``, with fresh random numbers on every call.
+    fn trace<H: Hittable>(camera: &Camera, ray: &Ray, world: &H) -> Color {
+        let rng = Pcg32::new(rand::random(), rand::random());
+        camera.trace_wavefront(vec![Path::new(*ray, 0, rng)], world)[0].radiance
+    }
+
+    #[test]
+    fn test_is_valid_radiance() {
+        assert!(is_valid_radiance(&Color::new(0.0, 0.5, 10.0)));
+        assert!(!is_valid_radiance(&Color::new(Float::NAN, 0.5, 10.0)));
+        assert!(!is_valid_radiance(&Color::new(0.0, Float::INFINITY, 10.0)));
+        assert!(!is_valid_radiance(&Color::new(0.0, 0.5, -1.0)));
+    }
+
+    #[test]
+    fn test_offset_ray_origin() {
+        let mut camera = Camera::new();
+        camera.set_hit_epsilon(0.5);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+
+        let reflected = Ray::new(Point3d::zero(), Vec3d::new(1.0, 1.0, 0.0), 0.0);
+        let reflected = camera.offset_ray_origin(reflected, &normal);
+        assert_eq!(reflected.origin, Point3d::new(0.0, 0.5, 0.0));
+        assert_eq!(reflected.direction, Vec3d::new(1.0, 1.0, 0.0));
+
+        let refracted = Ray::new(Point3d::zero(), Vec3d::new(1.0, -1.0, 0.0), 0.0);
+        let refracted = camera.offset_ray_origin(refracted, &normal);
+        assert_eq!(refracted.origin, Point3d::new(0.0, -0.5, 0.0));
+    }
+
+    #[test]
+    fn test_guide_through_portals() {
+        use crate::object::material::{Material, Lambertian, Metal};
+
+        let mut camera = Camera::new();
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 1.0), Vec3d::new(0.0, -1.0, -1.0), 0.0);
+        let scattered = Ray::new(Point3d::zero(), Vec3d::new(0.0, 1.0, 0.0), 0.0);
+
+        let diffuse = Material::Lambertian(Lambertian::new(Color::splat(0.5)));
+        hit_record.material = &diffuse;
+        let (_, weight) = camera.guide_through_portals(&ray_in, &hit_record, scattered);
+        assert_eq!(weight, 1.0);
+
+        camera.add_portal(Portal::new(
+            Point3d::new(-1.0, 2.0, -1.0), Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 2.0),
+        ));
+        for _ in 0..32 {
+            // Only half of the rays come from the material, so weights stay in ``[0, 2]``.
+            let (_, weight) = camera.guide_through_portals(&ray_in, &hit_record, scattered);
+            assert!((0.0..=2.0).contains(&weight));
+        }
+
+        let mirror = Material::Metal(Metal::new(Color::splat(0.5), 0.0));
+        hit_record.material = &mirror;
+        let (ray, weight) = camera.guide_through_portals(&ray_in, &hit_record, scattered);
+        assert_eq!((ray.direction, weight), (scattered.direction, 1.0));
+    }
+
+    #[test]
+    fn test_photographic_lens() {
+        use assert_approx_eq::assert_approx_eq;
+
+        let mut camera = Camera::new();
+        camera.set_focal_length_mm(50.0);
+        assert_approx_eq!(camera.v_fov, 2.0 * (0.24 as Float).atan().to_degrees());
+        assert_approx_eq!(camera.focal_length_mm(), 50.0);
+
+        // A 50mm lens at f/2 has a 25mm aperture.
+        camera.set_f_number(2.0);
+        camera.set_focus_dist(2.0);
+        camera.initialize();
+        assert_approx_eq!(camera.defocus_radius, 0.0125);
+
+        camera.set_defocus_angle(0.0);
+        assert_eq!(camera.f_number(), None);
+        camera.initialize();
+        assert_eq!(camera.defocus_radius, 0.0);
+    }
+
+    #[test]
+    fn test_per_material_max_bounces() {
+        use crate::object::material::{Lambertian, Dielectric};
+
+        let mut camera = Camera::new();
+        camera.set_depth(4);
+        let diffuse = Material::Lambertian(Lambertian::new(Color::splat(0.5)));
+        let glass = Material::Dielectric(Dielectric::new(1.5).with_max_bounces(16));
+
+        let mut bounces = Bounces::default();
+        for _ in 0..10 {
+            bounces = bounces.after(&glass);
+        }
+        assert!(!camera.exhausted(&bounces, &glass));
+        // Glass bounces don't use up the depth shared by other materials.
+        assert!(!camera.exhausted(&bounces, &diffuse));
+
+        for _ in 0..4 {
+            bounces = bounces.after(&diffuse);
+        }
+        assert!(camera.exhausted(&bounces, &diffuse));
+        assert!(!camera.exhausted(&bounces, &glass));
+        for _ in 0..6 {
+            bounces = bounces.after(&glass);
+        }
+        assert!(camera.exhausted(&bounces, &glass));
+    }
+
+    #[test]
+    fn test_trace_wavefront() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::Light;
+
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::splat(0.25));
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-1.0, -1.0, -2.0), Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 2.0, 0.0),
+            Material::Light(Light::from_color(Color::splat(4.0))),
+        )));
+
+        // Every other path misses the light, and paths keep the pixel they belong to.
+        let paths = (0..100)
+            .map(|pixel| {
+                let direction = if pixel % 2 == 0 { Vec3d::new(0.0, 0.0, -1.0) } else { Vec3d::new(0.0, 0.0, 1.0) };
+                Path::new(Ray::new(Point3d::zero(), direction, 0.0), pixel, Pcg32::for_sample(0, pixel as u64, 0))
+            })
+            .collect();
+        let finished = camera.trace_wavefront(paths, &world);
+        assert_eq!(finished.len(), 100);
+        for path in finished {
+            let expected = if path.pixel % 2 == 0 { Color::splat(4.0) } else { Color::splat(0.25) };
+            assert_eq!(path.radiance, expected);
+        }
+
+        camera.set_depth(0);
+        let ray = Ray::new(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(trace(&camera, &ray, &world), Color::zero());
+    }
+
+    #[test]
+    fn test_light_sampling_is_unbiased() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::{Lambertian, Light};
+
+        // A small light above a diffuse floor.
+        let light = Arc::new(Quad::new(
+            Point3d::new(-0.25, 1.0, -0.25), Vec3d::new(0.5, 0.0, 0.0), Vec3d::new(0.0, 0.0, 0.5),
+            Material::Light(Light::from_color(Color::splat(4.0))),
+        ));
+        let mut world = HittableVec::new();
+        world.add(light.clone());
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-5.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 10.0), Vec3d::new(10.0, 0.0, 0.0),
+            Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+
+        let ray = Ray::new(Point3d::new(0.0, 0.5, 1.0), Vec3d::new(0.0, -0.5, -1.0), 0.0);
+        let mean = |camera: &Camera| {
+            let samples = 20000;
+            (0..samples).map(|_| trace(camera, &ray, &world).g()).sum::<Float>() / samples as Float
+        };
+
+        let mut camera = Camera::new();
+        camera.set_depth(2);
+        let scattered_only = mean(&camera);
+        camera.add_light(light);
+        let with_lights = mean(&camera);
+        assert!(scattered_only > 0.1);
+        assert!((with_lights - scattered_only).abs() < 0.02, "{} vs {}", with_lights, scattered_only);
+    }
+
+    #[test]
+    fn test_light_sampling_on_last_bounce() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::{Lambertian, Light};
+
+        // A light as wide as the floor, so scattered rays find it as often as light samples do.
+        let light = Arc::new(Quad::new(
+            Point3d::new(-5.0, 0.5, -5.0), Vec3d::new(10.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 10.0),
+            Material::Light(Light::from_color(Color::splat(1.0))),
+        ));
+        let mut world = HittableVec::new();
+        world.add(light.clone());
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-5.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 10.0), Vec3d::new(10.0, 0.0, 0.0),
+            Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+
+        let ray = Ray::new(Point3d::new(0.0, 0.25, 0.0), Vec3d::new(0.0, -1.0, -0.1), 0.0);
+        let mean = |camera: &Camera| {
+            let samples = 50000;
+            (0..samples).map(|_| trace(camera, &ray, &world).g()).sum::<Float>() / samples as Float
+        };
+
+        let mut camera = Camera::new();
+        camera.set_depth(2);
+        let scattered_only = mean(&camera);
+        // The scattered ray of the only bounce is cut, light samples stand in for it in full.
+        camera.add_light(light);
+        camera.set_depth(1);
+        let last_bounce = mean(&camera);
+        assert!(scattered_only > 0.1);
+        assert!((last_bounce - scattered_only).abs() < 0.05, "{} vs {}", last_bounce, scattered_only);
+    }
+
+    #[test]
+    fn test_orbit_keeps_distance_and_stops_at_the_pole() {
+        let mut camera = Camera::new();
+        camera.set_look_from(Point3d::new(1.0, 0.0, 4.0));
+        camera.set_look_at(Point3d::new(1.0, 0.0, 0.0));
+
+        camera.orbit(30.0, 45.0);
+        assert!((camera.focal_length() - 4.0).abs() < 1e-9);
+        // Half way up, and turned towards ``+x``.
+        let offset = (camera.look_from() - camera.look_at()).unit_vector();
+        assert!((offset.y() - (0.5 as Float).sqrt()).abs() < 1e-9);
+        assert!(offset.x() > 0.0);
+
+        camera.orbit(0.0, 120.0);
+        assert!(camera.look_from().y() < 4.0 && camera.look_from().y() > 3.99);
+        // Back down past the horizon, the direction around the up vector is kept.
+        camera.orbit(0.0, -135.0);
+        let offset = (camera.look_from() - camera.look_at()).unit_vector();
+        assert!(offset.y() < 0.0 && offset.x() > 0.0);
+        assert!((camera.focal_length() - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_frame_fits_the_narrower_view() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Empty;
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(Point3d::new(0.0, 1.0, 0.0), 1.0, Material::Empty(Empty {}))));
+        let mut camera = Camera::new();
+        camera.set_look_from(Point3d::new(3.0, 1.0, 0.0));
+        camera.set_look_at(Point3d::zero());
+
+        // The bounding box of the sphere reaches a sphere of radius sqrt(3), seen at 90 degrees.
+        camera.set_aspect_ratio(2.0);
+        camera.frame(&world, 90.0, 0.0);
+        let expected = (3.0 as Float).sqrt() / (45.0 as Float).to_radians().sin();
+        assert!((camera.focal_length() - expected).abs() < 1e-9);
+        assert!((camera.look_at() - Point3d::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((camera.look_from() - camera.look_at()).unit_vector().x() > 0.9);
+
+        // Portrait, the horizontal view of half the height limits the distance.
+        camera.set_aspect_ratio(0.5);
+        camera.frame(&world, 90.0, 0.5);
+        let expected = 1.5 * (3.0 as Float).sqrt() / (0.5 as Float).atan().sin();
+        assert!((camera.focal_length() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_focus_preview_bands() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::Empty;
+
+        // A floor running away from the camera, in focus a few units ahead.
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-50.0, -1.0, 1.0), Vec3d::new(100.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, -100.0), Material::Empty(Empty {}),
+        )));
+        let mut camera = Camera::new();
+        camera.set_resolution_width(40);
+        camera.set_aspect_ratio(1.0);
+        camera.set_v_fov(90.0);
+        camera.set_look_from(Point3d::zero());
+        camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+        camera.set_focus_dist(4.0);
+        camera.set_defocus_angle(10.0);
+        let preview = camera.render_focus_preview(&world);
+
+        let column = |y: usize| preview[y * 40 + 20];
+        // The bottom rows see the floor close by, the rows near the horizon far away.
+        assert!(column(39).b() > column(39).r());
+        assert!(column(21).r() > column(21).b());
+        // Looking down at 14 degrees the floor is 4 units ahead, where the focus plane is.
+        let plane = (0..40).filter(|&y| column(y) == Color::new(1.0, 0.9, 0.1)).collect::<Vec<_>>();
+        assert!(!plane.is_empty() && plane.iter().all(|&y| (24..28).contains(&y)), "{:?}", plane);
+        assert_eq!(column(0), Color::splat(0.05));
+    }
+
+    #[test]
+    fn test_minimap_shows_boxes_and_view() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Empty;
+
+        // A ball from -1 to 1 and a camera at z = 8 looking along -z, away from it at first.
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}))));
+        let world = BVHNode::from_hittable_vec(Arc::new(world));
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(1.0);
+        camera.set_v_fov(20.0);
+        camera.set_look_from(Point3d::new(0.0, 0.0, 8.0));
+        camera.set_look_at(Point3d::new(0.0, 0.0, 9.0));
+        // The map spans 10.8 units around z = 3.5, so a unit is 10 pixels and z = 0 is row 19.
+        let minimap = camera.render_minimap(&world, 108);
+        let pixel = |x: usize, y: usize| minimap[y * 108 + x];
+        assert_eq!(pixel(54, 99), Color::new(1.0, 0.2, 0.2));
+        assert_eq!(pixel(54, 19), Color::new(0.15, 0.15, 0.2));
+        assert_eq!(pixel(44, 19), Color::new(0.6, 0.6, 0.6));
+        assert_eq!(pixel(54, 60), Color::splat(0.05));
+        let view: Vec<usize> = (0..108).filter(|&x| pixel(x, 105) == Color::new(1.0, 0.9, 0.1)).collect();
+        assert!(view.len() == 2 && view[0] < 54 && view[1] > 54, "{:?}", view);
+
+        // Turned around and clipped, the view's far edge closes it short of the ball.
+        camera.set_look_at(Point3d::zero());
+        camera.set_clipping(0.5, 4.0);
+        let minimap = camera.render_minimap(&world, 108);
+        // The near and far edges cross the middle at z = 7.5 and z = 4.
+        let rows: Vec<usize> = (0..108).filter(|&y| minimap[y * 108 + 54] == Color::new(1.0, 0.9, 0.1)).collect();
+        assert!(rows.len() == 2 && rows[0].abs_diff(59) <= 1 && rows[1].abs_diff(94) <= 1, "{:?}", rows);
+    }
+
+    #[test]
+    fn test_preview_written_while_rendering() {
+        let path = std::env::temp_dir().join("ray_tracing_test_camera_preview.jpg");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let mut camera = Camera::new();
+        camera.set_resolution_width(32);
+        camera.set_aspect_ratio(2.0);
+        camera.set_background_color(Color::splat(0.5));
+        camera.set_progress(|_, _| {});
+        camera.set_preview(Preview::new(path).with_max_width(8));
+        camera.render(&crate::object::HittableVec::new());
+        let (pixels, width, height) = crate::image::read_image(path).unwrap();
+        assert_eq!((width, height), (8, 4));
+        assert!(pixels.iter().all(|pixel| pixel.g() > 0.1));
+    }
+
+    #[test]
+    fn test_cubemap_faces_look_along_their_axes() {
+        /// Colors every direction by its unit vector, shifted to positive values.
+        #[derive(Debug)]
+        struct Directions;
+
+        impl Background for Directions {
+            fn value(&self, direction: &Vec3d) -> Color {
+                let d = direction.unit_vector();
+                Color::new(d.x() + 1.0, d.y() + 1.0, d.z() + 1.0)
+            }
+        }
+
+        let mut camera = Camera::new();
+        camera.set_background(Directions);
+        camera.set_progress(|_, _| {});
+        camera.set_defocus_angle(3.0);
+        camera.set_samples_per_pixel(32);
+        let cubemap = camera.render_cubemap(&crate::object::HittableVec::new(), Point3d::new(5.0, 1.0, 2.0), 9);
+        assert_eq!(cubemap.size(), 9);
+        for face in CubeFace::ALL {
+            let center = cubemap.face(face)[4 * 9 + 4];
+            // The middle pixel looks along the axis to within a pixel, about 0.2 of a unit.
+            let expected = face.direction() + Vec3d::new(1.0, 1.0, 1.0);
+            assert!((0..3).all(|i| (center[i] - expected[i]).abs() < 0.2), "{:?} {:?}", face, center);
+        }
+        // The top of the front face looks up, the bottom of the top face to the front.
+        assert!(cubemap.face(CubeFace::NegativeZ)[4].g() > 1.5);
+        assert!(cubemap.face(CubeFace::PositiveY)[8 * 9 + 4].b() < 0.5);
+        // Corners look diagonally out between three faces, the top left of +x up and to -z.
+        let corner = cubemap.face(CubeFace::PositiveX)[0];
+        assert!(corner.g() > 1.4 && corner.b() < 0.6, "{:?}", corner);
+    }
+
+    #[test]
+    fn test_clipping_cuts_away_for_camera_rays() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::Light;
+
+        // Two lights straight ahead, green in front of red.
+        let wall = |z: Float, color: Color| Arc::new(Quad::new(
+            Point3d::new(-5.0, -5.0, z), Vec3d::new(10.0, 0.0, 0.0), Vec3d::new(0.0, 10.0, 0.0),
+            Material::Light(Light::from_color(color)),
+        ));
+        let mut world = HittableVec::new();
+        world.add(wall(-2.0, Color::new(0.0, 1.0, 0.0)));
+        world.add(wall(-5.0, Color::new(1.0, 0.0, 0.0)));
+        let mut camera = Camera::new();
+        camera.set_look_from(Point3d::zero());
+        camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+        camera.set_background_color(Color::zero());
+        let ray = |kind| Ray::new(Point3d::zero(), Vec3d::new(0.5, 0.0, -1.0), 0.0).with_kind(kind);
+
+        assert_eq!(trace(&camera, &ray(RayKind::Camera), &world), Color::new(0.0, 1.0, 0.0));
+        camera.set_clipping(3.0, 10.0);
+        assert_eq!(trace(&camera, &ray(RayKind::Camera), &world), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(trace(&camera, &ray(RayKind::Reflection), &world), Color::new(0.0, 1.0, 0.0));
+        camera.set_clipping(0.0, 4.0);
+        assert_eq!(trace(&camera, &ray(RayKind::Camera), &world), Color::new(0.0, 1.0, 0.0));
+        camera.set_clipping(2.5, 4.0);
+        assert_eq!(trace(&camera, &ray(RayKind::Camera), &world), Color::zero());
+        camera.clear_clipping();
+        assert_eq!(trace(&camera, &ray(RayKind::Camera), &world), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_light_groups_split_direct_light() {
+        use crate::object::{PointLight, Quad};
+        use crate::object::material::Lambertian;
+
+        // A floor lit by a point light in the ``key`` group and one in no group.
+        let floor = Quad::new(
+            Point3d::new(-5.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 10.0), Vec3d::new(10.0, 0.0, 0.0),
+            Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        );
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::zero());
+        camera.add_light_group("rim");
+        camera.add_light_group("key");
+        camera.add_light_group("key");
+        camera.add_light(Arc::new(PointLight::new(Point3d::new(0.0, 1.0, 0.0), Color::splat(2.0)).with_group("key")));
+        camera.add_light(Arc::new(PointLight::new(Point3d::new(0.0, 2.0, 0.0), Color::splat(4.0))));
+        assert_eq!(camera.light_groups, ["rim", "key"]);
+
+        let ray = Ray::new(Point3d::new(0.0, 1.0, 1.0), Vec3d::new(0.0, -1.0, -1.0), 0.0);
+        let (mut key, mut total) = (Color::zero(), Color::zero());
+        for seed in 0..64 {
+            let path = camera.trace_wavefront(vec![Path::new(ray, 0, Pcg32::new(seed, 0))], &floor).remove(0);
+            assert_eq!(path.group(0), Color::zero());
+            key += path.group(1);
+            total += path.radiance;
+        }
+        // The key light brings part of the light, the light of no group the rest.
+        assert!(key[0] > 0.0 && key[0] < total[0], "{} {}", key, total);
+    }
+
+    #[test]
+    fn test_holdout_cuts_a_hole() {
+        use crate::object::{HittableVec, Quad, Sphere, Visibility, VisibilityFlags};
+        use crate::object::material::{Light, Metal};
+
+        // A holdout ball in front of a light, and a mirror below reflecting it.
+        let mut world = HittableVec::new();
+        let holdout = VisibilityFlags { holdout: true, ..Default::default() };
+        let ball = Sphere::static_sphere(Point3d::new(0.0, 0.0, -3.0), 1.0, Material::Light(Light::from_color(Color::splat(2.0))));
+        world.add(Arc::new(Visibility::new(Arc::new(ball), holdout)));
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-5.0, -2.0, 0.0), Vec3d::new(10.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, -10.0),
+            Material::Metal(Metal::new(Color::splat(1.0), 0.0)),
+        )));
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::splat(0.5));
+        let path = |direction: Vec3d| {
+            let ray = Ray::new(Point3d::zero(), direction, 0.0);
+            camera.trace_wavefront(vec![Path::new(ray, 0, Pcg32::new(1, 0))], &world).remove(0)
+        };
+
+        let straight = path(Vec3d::new(0.0, 0.0, -1.0));
+        assert_eq!((straight.radiance, straight.alpha), (Color::zero(), 0.0));
+        // Seen in the mirror, the ball is an ordinary object.
+        let reflected = path(Vec3d::new(0.0, -2.0, -1.5));
+        assert_eq!((reflected.radiance, reflected.alpha), (Color::splat(2.0), 1.0));
+        let sky = path(Vec3d::new(0.0, 1.0, 0.0));
+        assert_eq!((sky.radiance, sky.alpha), (Color::splat(0.5), 0.0));
+    }
+
+    #[test]
+    fn test_render_pixel_matches_render() {
+        use crate::object::Sphere;
+        use crate::object::material::Lambertian;
+
+        let ball = Sphere::static_sphere(Point3d::new(0.0, 0.0, -3.0), 1.0, Material::Lambertian(Lambertian::new(Color::splat(0.5))));
+        let mut camera = Camera::new();
+        camera.set_resolution_width(8);
+        camera.set_aspect_ratio(1.0);
+        camera.set_samples_per_pixel(4);
+        camera.set_look_from(Point3d::zero());
+        camera.set_look_at(Point3d::new(0.0, 0.0, -1.0));
+        camera.set_seed(7);
+        let image = camera.render_output(&ball).image;
+        for (x, y) in [(0, 0), (3, 4), (7, 6)] {
+            let pixel = camera.render_pixel(&ball, x, y, 4, 7);
+            for c in 0..3 {
+                assert!((pixel[c] - image[y * 8 + x][c]).abs() < 1e-9, "{} {}", pixel, image[y * 8 + x]);
+            }
+        }
+        assert_eq!(camera.render_pixel(&ball, 3, 4, 0, 7), Color::zero());
+    }
+
+    #[test]
+    fn test_medium_inside_dielectric() {
+        use crate::object::{Medium, Sphere};
+        use crate::object::material::Dielectric;
+        use crate::object::texture::SolidColor;
+
+        // Glass not bending rays, filled with smoke absorbing everything it scatters.
+        let glass = |density: Float| {
+            let smoke = Medium::interior(density, Arc::new(SolidColor::new(Color::zero())));
+            let material = Material::Dielectric(Dielectric::new(1.0).with_interior(Arc::new(smoke)));
+            Sphere::static_sphere(Point3d::new(0.0, 0.0, -3.0), 1.0, material)
+        };
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::splat(1.0));
+        let ray = Ray::new(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+
+        assert_eq!(trace(&camera, &ray, &glass(0.0)), Color::splat(1.0));
+        assert_eq!(trace(&camera, &ray, &glass(1e9)), Color::zero());
+        // The light crossing the diameter of ``2`` unscattered.
+        let samples = 4000;
+        let thin = glass(0.5);
+        let passed = (0..samples).map(|_| trace(&camera, &ray, &thin)[0]).sum::<Float>() / samples as Float;
+        assert!((passed - (-1.0 as Float).exp()).abs() < 0.03, "{}", passed);
+    }
+
+    #[test]
+    fn test_pan_and_zoom() {
+        let mut camera = Camera::new();
+        camera.set_v_fov(90.0);
+        camera.set_look_from(Point3d::new(0.0, 0.0, 2.0));
+        camera.set_look_at(Point3d::zero());
+
+        // The view is four units high at the point looked at.
+        camera.pan(0.25, -0.5);
+        assert!((camera.look_at() - Point3d::new(1.0, -2.0, 0.0)).length() < 1e-9);
+        assert!((camera.look_from() - Point3d::new(1.0, -2.0, 2.0)).length() < 1e-9);
+
+        camera.zoom(4.0);
+        assert!((camera.look_from() - Point3d::new(1.0, -2.0, 0.5)).length() < 1e-9);
+        camera.zoom(0.0);
+        camera.zoom(1e9);
+        assert!(camera.look_from().z() > 0.0);
+    }
+
+    #[test]
+    fn test_render_output_variance() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Lambertian;
+
+        let empty: &'static HittableVec = Box::leak(Box::new(HittableVec::new()));
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::new(0.0, 0.0, -1.0), 0.5, Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        let world: &'static HittableVec = Box::leak(Box::new(world));
+
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(1.0);
+        camera.set_resolution_width(4);
+        camera.set_samples_per_pixel(8);
+        camera.set_background_color(Color::splat(0.5));
+
+        // Every sample of the plain background is the same.
+        let output = camera.render_output(empty);
+        assert!(output.variance.iter().all(|&variance| variance == Color::zero()));
+
+        let output = camera.render_output(world);
+        assert_eq!(output.variance.len(), output.image.len());
+        assert!(output.variance.iter().any(|variance| variance.max_component() > 0.0));
+        assert!(output.variance.iter().all(|variance| (0..3).all(|i| variance[i] >= 0.0)));
+    }
+
+    #[test]
+    fn test_render_output_path_lengths() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Lambertian;
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::new(0.0, 0.0, -1.0), 0.5, Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        // Enclosing the camera, so paths leaving the small sphere keep bouncing.
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::zero(), 10.0, Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        let world: &'static HittableVec = Box::leak(Box::new(world));
+
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(1.0);
+        camera.set_resolution_width(4);
+        camera.set_samples_per_pixel(4);
+        camera.set_depth(3);
+        camera.set_progress(|_, _| {});
+
+        // One ray escapes straight away.
+        let output = camera.render_output(&HittableVec::new());
+        assert!(output.path_lengths.iter().all(|&length| length == 1.0));
+
+        // Every bounce traces another ray, until the last hit may not scatter any more.
+        let output = camera.render_output(world);
+        assert_eq!(output.path_lengths.len(), output.image.len());
+        assert!(output.path_lengths.iter().all(|&length| length == 3.0), "{:?}", output.path_lengths);
+    }
+
+    #[test]
+    fn test_sobol_pixel_sampler_stratifies() {
+        use crate::object::HittableVec;
+
+        /// Lit only to the left of the view axis.
+        #[derive(Debug)]
+        struct Half;
+
+        impl Background for Half {
+            fn value(&self, direction: &Vec3d) -> Color {
+                if direction.x() < 0.0 { Color::splat(1.0) } else { Color::zero() }
+            }
+        }
+
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(2.0);
+        camera.set_resolution_width(2);
+        camera.set_samples_per_pixel(16);
+        camera.set_background(Half);
+        camera.set_pixel_sampler(PixelSampler::Sobol);
+        camera.set_progress(|_, _| {});
+        // The samples of the first pixel reach from its center to the edge, half on each side
+        // of it, and exactly half of a stratified set of them lands on the lit side.
+        for seed in 0..4 {
+            camera.set_seed(seed);
+            let image = camera.render(&HittableVec::new());
+            assert_eq!(image[0], Color::splat(0.5), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_rerender_changed_tiles() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Light;
+
+        let light = |x: Float, brightness: Float| -> Arc<dyn Hittable> {
+            Arc::new(Sphere::static_sphere(
+                Point3d::new(x, 0.0, -3.0), 0.5, Material::Light(Light::from_color(Color::splat(brightness))),
+            ))
+        };
+        let world = |objects: &[&Arc<dyn Hittable>]| {
+            let mut world = HittableVec::new();
+            for &object in objects {
+                world.add(object.clone());
+            }
+            BVHNode::from_hittable_vec(Arc::new(world))
+        };
+        let (left, right, moved) = (light(-2.0, 1.0), light(2.0, 1.0), light(1.5, 2.0));
+        let before = world(&[&left, &right]);
+        let after = world(&[&left, &moved]);
+
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(2.0);
+        camera.set_resolution_width(32);
+        camera.set_samples_per_pixel(2);
+        camera.set_tile_size(4);
+        camera.set_progress(|_, _| {});
+
+        // Nothing changed, nothing to render.
+        assert!(camera.changed_tiles(&before, &world(&[&right, &left])).is_empty());
+
+        let mut film = camera.render_film(&before, 0..2);
+        let tiles = camera.rerender_changed(&mut film, &before, &after).unwrap();
+        assert!(!tiles.is_empty() && tiles.len() < 8);
+        assert!(tiles.iter().all(|tile| tile.x >= 16), "{:?}", tiles);
+        // Lights cast nothing on each other, so the result is the render of the new world.
+        assert_eq!(film.image(), camera.render_film(&after, 0..2).image());
+
+        assert!(camera.rerender_changed(&mut Film::new(4, 4), &before, &after).is_err());
+    }
+
+    #[test]
+    fn test_render_matches_across_tiles_and_seeds() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::{Dielectric, Lambertian};
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::new(-0.5, 0.0, -1.5), 0.5, Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::new(0.5, 0.0, -1.5), 0.5, Material::Dielectric(Dielectric::new(1.5)),
+        )));
+        let world: &'static HittableVec = Box::leak(Box::new(world));
+
+        let render = |tile_size, tile_order, seed| {
+            let mut camera = Camera::new();
+            camera.set_aspect_ratio(1.0);
+            camera.set_resolution_width(9);
+            camera.set_samples_per_pixel(4);
+            camera.set_background_color(Color::new(0.5, 0.7, 1.0));
+            camera.set_tile_size(tile_size);
+            camera.set_tile_order(tile_order);
+            camera.set_seed(seed);
+            camera.render(world)
+        };
+        // Splitting the image differently, as when rendering tiles on other machines, changes
+        // nothing; a new seed changes the noise.
+        let reference = render(16, TileOrder::Scanline, 1);
+        assert_eq!(render(2, TileOrder::Hilbert, 1), reference);
+        assert_eq!(render(4, TileOrder::Spiral, 1), reference);
+        assert_ne!(render(16, TileOrder::Scanline, 2), reference);
+    }
+
+    #[test]
+    fn test_merged_sample_ranges_match_full_render() {
+        use crate::object::{HittableVec, Sphere};
+        use crate::object::material::Lambertian;
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Point3d::new(0.0, 0.0, -1.5), 0.5, Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(1.0);
+        camera.set_resolution_width(6);
+        camera.set_samples_per_pixel(5);
+        camera.set_background_color(Color::new(0.5, 0.7, 1.0));
+        camera.set_seed(4);
+
+        let full = camera.render_output(&world);
+        let mut film = camera.render_film(&world, 0..2);
+        film.merge(&camera.render_film(&world, 2..5)).unwrap();
+        assert_eq!(film.sample_counts(), full.sample_counts.as_slice());
+        for (merged, single) in film.image().iter().zip(&full.image) {
+            for i in 0..3 {
+                assert!((merged[i] - single[i]).abs() < 1e-9, "{} vs {}", merged, single);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lighting_background() {
+        use crate::background::SkyGradient;
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::Lambertian;
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-500.0, -1.0, -500.0), Vec3d::new(1000.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 1000.0),
+            Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        let mut camera = Camera::new();
+        camera.set_depth(4);
+        camera.set_background_color(Color::splat(0.1));
+        camera.set_lighting_background(Color::splat(1.0));
+
+        // The floor only ever sees the lighting background, the camera only the backdrop.
+        let down = Ray::new(Point3d::zero(), Vec3d::new(0.0, -1.0, -0.5), 0.0);
+        let lit = trace(&camera, &down, &world);
+        assert!((0..3).all(|i| (lit[i] - 0.5).abs() < 1e-9), "{}", lit);
+        let up = Ray::new(Point3d::zero(), Vec3d::new(0.0, 1.0, 0.0), 0.0);
+        assert_eq!(trace(&camera, &up, &world), Color::splat(0.1));
+
+        camera.set_background(SkyGradient::new(Color::zero(), Color::splat(0.2)));
+        assert_eq!(trace(&camera, &up, &world), Color::splat(0.2));
+        let sideways = Ray::new(Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(trace(&camera, &sideways, &world), Color::splat(0.1));
+    }
+
+    #[test]
+    fn test_render_tiles_cover_image() {
+        use crate::object::HittableVec;
+
+        let world: &'static HittableVec = Box::leak(Box::new(HittableVec::new()));
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let mut camera = Camera::new();
+            camera.set_aspect_ratio(7.0 / 5.0);
+            camera.set_resolution_width(7);
+            camera.set_samples_per_pixel(3);
+            camera.set_background_color(Color::new(0.5, 0.25, 1.0));
+            camera.set_tile_size(3);
+            camera.set_tile_order(order);
+
+            let (image, counts) = camera.render_with_sample_counts(world);
+            assert_eq!(image.len(), 35);
+            for color in image {
+                for i in 0..3 {
+                    assert!((color[i] - [0.5, 0.25, 1.0][i]).abs() < 1e-9);
+                }
+            }
+            assert!(counts.iter().all(|&count| count == 3));
+        }
+    }
+
+    #[test]
+    fn test_atmosphere_hazes_distance() {
+        use crate::object::{HittableVec, Atmosphere};
+
+        let mut camera = Camera::new();
+        camera.set_background_color(Color::new(0.0, 0.0, 1.0));
+        camera.set_atmosphere(Atmosphere::new(Color::splat(0.5), 0.5));
+        let ray = Ray::new(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        // Looking at the empty sky through endless fog only shows the fog.
+        assert_eq!(trace(&camera, &ray, &HittableVec::new()), Color::splat(0.5));
+    }
+
+    #[test]
+    fn test_point_light_lights_floor() {
+        use crate::object::{HittableVec, PointLight, Quad};
+        use crate::object::material::Lambertian;
+
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Quad::new(
+            Point3d::new(-5.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 10.0), Vec3d::new(10.0, 0.0, 0.0),
+            Material::Lambertian(Lambertian::new(Color::splat(0.5))),
+        )));
+        let mut camera = Camera::new();
+        camera.set_depth(2);
+        camera.add_light(Arc::new(PointLight::new(Point3d::new(0.0, 1.0, 0.0), Color::splat(4.0))));
+
+        // Straight below the light, the floor reflects albedo / pi of the arriving intensity.
+        let ray = Ray::new(Point3d::new(0.0, 0.5, 1.0), Vec3d::new(0.0, -0.5, -1.0), 0.0);
+        let color = trace(&camera, &ray, &world);
+        assert!((color.g() - 0.5 / consts::PI * 4.0).abs() < 1e-5, "{}", color.g());
+    }
+
+    #[test]
+    fn test_back_face_modes() {
+        use crate::object::{HittableVec, Quad};
+        use crate::object::material::{Material, Lambertian, Light};
+
+        let camera = Camera::new();
+        let light = Color::splat(4.0);
+        // A wall at z = 0 facing +z, seen from behind, in front of a light facing the camera.
+        let scene = |wall: Material, light_back_face: BackFace| {
+            let mut world = HittableVec::new();
+            world.add(Arc::new(Quad::new(
+                Point3d::new(-1.0, -1.0, 0.0), Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 2.0, 0.0), wall,
+            )));
+            world.add(Arc::new(Quad::new(
+                Point3d::new(-1.0, -1.0, 2.0), Vec3d::new(0.0, 2.0, 0.0), Vec3d::new(2.0, 0.0, 0.0),
+                Material::Light(Light::from_color(light).with_back_face(light_back_face)),
+            )));
+            world
+        };
+        let ray = Ray::new(Point3d::new(0.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 1.0), 0.0);
+        let wall = |back_face| Material::Lambertian(Lambertian::new(Color::splat(0.5)).with_back_face(back_face));
+
+        let world = scene(wall(BackFace::Culled), BackFace::TwoSided);
+        assert_eq!(trace(&camera, &ray, &world), light);
+
+        let world = scene(wall(BackFace::Black), BackFace::TwoSided);
+        assert_eq!(trace(&camera, &ray, &world), Color::zero());
+
+        // Seen from behind, a one-sided light stays dark.
+        let world = scene(wall(BackFace::Culled), BackFace::Black);
+        let reversed = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(trace(&camera, &reversed, &world), Color::zero());
+        assert_eq!(trace(&camera, &ray, &world), light);
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt::Formatter;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Div, DivAssign, Index};
+use rand::Rng;
+use rand::distr::{Distribution, StandardUniform};
+
+use crate::vec3d::{Vec3d, Float};
+
+
+/// The RGB primaries colors are expressed in while rendering.
+///
+/// Light transport works the same in any linear space, but wider gamuts like ACEScg mix
+/// saturated colors more faithfully. Everything entering the render, e.g. texture texels, is
+/// converted into the working space, and images are converted back out of it when written, see
+/// ``image::write_image_with``.
+/// # Examples
+/// ```
+/// use ray_tracing::color::{Color, ColorSpace};
+/// let red = ColorSpace::AcesCg.from_linear_srgb(Color::new(1.0, 0.0, 0.0));
+/// assert!(red.r() < 1.0 && red.g() > 0.0);
+/// let back = ColorSpace::AcesCg.to_linear_srgb(red);
+/// assert!((back.r() - 1.0).abs() < 1e-4 && back.g().abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Rec. 709 primaries with a D65 white point, without the transfer curve.
+    #[default]
+    LinearSrgb,
+    /// The ACES AP1 primaries with a D60 white point, used for rendering in ACES pipelines.
+    AcesCg,
+}
+
+
+/// Luminance of middle grey, which a well exposed image puts its mid-tones at.
+pub const MIDDLE_GREY: Float = 0.18;
+
+
+/// Linear sRGB to ACEScg, including the Bradford adaptation from D65 to D60.
+const SRGB_TO_ACESCG: [[Float; 3]; 3] = [
+    [0.6130974, 0.3395231, 0.0473795],
+    [0.0701937, 0.9163539, 0.0134524],
+    [0.0206156, 0.1095698, 0.8698147],
+];
+
+const ACESCG_TO_SRGB: [[Float; 3]; 3] = [
+    [1.705051, -0.6217921, -0.0832589],
+    [-0.1302564, 1.1408048, -0.0105485],
+    [-0.0240033, -0.128969, 1.1529723],
+];
+
+
+impl ColorSpace {
+    /// Converts a linear sRGB color into this space.
+    pub fn from_linear_srgb(&self, color: Color) -> Color {
+        match self {
+            ColorSpace::LinearSrgb => color,
+            ColorSpace::AcesCg => color.transform(&SRGB_TO_ACESCG),
+        }
+    }
+
+    /// Converts a color of this space into linear sRGB.
+    pub fn to_linear_srgb(&self, color: Color) -> Color {
+        match self {
+            ColorSpace::LinearSrgb => color,
+            ColorSpace::AcesCg => color.transform(&ACESCG_TO_SRGB),
+        }
+    }
+}
+
+
+/// Linear RGB radiance or reflectance.
+///
+/// Unlike positions and directions, colors are never negated or crossed, so ``Color`` only
+/// implements the arithmetic light transport needs. None of the operators clamp, which keeps
+/// high dynamic range values (e.g. emitters brighter than ``1.0``) intact until the image is
+/// written out. Use ``From``/``Into`` to convert from and to ``Vec3d`` when needed.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// let color = Color::new(0.5, 2.0, 0.0) * Color::new(0.5, 0.5, 1.0);
+/// assert_eq!(color, Color::new(0.25, 1.0, 0.0));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Color {
+    rgb: [Float; 3],
+}
+
+
+impl Color {
+    pub const fn new(r: Float, g: Float, b: Float) -> Self {
+        Self { rgb: [r, g, b] }
+    }
+
+    pub const fn zero() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+
+    /// Returns a gray color with every channel set to ``value``.
+    pub const fn splat(value: Float) -> Self {
+        Self::new(value, value, value)
+    }
+
+    pub fn r(&self) -> Float { self.rgb[0] }
+
+    pub fn g(&self) -> Float { self.rgb[1] }
+
+    pub fn b(&self) -> Float { self.rgb[2] }
+
+    /// Returns the color of a black body at ``kelvin``, scaled to a luminance of ``1.0``.
+    ///
+    /// Follows the approximation of the Planckian locus by Kim et al., valid from ``1667`` to
+    /// ``25000`` kelvin, outside of which the temperature is clamped. Candle light is around
+    /// ``1900``, incandescent bulbs ``2700``, noon daylight ``5500`` and overcast sky ``7000``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// let bulb = Color::from_temperature(2700.0);
+    /// assert!(bulb.r() > bulb.g() && bulb.g() > bulb.b());
+    /// assert!((bulb.luminance() - 1.0).abs() < 1e-3);
+    /// ```
+    pub fn from_temperature(kelvin: Float) -> Self {
+        let t = kelvin.clamp(1667.0, 25000.0);
+        let (t2, t3) = (t * t, t * t * t);
+        let x = if t <= 4000.0 {
+            -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+        } else {
+            -3.0258469e9 / t3 + 2.107038e6 / t2 + 0.2226347e3 / t + 0.240390
+        };
+        let (x2, x3) = (x * x, x * x * x);
+        let y = if t <= 2222.0 {
+            -1.1063814 * x3 - 1.3481102 * x2 + 2.1855583 * x - 0.20219683
+        } else if t <= 4000.0 {
+            -0.9549476 * x3 - 1.3741859 * x2 + 2.09137 * x - 0.16748867
+        } else {
+            3.081758 * x3 - 5.873387 * x2 + 3.7511299 * x - 0.37001483
+        };
+
+        // From the chromaticity at a luminance of 1 to linear sRGB, through CIE XYZ.
+        let (cx, cy, cz) = (x / y, 1.0, (1.0 - x - y) / y);
+        let color = Self::new(
+            3.2404542 * cx - 1.5371385 * cy - 0.4985314 * cz,
+            -0.969266 * cx + 1.8760108 * cy + 0.0415560 * cz,
+            0.0556434 * cx - 0.2040259 * cy + 1.0572252 * cz,
+        ).map(|c| c.max(0.0));
+        color / color.luminance()
+    }
+
+    /// Returns the relative luminance using the Rec. 709 / sRGB primaries.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// assert_eq!(Color::splat(2.0).luminance(), 2.0);
+    /// assert_eq!(Color::new(0.0, 1.0, 0.0).luminance(), 0.7152);
+    /// ```
+    pub fn luminance(&self) -> Float {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
+    /// Returns the exposure of the luminance in stops above ``MIDDLE_GREY``, negative below
+    /// it and negative infinity for black.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// assert_eq!(Color::splat(0.36).stops(), 1.0);
+    /// assert_eq!(Color::splat(0.045).stops(), -2.0);
+    /// ```
+    pub fn stops(&self) -> Float {
+        (self.luminance() / MIDDLE_GREY).log2()
+    }
+
+    /// Returns the largest channel value.
+    pub fn max_component(&self) -> Float {
+        self.r().max(self.g()).max(self.b())
+    }
+
+    /// Encodes the linear color with the given gamma, e.g. ``2.0`` as done when writing images.
+    /// Negative and ``NaN`` channels are mapped to ``0.0``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// let color = Color::new(0.25, 4.0, -1.0).to_gamma(2.0);
+    /// assert_eq!(color, Color::new(0.5, 2.0, 0.0));
+    /// ```
+    pub fn to_gamma(&self, gamma: Float) -> Self {
+        self.map(|c| c.max(0.0).powf(1.0 / gamma))
+    }
+
+    /// Decodes a gamma encoded color back to linear space, the inverse of ``to_gamma``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// let color = Color::new(0.5, 2.0, 0.0).to_linear(2.0);
+    /// assert_eq!(color, Color::new(0.25, 4.0, 0.0));
+    /// ```
+    pub fn to_linear(&self, gamma: Float) -> Self {
+        self.map(|c| c.max(0.0).powf(gamma))
+    }
+
+    /// Whether every channel is finite, i.e. neither ``NaN`` nor infinite.
+    pub fn is_finite(&self) -> bool {
+        self.rgb.iter().all(|c| c.is_finite())
+    }
+
+    /// Multiplies the channels by a ``3 x 3`` matrix, e.g. to change the primaries.
+    pub fn transform(&self, matrix: &[[Float; 3]; 3]) -> Self {
+        let row = |r: &[Float; 3]| r[0] * self.r() + r[1] * self.g() + r[2] * self.b();
+        Self::new(row(&matrix[0]), row(&matrix[1]), row(&matrix[2]))
+    }
+
+    #[inline]
+    pub fn map(&self, f: impl Fn(Float) -> Float) -> Self {
+        Self::new(f(self.r()), f(self.g()), f(self.b()))
+    }
+
+    #[inline]
+    fn zip_with(&self, other: &Color, f: impl Fn(Float, Float) -> Float) -> Self {
+        Self::new(f(self.r(), other.r()), f(self.g(), other.g()), f(self.b(), other.b()))
+    }
+}
+
+
+/// Random color with every channel uniformly sampled in ``[0, 1)``.
+impl Distribution<Color> for StandardUniform {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Color {
+        Color::new(rng.random(), rng.random(), rng.random())
+    }
+}
+
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Color[{}, {}, {}]", self.r(), self.g(), self.b())
+    }
+}
+
+
+impl Add<Color> for Color {
+    type Output = Self;
+
+    fn add(self, rhs: Color) -> Self {
+        self.zip_with(&rhs, Add::add)
+    }
+}
+
+
+impl AddAssign<Color> for Color {
+    fn add_assign(&mut self, rhs: Color) {
+        *self = *self + rhs;
+    }
+}
+
+
+impl Mul<Color> for Color {
+    type Output = Self;
+
+    /// Component-wise product, e.g. an attenuation applied to incoming light.
+    fn mul(self, rhs: Color) -> Self {
+        self.zip_with(&rhs, Mul::mul)
+    }
+}
+
+
+impl Mul<Float> for Color {
+    type Output = Self;
+
+    fn mul(self, rhs: Float) -> Self {
+        self.map(|c| c * rhs)
+    }
+}
+
+
+impl Mul<Color> for Float {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Color {
+        rhs * self
+    }
+}
+
+
+impl MulAssign<Color> for Color {
+    fn mul_assign(&mut self, rhs: Color) {
+        *self = *self * rhs;
+    }
+}
+
+
+impl MulAssign<Float> for Color {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+
+impl Div<Float> for Color {
+    type Output = Self;
+
+    fn div(self, rhs: Float) -> Self {
+        self.map(|c| c / rhs)
+    }
+}
+
+
+impl DivAssign<Float> for Color {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Color>>(iter: I) -> Self {
+        iter.fold(Color::zero(), Add::add)
+    }
+}
+
+
+/// Indexing of the ``r``, ``g`` and ``b`` channels.
+impl Index<usize> for Color {
+    type Output = Float;
+
+    fn index(&self, index: usize) -> &Float {
+        &self.rgb[index]
+    }
+}
+
+
+impl From<Vec3d> for Color {
+    fn from(vec: Vec3d) -> Self {
+        Self::new(vec.x(), vec.y(), vec.z())
+    }
+}
+
+
+impl From<Color> for Vec3d {
+    fn from(color: Color) -> Self {
+        Vec3d::new(color.r(), color.g(), color.b())
+    }
+}
+
+
+impl From<[Float; 3]> for Color {
+    fn from(rgb: [Float; 3]) -> Self {
+        Self { rgb }
+    }
+}
+
+
+impl From<Color> for [Float; 3] {
+    fn from(color: Color) -> Self {
+        color.rgb
+    }
+}
+
+
+#[cfg(test)]
+mod test_color {
+    use super::*;
+
+    #[test]
+    fn test_color_space_round_trip() {
+        let color = Color::new(0.2, 0.7, 0.1);
+        let aces = ColorSpace::AcesCg.from_linear_srgb(color);
+        let back = ColorSpace::AcesCg.to_linear_srgb(aces);
+        for i in 0..3 {
+            assert!((back[i] - color[i]).abs() < 1e-5, "{} != {}", back, color);
+        }
+        // White stays white across white points.
+        let white = ColorSpace::AcesCg.from_linear_srgb(Color::splat(1.0));
+        for i in 0..3 {
+            assert!((white[i] - 1.0).abs() < 1e-5, "{}", white);
+        }
+        assert_eq!(ColorSpace::LinearSrgb.from_linear_srgb(color), color);
+    }
+
+    #[test]
+    fn test_color_from_temperature() {
+        // Close to the white point of sRGB, and bluer the hotter.
+        let daylight = Color::from_temperature(6504.0);
+        for i in 0..3 {
+            assert!((daylight[i] - 1.0).abs() < 0.05, "{}", daylight);
+        }
+        let sky = Color::from_temperature(12000.0);
+        assert!(sky.b() > sky.g() && sky.g() > sky.r());
+        assert_eq!(Color::from_temperature(500.0), Color::from_temperature(1667.0));
+        for kelvin in [1667.0, 2222.0, 4000.0, 25000.0] {
+            assert!((Color::from_temperature(kelvin).luminance() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_color_ops() {
+        let color = Color::new(1.0, 2.0, 4.0);
+        assert_eq!(color + Color::splat(1.0), Color::new(2.0, 3.0, 5.0));
+        assert_eq!(color * 0.5, Color::new(0.5, 1.0, 2.0));
+        assert_eq!(0.5 * color, Color::new(0.5, 1.0, 2.0));
+        assert_eq!(color / 2.0, Color::new(0.5, 1.0, 2.0));
+
+        let mut accum = Color::zero();
+        accum += color;
+        accum *= Color::new(2.0, 1.0, 0.0);
+        assert_eq!(accum, Color::new(2.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_hdr_not_clamped() {
+        let light = Color::splat(15.0);
+        assert_eq!(light * Color::splat(0.5), Color::splat(7.5));
+        assert_eq!(light.max_component(), 15.0);
+    }
+
+    #[test]
+    fn test_color_sum() {
+        let colors = vec![Color::splat(1.0), Color::new(0.0, 1.0, 2.0)];
+        assert_eq!(colors.into_iter().sum::<Color>(), Color::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_color_is_finite() {
+        assert!(Color::new(1.0, 0.0, 100.0).is_finite());
+        assert!(!Color::new(Float::NAN, 0.0, 0.0).is_finite());
+        assert!(!Color::new(0.0, Float::INFINITY, 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_color_vec3d_conversion() {
+        let color = Color::from(Vec3d::new(0.1, 0.2, 0.3));
+        assert_eq!(color, Color::new(0.1, 0.2, 0.3));
+        assert_eq!(Vec3d::from(color), Vec3d::new(0.1, 0.2, 0.3));
+        assert_eq!(<[Float; 3]>::from(color), [0.1, 0.2, 0.3]);
+    }
+}
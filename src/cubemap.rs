@@ -0,0 +1,178 @@
+//! The six square views of a cubemap around a point, rendered by ``Camera::render_cubemap`` to
+//! bake environment maps of a scene, e.g. for reflections in a game engine.
+//!
+//! Every face sees a quarter turn of the surroundings along one axis, as a camera with a field
+//! of view of ``90`` degrees would from inside the cube: the sides with ``+y`` up, the top
+//! with ``-z`` at its bottom edge and the bottom with ``-z`` at its top edge. Unfolded into a
+//! ``cross``, the faces meet along their shared edges:
+//!
+//! ```text
+//!       +y
+//!  -x   -z   +x   +z
+//!       -y
+//! ```
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::color::Color;
+//! use ray_tracing::cubemap::{CubeFace, Cubemap};
+//! use ray_tracing::vec3d::Float;
+//! let faces = CubeFace::ALL.map(|face| vec![Color::splat(face as usize as Float); 4]);
+//! let cubemap = Cubemap::new(2, faces);
+//! let (cross, width, height) = cubemap.cross();
+//! assert_eq!((width, height), (8, 6));
+//! // The top left corner is left empty, and the front face is second in the middle row.
+//! assert_eq!(cross[0], Color::zero());
+//! assert_eq!(cross[2 * width + 2], Color::splat(CubeFace::NegativeZ as usize as Float));
+//! ```
+use crate::color::Color;
+use crate::image::{suffixed_path, write_image};
+use crate::vec3d::Vec3d;
+
+
+/// A face of a cubemap, named after the axis it looks along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+
+impl CubeFace {
+    /// All faces, in the order the faces of a ``Cubemap`` are kept in.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX, CubeFace::NegativeX, CubeFace::PositiveY,
+        CubeFace::NegativeY, CubeFace::PositiveZ, CubeFace::NegativeZ,
+    ];
+
+    /// The unit direction the face looks along.
+    pub fn direction(&self) -> Vec3d {
+        match self {
+            CubeFace::PositiveX => Vec3d::new(1.0, 0.0, 0.0),
+            CubeFace::NegativeX => Vec3d::new(-1.0, 0.0, 0.0),
+            CubeFace::PositiveY => Vec3d::new(0.0, 1.0, 0.0),
+            CubeFace::NegativeY => Vec3d::new(0.0, -1.0, 0.0),
+            CubeFace::PositiveZ => Vec3d::new(0.0, 0.0, 1.0),
+            CubeFace::NegativeZ => Vec3d::new(0.0, 0.0, -1.0),
+        }
+    }
+
+    /// The direction that is up in the image of the face, see the module documentation.
+    pub fn up(&self) -> Vec3d {
+        match self {
+            CubeFace::PositiveY => Vec3d::new(0.0, 0.0, 1.0),
+            CubeFace::NegativeY => Vec3d::new(0.0, 0.0, -1.0),
+            _ => Vec3d::new(0.0, 1.0, 0.0),
+        }
+    }
+
+    /// Short name of the face, e.g. ``"px"`` for ``PositiveX``, which ``Cubemap::write_faces``
+    /// names the images after.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            CubeFace::PositiveX => "px",
+            CubeFace::NegativeX => "nx",
+            CubeFace::PositiveY => "py",
+            CubeFace::NegativeY => "ny",
+            CubeFace::PositiveZ => "pz",
+            CubeFace::NegativeZ => "nz",
+        }
+    }
+
+    /// Column and row of the face in the ``Cubemap::cross``, in faces.
+    fn cross_cell(&self) -> (usize, usize) {
+        match self {
+            CubeFace::PositiveY => (1, 0),
+            CubeFace::NegativeX => (0, 1),
+            CubeFace::NegativeZ => (1, 1),
+            CubeFace::PositiveX => (2, 1),
+            CubeFace::PositiveZ => (3, 1),
+            CubeFace::NegativeY => (1, 2),
+        }
+    }
+}
+
+
+/// The six faces of a cubemap, each ``size x size`` linear pixels in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cubemap {
+    size: usize,
+    faces: [Vec<Color>; 6],
+}
+
+
+impl Cubemap {
+    /// A cubemap of the ``faces`` in the order of ``CubeFace::ALL``.
+    /// # Panics
+    /// If a face does not have ``size * size`` pixels.
+    pub fn new(size: usize, faces: [Vec<Color>; 6]) -> Self {
+        assert!(faces.iter().all(|face| face.len() == size * size), "Every face must have {} pixels", size * size);
+        Self { size, faces }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn face(&self, face: CubeFace) -> &[Color] {
+        &self.faces[face as usize]
+    }
+
+    /// All faces packed into one image four faces wide and three high, returned with its
+    /// width and height, see the module documentation. The corners are black.
+    pub fn cross(&self) -> (Vec<Color>, usize, usize) {
+        let (width, height) = (4 * self.size, 3 * self.size);
+        let mut cross = vec![Color::zero(); width * height];
+        for face in CubeFace::ALL {
+            let (column, row) = face.cross_cell();
+            for y in 0..self.size {
+                let start = (row * self.size + y) * width + column * self.size;
+                cross[start..start + self.size].copy_from_slice(&self.face(face)[y * self.size..(y + 1) * self.size]);
+            }
+        }
+        (cross, width, height)
+    }
+
+    /// Writes every face like ``write_image``, named after ``path`` with the suffix of the
+    /// face before the extension, e.g. ``sky_px.png`` for ``sky.png``, and returns the paths.
+    pub fn write_faces(&self, path: &str) -> Vec<String> {
+        CubeFace::ALL.iter().map(|face| {
+            let face_path = suffixed_path(path, &format!("_{}", face.suffix()));
+            write_image(&face_path, &self.faces[*face as usize].to_vec(), self.size as i32, self.size as i32);
+            face_path
+        }).collect()
+    }
+
+    /// Writes the ``cross`` to ``path`` like ``write_image``.
+    pub fn write_cross(&self, path: &str) {
+        let (cross, width, height) = self.cross();
+        write_image(path, &cross, width as i32, height as i32);
+    }
+}
+
+
+#[cfg(test)]
+mod test_cubemap {
+    use super::*;
+    use crate::vec3d::cross;
+
+    #[test]
+    fn test_faces_meet_in_the_cross() {
+        // Image right of every face, as the camera has it, and where its neighbours in the cross are.
+        let right = |face: CubeFace| cross(&face.up(), &-face.direction());
+        for (left, next) in [(CubeFace::NegativeX, CubeFace::NegativeZ), (CubeFace::NegativeZ, CubeFace::PositiveX), (CubeFace::PositiveX, CubeFace::PositiveZ)] {
+            assert_eq!(right(left), next.direction());
+            assert_eq!(left.cross_cell().0 + 1, next.cross_cell().0);
+        }
+        // The top and bottom share their right with the front face, and meet it at its edges.
+        assert_eq!(right(CubeFace::PositiveY), right(CubeFace::NegativeZ));
+        assert_eq!(right(CubeFace::NegativeY), right(CubeFace::NegativeZ));
+        assert_eq!(CubeFace::PositiveY.up(), -CubeFace::NegativeZ.direction());
+        assert_eq!(CubeFace::NegativeY.up(), CubeFace::NegativeZ.direction());
+        assert_eq!(CubeFace::ALL.map(|face| face as usize), [0, 1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,200 @@
+//! Image comparison metrics.
+//!
+//! Useful to check a render against a reference image, or to compare how quickly different
+//! sampling strategies converge. Every function takes the pixels of both images in row-major
+//! order, as returned by ``Camera::render`` or ``image::read_image``.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::color::Color;
+//! use ray_tracing::diff;
+//! let reference = vec![Color::splat(0.5); 4];
+//! let noisy = vec![Color::splat(0.5), Color::splat(0.6), Color::splat(0.4), Color::splat(0.5)];
+//! assert!(diff::mse(&reference, &reference).unwrap() == 0.0);
+//! assert!(diff::psnr(&reference, &noisy).unwrap() > 20.0);
+//! ```
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::vec3d::Float;
+
+
+/// Side length of the square windows SSIM is computed over.
+const SSIM_WINDOW: usize = 8;
+
+/// Stabilizing constants of SSIM for a dynamic range of ``1.0``.
+const SSIM_C1: Float = 0.01 * 0.01;
+const SSIM_C2: Float = 0.03 * 0.03;
+
+
+fn check_size(a: &[Color], b: &[Color]) -> Result<()> {
+    if a.len() != b.len() {
+        return Err(Error::SizeMismatch { expected: a.len(), found: b.len() });
+    }
+    Ok(())
+}
+
+
+/// Mean squared error over all channels of all pixels.
+pub fn mse(a: &[Color], b: &[Color]) -> Result<Float> {
+    check_size(a, b)?;
+    if a.is_empty() { return Ok(0.0); }
+
+    let sum: Float = a.iter().zip(b.iter())
+        .map(|(pa, pb)| (0..3).map(|i| (pa[i] - pb[i]).powi(2)).sum::<Float>())
+        .sum();
+    Ok(sum / (a.len() * 3) as Float)
+}
+
+
+/// Peak signal-to-noise ratio in decibels, for colors in ``[0, 1]``. Identical images yield
+/// infinity.
+pub fn psnr(a: &[Color], b: &[Color]) -> Result<Float> {
+    let mse = mse(a, b)?;
+    Ok(10.0 * (1.0 / mse).log10())
+}
+
+
+/// Mean structural similarity of the luminance of both images, in ``[-1, 1]`` where ``1.0``
+/// means identical.
+///
+/// Computed over ``8 x 8`` windows overlapping by half, or over the whole image if it is
+/// smaller than a window. Fails unless the images are of whole rows of ``width`` pixels.
+pub fn ssim(a: &[Color], b: &[Color], width: usize) -> Result<Float> {
+    check_size(a, b)?;
+    if width == 0 || !a.len().is_multiple_of(width) {
+        return Err(Error::InvalidWidth { width, len: a.len() });
+    }
+    if a.is_empty() { return Ok(1.0); }
+    let height = a.len() / width;
+
+    let window_w = SSIM_WINDOW.min(width);
+    let window_h = SSIM_WINDOW.min(height);
+    let stride = (SSIM_WINDOW / 2).max(1);
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    let mut y = 0;
+    while y + window_h <= height {
+        let mut x = 0;
+        while x + window_w <= width {
+            total += ssim_window(a, b, width, x, y, window_w, window_h);
+            windows += 1;
+            x += stride;
+        }
+        y += stride;
+    }
+    Ok(total / windows as Float)
+}
+
+
+fn ssim_window(
+    a: &[Color],
+    b: &[Color],
+    width: usize,
+    x0: usize,
+    y0: usize,
+    window_w: usize,
+    window_h: usize,
+) -> Float {
+    let samples = || (y0..y0 + window_h)
+        .flat_map(move |y| (x0..x0 + window_w).map(move |x| y * width + x))
+        .map(|i| (a[i].luminance(), b[i].luminance()));
+    let n = (window_w * window_h) as Float;
+
+    let (sum_a, sum_b) = samples().fold((0.0, 0.0), |(sa, sb), (la, lb)| (sa + la, sb + lb));
+    let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+
+    let (var_a, var_b, cov) = samples().fold((0.0, 0.0, 0.0), |(va, vb, c), (la, lb)| {
+        let (da, db) = (la - mean_a, lb - mean_b);
+        (va + da * da, vb + db * db, c + da * db)
+    });
+    let (var_a, var_b, cov) = (var_a / n, var_b / n, cov / n);
+
+    ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * cov + SSIM_C2))
+        / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2))
+}
+
+
+/// Per-pixel error heatmap, ready to be written with ``image::write_image``.
+///
+/// The absolute luminance difference of every pixel is normalized by the largest one and
+/// mapped from black (no error) through red and yellow to white (largest error).
+pub fn heatmap(a: &[Color], b: &[Color]) -> Result<Vec<Color>> {
+    check_size(a, b)?;
+
+    let errors: Vec<Float> = a.iter().zip(b.iter())
+        .map(|(pa, pb)| (pa.luminance() - pb.luminance()).abs())
+        .collect();
+    let max_error = errors.iter().cloned().fold(0.0, Float::max);
+    if max_error <= 0.0 {
+        return Ok(vec![Color::zero(); errors.len()]);
+    }
+
+    Ok(errors.iter().map(|e| heatmap_color(e / max_error)).collect())
+}
+
+
+/// Maps ``value`` in ``[0, 1]`` from black through red and yellow to white, the ramp of
+/// ``heatmap``.
+pub fn heatmap_color(value: Float) -> Color {
+    let t = 3.0 * value.clamp(0.0, 1.0);
+    Color::new(t.min(1.0), (t - 1.0).clamp(0.0, 1.0), (t - 2.0).clamp(0.0, 1.0))
+}
+
+
+#[cfg(test)]
+mod test_diff {
+    use super::*;
+
+    fn gradient(width: usize, height: usize) -> Vec<Color> {
+        (0..width * height).map(|i| Color::splat(i as Float / (width * height) as Float)).collect()
+    }
+
+    #[test]
+    fn test_mse() {
+        let a = vec![Color::zero(), Color::zero()];
+        let b = vec![Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.5)];
+        assert_eq!(mse(&a, &b).unwrap(), (1.0 + 0.25) / 6.0);
+    }
+
+    #[test]
+    fn test_psnr_identical() {
+        let a = gradient(4, 4);
+        assert_eq!(psnr(&a, &a).unwrap(), Float::INFINITY);
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let a = gradient(2, 2);
+        let b = gradient(3, 2);
+        assert!(matches!(mse(&a, &b), Err(Error::SizeMismatch { expected: 4, found: 6 })));
+        assert!(ssim(&a, &b, 2).is_err());
+        assert!(matches!(ssim(&a, &a, 3), Err(Error::InvalidWidth { width: 3, len: 4 })));
+        assert!(matches!(ssim(&a, &a, 8), Err(Error::InvalidWidth { .. })));
+        assert!(ssim(&a, &a, 0).is_err());
+        assert!(heatmap(&a, &b).is_err());
+    }
+
+    #[test]
+    fn test_ssim() {
+        let a = gradient(16, 12);
+        assert!((ssim(&a, &a, 16).unwrap() - 1.0).abs() < 1e-9);
+
+        let inverted: Vec<Color> = a.iter().map(|c| Color::splat(1.0) + *c * -1.0).collect();
+        assert!(ssim(&a, &inverted, 16).unwrap() < 0.0);
+
+        // Smaller than a single window.
+        let small = gradient(3, 2);
+        assert!((ssim(&small, &small, 3).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let a = vec![Color::zero(); 3];
+        let b = vec![Color::zero(), Color::splat(0.5), Color::splat(1.0)];
+        let map = heatmap(&a, &b).unwrap();
+        assert_eq!(map[0], Color::zero());
+        assert_eq!(map[2], Color::splat(1.0));
+        assert_eq!(heatmap(&a, &a).unwrap(), vec![Color::zero(); 3]);
+    }
+}
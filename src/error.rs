@@ -0,0 +1,159 @@
+use std::fmt::Formatter;
+
+use crate::vec3d::Float;
+
+
+/// Errors raised when constructing scene objects from invalid input.
+///
+/// The panicking constructors (``Sphere::static_sphere``, ``Metal::new``, ...) are kept for
+/// quick scene scripts; their ``try_*`` counterparts return this error instead so embedding
+/// applications can recover from bad input or assets.
+#[derive(Debug)]
+pub enum Error {
+    /// A sphere or curve radius that is not strictly positive, or ``NaN``.
+    InvalidRadius(Float),
+    /// A metal fuzz factor outside of ``[0, 1]``, or ``NaN``.
+    InvalidFuzz(Float),
+    /// A transform matrix that cannot be inverted, along with its determinant.
+    SingularMatrix(Float),
+    /// A transform of a light that does more than rotate, move and scale uniformly, along
+    /// with the lengths its axes are scaled to.
+    NonUniformScale([Float; 3]),
+    /// An animation mirroring the object at only one of its ends, which would flatten the
+    /// object on the way from one to the other.
+    MirroredAnimation,
+    /// Two images compared pixel by pixel with different pixel counts.
+    SizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// Pixels of an image that do not split into rows of ``width``.
+    InvalidWidth {
+        width: usize,
+        len: usize,
+    },
+    /// A curve given fewer control points than it needs.
+    TooFewPoints {
+        minimum: usize,
+        found: usize,
+    },
+    /// A mesh triangle referring to a vertex that does not exist.
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    /// A mesh vertex attribute with a different number of values than there are vertices.
+    AttributeCount {
+        attribute: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A file that could not be read.
+    Io {
+        file: String,
+        source: std::io::Error,
+    },
+    /// A malformed line of a model file, numbered from ``1``.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// An image file that could not be opened or decoded.
+    Image {
+        file: String,
+        source: image::ImageError,
+    },
+    /// A render setting, e.g. of a render server request or a scene generator, that is
+    /// unknown or has an invalid value.
+    InvalidSetting {
+        name: String,
+        value: String,
+    },
+    /// A render server request arriving while the server already has this many renders
+    /// waiting or running.
+    QueueFull(usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidRadius(radius) => write!(
+                f, "Radius must be greater than 0, but was {} instead.", radius,
+            ),
+            Error::InvalidFuzz(fuzz) => write!(
+                f, "Fuzz must be between 0.0 and 1.0, but was {} instead.", fuzz,
+            ),
+            Error::SingularMatrix(det) => write!(
+                f, "Transform matrix must be invertible, but its determinant was {} instead.", det,
+            ),
+            Error::NonUniformScale([x, y, z]) => write!(
+                f, "Light transform must only rotate, move and scale uniformly, but scaled its axes to {}, {} and {}.", x, y, z,
+            ),
+            Error::MirroredAnimation => write!(
+                f, "Animation transforms must mirror the object at both ends or at neither.",
+            ),
+            Error::SizeMismatch { expected, found } => write!(
+                f, "Images must have the same size, expected {} pixels but got {} instead.", expected, found,
+            ),
+            Error::TooFewPoints { minimum, found } => write!(
+                f, "Curve needs at least {} control points, but got {} instead.", minimum, found,
+            ),
+            Error::IndexOutOfRange { index, len } => write!(
+                f, "Vertex index {} is out of range for a mesh of {} vertices.", index, len,
+            ),
+            Error::AttributeCount { attribute, expected, found } => write!(
+                f, "Mesh has {} vertices, but {} {} values were given.", expected, found, attribute,
+            ),
+            Error::Io { file, source } => write!(
+                f, "Could not read file {}: {}", file, source,
+            ),
+            Error::Parse { line, message } => write!(
+                f, "Line {}: {}", line, message,
+            ),
+            Error::Image { file, source } => write!(
+                f, "Could not open image file {}: {}", file, source,
+            ),
+            Error::InvalidWidth { width, len } => write!(
+                f, "An image of {} pixels does not split into rows of {} pixels.", len, width,
+            ),
+            Error::InvalidSetting { name, value } => write!(
+                f, "Invalid render setting {}={}", name, value,
+            ),
+            Error::QueueFull(limit) => write!(
+                f, "Too many renders queued, at most {} can wait or run at once.", limit,
+            ),
+        }
+    }
+}
+
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Image { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test_error {
+    use super::*;
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            Error::InvalidRadius(-1.0).to_string(),
+            "Radius must be greater than 0, but was -1 instead.",
+        );
+        assert_eq!(
+            Error::InvalidFuzz(1.5).to_string(),
+            "Fuzz must be between 0.0 and 1.0, but was 1.5 instead.",
+        );
+    }
+}
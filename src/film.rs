@@ -0,0 +1,339 @@
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::tile::Tile;
+use crate::vec3d::Float;
+
+use std::io::{self, Read};
+
+
+/// Per-pixel sums of the samples of a render, before they are averaged into an image.
+///
+/// A film can hold any share of the samples, e.g. the range rendered by one process with
+/// ``Camera::render_film``. Films of the same view and seed with different samples ``merge``
+/// into the film of all of them, averaging each pixel weighted by its sample counts, which
+/// turns a long render into independent jobs for as many processes or machines as there are.
+/// Partial films are moved between them with ``write`` and ``read``.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::film::Film;
+/// let (mut first, mut second) = (Film::new(1, 1), Film::new(1, 1));
+/// first.add_sample(0, Color::splat(1.0));
+/// second.add_sample(0, Color::splat(0.0));
+/// second.add_sample(0, Color::splat(0.5));
+/// first.merge(&second).unwrap();
+/// assert_eq!(first.image(), vec![Color::splat(0.5)]);
+/// assert_eq!(first.sample_counts(), &[3]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    sums: Vec<Color>,
+    /// Sums of the squared samples, for the variance.
+    squares: Vec<Color>,
+    counts: Vec<u32>,
+}
+
+
+/// How many pixels of an image are how bright, in stops of exposure, see
+/// ``Film::luminance_histogram``.
+///
+/// A well exposed render has its bulk around ``0`` stops, i.e. middle grey. Much of it in the
+/// top bins or ``clipped`` means highlights blown out to white; much of it in the bottom bins
+/// means shadows crushed to black, or too few samples to find the light.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::film::LuminanceHistogram;
+/// let histogram = LuminanceHistogram::from_pixels(&[Color::splat(0.18), Color::splat(0.2), Color::splat(3.0), Color::zero()]);
+/// assert_eq!(histogram.count(0), 2);
+/// assert_eq!(histogram.count(4), 1);
+/// assert_eq!(histogram.clipped, 1);
+/// assert_eq!(histogram.count(LuminanceHistogram::MIN_STOP), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuminanceHistogram {
+    /// Pixels of each stop from ``MIN_STOP`` to ``MAX_STOP``, rounded to the nearest one.
+    /// Darker and brighter pixels, including black ones, count into the first and last bin.
+    pub bins: Vec<u32>,
+    /// Pixels brighter than white in any channel, which clip in the written image.
+    pub clipped: u32,
+}
+
+
+impl LuminanceHistogram {
+    /// Lowest stop of the histogram, ten stops below middle grey, hardly above black.
+    pub const MIN_STOP: i32 = -10;
+    /// Highest stop of the histogram, far brighter than white at about ``2.5`` stops.
+    pub const MAX_STOP: i32 = 6;
+
+    pub fn from_pixels(pixels: &[Color]) -> Self {
+        let mut bins = vec![0; (Self::MAX_STOP - Self::MIN_STOP + 1) as usize];
+        let mut clipped = 0;
+        for pixel in pixels {
+            // Black and NaN pixels have no stop to round, so they count as the darkest.
+            let stop = pixel.stops().round().max(Self::MIN_STOP as Float).min(Self::MAX_STOP as Float);
+            let stop = if stop.is_nan() { Self::MIN_STOP } else { stop as i32 };
+            bins[(stop - Self::MIN_STOP) as usize] += 1;
+            if pixel.max_component() > 1.0 { clipped += 1; }
+        }
+        Self { bins, clipped }
+    }
+
+    /// Pixels at ``stop``, zero outside of ``MIN_STOP`` to ``MAX_STOP``.
+    pub fn count(&self, stop: i32) -> u32 {
+        if !(Self::MIN_STOP..=Self::MAX_STOP).contains(&stop) { return 0; }
+        self.bins[(stop - Self::MIN_STOP) as usize]
+    }
+
+    pub fn total(&self) -> u32 {
+        self.bins.iter().sum()
+    }
+
+    /// Share of the pixels at ``stop`` or brighter.
+    pub fn share_above(&self, stop: i32) -> Float {
+        let above: u32 = (stop.max(Self::MIN_STOP)..=Self::MAX_STOP).map(|stop| self.count(stop)).sum();
+        above as Float / self.total().max(1) as Float
+    }
+}
+
+
+/// Leading bytes of a film written by ``Film::write``.
+const MAGIC: &[u8; 8] = b"RTFILM01";
+
+
+/// Bytes of the header of a film file, ``MAGIC`` and the width and height.
+const HEADER_BYTES: u64 = 24;
+
+
+/// Bytes each pixel takes in a film file, the sum and square per channel and the count.
+const PIXEL_BYTES: u64 = 3 * 2 * 8 + 4;
+
+
+impl Film {
+    /// Creates a film without samples, of ``width x height`` pixels.
+    pub fn new(width: usize, height: usize) -> Self {
+        let len = width * height;
+        Self { width, height, sums: vec![Color::zero(); len], squares: vec![Color::zero(); len], counts: vec![0; len] }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    /// Adds a sample of ``radiance`` to the pixel at row-major ``index``.
+    pub fn add_sample(&mut self, index: usize, radiance: Color) {
+        self.sums[index] += radiance;
+        self.squares[index] += radiance * radiance;
+        self.counts[index] += 1;
+    }
+
+    /// Sets the pixel at ``index`` to ``count`` samples all of ``color``, overriding what it
+    /// had, e.g. to flag it in diagnostics mode.
+    pub(crate) fn fill(&mut self, index: usize, color: Color, count: u32) {
+        self.sums[index] = color * count as Float;
+        self.squares[index] = color * color * count as Float;
+        self.counts[index] = count;
+    }
+
+    /// Replaces the pixels covered by ``other`` with its own, ``other`` starting at ``x`` and
+    /// ``y`` from the top left.
+    pub(crate) fn paste(&mut self, other: &Film, x: usize, y: usize) {
+        for row in 0..other.height {
+            let (from, to) = (row * other.width, (y + row) * self.width + x);
+            self.sums[to..to + other.width].copy_from_slice(&other.sums[from..from + other.width]);
+            self.squares[to..to + other.width].copy_from_slice(&other.squares[from..from + other.width]);
+            self.counts[to..to + other.width].copy_from_slice(&other.counts[from..from + other.width]);
+        }
+    }
+
+    /// Replaces the pixels of ``tile`` with those of ``other``, a film of the same size.
+    pub(crate) fn copy_tile(&mut self, other: &Film, tile: &Tile) {
+        for row in tile.y..tile.y + tile.height {
+            let range = row * self.width + tile.x..row * self.width + tile.x + tile.width;
+            self.sums[range.clone()].copy_from_slice(&other.sums[range.clone()]);
+            self.squares[range.clone()].copy_from_slice(&other.squares[range.clone()]);
+            self.counts[range.clone()].copy_from_slice(&other.counts[range]);
+        }
+    }
+
+    /// Adds the samples of ``other`` to this film, failing if their sizes differ.
+    pub fn merge(&mut self, other: &Film) -> Result<()> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(Error::SizeMismatch { expected: self.sums.len(), found: other.sums.len() });
+        }
+        for i in 0..self.sums.len() {
+            self.sums[i] += other.sums[i];
+            self.squares[i] += other.squares[i];
+            self.counts[i] += other.counts[i];
+        }
+        Ok(())
+    }
+
+    /// The mean of the samples of every pixel, black for pixels without any.
+    pub fn image(&self) -> Vec<Color> {
+        self.sums.iter().zip(&self.counts)
+            .map(|(&sum, &count)| if count == 0 { Color::zero() } else { sum / count as Float })
+            .collect()
+    }
+
+    /// The histogram of the exposure of ``image``.
+    pub fn luminance_histogram(&self) -> LuminanceHistogram {
+        LuminanceHistogram::from_pixels(&self.image())
+    }
+
+    /// Samples of every pixel.
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// Estimated variance of every pixel of ``image``, see ``RenderOutput::variance``.
+    pub fn variance(&self) -> Vec<Color> {
+        (0..self.sums.len()).map(|i| variance_of_mean(self.sums[i], self.squares[i], self.counts[i])).collect()
+    }
+
+    /// Writes the film to ``path`` in a binary format only ``read`` understands, keeping the
+    /// sums in double precision so merging loses nothing.
+    // ``Float`` is only ``f64`` without the ``f32`` feature.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write(&self, path: &str) -> Result<()> {
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&(self.width as u64).to_le_bytes());
+        data.extend_from_slice(&(self.height as u64).to_le_bytes());
+        for i in 0..self.sums.len() {
+            for c in 0..3 {
+                data.extend_from_slice(&(self.sums[i][c] as f64).to_le_bytes());
+                data.extend_from_slice(&(self.squares[i][c] as f64).to_le_bytes());
+            }
+            data.extend_from_slice(&self.counts[i].to_le_bytes());
+        }
+        std::fs::write(path, data).map_err(|source| Error::Io { file: path.to_string(), source })
+    }
+
+    /// Reads a film written by ``write``, failing for files whose size does not match the
+    /// width and height in their header.
+    pub fn read(path: &str) -> Result<Self> {
+        let error = |source| Error::Io { file: path.to_string(), source };
+        let invalid = |message| error(io::Error::new(io::ErrorKind::InvalidData, message));
+        let file = std::fs::File::open(path).map_err(error)?;
+        let file_bytes = file.metadata().map_err(error)?.len();
+        let mut file = io::BufReader::new(file);
+        let mut read = |bytes: &mut [u8]| file.read_exact(bytes).map_err(error);
+
+        let mut magic = [0; 8];
+        read(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a film file"));
+        }
+        let mut size = [0; 8];
+        read(&mut size)?;
+        let width = u64::from_le_bytes(size) as usize;
+        read(&mut size)?;
+        let height = u64::from_le_bytes(size) as usize;
+        // Checked before allocating, a corrupt header must not ask for more than the file holds.
+        let payload = width.checked_mul(height)
+            .and_then(|pixels| (pixels as u64).checked_mul(PIXEL_BYTES));
+        if payload != Some(file_bytes.saturating_sub(HEADER_BYTES)) {
+            return Err(invalid("size does not match the width and height"));
+        }
+
+        let mut film = Film::new(width, height);
+        let (mut value, mut count) = ([0; 8], [0; 4]);
+        for i in 0..width * height {
+            let (mut sum, mut square) = ([0.0; 3], [0.0; 3]);
+            for c in 0..3 {
+                read(&mut value)?;
+                sum[c] = f64::from_le_bytes(value) as Float;
+                read(&mut value)?;
+                square[c] = f64::from_le_bytes(value) as Float;
+            }
+            read(&mut count)?;
+            film.sums[i] = Color::from(sum);
+            film.squares[i] = Color::from(square);
+            film.counts[i] = u32::from_le_bytes(count);
+        }
+        Ok(film)
+    }
+}
+
+
+/// Variance of the mean of ``count`` samples per channel, from their ``sum`` and the ``sum``
+/// of their squares. Zero with fewer than two samples, where it cannot be estimated.
+fn variance_of_mean(sum: Color, squares: Color, count: u32) -> Color {
+    if count < 2 { return Color::zero(); }
+    let n = count as Float;
+    // Unbiased sample variance, divided by ``n`` once more for the variance of the mean.
+    let channel = |i: usize| ((squares[i] - sum[i] * sum[i] / n) / (n - 1.0)).max(0.0) / n;
+    Color::new(channel(0), channel(1), channel(2))
+}
+
+
+#[cfg(test)]
+mod test_film {
+    use super::*;
+
+    #[test]
+    fn test_variance_of_mean() {
+        let samples = [Color::new(1.0, 2.0, 0.5), Color::new(3.0, 2.0, 0.5)];
+        let sum = samples.iter().fold(Color::zero(), |sum, &sample| sum + sample);
+        let squares = samples.iter().fold(Color::zero(), |sum, &sample| sum + sample * sample);
+        // Sample variance of 2 in red, halved over the two samples; green and blue are exact.
+        assert_eq!(variance_of_mean(sum, squares, 2), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(variance_of_mean(samples[0], samples[0] * samples[0], 1), Color::zero());
+    }
+
+    #[test]
+    fn test_luminance_histogram() {
+        let mut film = Film::new(4, 1);
+        film.add_sample(0, Color::splat(0.18));
+        // Averaged to two stops below middle grey.
+        film.add_sample(1, Color::splat(0.09));
+        film.add_sample(1, Color::zero());
+        film.add_sample(2, Color::new(2.0, 0.0, 0.0));
+        film.add_sample(3, Color::splat(1e-12));
+        let histogram = film.luminance_histogram();
+        assert_eq!(histogram.bins.len(), 17);
+        assert_eq!((histogram.count(0), histogram.count(-2), histogram.count(1)), (1, 1, 1));
+        assert_eq!(histogram.count(LuminanceHistogram::MIN_STOP), 1);
+        assert_eq!((histogram.total(), histogram.clipped), (4, 1));
+        assert_eq!(histogram.share_above(0), 0.5);
+        assert_eq!(histogram.count(LuminanceHistogram::MAX_STOP + 1), 0);
+    }
+
+    #[test]
+    fn test_merge_size_mismatch() {
+        let mut film = Film::new(2, 2);
+        assert!(matches!(film.merge(&Film::new(3, 1)), Err(Error::SizeMismatch { expected: 4, found: 3 })));
+        assert!(film.merge(&Film::new(2, 2)).is_ok());
+        assert_eq!(film.image(), vec![Color::zero(); 4]);
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let path = std::env::temp_dir().join("ray_tracing_test_film.film");
+        let path = path.to_str().unwrap();
+        let mut film = Film::new(3, 1);
+        film.add_sample(0, Color::new(0.1, 0.2, 0.3));
+        film.add_sample(0, Color::new(1.0, 0.0, 7.5));
+        film.add_sample(2, Color::splat(2.0));
+        film.write(path).unwrap();
+        assert_eq!(Film::read(path).unwrap(), film);
+
+        std::fs::write(path, b"RTFILM01\x03").unwrap();
+        assert!(matches!(Film::read(path), Err(Error::Io { .. })));
+
+        let mut huge = MAGIC.to_vec();
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        huge.extend_from_slice(&2u64.to_le_bytes());
+        std::fs::write(path, &huge).unwrap();
+        assert!(matches!(Film::read(path), Err(Error::Io { .. })));
+
+        let mut truncated = MAGIC.to_vec();
+        truncated.extend_from_slice(&1u64.to_le_bytes());
+        truncated.extend_from_slice(&2u64.to_le_bytes());
+        truncated.extend_from_slice(&[0; PIXEL_BYTES as usize]);
+        std::fs::write(path, &truncated).unwrap();
+        assert!(matches!(Film::read(path), Err(Error::Io { .. })));
+    }
+}
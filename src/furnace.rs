@@ -0,0 +1,109 @@
+//! White furnace tests of materials: a sphere of the material under a uniform white sky.
+//!
+//! Light arriving equally from everywhere, every pixel of the sphere shows the share of it the
+//! material sends back, which can't exceed ``1`` without the material creating energy. A
+//! material reflecting or transmitting everything, like a smooth ``Dielectric``, disappears
+//! into the sky. This catches broken materials, e.g. an over-bright fuzzy metal, at a glance.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::color::Color;
+//! use ray_tracing::furnace::furnace_test;
+//! use ray_tracing::object::material::{Material, Lambertian, Metal};
+//! let report = furnace_test(Material::Lambertian(Lambertian::new(Color::splat(0.8))), 8, 1);
+//! report.assert_conserves_energy(1e-9);
+//! // Rays scattered off a convex sphere never hit it again, so it shows its albedo.
+//! assert!((report.mean.g() - 0.8).abs() < 1e-9);
+//! furnace_test(Material::Metal(Metal::new(Color::splat(1.0), 1.0)), 8, 1).assert_conserves_energy(1e-9);
+//! ```
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::object::Sphere;
+use crate::object::material::Material;
+use crate::vec3d::{Point3d, Float};
+
+
+/// Pixels along each side of the furnace image.
+const RESOLUTION: i32 = 24;
+
+
+/// What ``furnace_test`` saw of the sphere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FurnaceReport {
+    /// Mean of the pixels covered by the sphere, the material's albedo averaged over the angles
+    /// it is seen at.
+    pub mean: Color,
+    /// Brightest value of each channel over those pixels.
+    pub max: Color,
+}
+
+
+impl FurnaceReport {
+    /// Whether no pixel of the sphere is brighter than the sky by more than ``tolerance``.
+    pub fn conserves_energy(&self, tolerance: Float) -> bool {
+        (0..3).all(|c| self.max[c] <= 1.0 + tolerance)
+    }
+
+    /// Panics unless the material ``conserves_energy``.
+    pub fn assert_conserves_energy(&self, tolerance: Float) {
+        assert!(
+            self.conserves_energy(tolerance),
+            "The material reflects more light than it receives: up to {} of a white furnace, mean {}",
+            self.max, self.mean,
+        );
+    }
+}
+
+
+/// Renders a unit sphere of ``material`` under a sky of ``1`` with ``samples`` samples per
+/// pixel drawn from ``seed``, seen whole including its silhouette, where the light grazes it.
+pub fn furnace_test(material: Material, samples: u32, seed: u64) -> FurnaceReport {
+    let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, material);
+    let mut camera = Camera::new();
+    camera.set_resolution_width(RESOLUTION);
+    camera.set_aspect_ratio(1.0);
+    camera.set_samples_per_pixel(samples as i32);
+    camera.set_depth(50);
+    camera.set_look_from(Point3d::new(0.0, 0.0, 4.0));
+    camera.set_look_at(Point3d::zero());
+    camera.set_v_fov(32.0);
+    camera.set_defocus_angle(0.0);
+    camera.set_background_color(Color::splat(1.0));
+    camera.set_seed(seed);
+    camera.set_progress(|_, _| {});
+
+    let output = camera.render_output(&sphere);
+    // Pixels only partly covered by the sphere mix in the sky.
+    let covered: Vec<Color> = output.image.iter().zip(&output.alpha)
+        .filter(|&(_, &alpha)| alpha >= 1.0)
+        .map(|(&pixel, _)| pixel)
+        .collect();
+    let count = covered.len().max(1) as Float;
+    let mean = covered.iter().fold(Color::zero(), |sum, &pixel| sum + pixel) / count;
+    let max = covered.iter().fold(Color::zero(), |max, pixel| {
+        Color::new(max.r().max(pixel.r()), max.g().max(pixel.g()), max.b().max(pixel.b()))
+    });
+    FurnaceReport { mean, max }
+}
+
+
+#[cfg(test)]
+mod test_furnace {
+    use super::*;
+    use crate::object::material::{Coat, Dielectric, Lambertian, Light};
+
+    #[test]
+    fn test_furnace_materials() {
+        // Smooth glass reflects or refracts all light, so it vanishes into the sky.
+        let glass = furnace_test(Material::Dielectric(Dielectric::new(1.5)), 4, 3);
+        assert!((glass.mean.r() - 1.0).abs() < 1e-9 && glass.conserves_energy(1e-9), "{:?}", glass);
+
+        let base = Material::Lambertian(Lambertian::new(Color::splat(0.5)));
+        furnace_test(Material::Coat(Coat::new(base)), 16, 3).assert_conserves_energy(1e-9);
+
+        // A light gives off more than it receives.
+        let light = furnace_test(Material::Light(Light::from_color(Color::splat(2.0))), 1, 3);
+        assert!(!light.conserves_energy(0.5));
+        assert_eq!(light.max, Color::splat(2.0));
+    }
+}
@@ -1,16 +1,181 @@
 use image;
-use Vec3d as Color;
 
-use crate::vec3d::Vec3d;
+use crate::color::{Color, ColorSpace};
+use crate::diff;
+use crate::error::{Error, Result};
 use crate::ray::Interval;
+use crate::vec3d::Float;
 
+use std::time::Duration;
 
-fn linear_to_gamma(value: f64) -> f64 {
-    if value > 0.0 {value.sqrt()} else {0.0}
+
+/// How the linear radiance of a render is mapped onto the displayable range of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputTransform {
+    /// Clips every channel to ``[0, 1]``.
+    #[default]
+    Clip,
+    /// The filmic tone curve of the ACES reference rendering and sRGB output transforms, as
+    /// fitted by Stephen Hill, which rolls highlights off smoothly instead of clipping them.
+    AcesFilmic,
 }
 
 
+/// Linear sRGB to the space of the ACES reference rendering transform fit.
+const ACES_INPUT: [[Float; 3]; 3] = [
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+];
+
+/// Back from the ACES fit to linear sRGB.
+const ACES_OUTPUT: [[Float; 3]; 3] = [
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+];
+
+
+impl OutputTransform {
+    /// Maps a linear sRGB color to a display referred one, still linear.
+    pub fn apply(&self, color: Color) -> Color {
+        match self {
+            OutputTransform::Clip => color,
+            OutputTransform::AcesFilmic => {
+                let fitted = color.transform(&ACES_INPUT).map(|v| {
+                    let a = v * (v + 0.0245786) - 0.000090537;
+                    let b = v * (0.983729 * v + 0.432951) + 0.238081;
+                    a / b
+                });
+                fitted.transform(&ACES_OUTPUT).map(|c| c.clamp(0.0, 1.0))
+            }
+        }
+    }
+}
+
+
+/// Writes linear sRGB ``pixels`` to ``path`` with gamma ``2.0``, clipping them to ``[0, 1]``.
 pub fn write_image(path: &str, pixels: &Vec<Color>, width: i32, height: i32) {
+    write_image_with(path, pixels, width, height, ColorSpace::LinearSrgb, OutputTransform::Clip);
+}
+
+
+/// Writes ``pixels`` rendered in the working space ``color_space`` to ``path``, mapping them to
+/// the displayable range with ``output`` before encoding them with gamma ``2.0``.
+pub fn write_image_with(path: &str, pixels: &[Color], width: i32, height: i32, color_space: ColorSpace, output: OutputTransform) {
+    to_rgb8(pixels, width, height, color_space, output).save(path).unwrap();
+}
+
+
+/// Writes ``pixels`` like ``write_image`` along with their ``alpha``, e.g. ``RenderOutput::alpha``,
+/// so the holes cut by holdouts show the plate the image is composited over.
+pub fn write_image_with_alpha(path: &str, pixels: &[Color], alpha: &[Float], width: i32, height: i32) {
+    let rgb = to_rgb8(pixels, width, height, ColorSpace::LinearSrgb, OutputTransform::Clip);
+    let rgba = image::RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        let a = alpha[(y * width as u32 + x) as usize].clamp(0.0, 1.0);
+        image::Rgba([r, g, b, (a * 255.0).round() as u8])
+    });
+    rgba.save(path).unwrap();
+}
+
+
+/// Encodes linear sRGB ``pixels`` as a PNG file in memory, as ``write_image`` would write it,
+/// e.g. to send a render over the network.
+pub fn encode_png(pixels: &[Color], width: i32, height: i32) -> Vec<u8> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    to_rgb8(pixels, width, height, ColorSpace::default(), OutputTransform::default())
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    png.into_inner()
+}
+
+
+/// A small tone mapped image of a render in progress, written every so often to watch a remote
+/// or headless render by refreshing it in a browser, see ``Camera::set_preview``.
+///
+/// The file is replaced at once, so a refresh never catches it half written.
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::image::{read_image, Preview};
+/// let path = std::env::temp_dir().join("ray_tracing_doc_preview.jpg");
+/// let path = path.to_str().unwrap();
+/// let preview = Preview::new(path).with_max_width(2).with_interval(Duration::from_secs(10));
+/// preview.write(&vec![Color::splat(0.5); 8 * 4], 8, 4).unwrap();
+/// let (_, width, height) = read_image(path).unwrap();
+/// assert_eq!((width, height), (2, 1));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    path: String,
+    interval: Duration,
+    max_width: usize,
+    output: OutputTransform,
+}
+
+
+impl Preview {
+    /// A preview written to ``path``, a ``.jpg`` or ``.webp`` file, every two seconds, at most
+    /// ``480`` pixels wide and tone mapped with ``OutputTransform::AcesFilmic``.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into(), interval: Duration::from_secs(2), max_width: 480, output: OutputTransform::AcesFilmic }
+    }
+
+    /// Sets the time between writes. The finished image is always written too.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the width larger images are scaled down to, by a whole factor.
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width.max(1);
+        self
+    }
+
+    pub fn with_output(mut self, output: OutputTransform) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Writes linear sRGB ``pixels`` to the preview's path, averaged down to the width.
+    pub fn write(&self, pixels: &[Color], width: i32, height: i32) -> Result<()> {
+        let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+        let factor = width.div_ceil(self.max_width);
+        let (small_width, small_height) = (width.div_ceil(factor), height.div_ceil(factor));
+        let small: Vec<Color> = (0..small_width * small_height).map(|index| {
+            let (x, y) = (index % small_width * factor, index / small_width * factor);
+            let (right, bottom) = ((x + factor).min(width), (y + factor).min(height));
+            let sum = (y..bottom).flat_map(|row| &pixels[row * width + x..row * width + right]).copied().sum::<Color>();
+            sum / ((right - x) * (bottom - y)) as Float
+        }).collect();
+
+        let format = image::ImageFormat::from_path(&self.path)
+            .map_err(|source| Error::Image { file: self.path.clone(), source })?;
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        to_rgb8(&small, small_width as i32, small_height as i32, ColorSpace::LinearSrgb, self.output)
+            .write_to(&mut encoded, format)
+            .map_err(|source| Error::Image { file: self.path.clone(), source })?;
+        let partial = format!("{}.part", self.path);
+        let error = |source| Error::Io { file: self.path.clone(), source };
+        std::fs::write(&partial, encoded.into_inner()).map_err(error)?;
+        std::fs::rename(&partial, &self.path).map_err(error)
+    }
+}
+
+
+/// Converts ``pixels`` to 8 bit display colors, see ``write_image_with``.
+fn to_rgb8(pixels: &[Color], width: i32, height: i32, color_space: ColorSpace, output: OutputTransform) -> image::RgbImage {
     let mut img = image::ImageBuffer::new(width as u32, height as u32);
 
     let color_interval = Interval { min: 0.0, max: 0.999 };
@@ -18,18 +183,215 @@ pub fn write_image(path: &str, pixels: &Vec<Color>, width: i32, height: i32) {
     for (x, y, pixel) in img.enumerate_pixels_mut() {
         let index = (y * width as u32 + x) as usize;
 
-        let r = linear_to_gamma(pixels[index].x());
-        let g = linear_to_gamma(pixels[index].y());
-        let b = linear_to_gamma(pixels[index].z());
+        let display = output.apply(color_space.to_linear_srgb(pixels[index]));
+        let color = display.to_gamma(2.0).map(|c| color_interval.clamp(c)) * 256.0;
+
+        *pixel = image::Rgb([color.r() as u8, color.g() as u8, color.b() as u8]);
+    }
+    img
+}
+
 
-        let color = Vec3d::new(
-            color_interval.clamp(r),
-            color_interval.clamp(g),
-            color_interval.clamp(b),
-        ) * 256.0;
+/// Exposures, in stops, written by ``write_exposure_bracket`` by default.
+pub const DEFAULT_BRACKET: [Float; 3] = [-2.0, 0.0, 2.0];
 
-        *pixel = image::Rgb([color.x() as u8, color.y() as u8, color.z() as u8]);
+
+/// Writes the linear ``pixels`` once per exposure in ``stops``, each scaled by ``2^stop``
+/// before the usual gamma and clamping of ``write_image``.
+///
+/// Every image is named after ``path`` with the exposure before the extension, e.g.
+/// ``render_ev-2.png``, ``render_ev+0.png`` and ``render_ev+2.png`` for ``render.png`` and the
+/// ``DEFAULT_BRACKET``. Seeing what clips in the bright image and what drowns in the dark one
+/// helps balance the lighting, and the set can be merged back into an HDR image. Returns the
+/// paths written.
+pub fn write_exposure_bracket(path: &str, pixels: &[Color], width: i32, height: i32, stops: &[Float]) -> Vec<String> {
+    stops.iter().map(|&stop| {
+        let exposed: Vec<Color> = pixels.iter().map(|&pixel| pixel * stop.exp2()).collect();
+        let bracket_path = exposure_path(path, stop);
+        write_image(&bracket_path, &exposed, width, height);
+        bracket_path
+    }).collect()
+}
+
+
+fn exposure_path(path: &str, stop: Float) -> String {
+    suffixed_path(path, &format!("_ev{:+}", stop))
+}
+
+
+/// ``path`` with ``suffix`` before its extension, if it has one.
+pub(crate) fn suffixed_path(path: &str, suffix: &str) -> String {
+    match path.rfind('.') {
+        Some(dot) if !path[dot..].contains(['/', '\\']) => format!("{}{}{}", &path[..dot], suffix, &path[dot..]),
+        _ => format!("{}{}", path, suffix),
+    }
+}
+
+
+/// Writes per-pixel ``values``, e.g. the sample counts of
+/// ``Camera::render_with_sample_counts``, as a heatmap scaled from zero to the largest value
+/// with the ramp of ``diff::heatmap``.
+pub fn write_heatmap<T: Copy + Into<Float>>(path: &str, values: &[T], width: i32, height: i32) {
+    let max = values.iter().map(|&value| value.into()).fold(0.0, Float::max);
+    let pixels: Vec<Color> = values.iter()
+        .map(|&value| if max > 0.0 { value.into() / max } else { 0.0 })
+        .map(diff::heatmap_color)
+        .collect();
+    write_image(path, &pixels, width, height);
+}
+
+
+/// Writes linear ``pixels`` unchanged as a PFM image of 32 bit floats.
+///
+/// Unlike ``write_image`` nothing is clamped or gamma encoded, which is what AOVs such as
+/// ``RenderOutput::variance`` need when handed to a denoiser.
+#[allow(clippy::unnecessary_cast)]
+pub fn write_pfm(path: &str, pixels: &[Color], width: i32, height: i32) -> Result<()> {
+    let mut data = format!("PF\n{} {}\n-1.0\n", width, height).into_bytes();
+    // Rows go from the bottom up, and the negative scale above marks little endian values.
+    for row in pixels.chunks(width as usize).take(height as usize).rev() {
+        for pixel in row {
+            for i in 0..3 {
+                data.extend_from_slice(&(pixel[i] as f32).to_le_bytes());
+            }
+        }
+    }
+    std::fs::write(path, data).map_err(|source| Error::Io { file: path.to_string(), source })
+}
+
+
+/// Reads an image written by ``write_image`` back into linear colors.
+///
+/// Returns the pixels in row-major order along with the width and height, so renders can be
+/// compared against reference images with the ``diff`` module.
+pub fn read_image(path: &str) -> Result<(Vec<Color>, i32, i32)> {
+    let img = image::open(path)
+        .map_err(|source| Error::Image { file: path.to_string(), source })?
+        .to_rgb8();
+
+    let pixels = img.pixels()
+        .map(|p| Color::new(p[0] as Float, p[1] as Float, p[2] as Float) / 255.0)
+        .map(|c| c.to_linear(2.0))
+        .collect();
+    Ok((pixels, img.width() as i32, img.height() as i32))
+}
+
+
+#[cfg(test)]
+mod test_image {
+    use super::*;
+
+    #[test]
+    fn test_preview_averages_down() {
+        let path = std::env::temp_dir().join("ray_tracing_test_preview.webp");
+        let path = path.to_str().unwrap();
+        // White and black columns average to grey, the odd last column is kept as it is.
+        let pixels: Vec<Color> = (0..5 * 2).map(|i| Color::splat(if i % 5 == 4 || i % 2 == 0 { 1.0 } else { 0.0 })).collect();
+        Preview::new(path).with_max_width(3).with_output(OutputTransform::Clip).write(&pixels, 5, 2).unwrap();
+        let (small, width, height) = read_image(path).unwrap();
+        assert_eq!((width, height), (3, 1));
+        assert!((small[0].r() - 0.5).abs() < 0.02 && (small[2].r() - 1.0).abs() < 0.02, "{:?}", small);
+        assert!(!std::path::Path::new(&format!("{}.part", path)).exists());
+
+        let unknown = Preview::new(std::env::temp_dir().join("ray_tracing_test_preview.xyz").to_str().unwrap());
+        assert!(matches!(unknown.write(&pixels, 5, 2), Err(Error::Image { .. })));
+    }
+
+    #[test]
+    fn test_aces_filmic_rolls_off_highlights() {
+        let aces = OutputTransform::AcesFilmic;
+        assert!(aces.apply(Color::zero()).r().abs() < 1e-3);
+        let (mid, bright, blinding) = (aces.apply(Color::splat(0.18)), aces.apply(Color::splat(4.0)), aces.apply(Color::splat(100.0)));
+        assert!(mid.g() < bright.g() && bright.g() < blinding.g());
+        assert!(blinding.g() > 0.95 && blinding.g() <= 1.0);
+        assert_eq!(OutputTransform::Clip.apply(Color::splat(4.0)), Color::splat(4.0));
+    }
+
+    #[test]
+    fn test_write_aces_cg() {
+        let path = std::env::temp_dir().join("ray_tracing_test_aces_cg.png");
+        let path = path.to_str().unwrap();
+        let srgb = vec![Color::new(0.5, 0.25, 0.0)];
+        let aces: Vec<Color> = srgb.iter().map(|&c| ColorSpace::AcesCg.from_linear_srgb(c)).collect();
+        write_image_with(path, &aces, 1, 1, ColorSpace::AcesCg, OutputTransform::Clip);
+
+        let (read, _, _) = read_image(path).unwrap();
+        for i in 0..3 {
+            assert!((read[0][i] - srgb[0][i]).abs() < 0.01, "{} != {}", read[0], srgb[0]);
+        }
+    }
+
+    #[test]
+    fn test_encode_png_matches_write_image() {
+        let path = std::env::temp_dir().join("ray_tracing_test_encode.png");
+        let path = path.to_str().unwrap();
+        let pixels = vec![Color::new(0.25, 0.5, 1.0), Color::splat(2.0)];
+        write_image(path, &pixels, 2, 1);
+        assert_eq!(encode_png(&pixels, 2, 1), std::fs::read(path).unwrap());
+    }
+
+    #[test]
+    fn test_write_pfm() {
+        let path = std::env::temp_dir().join("ray_tracing_test_variance.pfm");
+        let path = path.to_str().unwrap();
+        write_pfm(path, &[Color::new(0.25, 2.0, 0.0), Color::splat(-1.0)], 1, 2).unwrap();
+
+        let data = std::fs::read(path).unwrap();
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&data[..header.len()], header);
+        let values: Vec<f32> = data[header.len()..].chunks(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        // The bottom row comes first.
+        assert_eq!(values, vec![-1.0, -1.0, -1.0, 0.25, 2.0, 0.0]);
+
+        let missing = std::env::temp_dir().join("ray_tracing_missing_directory").join("variance.pfm");
+        assert!(matches!(write_pfm(missing.to_str().unwrap(), &[Color::zero()], 1, 1), Err(Error::Io { .. })));
+    }
+
+    #[test]
+    fn test_write_heatmap() {
+        let path = std::env::temp_dir().join("ray_tracing_test_heatmap.png");
+        let path = path.to_str().unwrap();
+        write_heatmap(path, &[4.0 as Float, 12.0, 8.0, 0.0], 2, 2);
+        let (read, _, _) = read_image(path).unwrap();
+        assert!(read[0].r() > 0.99 && read[0].g() + read[0].b() < 0.01);
+        assert!(read[1].r() > 0.99 && read[1].g() > 0.99 && read[1].b() > 0.99);
+        assert!(read[2].r() > 0.99 && read[2].g() > 0.99 && read[2].b() < 0.01);
+        assert!(read[3].max_component() < 0.01);
     }
 
-    img.save(path).unwrap();
-}
\ No newline at end of file
+    #[test]
+    fn test_exposure_bracket() {
+        assert_eq!(exposure_path("out/render.png", -2.0), "out/render_ev-2.png");
+        assert_eq!(exposure_path("render.png", 0.0), "render_ev+0.png");
+        assert_eq!(exposure_path("render.v1/image", 1.5), "render.v1/image_ev+1.5");
+
+        let path = std::env::temp_dir().join("ray_tracing_test_bracket.png");
+        let pixels = vec![Color::splat(0.125), Color::splat(0.5)];
+        let written = write_exposure_bracket(path.to_str().unwrap(), &pixels, 2, 1, &DEFAULT_BRACKET);
+        assert_eq!(written.len(), 3);
+
+        // Two stops up brings the dark pixel to where the bright one was at the base exposure.
+        let (base, _, _) = read_image(&written[1]).unwrap();
+        let (bright, _, _) = read_image(&written[2]).unwrap();
+        assert!((bright[0].r() - base[1].r()).abs() < 0.01, "{} != {}", bright[0], base[1]);
+        assert!(bright[1].r() > 0.99);
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let path = std::env::temp_dir().join("ray_tracing_test_round_trip.png");
+        let path = path.to_str().unwrap();
+        let pixels = vec![Color::new(0.25, 0.0, 1.0), Color::new(0.5, 0.5, 0.5)];
+        write_image(path, &pixels, 2, 1);
+
+        let (read, width, height) = read_image(path).unwrap();
+        assert_eq!((width, height), (2, 1));
+        for (a, b) in pixels.iter().zip(read.iter()) {
+            for i in 0..3 {
+                assert!((a[i] - b[i]).abs() < 0.01, "{} != {}", a, b);
+            }
+        }
+    }
+}
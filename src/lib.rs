@@ -1,9 +1,28 @@
 
 pub mod vec3d;
+pub mod color;
+pub mod error;
 pub mod image;
+pub mod diff;
+pub mod postprocess;
 pub mod ray;
 pub mod camera;
+pub mod tile;
+pub mod film;
+pub mod background;
+pub mod pdf;
+pub mod sampler;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod stats;
+pub mod tolerance;
+pub mod raylog;
+pub mod furnace;
+pub mod cubemap;
+pub mod bake;
+mod trace;
 
 pub mod object;
+pub mod presets;
 
 pub mod scene;
\ No newline at end of file
@@ -1,16 +1,72 @@
 use ray_tracing::object::BVHNode;
-use ray_tracing::image::write_image;
+use ray_tracing::image::{write_exposure_bracket, write_heatmap, write_image, write_image_with_alpha, write_pfm, Preview, DEFAULT_BRACKET};
+use ray_tracing::postprocess;
+use ray_tracing::sampler::PixelSampler;
 use ray_tracing::scene;
+use ray_tracing::stats;
 use std::time::Instant;
 
 fn main() {
     let (mut camera, world) = scene::quads();
     let world_ref: &'static BVHNode = Box::leak(Box::new(world));
 
+    // ``--focus-preview`` only writes a quick preview of the depth of field, to set it up by.
+    if std::env::args().any(|arg| arg == "--focus-preview") {
+        let preview = camera.render_focus_preview(world_ref);
+        write_image("focus_preview.png", &preview, camera.resolution_width(), camera.resolution_height());
+        return;
+    }
+
+    // ``--minimap`` only writes the view from above, to find out why a render is empty.
+    if std::env::args().any(|arg| arg == "--minimap") {
+        let minimap = camera.render_minimap(world_ref, 512);
+        write_image("minimap.png", &minimap, 512, 512);
+        return;
+    }
+
+    // ``--preview`` keeps ``preview.jpg`` up to date while rendering, to watch from a browser.
+    if std::env::args().any(|arg| arg == "--preview") {
+        camera.set_preview(Preview::new("preview.jpg"));
+    }
+
+    // ``--sobol`` places the samples of each pixel along a scrambled Sobol sequence.
+    if std::env::args().any(|arg| arg == "--sobol") {
+        camera.set_pixel_sampler(PixelSampler::Sobol);
+    }
+
     let now = Instant::now();
-    let image = camera.render(world_ref);
+    let output = camera.render_output(world_ref);
+    let image = output.image;
     let elapsed = now.elapsed();
     println!("Elapsed: {:?}", elapsed);
+    if cfg!(feature = "stats") {
+        println!("{}", stats::snapshot());
+    }
 
+    // ``--bracket`` also writes the image two stops darker and brighter.
+    if std::env::args().any(|arg| arg == "--bracket") {
+        write_exposure_bracket("output.png", &image, camera.resolution_width(), camera.resolution_height(), &DEFAULT_BRACKET);
+    }
+    // ``--variance`` also writes the noise estimate of every pixel, for denoisers.
+    if std::env::args().any(|arg| arg == "--variance") {
+        if let Err(error) = write_pfm("variance.pfm", &output.variance, camera.resolution_width(), camera.resolution_height()) {
+            eprintln!("Could not write the variance: {}", error);
+        }
+    }
+    // ``--path-length`` also writes how many rays the samples of every pixel took, to find
+    // what is worth a lower depth.
+    if std::env::args().any(|arg| arg == "--path-length") {
+        write_heatmap("path_length.png", &output.path_lengths, camera.resolution_width(), camera.resolution_height());
+    }
+    // ``--false-color`` also writes the exposure zones of the image, to judge its exposure by.
+    if std::env::args().any(|arg| arg == "--false-color") {
+        let zones = postprocess::false_color(&image, camera.resolution_width() as usize);
+        write_image("false_color.png", &zones, camera.resolution_width(), camera.resolution_height());
+    }
+    // ``--alpha`` writes the image with its alpha, e.g. for holdouts.
+    if std::env::args().any(|arg| arg == "--alpha") {
+        write_image_with_alpha("output.png", &image, &output.alpha, camera.resolution_width(), camera.resolution_height());
+        return;
+    }
     write_image("output.png", &image, camera.resolution_width(), camera.resolution_height());
 }
\ No newline at end of file
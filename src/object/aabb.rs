@@ -1,5 +1,6 @@
 use crate::ray::{Interval, Ray};
-use crate::vec3d::Vec3d;
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::tolerance;
 use std::ops::{Add, Sub};
 
 
@@ -98,7 +99,7 @@ impl AABB {
     }
 
     fn pad_to_minimum(&mut self) {
-        let min = f32::EPSILON as f64;
+        let min = tolerance::min_padding();
         if self.interval_x.size() < min { self.interval_x = self.interval_x.expand(min); }
         if self.interval_y.size() < min { self.interval_y = self.interval_y.expand(min); }
         if self.interval_z.size() < min { self.interval_z = self.interval_z.expand(min); }
@@ -128,42 +129,136 @@ impl AABB {
         }
     }
 
+    /// Slab test against the ray's cached inverse direction.
+    ///
+    /// The sign bits pick the near and far plane of each slab, so no per-axis division or
+    /// parallel-ray branch is needed: an axis-parallel ray yields infinite slab distances,
+    /// and the ``NaN`` produced by an origin lying exactly on a slab plane is ignored by
+    /// ``min``/``max``, counting the ray as inside that slab.
     pub fn hit(&self, ray: &Ray, interval: &Interval) -> bool {
+        let inv_direction = ray.inv_direction();
+        let sign = ray.sign();
+        let mut t_min = interval.min;
+        let mut t_max = interval.max;
+
         for axis in 0..3 {
             let ax = self.axis_interval(axis);
+            let bounds = [ax.min, ax.max];
             let origin_axis = ray.origin[axis];
-            let ray_dir = ray.direction[axis];
-
-            if ray_dir.abs() < f64::EPSILON {
-                // Ray is parallel to the axis. Check if the origin is within the interval.
-                if origin_axis < ax.min || origin_axis > ax.max {
-                    return false;
-                }
-                continue;
-            }
-
-            let adinv = 1.0 / ray_dir;
-
-            let t0 = (ax.min - origin_axis) * adinv;
-            let t1 = (ax.max - origin_axis) * adinv;
-
-            let interval_hit: Interval = if t0 < t1 {
-                Interval {
-                    min: t0.max(interval.min),
-                    max: t1.min(interval.max),
-                }
-            } else {
-                Interval {
-                    min: t1.max(interval.min),
-                    max: t0.min(interval.max),
-                }
-            };
-
-            if interval_hit.max <= interval_hit.min {
-                return false;
-            }
+
+            let t_near = (bounds[sign[axis]] - origin_axis) * inv_direction[axis];
+            let t_far = (bounds[1 - sign[axis]] - origin_axis) * inv_direction[axis];
+
+            t_min = t_min.max(t_near);
+            t_max = t_max.min(t_far);
+        }
+        t_min < t_max
+    }
+
+    /// Returns the center point of the box.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let aabb = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(2.0, 4.0, 6.0));
+    /// assert_eq!(aabb.center(), Point3d::new(1.0, 2.0, 3.0));
+    /// ```
+    pub fn center(&self) -> Point3d {
+        Point3d::new(
+            (self.interval_x.min + self.interval_x.max) * 0.5,
+            (self.interval_y.min + self.interval_y.max) * 0.5,
+            (self.interval_z.min + self.interval_z.max) * 0.5,
+        )
+    }
+
+    /// Returns the center and radius of the smallest sphere around the box, of radius ``0.0``
+    /// around the origin if the box is empty.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let aabb = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(2.0, 4.0, 4.0));
+    /// assert_eq!(aabb.bounding_sphere(), (Point3d::new(1.0, 2.0, 2.0), 3.0));
+    /// ```
+    pub fn bounding_sphere(&self) -> (Point3d, Float) {
+        if self.is_empty() { return (Point3d::zero(), 0.0); }
+        let half_diagonal = Vec3d::new(self.interval_x.size(), self.interval_y.size(), self.interval_z.size()) / 2.0;
+        (self.center(), half_diagonal.length())
+    }
+
+    /// Returns the surface area of the box, or ``0.0`` if it is empty.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let aabb = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(1.0, 2.0, 3.0));
+    /// assert_eq!(aabb.surface_area(), 22.0);
+    /// assert_eq!(AABB::EMPTY.surface_area(), 0.0);
+    /// ```
+    pub fn surface_area(&self) -> Float {
+        if self.is_empty() { return 0.0; }
+        let x = self.interval_x.size();
+        let y = self.interval_y.size();
+        let z = self.interval_z.size();
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    /// Whether the point lies inside the box, boundaries included.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let aabb = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(1.0, 1.0, 1.0));
+    /// assert!(aabb.contains(&Point3d::new(0.5, 1.0, 0.0)));
+    /// assert!(!aabb.contains(&Point3d::new(0.5, 1.5, 0.0)));
+    /// ```
+    pub fn contains(&self, point: &Point3d) -> bool {
+        (0..3).all(|axis| self.axis_interval(axis).contains(point[axis]))
+    }
+
+    /// Returns the overlap of two boxes, which is empty if they do not intersect.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let box1 = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(2.0, 2.0, 2.0));
+    /// let box2 = AABB::from_points(&Point3d::new(1.0, 1.0, 1.0), &Point3d::new(3.0, 3.0, 3.0));
+    /// let overlap = box1.intersection(&box2);
+    /// assert_eq!(overlap, AABB::from_points(&Point3d::new(1.0, 1.0, 1.0), &Point3d::new(2.0, 2.0, 2.0)));
+    ///
+    /// let far = AABB::from_points(&Point3d::new(5.0, 5.0, 5.0), &Point3d::new(6.0, 6.0, 6.0));
+    /// assert!(box1.intersection(&far).is_empty());
+    /// ```
+    pub fn intersection(&self, other: &AABB) -> Self {
+        // Built directly so an empty overlap is not padded back into a valid box.
+        Self {
+            interval_x: self.interval_x.intersection(&other.interval_x),
+            interval_y: self.interval_y.intersection(&other.interval_y),
+            interval_z: self.interval_z.intersection(&other.interval_z),
         }
-        true
+    }
+
+    /// Returns the box enlarged to also enclose the given point.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::AABB;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let aabb = AABB::from_points(&Point3d::new(0.0, 0.0, 0.0), &Point3d::new(1.0, 1.0, 1.0))
+    ///     .grow(&Point3d::new(2.0, -1.0, 0.5));
+    /// assert_eq!(aabb, AABB::from_points(&Point3d::new(0.0, -1.0, 0.0), &Point3d::new(2.0, 1.0, 1.0)));
+    /// ```
+    pub fn grow(&self, point: &Point3d) -> Self {
+        let grow = |a: Interval, t: Float| Interval { min: a.min.min(t), max: a.max.max(t) };
+        Self::new(
+            grow(self.interval_x, point.x()),
+            grow(self.interval_y, point.y()),
+            grow(self.interval_z, point.z()),
+        )
+    }
+
+    /// Whether the box encloses no point at all, like ``AABB::EMPTY``.
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.axis_interval(axis).is_empty())
     }
 
     pub fn longest_axis(&self) -> usize {
@@ -197,6 +292,7 @@ impl AABB {
 #[cfg(test)]
 mod test_aabb {
     use super::*;
+    use assert_approx_eq::assert_approx_eq;
 
     #[test]
     fn test_aabb_new() {
@@ -362,6 +458,73 @@ mod test_aabb {
         assert!(!aabb.hit(&ray, &Interval { min: 0.0, max: 10.0 }));
     }
 
+    #[test]
+    fn test_aabb_hit_negative_direction() {
+        let aabb = AABB::new(
+            Interval { min: -1.0, max: 1.0 },
+            Interval { min: -1.0, max: 1.0 },
+            Interval { min: -1.0, max: 1.0 },
+        );
+
+        let ray = Ray::new(
+            Vec3d::new(5.0, 0.5, 0.5),
+            Vec3d::new(-1.0, 0.0, 0.0),
+            0.0,
+        );
+
+        assert!(aabb.hit(&ray, &Interval { min: 0.0, max: 10.0 }));
+        assert!(!aabb.hit(&ray, &Interval { min: 0.0, max: 3.0 }));
+    }
+
+    #[test]
+    fn test_aabb_hit_origin_on_slab() {
+        let aabb = AABB::new(
+            Interval { min: -1.0, max: 1.0 },
+            Interval { min: -1.0, max: 1.0 },
+            Interval { min: -1.0, max: 1.0 },
+        );
+
+        // Parallel to the x slab while lying exactly on its plane.
+        let ray = Ray::new(
+            Vec3d::new(1.0, -5.0, 0.0),
+            Vec3d::new(0.0, 1.0, 0.0),
+            0.0,
+        );
+
+        assert!(aabb.hit(&ray, &Interval { min: 0.0, max: 10.0 }));
+    }
+
+    #[test]
+    fn test_aabb_is_empty() {
+        assert!(AABB::EMPTY.is_empty());
+        assert!(!AABB::UNIVERSE.is_empty());
+        assert!(!AABB::from_points(&Vec3d::zero(), &Vec3d::zero()).is_empty());
+    }
+
+    #[test]
+    fn test_aabb_grow_empty() {
+        let aabb = AABB::EMPTY
+            .grow(&Point3d::new(1.0, 2.0, 3.0))
+            .grow(&Point3d::new(-1.0, 4.0, 5.0));
+        // The box holding only the first point gets padded, hence the tolerance.
+        assert_approx_eq!(aabb.interval_x.min, -1.0, 1e-4);
+        assert_approx_eq!(aabb.interval_x.max, 1.0, 1e-4);
+        assert_approx_eq!(aabb.interval_y.min, 2.0, 1e-4);
+        assert_approx_eq!(aabb.interval_y.max, 4.0, 1e-4);
+        assert_approx_eq!(aabb.interval_z.min, 3.0, 1e-4);
+        assert_approx_eq!(aabb.interval_z.max, 5.0, 1e-4);
+    }
+
+    #[test]
+    fn test_aabb_intersection_disjoint() {
+        let box1 = AABB::from_points(&Vec3d::new(0.0, 0.0, 0.0), &Vec3d::new(1.0, 1.0, 1.0));
+        let box2 = AABB::from_points(&Vec3d::new(2.0, 0.0, 0.0), &Vec3d::new(3.0, 1.0, 1.0));
+        let overlap = box1.intersection(&box2);
+        assert!(overlap.is_empty());
+        assert_eq!(overlap.surface_area(), 0.0);
+        assert!(!overlap.contains(&Vec3d::new(1.5, 0.5, 0.5)));
+    }
+
     #[test]
     fn test_aabb_add_vec3d() {
         let aabb = AABB::new(
@@ -0,0 +1,411 @@
+//! Boolean operations on closed meshes, computed once when loading a scene so the result is an
+//! ordinary ``Mesh`` of any size instead of a shape combined on every ray.
+//!
+//! Both meshes are split along each other's triangles with BSP trees, as in Evan Wallace's
+//! csg.js, and the pieces on the right side of the other mesh are kept. Vertex attributes are
+//! interpolated where triangles are cut, and kept if both meshes have them.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::object::{BooleanOp, MeshData};
+//! use ray_tracing::vec3d::Point3d;
+//! // A tetrahedron, and the same moved half its size along x.
+//! let tetrahedron = |x| MeshData {
+//!     positions: vec![
+//!         Point3d::new(x, 0.0, 0.0), Point3d::new(x + 1.0, 0.0, 0.0),
+//!         Point3d::new(x, 1.0, 0.0), Point3d::new(x, 0.0, 1.0),
+//!     ],
+//!     triangles: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+//!     ..Default::default()
+//! };
+//! let both = tetrahedron(0.0).boolean(&tetrahedron(0.5), BooleanOp::Union);
+//! assert!(both.triangles.len() > 4);
+//! assert!(both.positions.iter().all(|p| (0.0..=1.5).contains(&p.x())));
+//! ```
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
+use crate::color::Color;
+use crate::object::MeshData;
+
+
+/// How ``MeshData::boolean`` combines two closed meshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    /// Everything inside either mesh.
+    Union,
+    /// Only what is inside both meshes.
+    Intersection,
+    /// What is inside the first mesh but not the second.
+    Difference,
+}
+
+
+impl MeshData {
+    /// Combines the solid enclosed by this mesh with the one enclosed by ``other``.
+    ///
+    /// Both meshes must be closed, with their triangles facing outwards. The result shares no
+    /// vertices between triangles and takes ``flat_shading`` from this mesh.
+    pub fn boolean(&self, other: &MeshData, op: BooleanOp) -> MeshData {
+        let extent = self.positions.iter().chain(&other.positions)
+            .map(|p| p.x().abs().max(p.y().abs()).max(p.z().abs()))
+            .fold(1.0, Float::max);
+        // How far from a plane a vertex still counts as on it.
+        let epsilon = 1e-5 * extent;
+
+        let mut a = Bsp::new(epsilon);
+        a.build(polygons(self));
+        let mut b = Bsp::new(epsilon);
+        b.build(polygons(other));
+
+        match op {
+            BooleanOp::Union => {
+                a.clip_to(&b);
+                b.clip_to(&a);
+                // Drop the faces the two meshes share, keeping one copy.
+                b.invert();
+                b.clip_to(&a);
+                b.invert();
+                a.build(b.all_polygons());
+            }
+            BooleanOp::Intersection => {
+                a.invert();
+                b.clip_to(&a);
+                b.invert();
+                a.clip_to(&b);
+                b.clip_to(&a);
+                a.build(b.all_polygons());
+                a.invert();
+            }
+            BooleanOp::Difference => {
+                a.invert();
+                a.clip_to(&b);
+                b.clip_to(&a);
+                b.invert();
+                b.clip_to(&a);
+                b.invert();
+                a.build(b.all_polygons());
+                a.invert();
+            }
+        }
+
+        let mut result = MeshData { flat_shading: self.flat_shading, ..Default::default() };
+        let (normals, uvs, colors) = (
+            !self.normals.is_empty() && !other.normals.is_empty(),
+            !self.uvs.is_empty() && !other.uvs.is_empty(),
+            !self.colors.is_empty() && !other.colors.is_empty(),
+        );
+        for polygon in a.all_polygons() {
+            let first = result.positions.len();
+            for vertex in &polygon.vertices {
+                result.positions.push(vertex.position);
+                if normals { result.normals.push(vertex.normal); }
+                if uvs { result.uvs.push(vertex.uv); }
+                if colors { result.colors.push(vertex.color); }
+            }
+            for i in 1..polygon.vertices.len() - 1 {
+                result.triangles.push([first, first + i, first + i + 1]);
+            }
+        }
+        result
+    }
+}
+
+
+/// The triangles of ``mesh`` as polygons, leaving out degenerate ones.
+fn polygons(mesh: &MeshData) -> Vec<Polygon> {
+    mesh.triangles.iter()
+        .filter_map(|triangle| {
+            let vertices = triangle.map(|i| Vertex {
+                position: mesh.positions[i],
+                normal: mesh.normals.get(i).copied().unwrap_or(Vec3d::zero()),
+                uv: mesh.uvs.get(i).copied().unwrap_or((0.0, 0.0)),
+                color: mesh.colors.get(i).copied().unwrap_or(Color::zero()),
+            });
+            Polygon::new(vertices.to_vec())
+        })
+        .collect()
+}
+
+
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Point3d,
+    normal: Vec3d,
+    uv: (Float, Float),
+    color: Color,
+}
+
+
+impl Vertex {
+    fn lerp(&self, other: &Vertex, t: Float) -> Vertex {
+        Vertex {
+            position: self.position + (other.position - self.position) * t,
+            normal: self.normal + (other.normal - self.normal) * t,
+            uv: (self.uv.0 + (other.uv.0 - self.uv.0) * t, self.uv.1 + (other.uv.1 - self.uv.1) * t),
+            color: self.color * (1.0 - t) + other.color * t,
+        }
+    }
+}
+
+
+/// The plane of points ``p`` with ``dot(normal, p) == w``.
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vec3d,
+    w: Float,
+}
+
+
+/// A convex planar polygon, counter-clockwise seen from the side its plane faces.
+#[derive(Debug, Clone)]
+struct Polygon {
+    vertices: Vec<Vertex>,
+    plane: Plane,
+}
+
+
+impl Polygon {
+    /// The polygon through ``vertices``, ``None`` if the first three are on a line.
+    fn new(vertices: Vec<Vertex>) -> Option<Self> {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[i].position);
+        let normal = cross(&(b - a), &(c - a));
+        if normal.length_squared() == 0.0 { return None; }
+        let normal = normal.unit_vector();
+        Some(Self { vertices, plane: Plane { normal, w: dot(&normal, &a) } })
+    }
+
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        for vertex in &mut self.vertices {
+            vertex.normal = -vertex.normal;
+        }
+        self.plane = Plane { normal: -self.plane.normal, w: -self.plane.w };
+    }
+}
+
+
+const COPLANAR: u8 = 0;
+const FRONT: u8 = 1;
+const BACK: u8 = 2;
+const SPANNING: u8 = 3;
+
+
+impl Plane {
+    /// Sorts ``polygon`` by the side of the plane it is on, splitting it in two if it spans
+    /// the plane. Polygons in the plane go to ``coplanar_front`` or ``coplanar_back`` by the
+    /// way they face.
+    fn split(
+        &self,
+        polygon: Polygon,
+        epsilon: Float,
+        coplanar_front: &mut Vec<Polygon>,
+        coplanar_back: &mut Vec<Polygon>,
+        front: &mut Vec<Polygon>,
+        back: &mut Vec<Polygon>,
+    ) {
+        let sides: Vec<u8> = polygon.vertices.iter()
+            .map(|vertex| {
+                let distance = dot(&self.normal, &vertex.position) - self.w;
+                if distance < -epsilon { BACK } else if distance > epsilon { FRONT } else { COPLANAR }
+            })
+            .collect();
+
+        match sides.iter().fold(COPLANAR, |all, side| all | side) {
+            COPLANAR if dot(&self.normal, &polygon.plane.normal) > 0.0 => coplanar_front.push(polygon),
+            COPLANAR => coplanar_back.push(polygon),
+            FRONT => front.push(polygon),
+            BACK => back.push(polygon),
+            _ => {
+                let (mut in_front, mut behind) = (Vec::new(), Vec::new());
+                let count = polygon.vertices.len();
+                for i in 0..count {
+                    let j = (i + 1) % count;
+                    let (vi, vj) = (&polygon.vertices[i], &polygon.vertices[j]);
+                    if sides[i] != BACK { in_front.push(*vi); }
+                    if sides[i] != FRONT { behind.push(*vi); }
+                    if sides[i] | sides[j] == SPANNING {
+                        let t = (self.w - dot(&self.normal, &vi.position))
+                            / dot(&self.normal, &(vj.position - vi.position));
+                        let cut = vi.lerp(vj, t);
+                        in_front.push(cut);
+                        behind.push(cut);
+                    }
+                }
+                // The pieces lie in the plane of the whole, so keep it instead of recomputing
+                // it from vertices that may now be close together.
+                for (vertices, list) in [(in_front, front), (behind, back)] {
+                    if vertices.len() >= 3 {
+                        list.push(Polygon { vertices, plane: polygon.plane });
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+/// A node of a ``Bsp``, splitting space by the plane of its polygons.
+struct Node {
+    plane: Plane,
+    polygons: Vec<Polygon>,
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+
+/// A BSP tree of polygons, stored as a list of nodes so none of its operations recurse, which
+/// large meshes would overflow the stack with.
+struct Bsp {
+    nodes: Vec<Node>,
+    epsilon: Float,
+}
+
+
+impl Bsp {
+    fn new(epsilon: Float) -> Self {
+        Self { nodes: Vec::new(), epsilon }
+    }
+
+    fn add_node(&mut self, plane: Plane) -> usize {
+        self.nodes.push(Node { plane, polygons: Vec::new(), front: None, back: None });
+        self.nodes.len() - 1
+    }
+
+    /// Adds ``polygons`` to the tree, splitting them by the planes they cross.
+    fn build(&mut self, polygons: Vec<Polygon>) {
+        if polygons.is_empty() { return; }
+        if self.nodes.is_empty() {
+            self.add_node(polygons[0].plane);
+        }
+        let mut work = vec![(0, polygons)];
+        while let Some((node, polygons)) = work.pop() {
+            let plane = self.nodes[node].plane;
+            let (mut coplanar, mut front, mut back) = (Vec::new(), Vec::new(), Vec::new());
+            for polygon in polygons {
+                // Both coplanar lists are the same one, so split twice into temporaries.
+                let mut coplanar_back = Vec::new();
+                plane.split(polygon, self.epsilon, &mut coplanar, &mut coplanar_back, &mut front, &mut back);
+                coplanar.append(&mut coplanar_back);
+            }
+            self.nodes[node].polygons.append(&mut coplanar);
+
+            for (polygons, is_front) in [(front, true), (back, false)] {
+                if polygons.is_empty() { continue; }
+                let child = if is_front { self.nodes[node].front } else { self.nodes[node].back };
+                let child = child.unwrap_or_else(|| {
+                    let child = self.add_node(polygons[0].plane);
+                    if is_front { self.nodes[node].front = Some(child) } else { self.nodes[node].back = Some(child) }
+                    child
+                });
+                work.push((child, polygons));
+            }
+        }
+    }
+
+    /// Swaps the inside and outside of the solid.
+    fn invert(&mut self) {
+        for node in &mut self.nodes {
+            node.polygons.iter_mut().for_each(Polygon::flip);
+            node.plane = Plane { normal: -node.plane.normal, w: -node.plane.w };
+            std::mem::swap(&mut node.front, &mut node.back);
+        }
+    }
+
+    /// The parts of ``polygons`` outside the solid of the tree.
+    fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        if self.nodes.is_empty() { return polygons; }
+        let mut outside = Vec::new();
+        let mut work = vec![(0, polygons)];
+        while let Some((node, polygons)) = work.pop() {
+            let node = &self.nodes[node];
+            let (mut front, mut back) = (Vec::new(), Vec::new());
+            for polygon in polygons {
+                let (mut coplanar_front, mut coplanar_back) = (Vec::new(), Vec::new());
+                node.plane.split(polygon, self.epsilon, &mut coplanar_front, &mut coplanar_back, &mut front, &mut back);
+                front.append(&mut coplanar_front);
+                back.append(&mut coplanar_back);
+            }
+            match node.front {
+                Some(child) => work.push((child, front)),
+                None => outside.append(&mut front),
+            }
+            // Behind a leaf is inside the solid.
+            if let Some(child) = node.back {
+                work.push((child, back));
+            }
+        }
+        outside
+    }
+
+    /// Removes the parts of the polygons of this tree inside the solid of ``other``.
+    fn clip_to(&mut self, other: &Bsp) {
+        for node in &mut self.nodes {
+            let polygons = std::mem::take(&mut node.polygons);
+            node.polygons = other.clip_polygons(polygons);
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        self.nodes.iter().flat_map(|node| node.polygons.iter().cloned()).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod test_boolean {
+    use super::*;
+    use crate::object::{Hittable, Mesh};
+    use crate::ray::{Interval, Ray};
+    use crate::object::material::{Material, Empty};
+    use assert_approx_eq::assert_approx_eq;
+
+    /// An axis aligned cube from ``min`` with sides of ``size``, facing outwards.
+    fn cube(min: Point3d, size: Float) -> MeshData {
+        let positions = (0..8)
+            .map(|i| min + Vec3d::new((i & 1) as Float, ((i >> 1) & 1) as Float, ((i >> 2) & 1) as Float) * size)
+            .collect();
+        let faces = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        let triangles = faces.iter().flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]]).collect();
+        MeshData { positions, triangles, ..Default::default() }
+    }
+
+    /// Volume enclosed by a closed mesh, by the divergence theorem.
+    fn volume(mesh: &MeshData) -> Float {
+        mesh.triangles.iter()
+            .map(|t| dot(&mesh.positions[t[0]], &cross(&mesh.positions[t[1]], &mesh.positions[t[2]])) / 6.0)
+            .sum()
+    }
+
+    #[test]
+    fn test_overlapping_cubes() {
+        let (a, b) = (cube(Point3d::zero(), 2.0), cube(Point3d::new(1.0, 1.0, 1.0), 2.0));
+        assert_approx_eq!(volume(&a), 8.0);
+        assert_approx_eq!(volume(&a.boolean(&b, BooleanOp::Union)), 15.0);
+        assert_approx_eq!(volume(&a.boolean(&b, BooleanOp::Intersection)), 1.0);
+        assert_approx_eq!(volume(&a.boolean(&b, BooleanOp::Difference)), 7.0);
+        assert_approx_eq!(volume(&b.boolean(&a, BooleanOp::Difference)), 7.0);
+
+        let disjoint = cube(Point3d::new(5.0, 0.0, 0.0), 1.0);
+        assert_approx_eq!(volume(&a.boolean(&disjoint, BooleanOp::Union)), 9.0);
+        assert!(a.boolean(&disjoint, BooleanOp::Intersection).triangles.is_empty());
+    }
+
+    #[test]
+    fn test_hole_through_cube() {
+        // A long thin box punched through the middle of the cube, along z.
+        let block = cube(Point3d::zero(), 3.0);
+        let mut drill = cube(Point3d::new(1.0, 1.0, -1.0), 1.0);
+        for p in &mut drill.positions {
+            *p = Point3d::new(p.x(), p.y(), -1.0 + (p.z() + 1.0) * 5.0);
+        }
+        let mut cube_with_hole = block.boolean(&drill, BooleanOp::Difference);
+        assert_approx_eq!(volume(&cube_with_hole), 27.0 - 3.0);
+
+        // The result is a valid mesh, hollow along the drilled axis.
+        cube_with_hole.normals = vec![Vec3d::new(0.0, 0.0, 1.0); cube_with_hole.positions.len()];
+        let mesh = Mesh::new(cube_with_hole, Material::Empty(Empty {}));
+        let through_hole = Ray::new(Point3d::new(1.5, 1.5, 10.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(mesh.hit(&through_hole, &Interval::UNIVERSE).is_none());
+        let through_wall = Ray::new(Point3d::new(0.5, 1.5, 10.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_approx_eq!(mesh.hit(&through_wall, &Interval::UNIVERSE).unwrap().t, 7.0);
+    }
+}
@@ -1,62 +1,295 @@
-use std::sync::Arc;
 use crate::object::material::Material;
-use crate::vec3d::{Point3d, Vec3d};
-use crate::object::{HittableVec, Quad};
-
-pub fn bbox(a: Point3d, b: Point3d, material: Material) -> HittableVec {
-    let mut sides = HittableVec::new();
-
-    let min = Point3d::new(
-        a.x().min(b.x()),
-        a.y().min(b.y()),
-        a.z().min(b.z()),
-    );
-
-    let max = Point3d::new(
-        a.x().max(b.x()),
-        a.y().max(b.y()),
-        a.z().max(b.z()),
-    );
-
-    let dx = Vec3d::new(max.x() - min.x(), 0.0, 0.0);
-    let dy = Vec3d::new(0.0, max.y() - min.y(), 0.0);
-    let dz = Vec3d::new(0.0, 0.0, max.z() - min.z());
-
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(min.x(), min.y(), max.z()),
-            dx, dy, material.clone(),
-        )))
-    );
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(max.x(), min.y(), max.z()),
-            -dz, dy, material.clone(),
-        )))
-    );
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(max.x(), min.y(), min.z()),
-            -dx, dy, material.clone(),
-        )))
-    );
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(min.x(), min.y(), min.z()),
-            dz, dy, material.clone(),
-        )))
-    );
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(min.x(), max.y(), max.z()),
-            dx, -dz, material.clone(),
-        )))
-    );
-    sides.add(
-        Arc::new(Box::new(Quad::new(
-            Point3d::new(min.x(), min.y(), min.z()),
-            dx, dz, material.clone(),
-        )))
-    );
-    sides
-}
\ No newline at end of file
+use crate::object::aabb::AABB;
+use crate::object::{HitRecord, Hittable};
+use crate::ray::{Interval, Ray};
+use crate::vec3d::{Point3d, Vec3d, Float};
+
+
+/// How texture coordinates are laid out over the faces of a box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxUv {
+    /// Every face spans the whole ``[0, 1]`` texture range.
+    PerFace,
+    /// The faces are unwrapped into a cross on a ``4 x 3`` grid, so a single image wraps the
+    /// whole box like a crate:
+    /// ```text
+    ///         +-----+
+    ///         | top |
+    /// +-------+-----+-------+------+
+    /// | left  |front| right | back |
+    /// +-------+-----+-------+------+
+    ///         |bottom
+    ///         +-----+
+    /// ```
+    CubeUnwrap,
+}
+
+impl BoxUv {
+    /// Returns the cell (column, row from the bottom) of a face in the unwrapped cross.
+    fn cell(face: usize) -> (Float, Float) {
+        match face {
+            0 => (1.0, 1.0), // front
+            1 => (2.0, 1.0), // right
+            2 => (3.0, 1.0), // back
+            3 => (0.0, 1.0), // left
+            4 => (1.0, 2.0), // top
+            _ => (1.0, 0.0), // bottom
+        }
+    }
+}
+
+
+/// Materials of the six faces of a box, as seen from outside with ``+y`` up and the front
+/// facing ``+z``.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxFaces {
+    pub front: Material,
+    pub right: Material,
+    pub back: Material,
+    pub left: Material,
+    pub top: Material,
+    pub bottom: Material,
+}
+
+impl BoxFaces {
+    /// Uses the same material on every face.
+    pub fn uniform(material: Material) -> Self {
+        Self {
+            front: material.clone(),
+            right: material.clone(),
+            back: material.clone(),
+            left: material.clone(),
+            top: material.clone(),
+            bottom: material,
+        }
+    }
+}
+
+
+/// An axis-aligned box intersected analytically with the slab method.
+///
+/// Compared to six separate quads, a single slab test finds both the entry and exit face, and
+/// every hit reports the exact face normal and texture coordinates, which keeps boxes clean
+/// under rotation and translation instances.
+pub struct Box3d {
+    min: Point3d,
+    max: Point3d,
+    faces: [Material; 6],
+    uv: BoxUv,
+    bbox: AABB,
+}
+
+impl Box3d {
+    /// Builds a box spanning the corners ``a`` and ``b`` with a material per face and the given
+    /// texture coordinate layout.
+    ///
+    /// Every face is oriented so that, seen from outside, its ``u`` axis points right and its
+    /// ``v`` axis points up; the top face has the front below it and the bottom face has the
+    /// front above it.
+    pub fn new(a: Point3d, b: Point3d, faces: BoxFaces, uv: BoxUv) -> Self {
+        let min = a.min(&b);
+        let max = a.max(&b);
+        Self {
+            min,
+            max,
+            faces: [faces.front, faces.right, faces.back, faces.left, faces.top, faces.bottom],
+            uv,
+            bbox: AABB::from_points(&min, &max),
+        }
+    }
+
+    /// Returns the face index, in ``BoxFaces`` order, of the given side of an axis.
+    fn face(axis: usize, max_side: bool) -> usize {
+        match (axis, max_side) {
+            (0, true) => 1,
+            (0, false) => 3,
+            (1, true) => 4,
+            (1, false) => 5,
+            (_, true) => 0,
+            (_, false) => 2,
+        }
+    }
+
+    /// Returns the face local texture coordinates of a point lying on ``face``.
+    fn face_uv(&self, face: usize, point: &Point3d) -> (Float, Float) {
+        // Flat boxes have no extent along one axis, which only their degenerate faces use.
+        let size = (self.max - self.min).map(|s| if s > 0.0 { s } else { 1.0 });
+        let from_min = (*point - self.min) / size;
+        let from_max = (self.max - *point) / size;
+        let (alpha, beta) = match face {
+            0 => (from_min.x(), from_min.y()),
+            1 => (from_max.z(), from_min.y()),
+            2 => (from_max.x(), from_min.y()),
+            3 => (from_min.z(), from_min.y()),
+            4 => (from_min.x(), from_max.z()),
+            _ => (from_min.x(), from_min.z()),
+        };
+        let unit = Interval { min: 0.0, max: 1.0 };
+        let (alpha, beta) = (unit.clamp(alpha), unit.clamp(beta));
+
+        match self.uv {
+            BoxUv::PerFace => (alpha, beta),
+            BoxUv::CubeUnwrap => {
+                let (column, row) = BoxUv::cell(face);
+                ((column + alpha) / 4.0, (row + beta) / 3.0)
+            }
+        }
+    }
+
+    /// Returns the distance, axis and side of the nearest face crossed by the ray within
+    /// ``interval``.
+    fn slab(&self, ray: &Ray, interval: &Interval) -> Option<(Float, usize, bool)> {
+        let inv_direction = ray.inv_direction();
+        let sign = ray.sign();
+
+        let mut t_enter = Float::NEG_INFINITY;
+        let mut t_exit = Float::INFINITY;
+        let mut enter_face = None;
+        let mut exit_face = None;
+
+        for axis in 0..3 {
+            let bounds = [self.min[axis], self.max[axis]];
+            let origin_axis = ray.origin[axis];
+
+            // A NaN slab distance means the ray runs inside the slab plane, and never
+            // determines the entry or exit face since the comparisons below fail.
+            let t_near = (bounds[sign[axis]] - origin_axis) * inv_direction[axis];
+            let t_far = (bounds[1 - sign[axis]] - origin_axis) * inv_direction[axis];
+
+            if t_near > t_enter {
+                t_enter = t_near;
+                enter_face = Some((axis, sign[axis] == 1));
+            }
+            if t_far < t_exit {
+                t_exit = t_far;
+                exit_face = Some((axis, sign[axis] == 0));
+            }
+        }
+        if t_enter > t_exit { return None; }
+
+        // Hit the entry face, or the exit face if the ray starts inside the box.
+        match (enter_face, exit_face) {
+            (Some((axis, max_side)), _) if interval.contains(t_enter) => Some((t_enter, axis, max_side)),
+            (_, Some((axis, max_side))) if interval.contains(t_exit) => Some((t_exit, axis, max_side)),
+            _ => None,
+        }
+    }
+}
+
+impl Hittable for Box3d {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let (t, axis, max_side) = self.slab(ray, interval)?;
+
+        let face = Self::face(axis, max_side);
+        let point = ray.at(t);
+        let (u, v) = self.face_uv(face, &point);
+
+        let mut normal = Vec3d::zero();
+        normal[axis] = if max_side { 1.0 } else { -1.0 };
+
+        let mut rec = HitRecord::new(&self.faces[face], t, u, v, point);
+        rec.set_face_normal(ray, normal);
+        Some(rec)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.slab(ray, interval).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+pub fn bbox(a: Point3d, b: Point3d, material: Material) -> Box3d {
+    Box3d::new(a, b, BoxFaces::uniform(material), BoxUv::PerFace)
+}
+
+
+/// Shorthand for ``Box3d::new``.
+pub fn bbox_with(a: Point3d, b: Point3d, faces: BoxFaces, uv: BoxUv) -> Box3d {
+    Box3d::new(a, b, faces, uv)
+}
+
+
+#[cfg(test)]
+mod test_box {
+    use super::*;
+    use crate::object::material::{Empty, Lambertian};
+    use crate::color::Color;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn hit_uv(sides: &Box3d, origin: Point3d, direction: Vec3d) -> (Float, Float) {
+        let ray = Ray::new(origin, direction, 0.0);
+        let rec = sides.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        (rec.u, rec.v)
+    }
+
+    #[test]
+    fn test_bbox_cube_unwrap_uv() {
+        let faces = BoxFaces::uniform(Material::Empty(Empty {}));
+        let sides = bbox_with(Point3d::zero(), Point3d::new(1.0, 1.0, 1.0), faces, BoxUv::CubeUnwrap);
+
+        // Center of the front face is the center of the front cell.
+        let (u, v) = hit_uv(&sides, Point3d::new(0.5, 0.5, 5.0), Vec3d::new(0.0, 0.0, -1.0));
+        assert_approx_eq!(u, 0.375);
+        assert_approx_eq!(v, 0.5);
+
+        // Top face, near the edge shared with the front.
+        let (u, v) = hit_uv(&sides, Point3d::new(0.5, 5.0, 0.9), Vec3d::new(0.0, -1.0, 0.0));
+        assert_approx_eq!(u, 0.375);
+        assert_approx_eq!(v, (2.0 + 0.1) / 3.0);
+
+        // Right face, near the edge shared with the front.
+        let (u, v) = hit_uv(&sides, Point3d::new(5.0, 0.5, 0.9), Vec3d::new(-1.0, 0.0, 0.0));
+        assert_approx_eq!(u, (2.0 + 0.1) / 4.0);
+        assert_approx_eq!(v, 0.5);
+    }
+
+    #[test]
+    fn test_box3d_hit_from_outside() {
+        let sides = bbox(Point3d::zero(), Point3d::new(2.0, 1.0, 1.0), Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(-3.0, 0.5, 0.5), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        let rec = sides.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        assert_eq!(rec.t, 3.0);
+        assert_eq!(rec.point, Point3d::new(0.0, 0.5, 0.5));
+        assert_eq!(rec.normal, Vec3d::new(-1.0, 0.0, 0.0));
+        assert!(rec.front_face);
+
+        let miss = Ray::new(Point3d::new(-3.0, 1.5, 0.5), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        assert!(sides.hit(&miss, &Interval { min: 0.0, max: Float::INFINITY }).is_none());
+        assert!(sides.hit(&ray, &Interval { min: 0.0, max: 2.0 }).is_none());
+        assert!(sides.hit_any(&ray, &Interval { min: 0.0, max: Float::INFINITY }));
+        assert!(!sides.hit_any(&miss, &Interval { min: 0.0, max: Float::INFINITY }));
+        assert!(!sides.hit_any(&ray, &Interval { min: 0.0, max: 2.0 }));
+    }
+
+    #[test]
+    fn test_box3d_hit_from_inside() {
+        let sides = bbox(Point3d::zero(), Point3d::new(1.0, 1.0, 1.0), Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(0.5, 0.5, 0.5), Vec3d::new(0.0, 1.0, 1.0), 0.0);
+        let rec = sides.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        assert_eq!(rec.t, 0.5);
+        assert!(!rec.front_face);
+        // Both the top and front face are exited at the same time.
+        assert!(rec.normal == Vec3d::new(0.0, -1.0, 0.0) || rec.normal == Vec3d::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_bbox_per_face_materials() {
+        let white = Material::Lambertian(Lambertian::new(Color::splat(1.0)));
+        let red = Material::Lambertian(Lambertian::new(Color::new(1.0, 0.0, 0.0)));
+        let faces = BoxFaces { top: red.clone(), ..BoxFaces::uniform(white.clone()) };
+        let sides = bbox_with(Point3d::zero(), Point3d::new(1.0, 1.0, 1.0), faces, BoxUv::PerFace);
+
+        let down = Ray::new(Point3d::new(0.5, 5.0, 0.5), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        let rec = sides.hit(&down, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        assert_eq!(*rec.material, red);
+
+        let side = Ray::new(Point3d::new(5.0, 0.5, 0.5), Vec3d::new(-1.0, 0.0, 0.0), 0.0);
+        let rec = sides.hit(&side, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        assert_eq!(*rec.material, white);
+    }
+}
+
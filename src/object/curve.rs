@@ -0,0 +1,337 @@
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
+use crate::object::aabb::AABB;
+use crate::object::material::Material;
+use crate::object::{HitRecord, Hittable};
+use crate::ray::{Interval, Ray};
+use crate::error::{Error, Result};
+use crate::tolerance;
+
+
+/// Number of straight segments every cubic span of a curve is tessellated into.
+const SEGMENTS_PER_SPAN: usize = 16;
+
+
+/// How the cross-section of a curve is intersected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveShape {
+    /// A round tube, for thick strands seen up close.
+    Cylinder,
+    /// A flat strip always facing the incoming ray, cheaper and well suited to thin hair
+    /// and grass blades seen from afar.
+    Ribbon,
+}
+
+
+/// A straight piece of the tessellated curve.
+struct Segment {
+    start: Point3d,
+    end: Point3d,
+    radius: (Float, Float),
+    u: (Float, Float),
+}
+
+
+/// A smooth strand of varying thickness, e.g. a hair, a fur fiber or a grass blade.
+///
+/// The curve is tessellated into short straight segments when built. Hits report the curve
+/// parameter from root to tip as ``u`` and the position across the strand as ``v``, and set
+/// the ``tangent`` of the hit record along the strand for the ``Hair`` material. Large amounts
+/// of curves should be put into a ``BVHNode``.
+/// # Examples
+/// ```
+/// use ray_tracing::object::{Curve, CurveShape, Hittable};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let strand = Curve::catmull_rom(
+///     &[Point3d::zero(), Point3d::new(0.0, 1.0, 0.0), Point3d::new(0.5, 2.0, 0.0)],
+///     (0.05, 0.01),
+///     CurveShape::Cylinder,
+///     Material::Empty(Empty {}),
+/// );
+/// let ray = Ray::new(Point3d::new(0.0, 0.5, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// let rec = strand.hit(&ray, &Interval::UNIVERSE).unwrap();
+/// assert!(rec.u > 0.0 && rec.u < 0.5);
+/// ```
+pub struct Curve {
+    segments: Vec<Segment>,
+    shape: CurveShape,
+    material: Material,
+    bbox: AABB,
+}
+
+
+impl Curve {
+    /// Creates a curve from the four control points of a cubic Bézier, with the radius
+    /// varying linearly from ``radius.0`` at the root to ``radius.1`` at the tip.
+    /// # Panics
+    /// If a radius is not greater than 0, see ``try_bezier`` for a fallible version.
+    pub fn bezier(control: [Point3d; 4], radius: (Float, Float), shape: CurveShape, material: Material) -> Self {
+        Self::try_bezier(control, radius, shape, material).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_bezier(control: [Point3d; 4], radius: (Float, Float), shape: CurveShape, material: Material) -> Result<Self> {
+        Self::try_new(&[control], radius, shape, material)
+    }
+
+    /// Creates a curve passing through every point with a Catmull-Rom spline, with the radius
+    /// varying linearly from ``radius.0`` at the first point to ``radius.1`` at the last.
+    /// # Panics
+    /// If fewer than two points are given or a radius is not greater than 0, see
+    /// ``try_catmull_rom`` for a fallible version.
+    pub fn catmull_rom(points: &[Point3d], radius: (Float, Float), shape: CurveShape, material: Material) -> Self {
+        Self::try_catmull_rom(points, radius, shape, material).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_catmull_rom(points: &[Point3d], radius: (Float, Float), shape: CurveShape, material: Material) -> Result<Self> {
+        if points.len() < 2 {
+            return Err(Error::TooFewPoints { minimum: 2, found: points.len() });
+        }
+
+        // Every span between two points becomes a Bézier, the end points are repeated so the
+        // curve still passes through them.
+        let last = points.len() - 1;
+        let spans: Vec<[Point3d; 4]> = (0..last).map(|i| {
+            let p0 = points[i.saturating_sub(1)];
+            let (p1, p2) = (points[i], points[i + 1]);
+            let p3 = points[(i + 2).min(last)];
+            [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0, p2]
+        }).collect();
+        Self::try_new(&spans, radius, shape, material)
+    }
+
+    fn try_new(spans: &[[Point3d; 4]], radius: (Float, Float), shape: CurveShape, material: Material) -> Result<Self> {
+        for r in [radius.0, radius.1] {
+            if r.is_nan() || r <= 0.0 {
+                return Err(Error::InvalidRadius(r));
+            }
+        }
+
+        let count = (spans.len() * SEGMENTS_PER_SPAN) as Float;
+        let mut segments = Vec::with_capacity(spans.len() * SEGMENTS_PER_SPAN);
+        for (i, span) in spans.iter().enumerate() {
+            for j in 0..SEGMENTS_PER_SPAN {
+                let t0 = j as Float / SEGMENTS_PER_SPAN as Float;
+                let t1 = (j + 1) as Float / SEGMENTS_PER_SPAN as Float;
+                let u0 = (i * SEGMENTS_PER_SPAN + j) as Float / count;
+                let u1 = (i * SEGMENTS_PER_SPAN + j + 1) as Float / count;
+                segments.push(Segment {
+                    start: bezier_point(span, t0),
+                    end: bezier_point(span, t1),
+                    radius: (mix(radius.0, radius.1, u0), mix(radius.0, radius.1, u1)),
+                    u: (u0, u1),
+                });
+            }
+        }
+
+        let bbox = segments.iter()
+            .map(|segment| {
+                let r = segment.radius.0.max(segment.radius.1);
+                let extent = Vec3d::new(r, r, r);
+                AABB::from_points(
+                    &(segment.start.min(&segment.end) - extent),
+                    &(segment.start.max(&segment.end) + extent),
+                )
+            })
+            .reduce(|a, b| AABB::surrounding_box(&a, &b))
+            .unwrap_or(AABB::EMPTY);
+
+        Ok(Self { segments, shape, material, bbox })
+    }
+
+    /// Intersects a segment as a capsule of its average radius, returning the distance along
+    /// the unit direction, the position along the segment and the outward normal.
+    fn hit_cylinder(segment: &Segment, origin: &Point3d, direction: &Vec3d) -> Option<(Float, Float, Vec3d)> {
+        let radius = 0.5 * (segment.radius.0 + segment.radius.1);
+        let ba = segment.end - segment.start;
+        let oa = *origin - segment.start;
+
+        let baba = dot(&ba, &ba);
+        let bard = dot(&ba, direction);
+        let baoa = dot(&ba, &oa);
+        let a = baba - bard * bard;
+        let b = baba * dot(direction, &oa) - baoa * bard;
+        let c = baba * dot(&oa, &oa) - baoa * baoa - radius * radius * baba;
+        let h = b * b - a * c;
+        if h < 0.0 { return None; }
+
+        // Body of the tube, then either rounded end cap.
+        let mut distance = (-b - h.sqrt()) / a;
+        let y = baoa + distance * bard;
+        if !(y > 0.0 && y < baba) {
+            let oc = if y <= 0.0 { oa } else { *origin - segment.end };
+            let b = dot(direction, &oc);
+            let h = b * b - dot(&oc, &oc) + radius * radius;
+            if h <= 0.0 { return None; }
+            distance = -b - h.sqrt();
+        }
+
+        let point = *origin + *direction * distance;
+        let along = (dot(&(point - segment.start), &ba) / baba).clamp(0.0, 1.0);
+        let normal = (point - (segment.start + ba * along)).unit_vector();
+        Some((distance, along, normal))
+    }
+
+    /// Intersects a segment as a strip facing the ray, returning the distance along the unit
+    /// direction, the position along the segment and the signed offset across the strip in
+    /// ``[-1, 1]``.
+    fn hit_ribbon(segment: &Segment, origin: &Point3d, direction: &Vec3d) -> Option<(Float, Float, Float)> {
+        let ba = segment.end - segment.start;
+        let oa = *origin - segment.start;
+
+        // Closest points between the ray and the segment.
+        let b = dot(direction, &ba);
+        let c = dot(&ba, &ba);
+        let d = dot(direction, &oa);
+        let e = dot(&ba, &oa);
+        let denom = c - b * b;
+        if denom.abs() < tolerance::parallel() { return None; }
+
+        let along = ((e - d * b) / denom).clamp(0.0, 1.0);
+        let distance = along * b - d;
+        let offset = *origin + *direction * distance - (segment.start + ba * along);
+
+        let radius = mix(segment.radius.0, segment.radius.1, along);
+        let length = offset.length();
+        if length > radius { return None; }
+
+        let side = dot(&offset, &cross(&ba, direction));
+        let across = if side < 0.0 { -length / radius } else { length / radius };
+        Some((distance, along, across))
+    }
+}
+
+
+impl Hittable for Curve {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let length = ray.direction.length();
+        let direction = ray.direction / length;
+
+        let mut closest: Option<(Float, &Segment, Float, Float, Vec3d)> = None;
+        let mut max = interval.max;
+        for segment in self.segments.iter() {
+            let found = match self.shape {
+                CurveShape::Cylinder => Self::hit_cylinder(segment, &ray.origin, &direction)
+                    .map(|(distance, along, normal)| (distance, along, 0.5, normal)),
+                CurveShape::Ribbon => Self::hit_ribbon(segment, &ray.origin, &direction)
+                    .map(|(distance, along, across)| {
+                        // The strip faces the ray, whose backwards direction is its normal
+                        // once made perpendicular to the strand.
+                        let tangent = (segment.end - segment.start).unit_vector();
+                        let facing = -direction;
+                        let normal = (facing - tangent * dot(&facing, &tangent)).unit_vector();
+                        (distance, along, 0.5 + 0.5 * across, normal)
+                    }),
+            };
+
+            if let Some((distance, along, v, normal)) = found {
+                let t = distance / length;
+                if t > interval.min && t < max {
+                    max = t;
+                    closest = Some((t, segment, along, v, normal));
+                }
+            }
+        }
+
+        let (t, segment, along, v, normal) = closest?;
+        let u = mix(segment.u.0, segment.u.1, along);
+        let mut rec = HitRecord::new(&self.material, t, u, v, ray.at(t));
+        rec.set_face_normal(ray, normal);
+        rec.tangent = (segment.end - segment.start).unit_vector();
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+fn mix(a: Float, b: Float, t: Float) -> Float {
+    a + (b - a) * t
+}
+
+
+fn bezier_point(control: &[Point3d; 4], t: Float) -> Point3d {
+    let s = 1.0 - t;
+    control[0] * (s * s * s)
+        + control[1] * (3.0 * s * s * t)
+        + control[2] * (3.0 * s * t * t)
+        + control[3] * (t * t * t)
+}
+
+
+#[cfg(test)]
+mod test_curve {
+    use super::*;
+    use crate::object::material::Empty;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn straight(shape: CurveShape) -> Curve {
+        Curve::bezier(
+            [
+                Point3d::zero(),
+                Point3d::new(0.0, 1.0, 0.0),
+                Point3d::new(0.0, 2.0, 0.0),
+                Point3d::new(0.0, 3.0, 0.0),
+            ],
+            (0.2, 0.2),
+            shape,
+            Material::Empty(Empty {}),
+        )
+    }
+
+    #[test]
+    fn test_curve_cylinder_hit() {
+        let curve = straight(CurveShape::Cylinder);
+        let ray = Ray::new(Point3d::new(0.0, 1.5, 5.0), Vec3d::new(0.0, 0.0, -2.0), 0.0);
+        let rec = curve.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert_approx_eq!(rec.t, (5.0 - 0.2) / 2.0);
+        assert_approx_eq!(rec.u, 0.5);
+        assert_approx_eq!(rec.normal.z(), 1.0);
+        assert_approx_eq!(rec.tangent.y(), 1.0);
+        assert!(rec.front_face);
+
+        let miss = Ray::new(Point3d::new(0.3, 1.5, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(curve.hit(&miss, &Interval::UNIVERSE).is_none());
+    }
+
+    #[test]
+    fn test_curve_ribbon_hit() {
+        let curve = straight(CurveShape::Ribbon);
+        let ray = Ray::new(Point3d::new(0.1, 1.5, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        let rec = curve.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert_approx_eq!(rec.t, 5.0);
+        assert_approx_eq!(rec.normal.z(), 1.0);
+        assert!((rec.v - 0.25).abs() < 1e-6 || (rec.v - 0.75).abs() < 1e-6);
+
+        let miss = Ray::new(Point3d::new(0.0, 3.5, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(curve.hit(&miss, &Interval::UNIVERSE).is_none());
+    }
+
+    #[test]
+    fn test_catmull_rom_passes_through_points() {
+        let points = [Point3d::zero(), Point3d::new(1.0, 1.0, 0.0), Point3d::new(2.0, 0.0, 0.0)];
+        let curve = Curve::catmull_rom(&points, (0.1, 0.1), CurveShape::Cylinder, Material::Empty(Empty {}));
+        assert_eq!(curve.segments.len(), 2 * SEGMENTS_PER_SPAN);
+        assert_eq!(curve.segments[SEGMENTS_PER_SPAN].start, points[1]);
+        assert_eq!(curve.segments.last().unwrap().end, points[2]);
+
+        let bbox = curve.bounding_box();
+        assert!(bbox.contains(&Point3d::new(1.0, 1.05, 0.0)));
+    }
+
+    #[test]
+    fn test_curve_invalid_input() {
+        let material = Material::Empty(Empty {});
+        assert!(matches!(
+            Curve::try_catmull_rom(&[Point3d::zero()], (0.1, 0.1), CurveShape::Ribbon, material.clone()),
+            Err(Error::TooFewPoints { minimum: 2, found: 1 }),
+        ));
+        let control = [Point3d::zero(); 4];
+        assert!(matches!(
+            Curve::try_bezier(control, (0.1, 0.0), CurveShape::Ribbon, material),
+            Err(Error::InvalidRadius(_)),
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+//! Displacement mapping while loading a scene: meshes are subdivided and their vertices moved
+//! along their normals by a height texture, so a flat quad becomes terrain or a brick wall that
+//! casts its own shadows, unlike a bump map.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::object::MeshData;
+//! use ray_tracing::object::texture::SolidColor;
+//! use ray_tracing::color::Color;
+//! use ray_tracing::vec3d::{Point3d, Vec3d};
+//! let square = MeshData {
+//!     positions: vec![
+//!         Point3d::new(0.0, 0.0, 0.0), Point3d::new(1.0, 0.0, 0.0),
+//!         Point3d::new(1.0, 0.0, 1.0), Point3d::new(0.0, 0.0, 1.0),
+//!     ],
+//!     normals: vec![Vec3d::new(0.0, 1.0, 0.0); 4],
+//!     uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+//!     triangles: vec![[0, 2, 1], [0, 3, 2]],
+//!     ..Default::default()
+//! };
+//! let raised = square.displace(&SolidColor::new(Color::splat(0.5)), 0.2, 2).unwrap();
+//! assert_eq!(raised.triangles.len(), 2 * 16);
+//! assert!(raised.positions.iter().all(|p| (p.y() - 0.1).abs() < 1e-9));
+//! ```
+use crate::vec3d::{Vec3d, Point3d, Float, cross};
+use crate::error::{Error, Result};
+use crate::object::MeshData;
+use crate::object::texture::Texture;
+
+use std::collections::HashMap;
+
+
+impl MeshData {
+    /// Splits every triangle into ``4^subdivisions`` and moves each vertex ``scale`` times the
+    /// luminance of ``height`` along its normal. The texture is looked up at the texture
+    /// coordinates and point before displacement.
+    ///
+    /// Vertices on edges shared by triangles are shared too, so the mesh stays closed where it
+    /// was. Normals are interpolated from the given ones, or averaged from the triangles around
+    /// each vertex without them, and recomputed from the displaced surface in the result.
+    /// Fails without texture coordinates or with inconsistent attributes.
+    pub fn displace(&self, height: &dyn Texture, scale: Float, subdivisions: u32) -> Result<MeshData> {
+        self.validate()?;
+        if self.uvs.is_empty() && !self.positions.is_empty() {
+            return Err(Error::AttributeCount { attribute: "uv", expected: self.positions.len(), found: 0 });
+        }
+        let normals = if self.normals.is_empty() {
+            vertex_normals(&self.positions, &self.triangles)
+        } else {
+            self.normals.clone()
+        };
+        let n = 1u32 << subdivisions;
+
+        let mut result = MeshData { flat_shading: self.flat_shading, ..Default::default() };
+        let mut shared = HashMap::new();
+        for triangle in &self.triangles {
+            // Index of every grid point of the triangle, row ``i`` holding ``n + 1 - i`` of them.
+            let mut grid = Vec::with_capacity(((n + 1) * (n + 2) / 2) as usize);
+            for i in 0..=n {
+                for j in 0..=n - i {
+                    let weights = [n - i - j, i, j];
+                    let index = *shared.entry(grid_key(triangle, weights)).or_insert_with(|| {
+                        let w = weights.map(|w| w as Float / n as Float);
+                        let mix_point = |values: &[Point3d]| {
+                            values[triangle[0]] * w[0] + values[triangle[1]] * w[1] + values[triangle[2]] * w[2]
+                        };
+                        let point = mix_point(&self.positions);
+                        let normal = mix_point(&normals);
+                        let uv = (0..3).fold((0.0, 0.0), |(u, v), k| {
+                            let (vertex_u, vertex_v) = self.uvs[triangle[k]];
+                            (u + vertex_u * w[k], v + vertex_v * w[k])
+                        });
+                        let offset = height.value(uv.0, uv.1, &point).luminance() * scale;
+                        let normal = if normal.length_squared() > 0.0 { normal.unit_vector() } else { normal };
+                        result.positions.push(point + normal * offset);
+                        result.uvs.push(uv);
+                        if !self.colors.is_empty() {
+                            let colors = &self.colors;
+                            result.colors.push(
+                                colors[triangle[0]] * w[0] + colors[triangle[1]] * w[1] + colors[triangle[2]] * w[2],
+                            );
+                        }
+                        result.positions.len() - 1
+                    });
+                    grid.push(index);
+                }
+            }
+
+            let at = |i: u32, j: u32| grid[(i * (2 * n + 3 - i) / 2 + j) as usize];
+            for i in 0..n {
+                for j in 0..n - i {
+                    result.triangles.push([at(i, j), at(i + 1, j), at(i, j + 1)]);
+                    if j + 1 < n - i {
+                        result.triangles.push([at(i + 1, j), at(i + 1, j + 1), at(i, j + 1)]);
+                    }
+                }
+            }
+        }
+
+        if !self.flat_shading {
+            result.normals = vertex_normals(&result.positions, &result.triangles);
+        }
+        Ok(result)
+    }
+}
+
+
+/// Identifies the grid point of ``triangle`` with the integer barycentric ``weights`` by the
+/// vertices it mixes, so neighbouring triangles find the points on the edges they share.
+fn grid_key(triangle: &[usize; 3], weights: [u32; 3]) -> [(usize, u32); 3] {
+    let mut key = [0, 1, 2].map(|k| if weights[k] == 0 { (usize::MAX, 0) } else { (triangle[k], weights[k]) });
+    key.sort_unstable();
+    key
+}
+
+
+/// Normals of every vertex, averaged over the triangles around it weighted by their area.
+pub(super) fn vertex_normals(positions: &[Point3d], triangles: &[[usize; 3]]) -> Vec<Vec3d> {
+    let mut normals = vec![Vec3d::zero(); positions.len()];
+    for &[a, b, c] in triangles {
+        let normal = cross(&(positions[b] - positions[a]), &(positions[c] - positions[a]));
+        for index in [a, b, c] {
+            normals[index] += normal;
+        }
+    }
+    normals.iter().map(|&normal| if normal.length_squared() > 0.0 { normal.unit_vector() } else { normal }).collect()
+}
+
+
+#[cfg(test)]
+mod test_displacement {
+    use super::*;
+    use crate::color::Color;
+    use crate::object::texture::SolidColor;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Height of the ``u`` coordinate.
+    #[derive(Debug)]
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: Float, _v: Float, _p: &Vec3d) -> Color {
+            Color::splat(u)
+        }
+    }
+
+    /// Two triangles of a unit square in ``xz``, facing up.
+    fn square() -> MeshData {
+        MeshData {
+            positions: vec![
+                Point3d::new(0.0, 0.0, 0.0), Point3d::new(1.0, 0.0, 0.0),
+                Point3d::new(1.0, 0.0, 1.0), Point3d::new(0.0, 0.0, 1.0),
+            ],
+            uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+            triangles: vec![[0, 2, 1], [0, 3, 2]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shares_edge_vertices() {
+        let flat = square().displace(&SolidColor::new(Color::zero()), 1.0, 3).unwrap();
+        // A grid of 9 by 9 vertices, the diagonal shared by both triangles.
+        assert_eq!(flat.positions.len(), 81);
+        assert_eq!(flat.triangles.len(), 2 * 64);
+        assert_eq!(flat.uvs.len(), 81);
+        for normal in &flat.normals {
+            assert_approx_eq!(normal.y(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_displaces_along_normals() {
+        let slope = square().displace(&Ramp, 0.5, 2).unwrap();
+        for (point, &(u, _)) in slope.positions.iter().zip(&slope.uvs) {
+            assert_approx_eq!(point.x(), u);
+            assert_approx_eq!(point.y(), 0.5 * u);
+        }
+        // The surface rises along ``x``, so its normals lean back.
+        let normal = slope.normals[slope.positions.len() / 2];
+        assert_approx_eq!(normal.x() / normal.y(), -0.5);
+
+        let mut no_uvs = square();
+        no_uvs.uvs.clear();
+        assert!(matches!(no_uvs.displace(&Ramp, 1.0, 1), Err(Error::AttributeCount { attribute: "uv", .. })));
+    }
+}
@@ -0,0 +1,427 @@
+use crate::vec3d::{Vec3d, Point3d, Float, consts, cross, dot};
+use crate::color::Color;
+use crate::object::{IesProfile, Quad, Surface};
+use crate::sampler;
+use rand::Rng;
+
+use std::sync::Arc;
+
+
+/// A point on an emitter picked by ``Emitter::sample``, as seen from the shaded point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmitterSample {
+    /// From the shaded point to the sampled point, with the distance between them as length.
+    pub direction: Vec3d,
+    /// Radiance emitted from the sampled point towards the shaded point.
+    pub radiance: Color,
+    /// Solid angle density of ``direction``, or the probability of picking it for delta lights.
+    pub pdf: Float,
+}
+
+
+/// The power of a light in physical units, so lights keep their brightness when resized or
+/// swapped, see ``Light::from_power`` and ``PointLight::from_power``.
+///
+/// Rendered values are radiance in watts per square metre and steradian. The scale of the
+/// scene, in metres per unit, makes a light of the same power as bright in a scene modelled in
+/// centimetres as in one modelled in metres.
+/// # Examples
+/// ```
+/// use ray_tracing::object::LightPower;
+/// let bulb = LightPower::Lumens(683.0);
+/// assert_eq!(bulb.watts(), 1.0);
+/// // A panel twice as large glows half as bright, sending out the same light.
+/// let small = bulb.radiance(0.5, 1.0);
+/// assert!((bulb.radiance(1.0, 1.0) - small / 2.0).abs() < 1e-12);
+/// // A metre is a hundred units in a scene modelled in centimetres.
+/// assert!((bulb.radiance(100.0 * 100.0, 0.01) - bulb.radiance(1.0, 1.0)).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightPower {
+    /// Radiant power, the energy the light sends out every second.
+    Watts(Float),
+    /// Luminous power, as printed on light bulbs, converted at the peak efficacy of
+    /// ``LUMENS_PER_WATT``.
+    Lumens(Float),
+}
+
+
+impl LightPower {
+    /// Lumens per watt of light at the peak sensitivity of the eye, which the renderer's
+    /// colors take for all of their light.
+    pub const LUMENS_PER_WATT: Float = 683.0;
+
+    pub fn watts(&self) -> Float {
+        match *self {
+            LightPower::Watts(watts) => watts,
+            LightPower::Lumens(lumens) => lumens / Self::LUMENS_PER_WATT,
+        }
+    }
+
+    /// Radiance of a diffuse surface of ``area`` square units sending out this power from its
+    /// front side, in a scene of ``meters_per_unit``.
+    pub fn radiance(&self, area: Float, meters_per_unit: Float) -> Float {
+        self.watts() / (consts::PI * area * meters_per_unit * meters_per_unit)
+    }
+
+    /// Intensity of a point sending out this power evenly in all directions, per squared unit
+    /// of distance in a scene of ``meters_per_unit``.
+    pub fn intensity(&self, meters_per_unit: Float) -> Float {
+        self.watts() / (4.0 * consts::PI * meters_per_unit * meters_per_unit)
+    }
+}
+
+
+/// Scales ``color`` to a luminance of ``1``, so it only sets the hue of a light of given power.
+pub(crate) fn normalized_color(color: Color) -> Color {
+    let luminance = color.luminance();
+    if luminance > 0.0 { color / luminance } else { Color::zero() }
+}
+
+
+/// An emitting object the camera samples directly, registered with ``Camera::add_light``.
+///
+/// Every diffuse bounce then also sends a shadow ray towards a random light, which converges
+/// far faster than waiting for scattered rays to find small lights. Lights must still be part
+/// of the world, so they are seen by camera rays and occlude each other, except for delta lights
+/// like ``PointLight`` which no ray can hit.
+pub trait Emitter: Send + Sync {
+    /// Samples a point of the emitter visible from ``origin``, ``None`` if there is none.
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample>;
+
+    /// Solid angle density with which ``sample`` returns ``direction`` from ``origin``, zero if
+    /// it misses the emitter.
+    fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float;
+
+    /// Whether the emitter is a single point, only reachable by sampling it. The ``radiance``
+    /// of its samples is then the light arriving at the shaded point.
+    fn is_delta(&self) -> bool { false }
+
+    /// Name of the light group the emitter belongs to, see ``Camera::add_light_group``.
+    fn group(&self) -> Option<&str> { None }
+}
+
+
+/// A point light, optionally shaped into a spot or by an ``IesProfile`` of a real fixture.
+///
+/// Point lights are not part of the world: no ray can hit them, so they only light the scene
+/// through ``Camera::add_light``. The intensity falls off with the squared distance.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::{Emitter, PointLight};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let spot = PointLight::new(Point3d::new(0.0, 2.0, 0.0), Color::splat(8.0)).with_spot(30.0, 5.0);
+/// let below = spot.sample(&Point3d::zero()).unwrap();
+/// assert_eq!(below.radiance, Color::splat(2.0));
+/// assert!(spot.sample(&Point3d::new(5.0, 2.0, 0.0)).is_none());
+/// ```
+pub struct PointLight {
+    position: Point3d,
+    intensity: Color,
+    axis: Vec3d,
+    reference: Vec3d,
+    spot: Option<(Float, Float)>,
+    profile: Option<IesProfile>,
+    group: Option<String>,
+}
+
+
+impl PointLight {
+    /// Creates a light shining ``intensity`` in every direction, pointing down ``-y``.
+    pub fn new(position: Point3d, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            axis: Vec3d::new(0.0, -1.0, 0.0),
+            reference: Vec3d::new(1.0, 0.0, 0.0),
+            spot: None,
+            profile: None,
+            group: None,
+        }
+    }
+
+    /// Creates a light of ``color`` sending out ``power`` evenly in every direction, in a scene
+    /// of ``meters_per_unit``, see ``LightPower``. Spots and profiles narrow it down without
+    /// concentrating its power.
+    pub fn from_power(position: Point3d, color: Color, power: LightPower, meters_per_unit: Float) -> Self {
+        Self::new(position, normalized_color(color) * power.intensity(meters_per_unit))
+    }
+
+    /// Points the light along ``axis``, the center of the spot and the ``0`` vertical angle of
+    /// the profile. Horizontal angle ``0`` of the profile lies towards ``+x``, or ``+z`` for
+    /// lights pointing along ``x``.
+    pub fn with_axis(mut self, axis: Vec3d) -> Self {
+        self.axis = axis.unit_vector();
+        let helper = if self.axis.x().abs() > 0.9 { Vec3d::new(0.0, 0.0, 1.0) } else { Vec3d::new(1.0, 0.0, 0.0) };
+        self.reference = cross(&cross(&self.axis, &helper), &self.axis).unit_vector();
+        self
+    }
+
+    /// Limits the light to a cone of half angle ``angle`` degrees around the axis, fading out
+    /// over the last ``falloff`` degrees.
+    pub fn with_spot(mut self, angle: Float, falloff: Float) -> Self {
+        let inner = (angle - falloff).max(0.0);
+        self.spot = Some((angle.to_radians().cos(), inner.to_radians().cos()));
+        self
+    }
+
+    /// Shapes the light by the angular distribution of a fixture. ``intensity`` is then the
+    /// intensity in the profile's brightest direction.
+    pub fn with_profile(mut self, profile: IesProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Puts the light into the light group ``group``, see ``Camera::add_light_group``.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_string());
+        self
+    }
+
+    /// Share of the intensity sent along the unit vector ``direction``.
+    fn distribution(&self, direction: &Vec3d) -> Float {
+        let cosine = dot(direction, &self.axis);
+        let spot = match self.spot {
+            Some((outer, _)) if cosine <= outer => 0.0,
+            Some((outer, inner)) if cosine < inner => {
+                let t = (cosine - outer) / (inner - outer);
+                t * t * (3.0 - 2.0 * t)
+            }
+            _ => 1.0,
+        };
+        let profile = match &self.profile {
+            Some(profile) if spot > 0.0 => {
+                let vertical = cosine.clamp(-1.0, 1.0).acos().to_degrees();
+                let side = cross(&self.axis, &self.reference);
+                let horizontal = dot(direction, &side).atan2(dot(direction, &self.reference)).to_degrees();
+                profile.intensity(vertical, horizontal)
+            }
+            _ => 1.0,
+        };
+        spot * profile
+    }
+}
+
+
+impl Emitter for PointLight {
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample> {
+        let direction = self.position - *origin;
+        let distance_squared = direction.length_squared();
+        let share = self.distribution(&(-direction / distance_squared.sqrt()));
+        if share <= 0.0 || distance_squared <= 0.0 { return None; }
+        Some(EmitterSample { direction, radiance: self.intensity * (share / distance_squared), pdf: 1.0 })
+    }
+
+    fn pdf(&self, _origin: &Point3d, _direction: &Vec3d) -> Float { 0.0 }
+
+    fn is_delta(&self) -> bool { true }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+}
+
+
+/// Samples a textured ``Quad`` light in proportion to the luminance of its emission, e.g. a TV
+/// screen, so bright texels get proportionally more shadow rays than dark ones.
+///
+/// The emission is tabulated on a grid of ``resolution x resolution`` cells over the quad when
+/// the map is built. A quad with no emission at all is sampled uniformly.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::{EmissionMap, Emitter, Quad};
+/// use ray_tracing::object::material::{Material, Light};
+/// use ray_tracing::object::texture::ImageTexture;
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// # let path = std::env::temp_dir().join("ray_tracing_doc_emission_map.png");
+/// # let path = path.to_str().unwrap();
+/// # ray_tracing::image::write_image(path, &vec![Color::splat(1.0), Color::zero()], 2, 1);
+/// let screen = Arc::new(Quad::new(
+///     Point3d::new(-1.0, -1.0, -2.0), Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 2.0, 0.0),
+///     Material::Light(Light::new(Arc::new(ImageTexture::new(path)))),
+/// ));
+/// let light = EmissionMap::new(screen, 32);
+/// // Only the bright left half of the image is sampled.
+/// let sample = light.sample(&Point3d::zero()).unwrap();
+/// assert!(sample.direction.x() < 0.0);
+/// assert!(sample.radiance.r() > 0.9);
+/// ```
+pub struct EmissionMap {
+    quad: Arc<Quad>,
+    resolution: usize,
+    /// Cumulative share of the emission of the cells, in row-major order.
+    cdf: Vec<Float>,
+}
+
+
+impl EmissionMap {
+    pub fn new(quad: Arc<Quad>, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let mut weights = Vec::with_capacity(resolution * resolution);
+        for j in 0..resolution {
+            for i in 0..resolution {
+                let alpha = (i as Float + 0.5) / resolution as Float;
+                let beta = (j as Float + 0.5) / resolution as Float;
+                weights.push(quad.emission_at(alpha, beta).luminance().max(0.0));
+            }
+        }
+        if weights.iter().all(|&weight| weight == 0.0) {
+            weights.fill(1.0);
+        }
+
+        let total: Float = weights.iter().sum();
+        let mut sum = 0.0;
+        let cdf = weights.iter()
+            .map(|weight| {
+                sum += weight / total;
+                sum
+            })
+            .collect();
+        Self { quad, resolution, cdf }
+    }
+
+    /// Share of the emission in the cell with the given index.
+    fn probability(&self, cell: usize) -> Float {
+        let previous = if cell == 0 { 0.0 } else { self.cdf[cell - 1] };
+        self.cdf[cell] - previous
+    }
+
+    /// Area density on the quad inside the cell with the given index.
+    fn area_pdf(&self, cell: usize) -> Float {
+        self.probability(cell) * (self.resolution * self.resolution) as Float / self.quad.area()
+    }
+
+    fn cell(&self, alpha: Float, beta: Float) -> usize {
+        let column = ((alpha * self.resolution as Float) as usize).min(self.resolution - 1);
+        let row = ((beta * self.resolution as Float) as usize).min(self.resolution - 1);
+        row * self.resolution + column
+    }
+}
+
+
+impl Emitter for EmissionMap {
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample> {
+        let mut rng = sampler::rng();
+        let r = rng.random::<Float>();
+        let cell = self.cdf.partition_point(|&share| share <= r).min(self.cdf.len() - 1);
+        let alpha = ((cell % self.resolution) as Float + rng.random::<Float>()) / self.resolution as Float;
+        let beta = ((cell / self.resolution) as Float + rng.random::<Float>()) / self.resolution as Float;
+        self.quad.emitter_sample(origin, alpha, beta, self.area_pdf(cell))
+    }
+
+    fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        match self.quad.locate_emission(origin, direction) {
+            Some((alpha, beta, solid_angle_factor)) => self.area_pdf(self.cell(alpha, beta)) * solid_angle_factor,
+            None => 0.0,
+        }
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.quad.group()
+    }
+}
+
+
+#[cfg(test)]
+mod test_emitter {
+    use super::*;
+    use crate::object::material::{Material, Light, BackFace, Scatterable};
+    use crate::object::texture::{Texture, SolidColor};
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Bright on the left quarter of the quad, dark elsewhere.
+    #[derive(Debug)]
+    struct Stripe;
+
+    impl Texture for Stripe {
+        fn value(&self, u: Float, _v: Float, _p: &Vec3d) -> Color {
+            if u < 0.25 { Color::splat(3.0) } else { Color::zero() }
+        }
+    }
+
+    fn screen(material: Material) -> Arc<Quad> {
+        Arc::new(Quad::new(
+            Point3d::new(0.0, 0.0, -1.0), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0), material,
+        ))
+    }
+
+    #[test]
+    fn test_emission_map_follows_luminance() {
+        let light = EmissionMap::new(screen(Material::Light(Light::new(Arc::new(Stripe)))), 8);
+        let origin = Point3d::new(0.5, 0.5, 0.0);
+        for _ in 0..100 {
+            let sample = light.sample(&origin).unwrap();
+            assert!(sample.direction.x() < -0.25 + 1e-9);
+            assert_eq!(sample.radiance, Color::splat(3.0));
+            assert_approx_eq!(light.pdf(&origin, &sample.direction), sample.pdf);
+        }
+        // Dark parts are never sampled.
+        assert_eq!(light.pdf(&origin, &Vec3d::new(0.0, 0.0, -1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_light_power() {
+        // The power leaving a one-sided diffuse panel is ``pi * area`` times its radiance.
+        let panel = Light::from_power(Color::new(1.0, 0.5, 0.5), LightPower::Watts(40.0), 2.0, 0.5);
+        let radiance = panel.emitted(0.0, 0.0, &Point3d::zero());
+        assert_approx_eq!(radiance.luminance() * consts::PI * 2.0 * 0.25, 40.0);
+        assert_approx_eq!(radiance[1] / radiance[0], 0.5);
+        assert_eq!(panel.back_face(), BackFace::Black);
+
+        // Irradiance one metre away from a point light sending out one watt.
+        let bulb = PointLight::from_power(Point3d::zero(), Color::splat(1.0), LightPower::Watts(1.0), 0.01);
+        let sample = bulb.sample(&Point3d::new(100.0, 0.0, 0.0)).unwrap();
+        assert_approx_eq!(sample.radiance[0], 1.0 / (4.0 * consts::PI));
+    }
+
+    #[test]
+    fn test_dark_emission_map_is_uniform() {
+        let dark = Material::Light(Light::new(Arc::new(SolidColor::new(Color::zero()))));
+        let light = EmissionMap::new(screen(dark), 4);
+        let origin = Point3d::new(0.5, 0.5, 0.0);
+        // Straight ahead, the density is ``distance^2 / area``.
+        assert_approx_eq!(light.pdf(&origin, &Vec3d::new(0.0, 0.0, -1.0)), 1.0);
+    }
+
+    #[test]
+    fn test_point_light_profile_orientation() {
+        // Brightest at horizontal angle 90, off to the side of the reference direction.
+        let profile = IesProfile::parse("IESNA:LM-63-2002
+TILT=NONE
+1 -1 1 2 2 1 1 0 0 0
+1 1 50
+0 90
+0 90
+100 100
+200 200
+").unwrap();
+        let light = PointLight::new(Point3d::zero(), Color::splat(1.0))
+            .with_axis(Vec3d::new(0.0, 0.0, -1.0))
+            .with_profile(profile);
+        assert!(light.is_delta());
+        assert_eq!(light.pdf(&Point3d::new(0.0, 0.0, -1.0), &Vec3d::new(0.0, 0.0, 1.0)), 0.0);
+
+        let along_reference = light.sample(&Point3d::new(1.0, 0.0, 0.0)).unwrap();
+        let along_side = light.sample(&Point3d::new(0.0, 1.0, 0.0)).unwrap();
+        assert_approx_eq!(along_reference.radiance.r(), 0.5);
+        assert_approx_eq!(along_side.radiance.r(), 1.0);
+        // Behind the fixture, past the measured 90 degrees.
+        assert!(light.sample(&Point3d::new(0.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_one_sided_light_seen_from_behind() {
+        let one_sided = Material::Light(Light::from_color(Color::splat(1.0)).with_back_face(BackFace::Black));
+        let quad = screen(one_sided);
+        let behind = Point3d::new(0.5, 0.5, -2.0);
+        assert!(Emitter::sample(&*quad, &behind).is_none());
+        assert_eq!(quad.pdf(&behind, &Vec3d::new(0.0, 0.0, 1.0)), 0.0);
+
+        let front = Point3d::new(0.5, 0.5, 0.0);
+        assert!(Emitter::sample(&*quad, &front).is_some());
+        assert_approx_eq!(quad.pdf(&front, &Vec3d::new(0.0, 0.0, -1.0)), 1.0);
+    }
+}
@@ -1,30 +1,57 @@
-use crate::vec3d::{Point3d, Vec3d, dot};
+use crate::vec3d::{Point3d, Vec3d, Float, dot};
+use crate::color::Color;
 use crate::ray::{Ray, Interval};
 use crate::object::aabb::AABB;
 use super::material::{Material, Empty};
+use crate::stats::{self, Counter};
+use crate::trace;
 
 use rand::Rng;
 use std::cmp::Ordering;
 use std::sync::Arc;
+use std::time::Instant;
 
 use std::default::Default;
 
 
 #[derive(Debug, Clone, Copy)]
 pub struct HitRecord<'m> {
-    pub t: f64,
-    pub u: f64,
-    pub v: f64,
+    pub t: Float,
+    pub u: Float,
+    pub v: Float,
 
     pub point: Point3d,
     pub normal: Vec3d,
     pub front_face: bool,
 
+    /// Unit direction along fiber-like surfaces such as ``Curve``, used by anisotropic
+    /// materials like ``Hair``. Zero when the surface has no preferred direction.
+    pub tangent: Vec3d,
+
+    /// Color interpolated from the vertex colors of a ``Mesh``, read by the ``VertexColor``
+    /// texture. ``None`` for surfaces without vertex colors.
+    pub color: Option<Color>,
+
+    /// Barycentric coordinates of the hit on a triangle of a ``Mesh``, as the weights of its
+    /// second and third vertex, which its ``u`` and ``v`` are not when the mesh has texture
+    /// coordinates. ``None`` for other surfaces.
+    pub barycentric: Option<(Float, Float)>,
+
+    /// Time of the ray that made the hit, see ``Ray::time``, for textures changing over the
+    /// shutter. Set by the camera, ``0.0`` elsewhere.
+    pub time: Float,
+
     pub material: &'m Material,
+
+    /// Name of the innermost ``Named`` object the hit belongs to, if any.
+    pub name: Option<&'m str>,
+
+    /// Whether a camera ray hit a holdout, see ``VisibilityFlags::holdout``.
+    pub holdout: bool,
 }
 
 impl<'m> HitRecord<'m> {
-    pub fn new(material: &'m Material, t: f64, u: f64, v: f64, point: Point3d) -> Self {
+    pub fn new(material: &'m Material, t: Float, u: Float, v: Float, point: Point3d) -> Self {
         Self {
             t,
             u,
@@ -32,7 +59,13 @@ impl<'m> HitRecord<'m> {
             point,
             normal: Vec3d::zero(),
             front_face: false,
+            tangent: Vec3d::zero(),
+            color: None,
+            barycentric: None,
+            time: 0.0,
             material,
+            name: None,
+            holdout: false,
         }
     }
 
@@ -69,7 +102,13 @@ impl PartialEq for HitRecord<'_> {
             self.point == other.point &&
             self.normal == other.normal &&
             self.front_face == other.front_face &&
-            self.material == other.material
+            self.tangent == other.tangent &&
+            self.color == other.color &&
+            self.barycentric == other.barycentric &&
+            self.time == other.time &&
+            self.material == other.material &&
+            self.name == other.name &&
+            self.holdout == other.holdout
     }
 }
 
@@ -78,11 +117,22 @@ pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord>;
 
     fn bounding_box(&self) -> AABB;
+
+    /// Whether the ray hits anything within ``interval``.
+    ///
+    /// Occlusion queries such as shadow rays only need this answer, so aggregates override it
+    /// to stop at the first hit found instead of searching for the closest one.
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.hit(ray, interval).is_some()
+    }
+
+    /// Name identifying the object in hit records and messages, see ``Named``.
+    fn name(&self) -> Option<&str> { None }
 }
 
 
 pub struct HittableVec {
-    pub objects: Vec<Arc<Box<dyn Hittable>>>,
+    pub objects: Vec<Arc<dyn Hittable>>,
     bbox: AABB,
 }
 
@@ -94,13 +144,22 @@ impl HittableVec {
         }
     }
 
-    pub fn add(&mut self, object: Arc<Box<dyn Hittable>>) {
+    pub fn add(&mut self, object: Arc<dyn Hittable>) {
         self.bbox = AABB::surrounding_box(&self.bbox, &object.bounding_box());
         self.objects.push(object);
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bbox = AABB::EMPTY;
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
     }
 }
 
@@ -118,115 +177,190 @@ impl Hittable for HittableVec {
         hit_record
     }
 
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.objects.iter().any(|object| object.hit_any(ray, interval))
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox.clone()
     }
 }
 
 
+/// A bounding volume hierarchy over a list of objects.
+///
+/// All nodes live in one contiguous ``Vec`` and refer to their children by index, so building
+/// the tree allocates once instead of once per node and traversal stays cache friendly. The
+/// root is the first node; an empty world has no nodes at all.
 pub struct BVHNode {
-    left: Arc<Box<dyn Hittable>>,
-    right: Arc<Box<dyn Hittable>>,
+    nodes: Vec<Node>,
+    objects: Vec<Arc<dyn Hittable>>,
+}
+
+
+/// An inner node of a ``BVHNode``.
+struct Node {
     bbox: AABB,
+    left: Child,
+    /// ``None`` only for the root of a world of one object.
+    right: Option<Child>,
+}
+
+
+/// Index of either another node or an object of a ``BVHNode``.
+#[derive(Clone, Copy)]
+enum Child {
+    Node(usize),
+    Object(usize),
 }
 
 
 impl BVHNode {
+    /// Builds a BVH over all objects of ``hittable_vec``.
+    ///
+    /// An empty list yields an empty world, which has an empty bounding box and never reports
+    /// a hit, so rendering it simply shows the background.
     pub fn from_hittable_vec(hittable_vec: Arc<HittableVec>) -> Self {
-        Self::new(
+        let _span = trace::span("bvh::build");
+        let start = Instant::now();
+        let node = Self::new(
             hittable_vec.objects.clone(),
             0,
             hittable_vec.objects.len(),
-        )
+        );
+        stats::add_time(Counter::BvhBuildNanos, start.elapsed());
+        trace::event!(info, "Built BVH over {} objects in {:?}", hittable_vec.len(), start.elapsed());
+        node
     }
 
     pub fn new(
-        mut hittable_vec: Vec<Arc<Box<dyn Hittable>>>,
+        hittable_vec: Vec<Arc<dyn Hittable>>,
         start: usize,
         end: usize,
     ) -> Self {
+        let mut objects = hittable_vec;
+        objects.truncate(end);
+        objects.drain(..start);
+
+        // Every node has two children, so n objects need n - 1 nodes, or a single one
+        // referring to the only object.
+        let mut nodes = Vec::with_capacity(objects.len().saturating_sub(1).max(1));
+        if !objects.is_empty() {
+            Self::build(&mut nodes, &mut objects, 0);
+        }
+        Self { nodes, objects }
+    }
 
+    /// The objects the BVH was built over, in no particular order.
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    /// Appends the subtree over ``objects``, whose first object has the index ``offset``,
+    /// and returns the index of its root node.
+    fn build(nodes: &mut Vec<Node>, objects: &mut [Arc<dyn Hittable>], offset: usize) -> usize {
         // Sort the hittable objects along the longest axis of the bounding box
         let mut bbox = AABB::EMPTY;
-        for i in start..end {
-            bbox = AABB::surrounding_box(&bbox, &hittable_vec[i].bounding_box());
+        for object in objects.iter() {
+            bbox = AABB::surrounding_box(&bbox, &object.bounding_box());
         }
         let axis = bbox.longest_axis();
 
-        let mut left: Arc<Box<dyn Hittable>>;
-        let mut right: Arc<Box<dyn Hittable>>;
-
-        let object_span = end - start;
+        let index = nodes.len();
+        nodes.push(Node { bbox, left: Child::Object(offset), right: None });
 
-        match object_span {
-            1 => {
-                left = hittable_vec[start].clone();
-                right = hittable_vec[start].clone();
-            }
-            2 => {
-                left = hittable_vec[start].clone();
-                right = hittable_vec[start + 1].clone();
-            }
-            _ => {
-                let mut hit_vec = hittable_vec.clone();
-                hit_vec.sort_by(|a, b| {
+        match objects.len() {
+            1 => {}
+            2 => nodes[index].right = Some(Child::Object(offset + 1)),
+            object_span => {
+                objects.sort_by(|a, b| {
                     BVHNode::box_compare(a, b, axis)
                 });
 
-                let mid = start + object_span / 2;
-
-                let right_hittable = hittable_vec.drain(mid..end).collect();
-                let left_hittable = hittable_vec.drain(start..mid).collect();
-
-                left = Arc::new(Box::new(BVHNode::new(left_hittable, start - start, mid - start)));
-                right = Arc::new(Box::new(BVHNode::new(right_hittable, mid - mid, end - mid)));
+                let mid = object_span / 2;
+                let (left, right) = objects.split_at_mut(mid);
+                nodes[index].left = Self::build_child(nodes, left, offset);
+                nodes[index].right = Some(Self::build_child(nodes, right, offset + mid));
             }
         }
+        index
+    }
 
-        Self { left, right, bbox }
+    /// Single objects are referred to directly rather than through a node of their own.
+    fn build_child(nodes: &mut Vec<Node>, objects: &mut [Arc<dyn Hittable>], offset: usize) -> Child {
+        if objects.len() == 1 {
+            Child::Object(offset)
+        } else {
+            Child::Node(Self::build(nodes, objects, offset))
+        }
     }
 
     fn box_compare(
-        box_a: &Arc<Box<dyn Hittable>>,
-        box_b: &Arc<Box<dyn Hittable>>,
+        box_a: &Arc<dyn Hittable>,
+        box_b: &Arc<dyn Hittable>,
         axis: usize,
     ) -> Ordering {
         let a_axis_interval = box_a.bounding_box().axis_interval(axis);
         let b_axis_interval = box_b.bounding_box().axis_interval(axis);
-        a_axis_interval.min.partial_cmp(&b_axis_interval.min).unwrap()
+        // Degenerate (NaN) boxes compare as equal instead of aborting the build.
+        a_axis_interval.min.partial_cmp(&b_axis_interval.min).unwrap_or(Ordering::Equal)
     }
-}
 
-impl Hittable for BVHNode {
-    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
-        if !self.bbox.hit(ray, interval) {
+    fn hit_node(&self, index: usize, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        stats::count(Counter::BvhNodesVisited);
+        let node = &self.nodes[index];
+        if !node.bbox.hit(ray, interval) {
             return None;
         }
 
-        let hit_left = self.left.hit(ray, interval);
+        let hit_left = self.hit_child(node.left, ray, interval);
+        let Some(right) = node.right else { return hit_left; };
 
-        let mut right_interval = Interval {
+        // The right child only needs to find hits closer than the left one.
+        let right_interval = Interval {
             min: interval.min,
-            max: if hit_left.is_some() { hit_left?.t } else { interval.max },
+            max: hit_left.as_ref().map_or(interval.max, |rec| rec.t),
         };
-        let hit_right = self.right.hit(ray, &mut right_interval);
-
-        // Return the closest hit if both left and right hits are Some
-        if hit_left.is_some() && hit_right.is_some() {
-            if hit_left?.t < hit_right?.t {
-                hit_left
-            } else {
-                hit_right
-            }
-        } else if hit_left.is_some() {
-            hit_left
-        } else {
-            hit_right
+        self.hit_child(right, ray, &right_interval).or(hit_left)
+    }
+
+    fn hit_child(&self, child: Child, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        match child {
+            Child::Node(index) => self.hit_node(index, ray, interval),
+            Child::Object(index) => self.objects[index].hit(ray, interval),
         }
     }
 
+    fn hit_any_node(&self, index: usize, ray: &Ray, interval: &Interval) -> bool {
+        stats::count(Counter::BvhNodesVisited);
+        let node = &self.nodes[index];
+        node.bbox.hit(ray, interval)
+            && (self.hit_any_child(node.left, ray, interval)
+                || node.right.is_some_and(|right| self.hit_any_child(right, ray, interval)))
+    }
+
+    fn hit_any_child(&self, child: Child, ray: &Ray, interval: &Interval) -> bool {
+        match child {
+            Child::Node(index) => self.hit_any_node(index, ray, interval),
+            Child::Object(index) => self.objects[index].hit_any(ray, interval),
+        }
+    }
+}
+
+impl Hittable for BVHNode {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        self.hit_node(0, ray, interval)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        !self.nodes.is_empty() && self.hit_any_node(0, ray, interval)
+    }
+
     fn bounding_box(&self) -> AABB {
-        self.bbox.clone()
+        self.nodes.first().map_or(AABB::EMPTY, |node| node.bbox)
     }
 }
 
@@ -247,8 +381,8 @@ mod bvh_node_test {
 
         let sphere_box = sphere.bounding_box();
 
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(sphere)),
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(sphere),
         ];
         let node = BVHNode::new(
             object_vec.clone(),
@@ -268,19 +402,45 @@ mod bvh_node_test {
         assert_eq!(bbox, sphere_box);
     }
 
+    #[test]
+    fn test_bvh_node_one_object_tested_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counted(Sphere, AtomicUsize);
+
+        impl Hittable for Counted {
+            fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+                self.1.fetch_add(1, Ordering::Relaxed);
+                self.0.hit(ray, interval)
+            }
+
+            fn bounding_box(&self) -> AABB {
+                self.0.bounding_box()
+            }
+        }
+
+        let sphere = Sphere::static_sphere(Vec3d::zero(), 1.0, Material::Empty(Empty {}));
+        let counted = Arc::new(Counted(sphere, AtomicUsize::new(0)));
+        let node = BVHNode::new(vec![counted.clone()], 0, 1);
+        let ray = Ray::new(Vec3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(node.hit(&ray, &Interval::UNIVERSE).is_some());
+        assert!(node.hit_any(&ray, &Interval::UNIVERSE));
+        assert_eq!(counted.1.load(Ordering::Relaxed), 2);
+    }
+
     #[test]
     fn test_bvh_node_two_sphere_bounding_box() {
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(Sphere::static_sphere(
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(Sphere::static_sphere(
                 Vec3d::new(-1.0, 0.0, -1.0),
                 1.0,
                 Material::Empty(Empty {}),
-            ))),
-            Arc::new(Box::new(Sphere::static_sphere(
+            )),
+            Arc::new(Sphere::static_sphere(
                 Vec3d::new(1.0, 1.0, 0.0),
                 1.0,
                 Material::Empty(Empty {}),
-            ))),
+            )),
         ];
         let node = BVHNode::new(
             object_vec.clone(),
@@ -309,8 +469,8 @@ mod bvh_node_test {
 
         let quad_box = quad.bounding_box();
 
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(quad)),
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(quad),
         ];
 
         let node = BVHNode::new(
@@ -331,16 +491,16 @@ mod bvh_node_test {
 
     #[test]
     fn test_bvh_node_box_compare() {
-        let a: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Sphere::static_sphere(
+        let a: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
             Vec3d::new(-1.0, 0.0, -1.0),
             1.0,
             Material::Empty(Empty {}),
-        )));
-        let b: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Sphere::static_sphere(
+        ));
+        let b: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
             Vec3d::new(1.0, -1.0, 0.0),
             2.0,
             Material::Empty(Empty {}),
-        )));
+        ));
 
         let result_0 = BVHNode::box_compare(&a, &b, 0);
         let result_1 = BVHNode::box_compare(&a, &b, 1);
@@ -352,17 +512,17 @@ mod bvh_node_test {
 
     #[test]
     fn test_bvh_node_box_sort() {
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(Sphere::static_sphere(
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(Sphere::static_sphere(
                 Vec3d::new(-1.0, 0.0, -1.0),
                 1.0,
                 Material::Empty(Empty {}),
-            ))),
-            Arc::new(Box::new(Sphere::static_sphere(
+            )),
+            Arc::new(Sphere::static_sphere(
                 Vec3d::new(1.0, -1.0, 0.0),
                 2.0,
                 Material::Empty(Empty {}),
-            ))),
+            )),
         ];
 
         let mut object_vec_clone = object_vec.clone();
@@ -384,12 +544,12 @@ mod bvh_node_test {
 
     #[test]
     fn test_bvh_node_hit_one_sphere() {
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(Sphere::static_sphere(
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(Sphere::static_sphere(
                 Vec3d::new(0.0, 0.0, 0.0),
                 1.0,
                 Material::Empty(Empty {}),
-            ))),
+            )),
         ];
 
         let node = BVHNode::new(
@@ -403,7 +563,7 @@ mod bvh_node_test {
             Vec3d::new(1.0, 0.0, 0.0),
             0.0,
         );
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
 
         let hit_record = node.hit(&ray, &interval);
         let original_object_hit_record = object_vec[0].hit(&ray, &interval);
@@ -412,15 +572,132 @@ mod bvh_node_test {
         assert_eq!(hit_record.unwrap(), original_object_hit_record.unwrap());
     }
 
+    #[test]
+    fn test_bvh_node_empty_world() {
+        let node = BVHNode::from_hittable_vec(Arc::new(HittableVec::new()));
+        assert_eq!(node.bounding_box(), AABB::EMPTY);
+
+        let ray = Ray::new(
+            Vec3d::new(0.0, 0.0, 0.0),
+            Vec3d::new(1.0, 1.0, 1.0),
+            0.0,
+        );
+        assert!(node.hit(&ray, &Interval::UNIVERSE).is_none());
+        assert!(!node.hit_any(&ray, &Interval::UNIVERSE));
+    }
+
+    #[test]
+    fn test_bvh_node_hit_any_matches_hit() {
+        let mut world = HittableVec::new();
+        for i in 0..8 {
+            world.add(Arc::new(Sphere::static_sphere(
+                Vec3d::new(i as Float * 3.0, 0.0, -5.0),
+                1.0,
+                Material::Empty(Empty {}),
+            )));
+        }
+        let world = Arc::new(world);
+        let node = BVHNode::from_hittable_vec(world.clone());
+
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
+        for target in [Vec3d::new(9.0, 0.0, -5.0), Vec3d::new(10.5, 0.0, -5.0), Vec3d::new(0.0, 3.0, -5.0)] {
+            let ray = Ray::new(origin, target - origin, 0.0);
+            let expected = world.hit(&ray, &interval).is_some();
+            assert_eq!(world.hit_any(&ray, &interval), expected);
+            assert_eq!(node.hit_any(&ray, &interval), expected);
+        }
+
+        // Occluders beyond the end of the interval are ignored.
+        let ray = Ray::new(origin, Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(node.hit_any(&ray, &interval));
+        assert!(!node.hit_any(&ray, &Interval { min: 0.0, max: 3.0 }));
+    }
+
+    #[test]
+    fn test_bvh_node_arena_matches_linear_scan() {
+        let mut rng = rand::rng();
+        for count in [1, 2, 3, 7, 100] {
+            let mut world = HittableVec::new();
+            for _ in 0..count {
+                world.add(Arc::new(Sphere::static_sphere(
+                    Vec3d::new(rng.random_range(-10.0..10.0), rng.random_range(-10.0..10.0), rng.random_range(-10.0..10.0)),
+                    rng.random_range(0.1..1.0),
+                    Material::Empty(Empty {}),
+                )));
+            }
+            let world = Arc::new(world);
+            let node = BVHNode::from_hittable_vec(world.clone());
+            assert_eq!(node.nodes.len(), (count - 1).max(1));
+            assert_eq!(node.bounding_box(), world.bounding_box());
+
+            for _ in 0..50 {
+                let ray = Ray::new(Vec3d::zero(), Vec3d::random_unit_vector(), 0.0);
+                let interval = Interval { min: 0.001, max: Float::INFINITY };
+                let expected = world.hit(&ray, &interval).map(|rec| rec.t);
+                assert_eq!(node.hit(&ray, &interval).map(|rec| rec.t), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bvh_node_from_one_object_vec() {
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Vec3d::new(0.0, 0.0, -2.0),
+            1.0,
+            Material::Empty(Empty {}),
+        )));
+        let node = BVHNode::from_hittable_vec(Arc::new(world));
+
+        let ray = Ray::new(
+            Vec3d::new(0.0, 0.0, 0.0),
+            Vec3d::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit_record = node.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+        assert_eq!(hit_record.t, 1.0);
+    }
+
+    #[test]
+    fn test_bvh_node_box_compare_nan() {
+        let a: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
+            Vec3d::new(Float::NAN, 0.0, 0.0),
+            1.0,
+            Material::Empty(Empty {}),
+        ));
+        let b: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
+            Vec3d::new(1.0, 0.0, 0.0),
+            1.0,
+            Material::Empty(Empty {}),
+        ));
+        assert_eq!(BVHNode::box_compare(&a, &b, 0), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_hittable_vec_clear() {
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(
+            Vec3d::new(0.0, 0.0, 0.0),
+            1.0,
+            Material::Empty(Empty {}),
+        )));
+        assert_eq!(world.len(), 1);
+
+        world.clear();
+        assert!(world.is_empty());
+        assert_eq!(world.bounding_box(), AABB::EMPTY);
+    }
+
     #[test]
     fn test_bvh_node_hit_one_quad_1() {
-        let object_vec: Vec<Arc<Box<dyn Hittable>>> = vec![
-            Arc::new(Box::new(Quad::new(
+        let object_vec: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(Quad::new(
                 Point3d::zero(),
                 Vec3d::new(1.0, 0.0, 0.0),
                 Vec3d::new(0.0, 1.0, 0.0),
                 Material::Empty(Empty {}),
-            ))),
+            )),
         ];
 
         let node = BVHNode::new(
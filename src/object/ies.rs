@@ -0,0 +1,185 @@
+use crate::vec3d::Float;
+use crate::error::{Error, Result};
+
+
+/// Angular intensity distribution of a real light fixture, read from an IES LM-63 photometric
+/// file as published by lighting manufacturers.
+///
+/// Only type C photometry, the usual one for architectural fixtures, is supported: vertical
+/// angles start at ``0`` straight down the fixture's axis and reach ``180`` straight up, horizontal
+/// angles go around the axis. Intensities are normalized to a peak of ``1``, so the light a
+/// profile is applied to still sets the brightness, see ``PointLight::with_profile``.
+/// # Examples
+/// ```
+/// use ray_tracing::object::IesProfile;
+/// // A downlight, dimmer towards the sides and dark above the horizon.
+/// let profile = IesProfile::parse("IESNA:LM-63-2002
+/// [MANUFAC] Example
+/// TILT=NONE
+/// 1 1000 1 3 1 1 2 0 0 0
+/// 1 1 100
+/// 0 45 90
+/// 0
+/// 800 400 0
+/// ").unwrap();
+/// assert!((profile.intensity(0.0, 0.0) - 1.0).abs() < 1e-9);
+/// assert!((profile.intensity(22.5, 0.0) - 0.75).abs() < 1e-9);
+/// assert_eq!(profile.intensity(120.0, 0.0), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct IesProfile {
+    vertical: Vec<Float>,
+    horizontal: Vec<Float>,
+    /// Normalized intensities, ``vertical.len()`` values per horizontal angle.
+    candela: Vec<Float>,
+}
+
+
+impl IesProfile {
+    /// Reads an IES file, see ``parse``.
+    pub fn read(path: &str) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|source| Error::Io { file: path.to_string(), source })?;
+        Self::parse(&source)
+    }
+
+    /// Parses the contents of an IES LM-63 file with ``TILT=NONE``.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut lines = source.lines().enumerate().map(|(number, text)| (number + 1, text));
+        let tilt_line = loop {
+            match lines.next() {
+                Some((line, text)) if text.trim_start().starts_with("TILT=") => {
+                    if text.trim() != "TILT=NONE" {
+                        return Err(Error::Parse { line, message: format!("unsupported {}", text.trim()) });
+                    }
+                    break line;
+                }
+                Some(_) => {}
+                None => return Err(Error::Parse { line: 0, message: "missing TILT line".to_string() }),
+            }
+        };
+
+        // The rest is a flat list of numbers, wrapped over lines at will.
+        let mut numbers = Vec::new();
+        for (line, text) in lines {
+            for field in text.split_whitespace() {
+                let value: Float = field.parse()
+                    .map_err(|_| Error::Parse { line, message: format!("invalid number {:?}", field) })?;
+                numbers.push(value);
+            }
+        }
+        let missing = || Error::Parse { line: tilt_line, message: "too few values after TILT".to_string() };
+        if numbers.len() < 13 { return Err(missing()); }
+
+        let multiplier = numbers[2];
+        let vertical_count = numbers[3] as usize;
+        let horizontal_count = numbers[4] as usize;
+        if numbers[5] != 1.0 {
+            return Err(Error::Parse { line: tilt_line + 1, message: "only type C photometry is supported".to_string() });
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(Error::Parse { line: tilt_line + 1, message: "no angles given".to_string() });
+        }
+
+        let values = &numbers[13..];
+        let angles = vertical_count + horizontal_count;
+        if values.len() < angles + vertical_count * horizontal_count { return Err(missing()); }
+        let vertical = values[..vertical_count].to_vec();
+        let horizontal = values[vertical_count..angles].to_vec();
+        let mut candela: Vec<Float> = values[angles..angles + vertical_count * horizontal_count]
+            .iter()
+            .map(|value| value * multiplier)
+            .collect();
+
+        let peak = candela.iter().cloned().fold(0.0, Float::max);
+        if peak > 0.0 {
+            candela.iter_mut().for_each(|value| *value /= peak);
+        }
+        Ok(Self { vertical, horizontal, candela })
+    }
+
+    /// Normalized intensity at the ``vertical`` angle from the fixture's axis and the
+    /// ``horizontal`` angle around it, both in degrees. Zero outside the measured range.
+    pub fn intensity(&self, vertical: Float, horizontal: Float) -> Float {
+        // Fixtures with symmetries only list the distinct part of the horizontal range.
+        let last = *self.horizontal.last().unwrap();
+        let horizontal = horizontal.rem_euclid(360.0);
+        let horizontal = if last <= 0.0 {
+            0.0
+        } else if last <= 90.0 {
+            let folded = horizontal % 180.0;
+            if folded > 90.0 { 180.0 - folded } else { folded }
+        } else if last <= 180.0 {
+            if horizontal > 180.0 { 360.0 - horizontal } else { horizontal }
+        } else {
+            horizontal
+        };
+
+        let Some((v0, v1, tv)) = bracket(&self.vertical, vertical) else { return 0.0; };
+        let (h0, h1, th) = bracket(&self.horizontal, horizontal).unwrap_or((0, 0, 0.0));
+        let value = |h: usize, v: usize| self.candela[h * self.vertical.len() + v];
+        let at_h0 = value(h0, v0) * (1.0 - tv) + value(h0, v1) * tv;
+        let at_h1 = value(h1, v0) * (1.0 - tv) + value(h1, v1) * tv;
+        at_h0 * (1.0 - th) + at_h1 * th
+    }
+}
+
+
+/// Indices of the angles around ``angle`` in the ascending ``angles`` and the interpolation
+/// factor between them, ``None`` outside of the range.
+fn bracket(angles: &[Float], angle: Float) -> Option<(usize, usize, Float)> {
+    let (first, last) = (angles[0], angles[angles.len() - 1]);
+    if angles.len() == 1 || angle <= first {
+        return if (angle - first).abs() < 1e-9 || angles.len() == 1 { Some((0, 0, 0.0)) } else { None };
+    }
+    if angle >= last {
+        return if (angle - last).abs() < 1e-9 { Some((angles.len() - 1, angles.len() - 1, 0.0)) } else { None };
+    }
+    let upper = angles.partition_point(|&a| a <= angle);
+    let lower = upper - 1;
+    Some((lower, upper, (angle - angles[lower]) / (angles[upper] - angles[lower])))
+}
+
+
+#[cfg(test)]
+mod test_ies {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Brighter towards horizontal angle 90 than 0, quadrant symmetric.
+    const QUADRANT: &str = "IESNA:LM-63-1995
+TILT=NONE
+1 -1 2 2 2 1 1 0 0 0
+1 1 50
+0 90
+0 90
+100 100
+200 0
+";
+
+    #[test]
+    fn test_ies_quadrant_symmetry() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+        assert_approx_eq!(profile.intensity(0.0, 0.0), 0.5);
+        assert_approx_eq!(profile.intensity(0.0, 90.0), 1.0);
+        assert_approx_eq!(profile.intensity(0.0, 45.0), 0.75);
+        // Mirrored into the first quadrant.
+        assert_approx_eq!(profile.intensity(0.0, 270.0), 1.0);
+        assert_approx_eq!(profile.intensity(0.0, 180.0), 0.5);
+        assert_approx_eq!(profile.intensity(90.0, 90.0), 0.0);
+        assert_eq!(profile.intensity(135.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_ies_errors() {
+        assert!(matches!(IesProfile::parse("IESNA:LM-63-2002\n"), Err(Error::Parse { .. })));
+        assert!(matches!(
+            IesProfile::parse("IESNA:LM-63-2002\nTILT=INCLUDE\n"),
+            Err(Error::Parse { line: 2, .. }),
+        ));
+        assert!(matches!(
+            IesProfile::parse(&QUADRANT.replace("200 0", "200")),
+            Err(Error::Parse { line: 2, .. }),
+        ));
+    }
+}
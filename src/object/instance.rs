@@ -1,19 +1,378 @@
-use crate::vec3d::{Vec3d, Point3d};
-use super::{HitRecord, Hittable};
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
+use super::{Emitter, EmitterSample, HitRecord, Hittable};
+use crate::object::material::Material;
 use crate::object::aabb::AABB;
-use crate::ray::{Interval, Ray};
+use crate::ray::{Interval, Ray, RayKind};
+use crate::error::{Error, Result};
 
 use std::sync::Arc;
 
+/// Attaches a name to an object, reported in the ``HitRecord`` of every hit on it.
+///
+/// Names are meant for picking, object ID passes and readable error messages. When named
+/// objects are nested, e.g. a named wheel inside a named car, hits report the innermost name.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Hittable, Named, Sphere};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+/// let named = Named::new(Arc::new(sphere), "ball");
+/// let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// let rec = named.hit(&ray, &Interval::UNIVERSE).unwrap();
+/// assert_eq!(rec.name, Some("ball"));
+/// assert_eq!(named.name(), Some("ball"));
+/// ```
+pub struct Named {
+    name: String,
+    object: Arc<dyn Hittable>,
+}
+
+
+impl Named {
+    pub fn new(object: Arc<dyn Hittable>, name: impl Into<String>) -> Self {
+        Self { name: name.into(), object }
+    }
+}
+
+
+impl Hittable for Named {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let mut hit_record = self.object.hit(ray, interval)?;
+        hit_record.name.get_or_insert(&self.name);
+        Some(hit_record)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.object.hit_any(ray, interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.object.bounding_box()
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+
+/// Which kinds of rays can see an object, see ``Visibility``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisibilityFlags {
+    /// Seen directly by camera rays.
+    pub camera_visible: bool,
+    /// Blocks shadow rays.
+    pub casts_shadow: bool,
+    /// Seen by rays scattered off other objects, e.g. in mirrors or as indirect light.
+    pub visible_in_reflections: bool,
+    /// Seen by camera rays as a hole in the image, black with an alpha of ``0``, see
+    /// ``RenderOutput::alpha``. Other rays see the object as usual, so it still casts shadows
+    /// and shows up in reflections, e.g. for a stand-in of a real object of the plate a
+    /// render is composited over.
+    pub holdout: bool,
+}
+
+
+impl VisibilityFlags {
+    /// Visible to every kind of ray.
+    pub const ALL: Self = Self { camera_visible: true, casts_shadow: true, visible_in_reflections: true, holdout: false };
+
+    /// Whether rays of the given kind can see the object.
+    pub fn allows(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera_visible,
+            RayKind::Reflection => self.visible_in_reflections,
+            RayKind::Shadow => self.casts_shadow,
+        }
+    }
+}
+
+
+impl Default for VisibilityFlags {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+
+/// Hides an object from some kinds of rays, based on ``Ray::kind``.
+///
+/// Rays the object is hidden from pass straight through it. This allows the usual production
+/// tricks, such as emitters lighting the scene without showing up in the frame, or blockers
+/// shaping the light without casting a visible shadow.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Hittable, Sphere, Visibility, VisibilityFlags};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, RayKind, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+/// let flags = VisibilityFlags { camera_visible: false, ..Default::default() };
+/// let hidden = Visibility::new(Arc::new(sphere), flags);
+/// let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// assert!(hidden.hit(&ray, &Interval::UNIVERSE).is_none());
+/// assert!(hidden.hit(&ray.with_kind(RayKind::Reflection), &Interval::UNIVERSE).is_some());
+/// ```
+pub struct Visibility {
+    flags: VisibilityFlags,
+    object: Arc<dyn Hittable>,
+}
+
+
+impl Visibility {
+    pub fn new(object: Arc<dyn Hittable>, flags: VisibilityFlags) -> Self {
+        Self { flags, object }
+    }
+
+    pub fn flags(&self) -> VisibilityFlags {
+        self.flags
+    }
+}
+
+
+impl Hittable for Visibility {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        if !self.flags.allows(ray.kind) { return None; }
+        let mut hit_record = self.object.hit(ray, interval)?;
+        hit_record.holdout |= self.flags.holdout && ray.kind == RayKind::Camera;
+        Some(hit_record)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.flags.allows(ray.kind) && self.object.hit_any(ray, interval)
+    }
+
+    /// The full bounds of the object, so the flags can be checked per ray.
+    fn bounding_box(&self) -> AABB {
+        self.object.bounding_box()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.object.name()
+    }
+}
+
+
+#[cfg(test)]
+mod test_visibility {
+    use super::*;
+    use crate::object::{Sphere, HittableVec};
+    use crate::object::material::{Material, Empty};
+
+    fn sphere_at(z: Float) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::static_sphere(Point3d::new(0.0, 0.0, z), 1.0, Material::Empty(Empty {})))
+    }
+
+    #[test]
+    fn test_visibility_flags_allows() {
+        let flags = VisibilityFlags { casts_shadow: false, ..Default::default() };
+        assert!(flags.allows(RayKind::Camera));
+        assert!(flags.allows(RayKind::Reflection));
+        assert!(!flags.allows(RayKind::Shadow));
+    }
+
+    #[test]
+    fn test_hidden_object_is_passed_through() {
+        let mut world = HittableVec::new();
+        let blocker = VisibilityFlags { casts_shadow: false, ..Default::default() };
+        world.add(Arc::new(Visibility::new(sphere_at(0.0), blocker)));
+        world.add(sphere_at(-5.0));
+
+        let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(world.hit(&ray, &Interval::UNIVERSE).unwrap().t, 4.0);
+
+        let shadow = ray.with_kind(RayKind::Shadow);
+        assert_eq!(world.hit(&shadow, &Interval::UNIVERSE).unwrap().t, 9.0);
+        assert!(!world.hit_any(&shadow, &Interval { min: 0.0, max: 8.0 }));
+    }
+
+    #[test]
+    fn test_holdout_only_for_camera_rays() {
+        let holdout = VisibilityFlags { holdout: true, ..Default::default() };
+        let matte = Visibility::new(sphere_at(0.0), holdout);
+        let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(matte.hit(&ray, &Interval::UNIVERSE).unwrap().holdout);
+        assert!(!matte.hit(&ray.with_kind(RayKind::Reflection), &Interval::UNIVERSE).unwrap().holdout);
+        assert!(matte.hit_any(&ray.with_kind(RayKind::Shadow), &Interval::UNIVERSE));
+    }
+
+    #[test]
+    fn test_ray_kind_kept_through_instances() {
+        let hidden = VisibilityFlags { visible_in_reflections: false, ..Default::default() };
+        let object: Arc<dyn Hittable> = Arc::new(Visibility::new(sphere_at(0.0), hidden));
+        let moved = Transform::new(object, [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 2.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let ray = Ray::new(Point3d::new(0.0, 2.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(moved.hit_any(&ray, &Interval::UNIVERSE));
+        assert!(!moved.hit_any(&ray.with_kind(RayKind::Reflection), &Interval::UNIVERSE));
+    }
+}
+
+
+/// An object with a simplified proxy standing in for it where the detail can't be seen, e.g.
+/// a sphere for a dense mesh far away or on indirect bounces.
+///
+/// The proxy is hit instead of the object by rays starting farther than ``with_distance``
+/// from the center of the object, and by rays after ``with_bounces`` bounces or more. Without
+/// either set, the object is always used.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Hittable, LevelOfDetail, Sphere};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let detailed = Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {})));
+/// let proxy = Arc::new(Sphere::static_sphere(Point3d::zero(), 0.9, Material::Empty(Empty {})));
+/// let object = LevelOfDetail::new(detailed, proxy).with_bounces(1);
+///
+/// let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// assert_eq!(object.hit(&ray, &Interval::UNIVERSE).unwrap().t, 4.0);
+/// let t = object.hit(&ray.with_depth(1), &Interval::UNIVERSE).unwrap().t;
+/// assert!((t - 4.1).abs() < 1e-9);
+/// ```
+pub struct LevelOfDetail {
+    object: Arc<dyn Hittable>,
+    proxy: Arc<dyn Hittable>,
+    distance: Option<Float>,
+    bounces: Option<u32>,
+    center: Point3d,
+    bbox: AABB,
+}
+
+
+impl LevelOfDetail {
+    pub fn new(object: Arc<dyn Hittable>, proxy: Arc<dyn Hittable>) -> Self {
+        let center = object.bounding_box().center();
+        let bbox = AABB::surrounding_box(&object.bounding_box(), &proxy.bounding_box());
+        Self { object, proxy, distance: None, bounces: None, center, bbox }
+    }
+
+    /// Uses the proxy for rays starting farther than ``distance`` from the object.
+    pub fn with_distance(mut self, distance: Float) -> Self {
+        self.distance = Some(distance);
+        self
+    }
+
+    /// Uses the proxy for rays after ``bounces`` bounces or more.
+    pub fn with_bounces(mut self, bounces: u32) -> Self {
+        self.bounces = Some(bounces);
+        self
+    }
+
+    fn select(&self, ray: &Ray) -> &Arc<dyn Hittable> {
+        let far = self.distance
+            .is_some_and(|distance| (ray.origin - self.center).length_squared() > distance * distance);
+        let deep = self.bounces.is_some_and(|bounces| ray.depth >= bounces);
+        if far || deep { &self.proxy } else { &self.object }
+    }
+}
+
+
+impl Hittable for LevelOfDetail {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        self.select(ray).hit(ray, interval)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.select(ray).hit_any(ray, interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.object.name()
+    }
+}
+
+
+#[cfg(test)]
+mod test_level_of_detail {
+    use super::*;
+    use crate::object::Sphere;
+    use crate::object::material::{Material, Empty};
+
+    fn sphere(radius: Float) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::static_sphere(Point3d::zero(), radius, Material::Empty(Empty {})))
+    }
+
+    #[test]
+    fn test_proxy_beyond_distance() {
+        let object = LevelOfDetail::new(sphere(1.0), sphere(0.5)).with_distance(10.0);
+        assert_eq!(object.bounding_box(), sphere(1.0).bounding_box());
+
+        let near = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(object.hit(&near, &Interval::UNIVERSE).unwrap().t, 4.0);
+        // Deep rays still see the object as no bounce limit is set.
+        assert_eq!(object.hit(&near.with_depth(8), &Interval::UNIVERSE).unwrap().t, 4.0);
+
+        let far = Ray::new(Point3d::new(0.0, 0.0, 20.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(object.hit(&far, &Interval::UNIVERSE).unwrap().t, 19.5);
+
+        // A ray grazing the object misses the smaller proxy.
+        let grazing = Ray::new(Point3d::new(0.0, 0.75, 20.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(!object.hit_any(&grazing, &Interval::UNIVERSE));
+    }
+
+    #[test]
+    fn test_depth_kept_through_instances() {
+        let object: Arc<dyn Hittable> = Arc::new(LevelOfDetail::new(sphere(1.0), sphere(0.5)).with_bounces(2));
+        let moved = Translate::new(object, Vec3d::new(0.0, 3.0, 0.0));
+        let ray = Ray::new(Point3d::new(0.0, 3.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(moved.hit(&ray.with_depth(1), &Interval::UNIVERSE).unwrap().t, 4.0);
+        assert_eq!(moved.hit(&ray.with_depth(2), &Interval::UNIVERSE).unwrap().t, 4.5);
+    }
+}
+
+
+#[cfg(test)]
+mod test_named {
+    use super::*;
+    use crate::object::Sphere;
+    use crate::object::material::{Material, Empty};
+
+    #[test]
+    fn test_named_innermost_wins() {
+        let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+        let wheel: Arc<dyn Hittable> = Arc::new(Named::new(Arc::new(sphere), "wheel"));
+        let car = Named::new(Arc::new(Translate::new(wheel, Vec3d::new(1.0, 0.0, 0.0))), "car");
+
+        let ray = Ray::new(Point3d::new(1.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        let rec = car.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert_eq!(rec.name, Some("wheel"));
+        assert_eq!(car.name(), Some("car"));
+    }
+
+    #[test]
+    fn test_unnamed_hit() {
+        let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(sphere.hit(&ray, &Interval::UNIVERSE).unwrap().name, None);
+        assert_eq!(sphere.name(), None);
+    }
+}
+
+
 pub struct Translate {
     offset: Vec3d,
-    object: Arc<Box<dyn Hittable>>,
+    object: Arc<dyn Hittable>,
     bbox: AABB,
 }
 
 
 impl Translate {
-    pub fn new(object: Arc<Box<dyn Hittable>>, offset: Vec3d) -> Self {
+    pub fn new(object: Arc<dyn Hittable>, offset: Vec3d) -> Self {
         let bbox = object.bounding_box() + offset;
         Self {
             offset,
@@ -21,16 +380,20 @@ impl Translate {
             bbox,
         }
     }
+
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            ray.origin - self.offset,
+            ray.direction,
+            ray.time,
+        ).with_kind(ray.kind).with_depth(ray.depth)
+    }
 }
 
 
 impl Hittable for Translate {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
-        let offset_ray = Ray::new(
-            ray.origin - self.offset,
-            ray.direction,
-            ray.time,
-        );
+        let offset_ray = self.object_ray(ray);
 
         if let Some(mut hit_record) = self.object.hit(&offset_ray, interval) {
             hit_record.point += self.offset;
@@ -40,6 +403,10 @@ impl Hittable for Translate {
         }
     }
 
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.object.hit_any(&self.object_ray(ray), interval)
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
@@ -62,9 +429,7 @@ mod test_translate {
             Vec3d::new(0.0, 1.0, 0.0),
             Material::Empty(material::Empty {}),
         );
-        let translate = Translate::new(Arc::new(
-            Box::new(quad)), Vec3d::new(1.0, 0.0, 0.0),
-        );
+        let translate = Translate::new(Arc::new(quad), Vec3d::new(1.0, 0.0, 0.0));
 
         assert_eq!(
             translate.bounding_box(),
@@ -78,41 +443,39 @@ mod test_translate {
 
 
 pub struct RotateY {
-    object: Arc<Box<dyn Hittable>>,
-    sin_theta: f64,
-    cos_theta: f64,
+    object: Arc<dyn Hittable>,
+    sin_theta: Float,
+    cos_theta: Float,
     bbox: AABB,
 }
 
 
 impl RotateY {
-    pub fn new(object: Arc<Box<dyn Hittable>>, angle: f64) -> Self {
+    pub fn new(object: Arc<dyn Hittable>, angle: Float) -> Self {
         let radians = angle.to_radians();
         let sin_theta = radians.sin();
         let cos_theta = radians.cos();
 
         let bbox = object.bounding_box();
-        let mut min = Point3d::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
-        let mut max = Point3d::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        let mut min = Point3d::new(Float::INFINITY, Float::INFINITY, Float::INFINITY);
+        let mut max = Point3d::new(Float::NEG_INFINITY, Float::NEG_INFINITY, Float::NEG_INFINITY);
 
         for i in 0..2 {
             for j in 0..2 {
                 for k in 0..2 {
-                    let x = i as f64 * bbox.axis_interval(0).max +
-                        (1 - i) as f64 * bbox.axis_interval(0).min;
-                    let y = j as f64 * bbox.axis_interval(1).max +
-                        (1 - j) as f64 * bbox.axis_interval(1).min;
-                    let z = k as f64 * bbox.axis_interval(2).max +
-                        (1 - k) as f64 * bbox.axis_interval(2).min;
+                    let x = i as Float * bbox.axis_interval(0).max +
+                        (1 - i) as Float * bbox.axis_interval(0).min;
+                    let y = j as Float * bbox.axis_interval(1).max +
+                        (1 - j) as Float * bbox.axis_interval(1).min;
+                    let z = k as Float * bbox.axis_interval(2).max +
+                        (1 - k) as Float * bbox.axis_interval(2).min;
                     let new_x = cos_theta * x + sin_theta * z;
                     let new_z = -sin_theta * x + cos_theta * z;
 
                     let tester = Point3d::new(new_x, y, new_z);
 
-                    for c in 0..3{
-                        min[c] = min[c].min(tester[c]);
-                        max[c] = max[c].max(tester[c]);
-                    }
+                    min = min.min(&tester);
+                    max = max.max(&tester);
                 }
             }
         }
@@ -124,10 +487,8 @@ impl RotateY {
             bbox: AABB::from_points(&min, &max),
         }
     }
-}
 
-impl Hittable for RotateY {
-    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+    fn object_ray(&self, ray: &Ray) -> Ray {
         let origin = Point3d::new(
             self.cos_theta * ray.origin.x() - self.sin_theta * ray.origin.z(),
             ray.origin.y(),
@@ -140,9 +501,15 @@ impl Hittable for RotateY {
             self.sin_theta * ray.direction.x() + self.cos_theta * ray.direction.z(),
         );
 
-        let rotated_ray = Ray::new(
+        Ray::new(
             origin, direction, ray.time,
-        );
+        ).with_kind(ray.kind).with_depth(ray.depth)
+    }
+}
+
+impl Hittable for RotateY {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let rotated_ray = self.object_ray(ray);
 
         if let Some(mut hit_record) = self.object.hit(&rotated_ray, interval) {
             hit_record.point = Point3d::new(
@@ -157,14 +524,948 @@ impl Hittable for RotateY {
                 (-self.sin_theta * hit_record.normal.x() + self.cos_theta * hit_record.normal.z()),
             );
 
+            hit_record.tangent = Vec3d::new(
+                self.cos_theta * hit_record.tangent.x() + self.sin_theta * hit_record.tangent.z(),
+                hit_record.tangent.y(),
+                -self.sin_theta * hit_record.tangent.x() + self.cos_theta * hit_record.tangent.z(),
+            );
+
             Some(hit_record)
         } else {
             None
         }
     }
 
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.object.hit_any(&self.object_ray(ray), interval)
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
 }
 
+
+/// An instance placing its object with an arbitrary affine 4x4 matrix.
+///
+/// The matrix maps object space to world space. Only affine matrices are supported, i.e. the
+/// last row must be ``[0, 0, 0, 1]``; the projective part is ignored.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Hittable, Sphere, Transform};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::vec3d::Vec3d;
+///
+/// let sphere: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
+///     Vec3d::zero(), 1.0, Material::Empty(Empty {}),
+/// ));
+/// // Scale by 2 and move 5 units along x.
+/// let transform = Transform::new(sphere, [
+///     [2.0, 0.0, 0.0, 5.0],
+///     [0.0, 2.0, 0.0, 0.0],
+///     [0.0, 0.0, 2.0, 0.0],
+///     [0.0, 0.0, 0.0, 1.0],
+/// ]);
+/// assert_eq!(transform.transform_point(&Vec3d::new(1.0, 0.0, 0.0)), Vec3d::new(7.0, 0.0, 0.0));
+/// ```
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    matrix: [[Float; 4]; 4],
+    inverse: [[Float; 4]; 4],
+    bbox: AABB,
+}
+
+
+impl Transform {
+    /// Creates a new transform instance from a row-major matrix, where ``matrix[row][col]``.
+    /// # Panics
+    /// If the matrix is not invertible, see ``try_new`` for a fallible version.
+    pub fn new(object: Arc<dyn Hittable>, matrix: [[Float; 4]; 4]) -> Self {
+        Self::try_new(object, matrix).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(object: Arc<dyn Hittable>, matrix: [[Float; 4]; 4]) -> Result<Self> {
+        let inverse = affine_inverse(&matrix)?;
+
+        let bbox = object.bounding_box();
+        let mut min = Point3d::new(Float::INFINITY, Float::INFINITY, Float::INFINITY);
+        let mut max = Point3d::new(Float::NEG_INFINITY, Float::NEG_INFINITY, Float::NEG_INFINITY);
+
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    let corner = Point3d::new(
+                        if i == 0 { bbox.axis_interval(0).min } else { bbox.axis_interval(0).max },
+                        if j == 0 { bbox.axis_interval(1).min } else { bbox.axis_interval(1).max },
+                        if k == 0 { bbox.axis_interval(2).min } else { bbox.axis_interval(2).max },
+                    );
+                    let tester = transform_point(&matrix, &corner);
+
+                    min = min.min(&tester);
+                    max = max.max(&tester);
+                }
+            }
+        }
+
+        Ok(Self {
+            object,
+            matrix,
+            inverse,
+            bbox: AABB::from_points(&min, &max),
+        })
+    }
+
+    /// Creates a new transform instance from a column-major matrix, where ``columns[col][row]``.
+    pub fn from_columns(object: Arc<dyn Hittable>, columns: [[Float; 4]; 4]) -> Self {
+        Self::new(object, transpose(&columns))
+    }
+
+    /// Maps a point from object space to world space.
+    pub fn transform_point(&self, point: &Point3d) -> Point3d {
+        transform_point(&self.matrix, point)
+    }
+
+    /// Maps a direction from object space to world space, ignoring the translation.
+    pub fn transform_vector(&self, vector: &Vec3d) -> Vec3d {
+        transform_vector(&self.matrix, vector)
+    }
+
+    /// Moves the ray into object space. The direction is not normalized, so ``t`` stays valid
+    /// in both spaces.
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            transform_point(&self.inverse, &ray.origin),
+            transform_vector(&self.inverse, &ray.direction),
+            ray.time,
+        ).with_kind(ray.kind).with_depth(ray.depth)
+    }
+}
+
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let object_ray = self.object_ray(ray);
+
+        if let Some(mut hit_record) = self.object.hit(&object_ray, interval) {
+            hit_record.point = transform_point(&self.matrix, &hit_record.point);
+
+            // Normals transform with the inverse transpose to stay perpendicular to the surface.
+            let inverse_transpose = transpose(&self.inverse);
+            hit_record.normal = transform_vector(&inverse_transpose, &hit_record.normal).unit_vector();
+            if !hit_record.tangent.near_zero() {
+                hit_record.tangent = self.transform_vector(&hit_record.tangent).unit_vector();
+            }
+            Some(hit_record)
+        } else {
+            None
+        }
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.object.hit_any(&self.object_ray(ray), interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+fn transform_point(matrix: &[[Float; 4]; 4], point: &Point3d) -> Point3d {
+    transform_vector(matrix, point) + Vec3d::new(matrix[0][3], matrix[1][3], matrix[2][3])
+}
+
+fn transform_vector(matrix: &[[Float; 4]; 4], vector: &Vec3d) -> Vec3d {
+    Vec3d::new(
+        matrix[0][0] * vector.x() + matrix[0][1] * vector.y() + matrix[0][2] * vector.z(),
+        matrix[1][0] * vector.x() + matrix[1][1] * vector.y() + matrix[1][2] * vector.z(),
+        matrix[2][0] * vector.x() + matrix[2][1] * vector.y() + matrix[2][2] * vector.z(),
+    )
+}
+
+fn transpose(matrix: &[[Float; 4]; 4]) -> [[Float; 4]; 4] {
+    let mut result = [[0.0; 4]; 4];
+    for (row, values) in matrix.iter().enumerate() {
+        for (col, value) in values.iter().enumerate() {
+            result[col][row] = *value;
+        }
+    }
+    result
+}
+
+/// Inverts an affine matrix by inverting its linear 3x3 block and the translation separately.
+///
+/// The block counts as singular relative to the lengths of its columns, whose product bounds
+/// the determinant, so scaling a matrix up or down does not change whether it inverts.
+fn affine_inverse(m: &[[Float; 4]; 4]) -> Result<[[Float; 4]; 4]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+
+    let volume: Float = (0..3).map(|c| Vec3d::new(m[0][c], m[1][c], m[2][c]).length()).product();
+    if !det.is_finite() || det.abs() <= Float::EPSILON * volume {
+        return Err(Error::SingularMatrix(det));
+    }
+    let inv_det = 1.0 / det;
+
+    let mut inv = [[0.0; 4]; 4];
+    inv[0][0] = (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det;
+    inv[0][1] = (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det;
+    inv[0][2] = (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det;
+    inv[1][0] = (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det;
+    inv[1][1] = (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det;
+    inv[1][2] = (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det;
+    inv[2][0] = (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det;
+    inv[2][1] = (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det;
+    inv[2][2] = (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det;
+
+    let translation = transform_vector(&inv, &Vec3d::new(m[0][3], m[1][3], m[2][3]));
+    inv[0][3] = -translation.x();
+    inv[1][3] = -translation.y();
+    inv[2][3] = -translation.z();
+    inv[3][3] = 1.0;
+    Ok(inv)
+}
+
+
+/// Largest difference, relative to the squared scale, between the squared lengths of the axes
+/// of a uniformly scaling transform, or their dot products, see ``TransformedEmitter``.
+const UNIFORM_SCALE_TOLERANCE: Float = 1e-4;
+
+
+/// An emitter moved by a transform like ``Transform`` moves objects, to register the light of
+/// a transformed instance with ``Camera::add_light``.
+///
+/// The matrix may only rotate, move and scale uniformly, which keep solid angles, so the
+/// densities of the emitter hold in world space. Other matrices are rejected.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::{Emitter, PointLight, TransformedEmitter};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let bulb = Arc::new(PointLight::new(Point3d::zero(), Color::splat(4.0)));
+/// let raised = TransformedEmitter::new(bulb, [
+///     [1.0, 0.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0, 2.0],
+///     [0.0, 0.0, 1.0, 0.0],
+///     [0.0, 0.0, 0.0, 1.0],
+/// ]);
+/// let sample = raised.sample(&Point3d::zero()).unwrap();
+/// assert_eq!(sample.direction, Vec3d::new(0.0, 2.0, 0.0));
+/// assert_eq!(sample.radiance, Color::splat(1.0));
+/// ```
+pub struct TransformedEmitter {
+    emitter: Arc<dyn Emitter>,
+    matrix: [[Float; 4]; 4],
+    inverse: [[Float; 4]; 4],
+    scale: Float,
+}
+
+
+impl TransformedEmitter {
+    /// Moves ``emitter`` by a row-major matrix, see ``Transform::new``.
+    /// # Panics
+    /// If the matrix is not invertible, or does more than rotate, move and scale uniformly,
+    /// see ``try_new`` for a fallible version.
+    pub fn new(emitter: Arc<dyn Emitter>, matrix: [[Float; 4]; 4]) -> Self {
+        Self::try_new(emitter, matrix).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(emitter: Arc<dyn Emitter>, matrix: [[Float; 4]; 4]) -> Result<Self> {
+        let inverse = affine_inverse(&matrix)?;
+        let axes = [0, 1, 2].map(|c| Vec3d::new(matrix[0][c], matrix[1][c], matrix[2][c]));
+        let lengths = axes.map(|axis| axis.length());
+        let scale = lengths[0];
+        // Uniform scaling keeps the axes as long as each other and at right angles.
+        let tolerance = UNIFORM_SCALE_TOLERANCE * scale * scale;
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let same_length = (lengths[a] * lengths[a] - lengths[b] * lengths[b]).abs() <= tolerance;
+            if !same_length || dot(&axes[a], &axes[b]).abs() > tolerance {
+                return Err(Error::NonUniformScale(lengths));
+            }
+        }
+        Ok(Self { emitter, matrix, inverse, scale })
+    }
+}
+
+
+impl Emitter for TransformedEmitter {
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample> {
+        let mut sample = self.emitter.sample(&transform_point(&self.inverse, origin))?;
+        sample.direction = transform_vector(&self.matrix, &sample.direction);
+        // The light of a point arrives over the distance in world space.
+        if self.emitter.is_delta() {
+            sample.radiance /= self.scale * self.scale;
+        }
+        Some(sample)
+    }
+
+    fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        self.emitter.pdf(&transform_point(&self.inverse, origin), &transform_vector(&self.inverse, direction))
+    }
+
+    fn is_delta(&self) -> bool {
+        self.emitter.is_delta()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.emitter.group()
+    }
+}
+
+
+#[cfg(test)]
+mod test_transform {
+    use super::*;
+    use crate::object::{Quad, Sphere};
+    use crate::object::material::{Material, Empty};
+
+    const SCALE_AND_MOVE: [[Float; 4]; 4] = [
+        [2.0, 0.0, 0.0, 5.0],
+        [0.0, 2.0, 0.0, 0.0],
+        [0.0, 0.0, 2.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        Arc::new(Sphere::static_sphere(
+            Point3d::zero(),
+            1.0,
+            Material::Empty(Empty {}),
+        ))
+    }
+
+    #[test]
+    fn test_affine_inverse() {
+        let inverse = affine_inverse(&SCALE_AND_MOVE).unwrap();
+        let point = Point3d::new(1.0, 2.0, 3.0);
+        let round_trip = transform_point(&inverse, &transform_point(&SCALE_AND_MOVE, &point));
+        assert_eq!(round_trip, point);
+    }
+
+    #[test]
+    fn test_singular_matrix() {
+        let mut matrix = SCALE_AND_MOVE;
+        matrix[2][2] = 0.0;
+        assert!(matches!(Transform::try_new(unit_sphere(), matrix), Err(Error::SingularMatrix(_))));
+
+        // Whether a matrix inverts does not depend on its scale.
+        let mut tiny = IDENTITY;
+        for axis in 0..3 { tiny[axis][axis] = 0.005; }
+        assert!(affine_inverse(&tiny).is_ok());
+        let mut flat = [[1e6, 0.0, 0.0, 0.0], [0.0, 1e6, 1e6, 0.0], [0.0, 1e6, 1e6, 0.0], IDENTITY[3]];
+        assert!(matches!(affine_inverse(&flat), Err(Error::SingularMatrix(_))));
+        flat[2][2] = 1e6 * (1.0 + 1e-3);
+        assert!(affine_inverse(&flat).is_ok());
+    }
+
+    #[test]
+    fn test_transform_bounding_box() {
+        let transform = Transform::new(unit_sphere(), SCALE_AND_MOVE);
+        assert_eq!(
+            transform.bounding_box(),
+            AABB::from_points(&Point3d::new(3.0, -2.0, -2.0), &Point3d::new(7.0, 2.0, 2.0)),
+        );
+    }
+
+    #[test]
+    fn test_transform_from_columns() {
+        let columns = transpose(&SCALE_AND_MOVE);
+        let transform = Transform::from_columns(unit_sphere(), columns);
+        assert_eq!(transform.transform_point(&Point3d::zero()), Point3d::new(5.0, 0.0, 0.0));
+        assert_eq!(transform.transform_vector(&Vec3d::new(1.0, 0.0, 0.0)), Vec3d::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_transform_hit() {
+        let transform = Transform::new(unit_sphere(), SCALE_AND_MOVE);
+        let ray = Ray::new(Point3d::new(0.0, 0.0, 0.0), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        let hit_record = transform.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+
+        assert_eq!(hit_record.t, 3.0);
+        assert_eq!(hit_record.point, Point3d::new(3.0, 0.0, 0.0));
+        assert_eq!(hit_record.normal, Vec3d::new(-1.0, 0.0, 0.0));
+        assert!(hit_record.front_face);
+    }
+
+    #[test]
+    fn test_transform_hit_sheared_normal() {
+        // Shearing x by y keeps the quad in the z = 0 plane, so its normal must not change.
+        let quad: Arc<dyn Hittable> = Arc::new(Quad::new(
+            Point3d::zero(),
+            Vec3d::new(1.0, 0.0, 0.0),
+            Vec3d::new(0.0, 1.0, 0.0),
+            Material::Empty(Empty {}),
+        ));
+        let transform = Transform::new(quad, [
+            [1.0, 1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let ray = Ray::new(Point3d::new(1.0, 0.5, -1.0), Vec3d::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = transform.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+
+        assert_eq!(hit_record.t, 1.0);
+        assert_eq!(hit_record.normal, Vec3d::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_transformed_emitter() {
+        use crate::color::Color;
+        use crate::object::material::{Light, Material};
+        use assert_approx_eq::assert_approx_eq;
+
+        // A unit light facing down at y = 1, scaled to twice the size and raised to y = 4.
+        let light = Arc::new(Quad::new(
+            Point3d::new(-0.5, 1.0, -0.5),
+            Vec3d::new(0.0, 0.0, 1.0),
+            Vec3d::new(1.0, 0.0, 0.0),
+            Material::Light(Light::from_color(Color::splat(1.0))),
+        ));
+        let moved = TransformedEmitter::new(light.clone(), [
+            [2.0, 0.0, 0.0, 0.0], [0.0, 2.0, 0.0, 2.0], [0.0, 0.0, 2.0, 0.0], [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let world = Quad::new(
+            Point3d::new(-1.0, 4.0, -1.0), Vec3d::new(0.0, 0.0, 2.0), Vec3d::new(2.0, 0.0, 0.0), Material::Empty(Empty {}),
+        );
+        let up = Vec3d::new(0.0, 1.0, 0.0);
+        assert_approx_eq!(moved.pdf(&Point3d::zero(), &up), world.pdf(&Point3d::zero(), &up));
+        let sample = moved.sample(&Point3d::zero()).unwrap();
+        assert_approx_eq!(sample.direction.y(), 4.0);
+        assert!(sample.direction.x().abs() <= 1.0 && sample.direction.z().abs() <= 1.0);
+
+        // Stretching or shearing a light would change its solid angles.
+        let stretched = [[2.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 2.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        assert!(matches!(TransformedEmitter::try_new(light.clone(), stretched), Err(Error::NonUniformScale(_))));
+        let sheared = [[1.0, 1.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        assert!(matches!(TransformedEmitter::try_new(light.clone(), sheared), Err(Error::NonUniformScale(_))));
+        let (sin, cos) = (0.7 as Float).sin_cos();
+        let turned = [[3.0 * cos, 0.0, 3.0 * sin, 1.0], [0.0, 3.0, 0.0, 0.0], [-3.0 * sin, 0.0, 3.0 * cos, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        assert!(TransformedEmitter::try_new(light, turned).is_ok());
+    }
+}
+
+
+/// Moves an object from a ``start`` to an ``end`` transform over the shutter, giving motion
+/// blur to any primitive, e.g. quads, boxes and meshes.
+///
+/// Both transforms are row-major affine matrices like those of ``Transform``, without shear.
+/// They are split into a translation, a rotation and a scale, interpolated separately by the
+/// ``Ray::time`` from ``0`` to ``1``, the rotation along the shortest arc, so a spinning object
+/// keeps its shape halfway.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Animated, Hittable, Sphere};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Interval, Ray};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let ball = Sphere::static_sphere(Point3d::zero(), 0.5, Material::Empty(Empty {}));
+/// let rolling = Animated::translating(Arc::new(ball), Vec3d::new(4.0, 0.0, 0.0));
+/// let down = |time| Ray::new(Point3d::new(2.0, 5.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), time);
+/// assert!(rolling.hit(&down(0.0), &Interval::UNIVERSE).is_none());
+/// assert!(rolling.hit(&down(0.5), &Interval::UNIVERSE).is_some());
+/// ```
+pub struct Animated {
+    object: Arc<dyn Hittable>,
+    start: Pose,
+    end: Pose,
+    bbox: AABB,
+}
+
+
+/// Steps along the motion at which ``Animated`` bounds the object.
+const MOTION_STEPS: usize = 64;
+
+
+impl Animated {
+    /// # Panics
+    /// If a transform is singular or mirrors the object only at one end, see ``try_new`` for a
+    /// fallible version.
+    pub fn new(object: Arc<dyn Hittable>, start: [[Float; 4]; 4], end: [[Float; 4]; 4]) -> Self {
+        Self::try_new(object, start, end).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(object: Arc<dyn Hittable>, start: [[Float; 4]; 4], end: [[Float; 4]; 4]) -> Result<Self> {
+        let (start, end) = (Pose::try_from_matrix(&start)?, Pose::try_from_matrix(&end)?);
+        // Scales of opposite signs would pass through zero on the way.
+        let (a, b) = (start.scale, end.scale);
+        if a.x() * b.x() <= 0.0 || a.y() * b.y() <= 0.0 || a.z() * b.z() <= 0.0 {
+            return Err(Error::MirroredAnimation);
+        }
+
+        let bbox = object.bounding_box();
+        let corners: Vec<Point3d> = (0..8).map(|i| Point3d::new(
+            if i & 1 == 0 { bbox.axis_interval(0).min } else { bbox.axis_interval(0).max },
+            if i & 2 == 0 { bbox.axis_interval(1).min } else { bbox.axis_interval(1).max },
+            if i & 4 == 0 { bbox.axis_interval(2).min } else { bbox.axis_interval(2).max },
+        )).collect();
+        let mut min = Point3d::new(Float::INFINITY, Float::INFINITY, Float::INFINITY);
+        let mut max = Point3d::new(Float::NEG_INFINITY, Float::NEG_INFINITY, Float::NEG_INFINITY);
+        let mut reach: Float = 0.0;
+        for step in 0..=MOTION_STEPS {
+            let pose = start.lerp(&end, step as Float / MOTION_STEPS as Float);
+            let matrix = pose.matrix();
+            for corner in &corners {
+                let point = transform_point(&matrix, corner);
+                min = min.min(&point);
+                max = max.max(&point);
+                reach = reach.max((point - pose.translation).length());
+            }
+        }
+        // Corners swing along arcs between the steps, bulging out of their chords by at most
+        // this much.
+        let half_step = start.angle_to(&end) / (2 * MOTION_STEPS) as Float;
+        let bulge = reach * (1.0 / half_step.cos() - 1.0);
+        let bulge = Vec3d::new(bulge, bulge, bulge);
+
+        Ok(Self { object, start, end, bbox: AABB::from_points(&(min - bulge), &(max + bulge)) })
+    }
+
+    /// Moves ``object`` by ``offset`` over the shutter, like ``Sphere::moving_sphere`` did for
+    /// spheres.
+    pub fn translating(object: Arc<dyn Hittable>, offset: Vec3d) -> Self {
+        let mut end = IDENTITY;
+        (end[0][3], end[1][3], end[2][3]) = (offset.x(), offset.y(), offset.z());
+        Self::new(object, IDENTITY, end)
+    }
+
+    /// The object to world matrix at ``time``, clamped to the shutter.
+    pub fn matrix_at(&self, time: Float) -> [[Float; 4]; 4] {
+        self.start.lerp(&self.end, time.clamp(0.0, 1.0)).matrix()
+    }
+
+    /// The matrix at the time of ``ray``, its inverse, and the ray moved into object space.
+    fn object_ray(&self, ray: &Ray) -> ([[Float; 4]; 4], [[Float; 4]; 4], Ray) {
+        let pose = self.start.lerp(&self.end, ray.time.clamp(0.0, 1.0));
+        let (matrix, inverse) = (pose.matrix(), pose.inverse_matrix());
+        let object_ray = Ray::new(
+            transform_point(&inverse, &ray.origin),
+            transform_vector(&inverse, &ray.direction),
+            ray.time,
+        ).with_kind(ray.kind).with_depth(ray.depth);
+        (matrix, inverse, object_ray)
+    }
+}
+
+
+impl Hittable for Animated {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let (matrix, inverse, object_ray) = self.object_ray(ray);
+        let mut hit_record = self.object.hit(&object_ray, interval)?;
+        hit_record.point = transform_point(&matrix, &hit_record.point);
+        hit_record.normal = transform_vector(&transpose(&inverse), &hit_record.normal).unit_vector();
+        if !hit_record.tangent.near_zero() {
+            hit_record.tangent = transform_vector(&matrix, &hit_record.tangent).unit_vector();
+        }
+        Some(hit_record)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.object.hit_any(&self.object_ray(ray).2, interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+const IDENTITY: [[Float; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+
+/// An affine transform split into a scale, then a rotation as a unit quaternion ``[w, x, y, z]``,
+/// then a translation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pose {
+    translation: Vec3d,
+    rotation: [Float; 4],
+    scale: Vec3d,
+}
+
+
+impl Pose {
+    fn try_from_matrix(m: &[[Float; 4]; 4]) -> Result<Self> {
+        let column = |c: usize| Vec3d::new(m[0][c], m[1][c], m[2][c]);
+        let mut columns = [column(0), column(1), column(2)];
+        let mut scale = columns.map(|axis| axis.length());
+        // Relative to the longest axis, like in ``affine_inverse``, so small scales still split.
+        let longest = scale.iter().copied().fold(0.0, Float::max);
+        for (c, axis) in columns.iter_mut().enumerate() {
+            if !scale[c].is_finite() || scale[c] <= Float::EPSILON * longest {
+                return Err(Error::SingularMatrix(0.0));
+            }
+            *axis /= scale[c];
+        }
+        // A mirroring transform keeps its rotation proper by flipping the scale along x.
+        if dot(&cross(&columns[0], &columns[1]), &columns[2]) < 0.0 {
+            scale[0] = -scale[0];
+            columns[0] = -columns[0];
+        }
+        let r = |row: usize, c: usize| columns[c][row];
+
+        // Shepperd's method, dividing by the largest of the four candidates for stability.
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+        let rotation = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            [0.25 * s, (r(2, 1) - r(1, 2)) / s, (r(0, 2) - r(2, 0)) / s, (r(1, 0) - r(0, 1)) / s]
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = 2.0 * (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt();
+            [(r(2, 1) - r(1, 2)) / s, 0.25 * s, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s]
+        } else if r(1, 1) > r(2, 2) {
+            let s = 2.0 * (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt();
+            [(r(0, 2) - r(2, 0)) / s, (r(0, 1) + r(1, 0)) / s, 0.25 * s, (r(1, 2) + r(2, 1)) / s]
+        } else {
+            let s = 2.0 * (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt();
+            [(r(1, 0) - r(0, 1)) / s, (r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, 0.25 * s]
+        };
+        Ok(Self { translation: Vec3d::new(m[0][3], m[1][3], m[2][3]), rotation, scale: Vec3d::from(scale) })
+    }
+
+    /// Angle of the rotation from ``self`` to ``other``.
+    fn angle_to(&self, other: &Self) -> Float {
+        let cosine: Float = self.rotation.iter().zip(&other.rotation).map(|(a, b)| a * b).sum();
+        2.0 * cosine.abs().min(1.0).acos()
+    }
+
+    /// The pose ``t`` of the way from ``self`` to ``other``.
+    fn lerp(&self, other: &Self, t: Float) -> Self {
+        let (from, mut to) = (self.rotation, other.rotation);
+        let mut cosine: Float = from.iter().zip(&to).map(|(a, b)| a * b).sum();
+        // Quaternions ``q`` and ``-q`` are the same rotation, take the one closer by.
+        if cosine < 0.0 {
+            to = to.map(|x| -x);
+            cosine = -cosine;
+        }
+        let (a, b) = if cosine > 0.9995 {
+            // Nearly the same rotation, where slerp divides by almost zero.
+            (1.0 - t, t)
+        } else {
+            let angle = cosine.acos();
+            (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+        };
+        let rotation: [Float; 4] = std::array::from_fn(|i| a * from[i] + b * to[i]);
+        let length = rotation.iter().map(|x| x * x).sum::<Float>().sqrt();
+
+        Self {
+            translation: self.translation * (1.0 - t) + other.translation * t,
+            rotation: rotation.map(|x| x / length),
+            scale: self.scale * (1.0 - t) + other.scale * t,
+        }
+    }
+
+    fn rotation_matrix(&self) -> [[Float; 3]; 3] {
+        let [w, x, y, z] = self.rotation;
+        [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ]
+    }
+
+    fn matrix(&self) -> [[Float; 4]; 4] {
+        let rotation = self.rotation_matrix();
+        let mut matrix = IDENTITY;
+        for row in 0..3 {
+            for c in 0..3 {
+                matrix[row][c] = rotation[row][c] * self.scale[c];
+            }
+            matrix[row][3] = self.translation[row];
+        }
+        matrix
+    }
+
+    /// The inverse of ``matrix``, undoing the translation, the rotation by its transpose and
+    /// then the scale, without a general matrix inversion.
+    fn inverse_matrix(&self) -> [[Float; 4]; 4] {
+        let rotation = self.rotation_matrix();
+        let mut inverse = IDENTITY;
+        for c in 0..3 {
+            for row in 0..3 {
+                inverse[c][row] = rotation[row][c] / self.scale[c];
+            }
+            inverse[c][3] = -(0..3).map(|row| inverse[c][row] * self.translation[row]).sum::<Float>();
+        }
+        inverse
+    }
+}
+
+
+#[cfg(test)]
+mod test_animated {
+    use super::*;
+    use crate::vec3d::consts;
+    use crate::object::Quad;
+    use crate::object::material::{Material, Empty};
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Turns by ``angle`` around the y axis, scales by ``scale`` and moves by ``x`` along x.
+    fn turn(angle: Float, scale: Float, x: Float) -> [[Float; 4]; 4] {
+        let (sin, cos) = angle.sin_cos();
+        [
+            [cos * scale, 0.0, sin * scale, x],
+            [0.0, scale, 0.0, 0.0],
+            [-sin * scale, 0.0, cos * scale, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    #[test]
+    fn test_pose_round_trip() {
+        let mirrored = [[-2.0, 0.0, 0.0, 1.0], [0.0, 0.0, -1.0, 2.0], [0.0, 3.0, 0.0, 3.0], [0.0, 0.0, 0.0, 1.0]];
+        for matrix in [IDENTITY, turn(2.5, 0.5, 3.0), turn(-1.0, 2.0, 0.0), mirrored] {
+            let pose = Pose::try_from_matrix(&matrix).unwrap();
+            let (round_trip, inverse) = (pose.matrix(), affine_inverse(&matrix).unwrap());
+            for row in 0..4 {
+                for c in 0..4 {
+                    assert_approx_eq!(round_trip[row][c], matrix[row][c]);
+                    assert_approx_eq!(pose.inverse_matrix()[row][c], inverse[row][c]);
+                }
+            }
+        }
+        assert!(Pose::try_from_matrix(&[[0.0; 4]; 4]).is_err());
+        let tiny = [[0.005, 0.0, 0.0, 0.0], [0.0, 0.005, 0.0, 0.0], [0.0, 0.0, 0.005, 0.0], IDENTITY[3]];
+        assert_approx_eq!(Pose::try_from_matrix(&tiny).unwrap().scale.x(), 0.005);
+    }
+
+    #[test]
+    fn test_interpolates_rotation_and_stays_bounded() {
+        let quad: Arc<dyn Hittable> = Arc::new(Quad::new(
+            Point3d::new(1.0, 0.0, -0.5),
+            Vec3d::new(1.0, 0.0, 0.0),
+            Vec3d::new(0.0, 0.0, 1.0),
+            Material::Empty(Empty {}),
+        ));
+        let spinning = Animated::new(quad, turn(0.0, 1.0, 0.0), turn(consts::PI, 2.0, 0.0));
+
+        // Halfway it has turned a quarter and grown half again, not collapsed onto the axis.
+        let halfway = spinning.matrix_at(0.5);
+        let point = transform_point(&halfway, &Point3d::new(1.0, 0.0, 0.0));
+        assert_approx_eq!(point.x(), 0.0);
+        assert_approx_eq!(point.z(), -1.5);
+        let ray = Ray::new(Point3d::new(0.0, 5.0, -2.0), Vec3d::new(0.0, -1.0, 0.0), 0.5);
+        let hit = spinning.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert_approx_eq!(hit.normal.y().abs(), 1.0);
+        assert!(!spinning.hit_any(&Ray::new(ray.origin, ray.direction, 0.0), &Interval::UNIVERSE));
+
+        let bbox = spinning.bounding_box();
+        for step in 0..=100 {
+            let matrix = spinning.matrix_at(step as Float / 100.0);
+            for corner in [Point3d::new(1.0, 0.0, -0.5), Point3d::new(2.0, 0.0, 0.5), Point3d::new(2.0, 0.0, -0.5)] {
+                let point = transform_point(&matrix, &corner);
+                for axis in 0..3 {
+                    assert!(bbox.axis_interval(axis).contains(point[axis]), "{:?} outside {:?}", point, bbox);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_flipping_scale_is_rejected() {
+        let mut mirrored = IDENTITY;
+        mirrored[0][0] = -1.0;
+        let quad: Arc<dyn Hittable> = Arc::new(Quad::new(
+            Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0), Material::Empty(Empty {}),
+        ));
+        assert!(matches!(Animated::try_new(quad, IDENTITY, mirrored), Err(Error::MirroredAnimation)));
+    }
+}
+
+
+/// The region of space a ``Clip`` keeps of its object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipRegion {
+    /// Everything behind the plane through ``point``, ``normal`` pointing to the side cut away.
+    Plane { point: Point3d, normal: Vec3d },
+    /// Everything inside the box.
+    Box(AABB),
+}
+
+
+impl ClipRegion {
+    /// The span of ``ray`` inside the region, with the outward normals of the region where the
+    /// ray enters and leaves it, ``None`` if it misses the region.
+    fn span(&self, ray: &Ray) -> Option<((Float, Vec3d), (Float, Vec3d))> {
+        match *self {
+            ClipRegion::Plane { point, normal } => {
+                let (side, speed) = (dot(&(ray.origin - point), &normal), dot(&ray.direction, &normal));
+                if speed == 0.0 {
+                    return (side <= 0.0).then_some(((Float::NEG_INFINITY, normal), (Float::INFINITY, normal)));
+                }
+                let crossing = -side / speed;
+                if speed > 0.0 {
+                    Some(((Float::NEG_INFINITY, normal), (crossing, normal)))
+                } else {
+                    Some(((crossing, normal), (Float::INFINITY, normal)))
+                }
+            }
+            ClipRegion::Box(bbox) => {
+                let mut enter = (Float::NEG_INFINITY, Vec3d::zero());
+                let mut leave = (Float::INFINITY, Vec3d::zero());
+                for axis in 0..3 {
+                    let bounds = bbox.axis_interval(axis);
+                    let mut outward = Vec3d::zero();
+                    outward[axis] = 1.0;
+                    if ray.direction[axis] == 0.0 {
+                        if !bounds.contains(ray.origin[axis]) { return None; }
+                        continue;
+                    }
+                    let (t0, t1) = (
+                        (bounds.min - ray.origin[axis]) / ray.direction[axis],
+                        (bounds.max - ray.origin[axis]) / ray.direction[axis],
+                    );
+                    let (near, far, near_normal) = if t0 < t1 { (t0, t1, -outward) } else { (t1, t0, outward) };
+                    if near > enter.0 { enter = (near, near_normal); }
+                    if far < leave.0 { leave = (far, -near_normal); }
+                }
+                (enter.0 < leave.0).then_some((enter, leave))
+            }
+        }
+    }
+}
+
+
+/// Keeps only the part of an object inside a ``ClipRegion``, for section views of solids.
+///
+/// With a ``cap``, the cut through a closed object is closed by a surface of that material,
+/// so the object looks solid instead of hollow. Whether a point of the cut is inside the object
+/// is told by the next surface along the ray facing away, which takes closed objects with
+/// consistent normals.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::{Clip, ClipRegion, Hittable, Sphere};
+/// use ray_tracing::object::material::{Lambertian, Material, Empty};
+/// use ray_tracing::ray::{Interval, Ray};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// // The lower half of a ball, cut at y = 0 and filled with red.
+/// let ball = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+/// let red = Material::Lambertian(Lambertian::new(Color::new(0.8, 0.1, 0.1)));
+/// let half = Clip::new(Arc::new(ball), ClipRegion::Plane { point: Point3d::zero(), normal: Vec3d::new(0.0, 1.0, 0.0) })
+///     .with_cap(red.clone());
+/// let hit = half.hit(&Ray::new(Point3d::new(0.0, 5.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0), &Interval::UNIVERSE).unwrap();
+/// assert_eq!(hit.t, 5.0);
+/// assert_eq!(hit.material, &red);
+/// assert_eq!(hit.normal, Vec3d::new(0.0, 1.0, 0.0));
+/// ```
+pub struct Clip {
+    object: Arc<dyn Hittable>,
+    region: ClipRegion,
+    cap: Option<Material>,
+    bbox: AABB,
+}
+
+
+impl Clip {
+    /// Clips ``object`` to ``region``, leaving the cut open.
+    pub fn new(object: Arc<dyn Hittable>, region: ClipRegion) -> Self {
+        let bbox = match region {
+            ClipRegion::Box(bbox) => object.bounding_box().intersection(&bbox),
+            ClipRegion::Plane { .. } => object.bounding_box(),
+        };
+        Self { object, region, cap: None, bbox }
+    }
+
+    /// Closes the cut through the object with a surface of ``material``.
+    pub fn with_cap(mut self, material: Material) -> Self {
+        self.cap = Some(material);
+        self
+    }
+
+    fn cap_hit<'a>(&'a self, material: &'a Material, ray: &Ray, t: Float, outward_normal: Vec3d) -> HitRecord<'a> {
+        let mut hit_record = HitRecord::new(material, t, 0.0, 0.0, ray.at(t));
+        hit_record.set_face_normal(ray, outward_normal);
+        hit_record
+    }
+}
+
+
+impl Hittable for Clip {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let ((enter, enter_normal), (leave, leave_normal)) = self.region.span(ray)?;
+        let (min, max) = (interval.min.max(enter), interval.max.min(leave));
+        if min >= max { return None; }
+
+        // Hits beyond the region still tell whether the cut is inside the object.
+        let next = self.object.hit(ray, &Interval { min, max: Float::INFINITY });
+        let inside = next.as_ref().is_some_and(|hit_record| !hit_record.front_face);
+        if let Some(cap) = &self.cap {
+            if inside && enter >= interval.min {
+                return Some(self.cap_hit(cap, ray, enter, enter_normal));
+            }
+        }
+        match next {
+            Some(hit_record) if hit_record.t < max => Some(hit_record),
+            _ => match &self.cap {
+                Some(cap) if inside && leave <= interval.max => Some(self.cap_hit(cap, ray, leave, leave_normal)),
+                _ => None,
+            },
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+#[cfg(test)]
+mod test_clip {
+    use super::*;
+    use crate::object::Sphere;
+    use crate::object::material::{Empty, Lambertian};
+    use crate::color::Color;
+
+    fn ball() -> Arc<dyn Hittable> {
+        Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {})))
+    }
+
+    #[test]
+    fn test_open_cut() {
+        let half = Clip::new(ball(), ClipRegion::Plane { point: Point3d::zero(), normal: Vec3d::new(0.0, 1.0, 0.0) });
+        // From above, the ray passes through the cut to the inside of the lower half.
+        let down = Ray::new(Point3d::new(0.0, 5.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        let hit = half.hit(&down, &Interval::UNIVERSE).unwrap();
+        assert_eq!(hit.t, 6.0);
+        assert!(!hit.front_face);
+        // Sideways above the cut there is nothing left.
+        let above = Ray::new(Point3d::new(-5.0, 0.5, 0.0), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        assert!(half.hit(&above, &Interval::UNIVERSE).is_none());
+        let below = Ray::new(Point3d::new(-5.0, -0.5, 0.0), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        assert!(half.hit(&below, &Interval::UNIVERSE).unwrap().front_face);
+    }
+
+    #[test]
+    fn test_capped_box_section() {
+        let cap = Material::Lambertian(Lambertian::new(Color::new(1.0, 0.0, 0.0)));
+        let region = ClipRegion::Box(AABB::from_points(&Point3d::new(-2.0, -2.0, -2.0), &Point3d::new(2.0, 2.0, 0.5)));
+        let cut = Clip::new(ball(), region).with_cap(cap.clone());
+        assert_eq!(cut.bounding_box().axis_interval(2).max, 0.5);
+
+        // Head on into the cut face of the ball, and past the edge of the cut onto the ball.
+        let forward = |x| Ray::new(Point3d::new(x, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        let hit = cut.hit(&forward(0.0), &Interval::UNIVERSE).unwrap();
+        assert_eq!((hit.t, hit.material), (4.5, &cap));
+        assert_eq!(hit.normal, Vec3d::new(0.0, 0.0, 1.0));
+        let rim = cut.hit(&forward(0.9), &Interval::UNIVERSE).unwrap();
+        assert_eq!(rim.material, &Material::Empty(Empty {}));
+        assert!((rim.t - (5.0 - (0.19 as Float).sqrt())).abs() < 1e-9);
+
+        // From inside the ball, towards the cut, the cap is seen from behind.
+        let outwards = Ray::new(Point3d::zero(), Vec3d::new(0.0, 0.0, 1.0), 0.0);
+        let hit = cut.hit(&outwards, &Interval { min: 0.001, max: Float::INFINITY }).unwrap();
+        assert_eq!((hit.t, hit.front_face), (0.5, false));
+        // Away from the cut, the ball itself.
+        let hit = cut.hit(&Ray::new(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0), 0.0), &Interval { min: 0.001, max: Float::INFINITY }).unwrap();
+        assert_eq!((hit.t, hit.material), (1.0, &Material::Empty(Empty {})));
+    }
+}
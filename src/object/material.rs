@@ -1,7 +1,12 @@
-use rand::random;
-use crate::vec3d::{Vec3d, Color, dot};
+use crate::sampler::random;
+use crate::vec3d::{Vec3d, Float, consts, cross, dot, reflect, refract};
+use crate::color::Color;
 use crate::ray::Ray;
 use crate::object::hit::HitRecord;
+use crate::object::{LightPower, Medium};
+use crate::object::emitter::normalized_color;
+use crate::error::{Error, Result};
+use crate::stats::{self, Counter};
 
 use std::sync::Arc;
 use crate::object::texture::{Texture, SolidColor};
@@ -9,6 +14,22 @@ use crate::object::texture::{Texture, SolidColor};
 type Scattered = Option<(Ray, Color)>;
 
 
+/// How the back faces of a surface, the side its outward normal points away from, are
+/// rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackFace {
+    /// Treated as the flip side of the same surface, shaded like the front.
+    #[default]
+    TwoSided,
+    /// Invisible, rays pass through as if the surface wasn't there.
+    Culled,
+    /// Absorbs all light and emits nothing, e.g. so closed meshes don't leak light from
+    /// inside or one-sided area lights only shine forwards.
+    Black,
+}
+
+
+
 pub trait Scatterable: Send + Sync {
     fn scatter(
         &self,
@@ -16,7 +37,31 @@ pub trait Scatterable: Send + Sync {
         hit_record: &HitRecord,
     ) -> Scattered;
 
-    fn emitted(&self, _u: f64, _v: f64, _p: &Vec3d) -> Color { Color::zero() }
+    fn emitted(&self, _u: Float, _v: Float, _p: &Vec3d) -> Color { Color::zero() }
+
+    /// Radiance emitted at a hit, which by default only depends on its ``u``, ``v`` and point,
+    /// but may also use its time or vertex color like ``Texture::value_at``.
+    fn emitted_at(&self, hit_record: &HitRecord) -> Color {
+        self.emitted(hit_record.u, hit_record.v, &hit_record.point)
+    }
+
+    /// Solid angle density with which ``scatter`` picks the direction of ``scattered``.
+    ///
+    /// ``None`` for materials with a singular (specular) distribution, whose scattered rays
+    /// can't be replaced by ones sampled from elsewhere, e.g. towards light portals.
+    fn scattering_pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Option<Float> {
+        None
+    }
+
+    /// How back faces using the material are rendered.
+    fn back_face(&self) -> BackFace { BackFace::TwoSided }
+
+    /// Maximum number of times a path may scatter off materials of the same type, e.g.
+    /// allowing long refraction chains through glass while diffuse bounces stop early.
+    ///
+    /// ``None`` defers to the camera's maximum depth, shared by all such materials along the
+    /// path. Bounces off materials with their own limit don't count towards it.
+    fn max_bounces(&self) -> Option<i32> { None }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -27,6 +72,9 @@ pub enum Material {
     Metal(Metal),
     Dielectric(Dielectric),
     Isotropic(Isotropic),
+    Hair(Hair),
+    Coat(Coat),
+    Wireframe(Wireframe),
 }
 
 impl Scatterable for Material {
@@ -42,15 +90,86 @@ impl Scatterable for Material {
             Material::Metal(metal) => metal.scatter(ray_in, hit_record),
             Material::Dielectric(d) => d.scatter(ray_in, hit_record),
             Material::Isotropic(i) => i.scatter(ray_in, hit_record),
+            Material::Hair(h) => h.scatter(ray_in, hit_record),
+            Material::Coat(c) => c.scatter(ray_in, hit_record),
+            Material::Wireframe(w) => w.scatter(ray_in, hit_record),
         }
     }
 
-    fn emitted(&self, u: f64, v: f64, p: &Vec3d) -> Color {
+    fn emitted(&self, u: Float, v: Float, p: &Vec3d) -> Color {
         match self {
             Material::Light(li) => li.emitted(u, v, p),
+            Material::Coat(c) => c.emitted(u, v, p),
+            _ => Color::zero(),
+        }
+    }
+
+    fn emitted_at(&self, hit_record: &HitRecord) -> Color {
+        match self {
+            Material::Light(li) => li.emitted_at(hit_record),
+            Material::Coat(c) => c.emitted_at(hit_record),
             _ => Color::zero(),
         }
     }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Option<Float> {
+        match self {
+            Material::Lambertian(l) => l.scattering_pdf(ray_in, hit_record, scattered),
+            Material::Isotropic(i) => i.scattering_pdf(ray_in, hit_record, scattered),
+            Material::Wireframe(w) => w.scattering_pdf(ray_in, hit_record, scattered),
+            _ => None,
+        }
+    }
+
+    fn back_face(&self) -> BackFace {
+        match self {
+            Material::Light(li) => li.back_face(),
+            Material::Lambertian(l) => l.back_face(),
+            Material::Metal(metal) => metal.back_face(),
+            Material::Coat(c) => c.back_face(),
+            _ => BackFace::TwoSided,
+        }
+    }
+
+    fn max_bounces(&self) -> Option<i32> {
+        match self {
+            Material::Lambertian(l) => l.max_bounces(),
+            Material::Metal(metal) => metal.max_bounces(),
+            Material::Dielectric(d) => d.max_bounces(),
+            Material::Isotropic(i) => i.max_bounces(),
+            Material::Coat(c) => c.max_bounces(),
+            _ => None,
+        }
+    }
+}
+
+impl Material {
+    /// Number of material types, see ``type_index``.
+    pub(crate) const TYPES: usize = 9;
+
+    /// Index of the material type, used to count bounces per type.
+    pub(crate) fn type_index(&self) -> usize {
+        match self {
+            Material::Empty(_) => 0,
+            Material::Light(_) => 1,
+            Material::Lambertian(_) => 2,
+            Material::Metal(_) => 3,
+            Material::Dielectric(_) => 4,
+            Material::Isotropic(_) => 5,
+            Material::Hair(_) => 6,
+            Material::Coat(_) => 7,
+            Material::Wireframe(_) => 8,
+        }
+    }
+
+    /// Light group of the light emitted by the material, see ``Light::with_group``.
+    pub fn light_group(&self) -> Option<&str> {
+        match self {
+            Material::Light(light) => light.group(),
+            Material::Coat(coat) => coat.base.light_group(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -69,17 +188,60 @@ impl Scatterable for Empty {
 
 #[derive(Debug, Clone)]
 pub struct Light {
-    texture: Arc<Box<dyn Texture>>,
+    texture: Arc<dyn Texture>,
+    back_face: BackFace,
+    group: Option<String>,
 }
 
 impl Light {
     pub fn from_color(color: Color) -> Self {
-        let texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(SolidColor::new(color)));
+        let texture: Arc<dyn Texture> = Arc::new(SolidColor::new(color));
         Self::new(texture)
     }
 
-    pub fn new(texture: Arc<Box<dyn Texture>>) -> Self {
-        Self { texture }
+    /// Creates a light glowing like a black body at ``kelvin`` with the given ``luminance``,
+    /// see ``Color::from_temperature``.
+    pub fn from_temperature(kelvin: Float, luminance: Float) -> Self {
+        Self::from_color(Color::from_temperature(kelvin) * luminance)
+    }
+
+    /// Creates a one-sided light of ``color`` for a surface of ``area`` square units, which
+    /// sends out ``power`` in a scene of ``meters_per_unit``, see ``LightPower``. Making the
+    /// surface larger then spreads the same light over it, instead of brightening the scene.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::LightPower;
+    /// use ray_tracing::object::material::{Light, Scatterable};
+    /// use ray_tracing::vec3d::Point3d;
+    /// let small = Light::from_power(Color::splat(1.0), LightPower::Lumens(800.0), 0.1, 1.0);
+    /// let large = Light::from_power(Color::splat(1.0), LightPower::Lumens(800.0), 0.4, 1.0);
+    /// let radiance = |light: &Light| light.emitted(0.0, 0.0, &Point3d::zero());
+    /// assert!((radiance(&small)[0] - 4.0 * radiance(&large)[0]).abs() < 1e-9);
+    /// ```
+    pub fn from_power(color: Color, power: LightPower, area: Float, meters_per_unit: Float) -> Self {
+        Self::from_color(normalized_color(color) * power.radiance(area, meters_per_unit))
+            .with_back_face(BackFace::Black)
+    }
+
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self { texture, back_face: BackFace::TwoSided, group: None }
+    }
+
+    /// Sets how back faces are rendered, ``BackFace::Black`` making a one-sided light.
+    pub fn with_back_face(mut self, back_face: BackFace) -> Self {
+        self.back_face = back_face;
+        self
+    }
+
+    /// Puts the light into the light group ``group``, see ``Camera::add_light_group``.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_string());
+        self
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
     }
 }
 
@@ -90,31 +252,54 @@ impl Scatterable for Light {
         hit_record: &HitRecord,
     ) -> Scattered { None }
 
-    fn emitted(&self, _u: f64, _v: f64, _p: &Vec3d) -> Color {
+    fn emitted(&self, _u: Float, _v: Float, _p: &Vec3d) -> Color {
+        stats::count(Counter::TextureLookups);
         self.texture.value(_u, _v, _p)
     }
+
+    fn emitted_at(&self, hit_record: &HitRecord) -> Color {
+        stats::count(Counter::TextureLookups);
+        self.texture.value_at(hit_record)
+    }
+
+    fn back_face(&self) -> BackFace { self.back_face }
 }
 
 impl PartialEq for Light {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.texture, &other.texture)
+        Arc::ptr_eq(&self.texture, &other.texture) && self.back_face == other.back_face && self.group == other.group
     }
 }
 
 
 #[derive(Debug, Clone)]
 pub struct Lambertian {
-    texture: Arc<Box<dyn Texture>>,
+    texture: Arc<dyn Texture>,
+    back_face: BackFace,
+    max_bounces: Option<i32>,
 }
 
 impl Lambertian {
-    pub fn new(albedo: Vec3d) -> Self {
-        let texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(SolidColor::new(albedo)));
+    pub fn new(albedo: Color) -> Self {
+        let texture: Arc<dyn Texture> = Arc::new(SolidColor::new(albedo));
         Self::from_texture(texture)
     }
 
-    pub fn from_texture(texture: Arc<Box<dyn Texture>>) -> Self {
-        Self { texture }
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Self {
+        Self { texture, back_face: BackFace::TwoSided, max_bounces: None }
+    }
+
+    /// Sets how back faces are rendered.
+    pub fn with_back_face(mut self, back_face: BackFace) -> Self {
+        self.back_face = back_face;
+        self
+    }
+
+    /// Limits how many times a path may scatter off materials of this type, instead of the
+    /// camera's maximum depth, see ``Scatterable::max_bounces``.
+    pub fn with_max_bounces(mut self, max_bounces: i32) -> Self {
+        self.max_bounces = Some(max_bounces);
+        self
     }
 }
 
@@ -131,14 +316,27 @@ impl Scatterable for Lambertian {
             scatter_direction.clone_from(&hit_record.normal);
         }
 
-        let attenuation = self.texture.value(hit_record.u, hit_record.v, &hit_record.point);
+        stats::count(Counter::TextureLookups);
+        let attenuation = self.texture.value_at(hit_record);
         Some((Ray::new(hit_record.point, scatter_direction, ray_in.time), attenuation))
     }
+
+    /// Cosine weighted over the hemisphere around the normal.
+    fn scattering_pdf(&self, _ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Option<Float> {
+        let cosine = dot(&hit_record.normal, &scattered.direction.unit_vector());
+        Some(cosine.max(0.0) / consts::PI)
+    }
+
+    fn back_face(&self) -> BackFace { self.back_face }
+
+    fn max_bounces(&self) -> Option<i32> { self.max_bounces }
 }
 
 impl PartialEq for Lambertian {
     fn eq(&self, other: &Self) -> bool {
         Arc::ptr_eq(&self.texture, &other.texture)
+            && self.back_face == other.back_face
+            && self.max_bounces == other.max_bounces
     }
 }
 
@@ -146,15 +344,37 @@ impl PartialEq for Lambertian {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Metal {
     albedo: Color,
-    fuss: f64,
+    fuss: Float,
+    back_face: BackFace,
+    max_bounces: Option<i32>,
 }
 
 impl Metal {
-    pub fn new(albedo: Color, fuss: f64) -> Self {
-        if fuss > 1.0 {
-            panic!("Fuss must be less than 1.0, get {} instead.", fuss);
+    /// Creates a new metal material.
+    /// # Panics
+    /// If ``fuss`` is larger than 1.0, see ``try_new`` for a fallible version.
+    pub fn new(albedo: Color, fuss: Float) -> Self {
+        Self::try_new(albedo, fuss).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(albedo: Color, fuss: Float) -> Result<Self> {
+        if !(0.0..=1.0).contains(&fuss) {
+            return Err(Error::InvalidFuzz(fuss));
         }
-        Self { albedo, fuss }
+        Ok(Self { albedo, fuss, back_face: BackFace::TwoSided, max_bounces: None })
+    }
+
+    /// Sets how back faces are rendered.
+    pub fn with_back_face(mut self, back_face: BackFace) -> Self {
+        self.back_face = back_face;
+        self
+    }
+
+    /// Limits how many times a path may scatter off materials of this type, instead of the
+    /// camera's maximum depth, see ``Scatterable::max_bounces``.
+    pub fn with_max_bounces(mut self, max_bounces: i32) -> Self {
+        self.max_bounces = Some(max_bounces);
+        self
     }
 }
 
@@ -171,40 +391,79 @@ impl Scatterable for Metal {
         let dot = dot(&ray.direction, &hit_record.normal);
         if dot <= 0.0 { None } else { Some((ray, self.albedo)) }
     }
-}
 
-fn reflect(v_in: &Vec3d, normal: &Vec3d) -> Vec3d {
-    *v_in - *normal * dot(v_in, normal) * 2.0
-}
+    fn back_face(&self) -> BackFace { self.back_face }
 
+    fn max_bounces(&self) -> Option<i32> { self.max_bounces }
+}
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Dielectric {
-    refraction_index: f64,
+    refraction_index: Float,
+    roughness: Float,
+    max_bounces: Option<i32>,
+    interior: Option<Arc<Medium>>,
 }
 
 
 impl Dielectric {
-    pub fn new(refraction_index: f64) -> Self {
-        Self { refraction_index }
+    pub fn new(refraction_index: Float) -> Self {
+        Self { refraction_index, roughness: 0.0, max_bounces: None, interior: None }
+    }
+
+    /// Fills objects of this material with ``medium``, usually made with ``Medium::interior``,
+    /// e.g. smoke or juice in a glass. Rays refracted inside scatter in the medium until they
+    /// reach the surface again.
+    ///
+    /// The camera only accounts for the medium on rays ending on the inner side of the
+    /// surface, so other objects inside the glass sit in clear space.
+    pub fn with_interior(mut self, medium: Arc<Medium>) -> Self {
+        self.interior = Some(medium);
+        self
+    }
+
+    pub fn interior(&self) -> Option<&Medium> {
+        self.interior.as_deref()
+    }
+
+    /// Spreads the reflected and refracted rays like the fuzz of ``Metal``, frosting the
+    /// surface, ``0.0`` by default being perfectly smooth.
+    pub fn with_roughness(mut self, roughness: Float) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Limits how many times a path may scatter off materials of this type, instead of the
+    /// camera's maximum depth, see ``Scatterable::max_bounces``.
+    pub fn with_max_bounces(mut self, max_bounces: i32) -> Self {
+        self.max_bounces = Some(max_bounces);
+        self
     }
 }
 
 
 #[derive(Debug, Clone)]
 pub struct Isotropic {
-    texture: Arc<Box<dyn Texture>>,
+    texture: Arc<dyn Texture>,
+    max_bounces: Option<i32>,
 }
 
 
 impl Isotropic {
     pub fn from_color(albedo: Color) -> Self {
-        let texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(SolidColor::new(albedo)));
+        let texture: Arc<dyn Texture> = Arc::new(SolidColor::new(albedo));
         Self::new(texture)
     }
 
-    pub fn new(texture: Arc<Box<dyn Texture>>) -> Self {
-        Self { texture }
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self { texture, max_bounces: None }
+    }
+
+    /// Limits how many times a path may scatter off materials of this type, instead of the
+    /// camera's maximum depth, see ``Scatterable::max_bounces``.
+    pub fn with_max_bounces(mut self, max_bounces: i32) -> Self {
+        self.max_bounces = Some(max_bounces);
+        self
     }
 }
 
@@ -214,15 +473,35 @@ impl Scatterable for Isotropic {
         ray_in: &Ray,
         hit_record: &HitRecord,
     ) -> Scattered {
-        let attenuation = self.texture.value(hit_record.u, hit_record.v, &hit_record.point);
+        stats::count(Counter::TextureLookups);
+        let attenuation = self.texture.value_at(hit_record);
         let scattered = Ray::new(hit_record.point, Vec3d::random_unit_vector(), ray_in.time);
         Some((scattered, attenuation))
     }
+
+    /// Uniform over the sphere.
+    fn scattering_pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Option<Float> {
+        Some(1.0 / (4.0 * consts::PI))
+    }
+
+    fn max_bounces(&self) -> Option<i32> { self.max_bounces }
+}
+
+impl PartialEq for Dielectric {
+    fn eq(&self, other: &Self) -> bool {
+        let same_interior = match (&self.interior, &other.interior) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.refraction_index == other.refraction_index && self.roughness == other.roughness
+            && self.max_bounces == other.max_bounces && same_interior
+    }
 }
 
+
 impl PartialEq for Isotropic {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.texture, &other.texture)
+        Arc::ptr_eq(&self.texture, &other.texture) && self.max_bounces == other.max_bounces
     }
 }
 
@@ -241,141 +520,280 @@ impl Scatterable for Dielectric {
 
         let cannot_refract = ri * sin_theta > 1.0;
 
-        let direction = if cannot_refract || reflectance(cos_theta, ri) > random() {
+        let reflected = cannot_refract || reflectance(cos_theta, ri) > random();
+        let mut direction = if reflected {
             reflect(&unit_direction, &hit_record.normal)
         } else {
             refract(&unit_direction, &hit_record.normal, ri)
         };
+        if self.roughness > 0.0 {
+            direction = direction.unit_vector() + Vec3d::random_unit_vector() * self.roughness;
+            // Rays spread onto the wrong side of the surface are absorbed, like off metals.
+            if (dot(&direction, &hit_record.normal) > 0.0) != reflected { return None; }
+        }
 
         let attenuation = Color::new(1.0, 1.0, 1.0);
         let scattered = Ray::new(hit_record.point, direction, ray_in.time);
         Some((scattered, attenuation))
     }
-}
 
-
-fn refract(v_in: &Vec3d, normal: &Vec3d, etai_over_etat: f64) -> Vec3d {
-    let cos_theta = dot(&-*v_in, normal).min(1.0);
-    let r_out_perp = (*v_in + *normal * cos_theta) * etai_over_etat;
-    let r_out_parallel = *normal * -1.0 * (1.0 - r_out_perp.length_squared()).abs().sqrt();
-    r_out_perp + r_out_parallel
+    fn max_bounces(&self) -> Option<i32> { self.max_bounces }
 }
 
-fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
-    // use Schlick's approximation for reflectance
-    let mut r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
-    r0 *= r0;
-    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+
+/// A thin glossy varnish over a ``base`` material, like the clear coat of car paint or
+/// varnished wood.
+///
+/// The coat mirrors the share of light given by its Fresnel term, which grows towards
+/// grazing angles, and lets the rest through to be scattered by the base.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coat {
+    base: Box<Material>,
+    refraction_index: Float,
+    roughness: Float,
+    max_bounces: Option<i32>,
 }
 
 
-#[cfg(test)]
-mod test_scatter_fn {
-    use super::*;
-    use assert_approx_eq::assert_approx_eq;
+impl Coat {
+    /// Covers ``base`` with a smooth coat with the index of refraction of a typical varnish,
+    /// ``1.5``.
+    pub fn new(base: Material) -> Self {
+        Self { base: Box::new(base), refraction_index: 1.5, roughness: 0.0, max_bounces: None }
+    }
 
-    #[test]
-    fn test_reflect_output_1() {
-        let v_in = Vec3d::new(1.0, 1.0, 0.0);
-        let normal = Vec3d::new(-1.0, 0.0, 0.0);
-        let expected = Vec3d::new(-1.0, 1.0, 0.0);
-        let result = reflect(&v_in, &normal);
-        assert_eq!(result, expected);
+    /// Sets the index of refraction of the coat, higher ones reflecting more.
+    pub fn with_refraction_index(mut self, refraction_index: Float) -> Self {
+        self.refraction_index = refraction_index;
+        self
     }
 
-    #[test]
-    fn test_reflect_output_2() {
-        let v_in = Vec3d::new(1.0, 1.0, 0.0);
-        let normal = Vec3d::new(0.0, 1.0, 0.0);
-        let expected = Vec3d::new(1.0, -1.0, 0.0);
-        let result = reflect(&v_in, &normal);
-        assert_eq!(result, expected);
+    /// Spreads the reflection off the coat like the fuzz of ``Metal``, ``0.0`` by default.
+    pub fn with_roughness(mut self, roughness: Float) -> Self {
+        self.roughness = roughness;
+        self
     }
 
-    #[test]
-    fn test_reflect_output_3() {
-        let v_in = Vec3d::new(1.0, 1.0, 0.0);
-        let normal = Vec3d::new(-1.0, -1.0, 0.0);
-        let expected = Vec3d::new(-1.0, -1.0, 0.0);
-        let result = reflect(&v_in, &normal.unit_vector());
-        assert_approx_eq!(result.x(), expected.x(), f32::EPSILON as f64);
-        assert_approx_eq!(result.y(), expected.y(), f32::EPSILON as f64);
-        assert_eq!(result.z(), expected.z());
+    /// Limits how many times a path may scatter off materials of this type, instead of the
+    /// camera's maximum depth, see ``Scatterable::max_bounces``.
+    pub fn with_max_bounces(mut self, max_bounces: i32) -> Self {
+        self.max_bounces = Some(max_bounces);
+        self
     }
+}
+
+impl Scatterable for Coat {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+    ) -> Scattered {
+        let unit_direction = ray_in.direction.unit_vector();
+        let normal = hit_record.normal;
+        let cos_theta = dot(&-unit_direction, &normal).clamp(0.0, 1.0);
 
+        if reflectance(cos_theta, self.refraction_index) <= random() {
+            return self.base.scatter(ray_in, hit_record);
+        }
+        let direction = reflect(&unit_direction, &normal) + Vec3d::random_unit_vector() * self.roughness;
+        if dot(&direction, &normal) <= 0.0 { return None; }
+        Some((Ray::new(hit_record.point, direction, ray_in.time), Color::splat(1.0)))
+    }
 
-    #[test]
-    fn test_refract_perp_1() {
-        let v_in = Vec3d::new(0.0, -1.0, 0.0);
-        let normal = Vec3d::new(0.0, 1.0, 0.0);
-        let etai_over_etat = 1.0;
+    fn emitted(&self, u: Float, v: Float, p: &Vec3d) -> Color {
+        self.base.emitted(u, v, p)
+    }
 
-        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+    fn emitted_at(&self, hit_record: &HitRecord) -> Color {
+        self.base.emitted_at(hit_record)
     }
 
-    #[test]
-    fn test_refract_perp_2() {
-        let v_in = Vec3d::new(0.0, -1.0, 0.0);
-        let normal = Vec3d::new(0.0, 1.0, 0.0);
-        let etai_over_etat = 1.5;
+    fn back_face(&self) -> BackFace { self.base.back_face() }
+
+    fn max_bounces(&self) -> Option<i32> { self.max_bounces }
+}
+
+
+/// A debugging material drawing the edges of mesh triangles and quads as ``line`` colored
+/// lines over a diffuse ``fill``, to check imported geometry, or a grid of texture coordinates
+/// to check its UV layout.
+///
+/// Lines are ``width`` wide in barycentric coordinates on triangles and in ``u`` and ``v``
+/// elsewhere, so they get thinner on small faces like the faces themselves.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::HitRecord;
+/// use ray_tracing::object::material::{Scatterable, Wireframe};
+/// use ray_tracing::ray::Ray;
+/// use ray_tracing::vec3d::Vec3d;
+/// let wireframe = Wireframe::new(Color::new(1.0, 0.0, 0.0), Color::splat(0.5));
+/// let mut hit_record = HitRecord::empty();
+/// hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+/// let ray_in = Ray::new(Vec3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+/// (hit_record.u, hit_record.v) = (0.5, 0.005);
+/// assert_eq!(wireframe.scatter(&ray_in, &hit_record).unwrap().1, Color::new(1.0, 0.0, 0.0));
+/// (hit_record.u, hit_record.v) = (0.5, 0.5);
+/// assert_eq!(wireframe.scatter(&ray_in, &hit_record).unwrap().1, Color::splat(0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wireframe {
+    line: Color,
+    fill: Color,
+    width: Float,
+    uv_grid: Option<u32>,
+}
+
 
-        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+impl Wireframe {
+    pub fn new(line: Color, fill: Color) -> Self {
+        Self { line, fill, width: 0.02, uv_grid: None }
     }
 
-    #[test]
-    fn test_refract_perp_3() {
-        let v_in = Vec3d::new(0.0, -1.0, 0.0);
-        let normal = Vec3d::new(0.0, 1.0, 0.0);
-        let etai_over_etat = 0.5;
+    /// Sets the width of the lines, ``0.02`` by default.
+    pub fn with_width(mut self, width: Float) -> Self {
+        self.width = width;
+        self
+    }
 
-        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+    /// Draws the lines of a grid splitting the texture coordinates into ``cells x cells``
+    /// squares instead of the edges.
+    pub fn with_uv_grid(mut self, cells: u32) -> Self {
+        self.uv_grid = Some(cells.max(1));
+        self
     }
 
-    #[test]
-    fn test_refract_1() {
-        let v_in = Vec3d::new(1.0, 1.0, 0.0);
-        let normal = Vec3d::new(-1.0, 0.0, 0.0);
-        let etai_over_etat = 1.0;
+    /// Distance of the hit to the nearest line.
+    fn line_distance(&self, hit_record: &HitRecord) -> Float {
+        let (u, v) = (hit_record.u, hit_record.v);
+        match (self.uv_grid, hit_record.barycentric) {
+            (Some(cells), _) => {
+                let cells = cells as Float;
+                let to_line = |x: Float| {
+                    let fraction = (x * cells).rem_euclid(1.0);
+                    fraction.min(1.0 - fraction) / cells
+                };
+                to_line(u).min(to_line(v))
+            }
+            (None, Some((beta, gamma))) => beta.min(gamma).min(1.0 - beta - gamma),
+            (None, None) => u.min(v).min(1.0 - u).min(1.0 - v),
+        }
+    }
+}
 
-        let expected = Vec3d::new(0.0, 1.0, 0.0); // Corrected expected result
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+impl Scatterable for Wireframe {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+    ) -> Scattered {
+        let mut direction = hit_record.normal + Vec3d::random_unit_vector();
+        if direction.near_zero() {
+            direction = hit_record.normal;
+        }
+        let attenuation = if self.line_distance(hit_record) < 0.5 * self.width { self.line } else { self.fill };
+        Some((Ray::new(hit_record.point, direction, ray_in.time), attenuation))
     }
 
-    #[test]
-    fn test_refract_2() {
-        let v_in = Vec3d::new(1.0, 1.0, 0.0);
-        let normal = Vec3d::new(-1.0, 0.0, 0.0);
-        let etai_over_etat = 0.5;
+    /// Cosine weighted over the hemisphere around the normal.
+    fn scattering_pdf(&self, _ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Option<Float> {
+        let cosine = dot(&hit_record.normal, &scattered.direction.unit_vector());
+        Some(cosine.max(0.0) / consts::PI)
+    }
+}
+
+
+/// Index of refraction of keratin, the material of hair fibers.
+const HAIR_REFRACTION_INDEX: Float = 1.55;
+
 
-        let expected = Vec3d::new(0.8660254037844386, 0.5, 0.0); // Corrected expected result
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+/// A fiber material for ``Curve`` strands such as hair, fur or grass.
+///
+/// Light is either reflected off the cuticle, in a cone around the fiber following its
+/// ``tangent`` and tinted by ``specular``, or enters the fiber and leaves diffusely tinted by
+/// the pigment ``color``. The share of reflected light follows the Fresnel term of keratin.
+/// On surfaces without a tangent the reflection falls back to a mirror around the normal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hair {
+    color: Color,
+    specular: Color,
+    roughness: Float,
+}
+
+
+impl Hair {
+    /// Creates a hair material with the given pigment color and a white highlight spread by
+    /// ``roughness``, ``0.0`` being perfectly smooth.
+    pub fn new(color: Color, roughness: Float) -> Self {
+        Self { color, specular: Color::splat(1.0), roughness }
     }
 
-    #[test]
-    fn test_refract_3() {
-        let v_in = Vec3d::new(0.0, -1.0, 0.0);
-        let normal = Vec3d::new(0.0, 1.0, 0.0);
-        let etai_over_etat = 1.5;
+    /// Sets the tint of the highlight.
+    pub fn with_specular(mut self, specular: Color) -> Self {
+        self.specular = specular;
+        self
+    }
+}
+
+impl Scatterable for Hair {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+    ) -> Scattered {
+        let unit_direction = ray_in.direction.unit_vector();
+        let normal = hit_record.normal;
+        let cos_theta = dot(&-unit_direction, &normal).clamp(0.0, 1.0);
+
+        if reflectance(cos_theta, HAIR_REFRACTION_INDEX) > random() {
+            let tangent = hit_record.tangent;
+            let direction = if tangent.near_zero() {
+                reflect(&unit_direction, &normal)
+            } else {
+                // Reflection off a cylinder keeps the component along its axis, and leaves
+                // around it at any angle facing the incoming side.
+                let along = dot(&unit_direction, &tangent);
+                let facing = (normal - tangent * dot(&normal, &tangent)).unit_vector();
+                let side = cross(&tangent, &facing);
+                let phi = (random::<Float>() - 0.5) * consts::PI;
+                let around = facing * phi.cos() + side * phi.sin();
+                tangent * along + around * (1.0 - along * along).max(0.0).sqrt()
+            };
+            let direction = direction + Vec3d::random_unit_vector() * self.roughness;
+            if dot(&direction, &normal) <= 0.0 { return None; }
+            return Some((Ray::new(hit_record.point, direction, ray_in.time), self.specular));
+        }
 
-        let expected = Vec3d::new(0.0, -1.0, 0.0); // Corrected expected result
-        let result = refract(&v_in, &normal, etai_over_etat);
-        assert_eq!(result, expected);
+        let mut direction = normal + Vec3d::random_unit_vector();
+        if direction.near_zero() {
+            direction = normal;
+        }
+        Some((Ray::new(hit_record.point, direction, ray_in.time), self.color))
     }
 }
 
+
+fn reflectance(cosine: Float, refraction_index: Float) -> Float {
+    // use Schlick's approximation for reflectance
+    let mut r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
+    r0 *= r0;
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+}
+
+
 #[cfg(test)]
 mod test_material {
     use super::*;
     use crate::vec3d::{Point3d, Vec3d};
 
+    #[test]
+    fn test_metal_invalid_fuss() {
+        assert!(Metal::try_new(Color::zero(), 1.0).is_ok());
+        assert!(matches!(Metal::try_new(Color::zero(), 1.5), Err(Error::InvalidFuzz(f)) if f == 1.5));
+        assert!(matches!(Metal::try_new(Color::zero(), -0.1), Err(Error::InvalidFuzz(_))));
+        assert!(matches!(Metal::try_new(Color::zero(), Float::NAN), Err(Error::InvalidFuzz(_))));
+    }
+
     #[test]
     fn test_empty_material() {
         let empty = Empty {};
@@ -388,4 +806,109 @@ mod test_material {
         let ret = empty.scatter(&ray_in, &hit_record);
         assert!(ret.is_none());
     }
+
+    #[test]
+    fn test_hair_highlight_follows_fiber() {
+        let hair = Hair::new(Color::new(1.0, 0.0, 0.0), 0.0).with_specular(Color::new(0.0, 0.0, 1.0));
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 0.0, 1.0);
+        hit_record.tangent = Vec3d::new(0.0, 1.0, 0.0);
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 1.0), Vec3d::new(0.0, -1.0, -1.0), 0.0);
+        let along = dot(&ray_in.direction.unit_vector(), &hit_record.tangent);
+
+        let mut highlights = 0;
+        for _ in 0..500 {
+            if let Some((scattered, attenuation)) = hair.scatter(&ray_in, &hit_record) {
+                if attenuation == Color::new(0.0, 0.0, 1.0) {
+                    highlights += 1;
+                    let direction = scattered.direction.unit_vector();
+                    assert!((dot(&direction, &hit_record.tangent) - along).abs() < 1e-6);
+                    assert!(dot(&direction, &hit_record.normal) >= 0.0);
+                }
+            }
+        }
+        // Fresnel at 45 degrees reflects a few percent of the light.
+        assert!(highlights > 0 && highlights < 100);
+    }
+
+    #[test]
+    fn test_lambertian_scattering_pdf() {
+        let lambertian = Lambertian::new(Color::splat(0.5));
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+
+        let up = Ray::new(Point3d::zero(), Vec3d::new(0.0, 2.0, 0.0), 0.0);
+        assert_eq!(lambertian.scattering_pdf(&ray_in, &hit_record, &up), Some(1.0 / consts::PI));
+        let down = Ray::new(Point3d::zero(), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        assert_eq!(lambertian.scattering_pdf(&ray_in, &hit_record, &down), Some(0.0));
+
+        let metal = Material::Metal(Metal::new(Color::splat(0.5), 0.0));
+        assert!(metal.scattering_pdf(&ray_in, &hit_record, &up).is_none());
+    }
+
+    #[test]
+    fn test_frosted_dielectric_keeps_sides() {
+        let frosted = Dielectric::new(1.5).with_roughness(0.5);
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        hit_record.front_face = true;
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 1.0), Vec3d::new(0.0, -1.0, -1.0), 0.0);
+
+        let (mut reflected, mut refracted) = (0, 0);
+        for _ in 0..500 {
+            if let Some((scattered, _)) = frosted.scatter(&ray_in, &hit_record) {
+                if scattered.direction.y() > 0.0 { reflected += 1 } else { refracted += 1 }
+            }
+        }
+        assert!(refracted > 5 * reflected && reflected > 0);
+    }
+
+    #[test]
+    fn test_coat_reflects_at_grazing_angles() {
+        let paint = Coat::new(Material::Lambertian(Lambertian::new(Color::new(1.0, 0.0, 0.0))));
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        let highlights = |direction: Vec3d| {
+            let ray_in = Ray::new(Point3d::zero() - direction, direction, 0.0);
+            (0..1000)
+                .filter(|_| paint.scatter(&ray_in, &hit_record).unwrap().1 == Color::splat(1.0))
+                .count()
+        };
+        // About 4% head on, and most of the light close to grazing.
+        assert!(highlights(Vec3d::new(0.0, -1.0, 0.0)) < 100);
+        assert!(highlights(Vec3d::new(1.0, -0.01, 0.0)) > 700);
+    }
+
+    #[test]
+    fn test_wireframe_lines() {
+        let (line, fill) = (Color::splat(1.0), Color::zero());
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        let shade = |wireframe: &Wireframe, hit_record: &HitRecord| wireframe.scatter(&ray_in, hit_record).unwrap().1;
+
+        // Texture coordinates away from the edges don't matter on triangles.
+        let edges = Wireframe::new(line, fill).with_width(0.1);
+        (hit_record.u, hit_record.v) = (0.0, 0.0);
+        hit_record.barycentric = Some((0.3, 0.3));
+        assert_eq!(shade(&edges, &hit_record), fill);
+        hit_record.barycentric = Some((0.3, 0.67));
+        assert_eq!(shade(&edges, &hit_record), line);
+
+        let grid = Wireframe::new(line, fill).with_uv_grid(4);
+        (hit_record.u, hit_record.v) = (0.495, 0.4);
+        assert_eq!(shade(&grid, &hit_record), line);
+        (hit_record.u, hit_record.v) = (0.4, 0.4);
+        assert_eq!(shade(&grid, &hit_record), fill);
+    }
+
+    #[test]
+    fn test_coat_keeps_base_emission_and_back_face() {
+        let light = Light::from_color(Color::splat(2.0)).with_back_face(BackFace::Black);
+        let coated = Material::Coat(Coat::new(Material::Light(light)));
+        assert_eq!(coated.emitted(0.0, 0.0, &Point3d::zero()), Color::splat(2.0));
+        assert_eq!(coated.back_face(), BackFace::Black);
+        assert_eq!(coated.type_index(), 7);
+    }
 }
@@ -1,48 +1,256 @@
 use super::{HitRecord, Hittable};
 use crate::ray::{Interval, Ray};
-use crate::vec3d::{Vec3d, Color};
+use crate::vec3d::{Vec3d, Float};
+use crate::color::Color;
 use crate::object::aabb::AABB;
 use crate::object::texture::Texture;
 use crate::object::material;
 use crate::object::material::Material;
+use crate::sampler;
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 
 
+/// A distance along a ray through a ``Medium``, see ``Medium::sample_distance``.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceSample {
+    /// Distance from where the ray entered the medium, in units of the ray's length.
+    pub distance: Float,
+    /// Whether the ray scatters at ``distance``, or passes through the medium unscattered.
+    pub scatters: bool,
+    /// Density of ``distance`` if the ray scatters, otherwise the probability of passing.
+    pub pdf: Float,
+}
+
+
+/// A homogeneous participating medium, e.g. smoke or fog, filling a closed ``boundary``.
+///
+/// Rays inside the medium scatter at distances sampled proportionally to the transmittance,
+/// and then off the isotropic phase function, which the camera can importance sample like any
+/// other diffuse material. With a density equal to the extinction, the ratio of transmittance
+/// to ``DistanceSample::pdf`` is ``1`` for both outcomes, so paths only carry the albedo.
+///
+/// A medium made with ``interior`` has no boundary of its own, and instead fills the
+/// ``Dielectric`` it is given to with ``Dielectric::with_interior``, e.g. smoke in a glass
+/// sphere. The glass refracts rays at the boundary, and the medium scatters them inside.
 pub struct Medium {
-    boundary: Arc<Box<dyn Hittable>>,
-    neg_inv_density: f64,
+    boundary: Option<Arc<dyn Hittable>>,
+    neg_inv_density: Float,
     phase_func: Material,
 }
 
 impl Medium {
-    pub fn new(boundary: Arc<Box<dyn Hittable>>, density: f64, phase_func: Arc<Box<dyn Texture>>) -> Self {
+    pub fn new(boundary: Arc<dyn Hittable>, density: Float, phase_func: Arc<dyn Texture>) -> Self {
         Self {
-            boundary,
+            boundary: Some(boundary),
             neg_inv_density: -1.0 / density,
             phase_func: Material::Isotropic(material::Isotropic::new(phase_func)),
         }
     }
 
-    pub fn from_color(boundary: Arc<Box<dyn Hittable>>, density: f64, color: Vec3d) -> Self {
+    pub fn from_color(boundary: Arc<dyn Hittable>, density: Float, color: Color) -> Self {
         Self {
-            boundary,
+            boundary: Some(boundary),
             neg_inv_density: -1.0 / density,
             phase_func: Material::Isotropic(material::Isotropic::from_color(color)),
         }
     }
+
+    /// A medium without a boundary, to fill a ``Dielectric`` with. It hits nothing when added
+    /// to the world by itself.
+    pub fn interior(density: Float, phase_func: Arc<dyn Texture>) -> Self {
+        Self {
+            boundary: None,
+            neg_inv_density: -1.0 / density,
+            phase_func: Material::Isotropic(material::Isotropic::new(phase_func)),
+        }
+    }
+
+    pub fn density(&self) -> Float {
+        -1.0 / self.neg_inv_density
+    }
+
+    /// Fraction of the light crossing ``distance`` of the medium without scattering.
+    pub fn transmittance(&self, distance: Float) -> Float {
+        (distance / self.neg_inv_density).exp()
+    }
+
+    /// Samples where a ray crossing ``length`` of the medium first scatters, with an
+    /// exponential density of rate ``density``. Rays not scattering before ``length`` pass
+    /// through, with a probability of ``transmittance(length)``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::{Medium, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::vec3d::Point3d;
+    /// use std::sync::Arc;
+    /// let boundary = Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {})));
+    /// let fog = Medium::from_color(boundary, 0.5, Color::splat(1.0));
+    /// let sample = fog.sample_distance(2.0, &mut rand::rng());
+    /// if sample.scatters {
+    ///     assert!((sample.pdf - 0.5 * fog.transmittance(sample.distance)).abs() < 1e-12);
+    /// } else {
+    ///     assert_eq!((sample.distance, sample.pdf), (2.0, fog.transmittance(2.0)));
+    /// }
+    /// ```
+    pub fn sample_distance<R: Rng + ?Sized>(&self, length: Float, rng: &mut R) -> DistanceSample {
+        // ``1 - u`` lies in ``(0, 1]``, keeping the logarithm finite.
+        let distance = self.neg_inv_density * (1.0 - rng.random::<Float>()).ln();
+        if distance < length {
+            DistanceSample { distance, scatters: true, pdf: self.density() * self.transmittance(distance) }
+        } else {
+            DistanceSample { distance: length, scatters: false, pdf: self.transmittance(length) }
+        }
+    }
+
+    /// Samples whether a ray travelling through the medium from ``start`` to the hit ``exit``
+    /// scatters before reaching it. Returns the scattering off the phase function if it does,
+    /// and ``exit`` unchanged otherwise.
+    ///
+    /// This is how the camera renders the interior of a ``Dielectric``: every ray hitting its
+    /// inner side has crossed the medium on its way there.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::{Hittable, Medium, Sphere};
+    /// use ray_tracing::object::material::{Material, Dielectric};
+    /// use ray_tracing::object::texture::SolidColor;
+    /// use ray_tracing::ray::{Interval, Ray};
+    /// use ray_tracing::vec3d::{Float, Point3d, Vec3d};
+    /// use std::sync::Arc;
+    /// let smoke = Medium::interior(1e9, Arc::new(SolidColor::new(Color::splat(0.5))));
+    /// let glass = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Dielectric(Dielectric::new(1.5)));
+    /// let ray = Ray::new(Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+    /// let exit = glass.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY }).unwrap();
+    /// // Dense enough that the ray scatters right away, long before the glass.
+    /// let scattered = smoke.scatter_before(&ray, 0.0, exit);
+    /// assert!(scattered.t < 1e-6);
+    /// assert!(matches!(scattered.material, Material::Isotropic(_)));
+    /// ```
+    pub fn scatter_before<'m>(&'m self, ray: &Ray, start: Float, exit: HitRecord<'m>) -> HitRecord<'m> {
+        let start = start.max(0.0);
+        if exit.t <= start { return exit; }
+        let ray_length = ray.direction.length();
+        let sample = self.sample_distance((exit.t - start) * ray_length, &mut sampler::rng());
+        if sample.scatters {
+            self.scattering(ray, start + sample.distance / ray_length)
+        } else {
+            exit
+        }
+    }
+
+    /// The hit of a ray scattering at ``t`` inside the medium.
+    fn scattering(&self, ray: &Ray, t: Float) -> HitRecord<'_> {
+        HitRecord {
+            t,
+            u: 0.0,
+            v: 0.0,
+            point: ray.at(t),
+            normal: Vec3d::new(1.0, 0.0, 0.0), // arbitrary
+            front_face: true, // arbitrary
+            tangent: Vec3d::zero(),
+            color: None,
+            barycentric: None,
+            time: ray.time,
+            material: &self.phase_func,
+            name: None,
+            holdout: false,
+        }
+    }
+}
+
+
+impl Debug for Medium {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Medium w density {} phase {:?}", self.density(), self.phase_func)
+    }
+}
+
+
+/// Fog filling the whole scene, thinning out exponentially with height, set with
+/// ``Camera::set_atmosphere``.
+///
+/// Unlike a ``Medium`` it needs no boundary and never scatters rays: every ray segment, including
+/// rays missing the world, is blended towards ``color`` by the fraction of light the fog absorbs
+/// along it. This gives outdoor scenes their depth haze at no cost in noise.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::Atmosphere;
+/// use ray_tracing::ray::Ray;
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let haze = Atmosphere::new(Color::new(0.7, 0.8, 1.0), 0.1).with_height_falloff(0.0, 0.5);
+/// let level = Ray::new(Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+/// let upwards = Ray::new(Point3d::zero(), Vec3d::new(0.0, 1.0, 0.0), 0.0);
+/// // Looking along the ground the fog never ends, looking up it thins out.
+/// assert_eq!(haze.transmittance(&level, f64::INFINITY), 0.0);
+/// assert!((haze.transmittance(&upwards, f64::INFINITY) - (-0.2f64).exp()).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    color: Color,
+    density: Float,
+    base_height: Float,
+    falloff: Float,
+}
+
+
+impl Atmosphere {
+    /// Creates a fog of the same ``density`` everywhere, scattering ``color`` towards the
+    /// camera, e.g. the color of the sky.
+    pub fn new(color: Color, density: Float) -> Self {
+        Self { color, density, base_height: 0.0, falloff: 0.0 }
+    }
+
+    /// Makes the density ``density`` at ``base_height`` along ``y``, dividing it by ``e``
+    /// every ``1 / falloff`` higher up.
+    pub fn with_height_falloff(mut self, base_height: Float, falloff: Float) -> Self {
+        self.base_height = base_height;
+        self.falloff = falloff;
+        self
+    }
+
+    pub fn color(&self) -> Color { self.color }
+
+    /// Density of the fog at the height ``y``.
+    pub fn density_at(&self, y: Float) -> Float {
+        self.density * (-self.falloff * (y - self.base_height)).exp()
+    }
+
+    /// Integral of the density along ``ray`` up to the ray parameter ``t_max``, which may be
+    /// infinite.
+    pub fn optical_depth(&self, ray: &Ray, t_max: Float) -> Float {
+        if self.density <= 0.0 || t_max <= 0.0 { return 0.0; }
+        let density = self.density_at(ray.origin.y()) * ray.direction.length();
+        // The density changes by a factor of ``exp(-rate * t)`` along the ray.
+        let rate = self.falloff * ray.direction.y();
+        let x = rate * t_max;
+        if rate == 0.0 || x.abs() < 1e-9 {
+            density * t_max
+        } else {
+            density * -(-x).exp_m1() / rate
+        }
+    }
+
+    /// Fraction of the light passing along ``ray`` up to ``t_max`` through the fog.
+    pub fn transmittance(&self, ray: &Ray, t_max: Float) -> Float {
+        (-self.optical_depth(ray, t_max)).exp()
+    }
 }
 
 
 impl Hittable for Medium {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
-        let rec1 = self.boundary.hit(ray, &Interval::UNIVERSE);
+        let boundary = self.boundary.as_ref()?;
+        let rec1 = boundary.hit(ray, &Interval::UNIVERSE);
         if rec1.is_none() {
             return None;
         }
         let mut rec1 = rec1?;
-        let rec2 = self.boundary.hit(ray, &Interval {min: rec1.t + 0.0001, max: f64::INFINITY});
+        let rec2 = boundary.hit(ray, &Interval {min: rec1.t + 0.0001, max: Float::INFINITY});
         if rec2.is_none() {
             return None;
         }
@@ -59,27 +267,74 @@ impl Hittable for Medium {
 
         let ray_length = ray.direction.length();
         let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let random_num = thread_rng().random::<f64>();
-        let hit_distance = self.neg_inv_density * random_num.ln();
-
-        if hit_distance < distance_inside_boundary {
-            let t = rec1.t + hit_distance / ray_length;
-            let record = HitRecord {
-                t,
-                u: 0.0,
-                v: 0.0,
-                point: ray.at(t),
-                normal: Vec3d::new(1.0, 0.0, 0.0), // arbitrary
-                front_face: true, // arbitrary
-                material: &self.phase_func,
-            };
-            Some(record)
-        } else {
-            None
-        }
+        let sample = self.sample_distance(distance_inside_boundary, &mut sampler::rng());
+
+        sample.scatters.then(|| self.scattering(ray, rec1.t + sample.distance / ray_length))
     }
 
     fn bounding_box(&self) -> AABB {
-        self.boundary.bounding_box()
+        self.boundary.as_ref().map_or(AABB::EMPTY, |boundary| boundary.bounding_box())
+    }
+}
+
+
+#[cfg(test)]
+mod test_medium {
+    use super::*;
+    use crate::object::Sphere;
+    use crate::object::material::Empty;
+    use crate::vec3d::Point3d;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn fog(density: Float) -> Medium {
+        let boundary = Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {})));
+        Medium::from_color(boundary, density, Color::splat(1.0))
+    }
+
+    #[test]
+    fn test_atmosphere_optical_depth() {
+        let uniform = Atmosphere::new(Color::splat(1.0), 0.5);
+        let ray = Ray::new(Point3d::new(0.0, 3.0, 0.0), Vec3d::new(0.0, -2.0, 0.0), 0.0);
+        assert_approx_eq!(uniform.optical_depth(&ray, 1.0), 1.0);
+        assert_eq!(uniform.optical_depth(&ray, Float::INFINITY), Float::INFINITY);
+
+        // Going down from 1 to 0 with the density doubling every ``ln 2``.
+        let layered = uniform.with_height_falloff(0.0, (2.0 as Float).ln());
+        let ray = Ray::new(Point3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        assert_approx_eq!(layered.density_at(1.0), 0.25);
+        assert_approx_eq!(layered.optical_depth(&ray, 1.0), 0.25 / (2.0 as Float).ln());
+        assert_eq!(Atmosphere::new(Color::zero(), 0.0).transmittance(&ray, Float::INFINITY), 1.0);
+    }
+
+    #[test]
+    fn test_medium_transmittance() {
+        let medium = fog(2.0);
+        assert_eq!(medium.density(), 2.0);
+        assert_eq!(medium.transmittance(0.0), 1.0);
+        assert_approx_eq!(medium.transmittance(0.5), (-1.0 as Float).exp());
+    }
+
+    #[test]
+    fn test_medium_distance_sampling() {
+        let medium = fog(1.0);
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples = 20000;
+        let passed = (0..samples)
+            .map(|_| medium.sample_distance(1.0, &mut rng))
+            .inspect(|sample| {
+                let expected = if sample.scatters {
+                    medium.density() * medium.transmittance(sample.distance)
+                } else {
+                    assert_eq!(sample.distance, 1.0);
+                    medium.transmittance(1.0)
+                };
+                assert_approx_eq!(sample.pdf, expected);
+            })
+            .filter(|sample| !sample.scatters)
+            .count();
+        let passing = passed as Float / samples as Float;
+        assert!((passing - medium.transmittance(1.0)).abs() < 0.01, "{}", passing);
     }
 }
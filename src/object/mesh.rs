@@ -0,0 +1,387 @@
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
+use crate::color::Color;
+use crate::object::aabb::AABB;
+use crate::object::material::Material;
+use crate::object::{BVHNode, HitRecord, Hittable, HittableVec, Surface};
+use crate::ray::{Interval, Ray};
+use crate::error::{Error, Result};
+use crate::tolerance;
+
+use rand::Rng;
+use std::sync::Arc;
+
+
+/// Vertex and triangle data of a mesh, as produced by tessellation or model loaders.
+///
+/// Per vertex attributes other than ``positions`` are optional; leave them empty or give one
+/// value per position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<Point3d>,
+    /// Shading normals, interpolated over every triangle so coarse meshes of smooth objects
+    /// don't look faceted. Zero normals fall back to the geometric normal of the triangle.
+    pub normals: Vec<Vec3d>,
+    /// Texture coordinates, interpolated over every triangle. Without them the barycentric
+    /// coordinates of the hit are reported instead.
+    pub uvs: Vec<(Float, Float)>,
+    /// Linear vertex colors, interpolated over every triangle and shown by materials using
+    /// the ``VertexColor`` texture.
+    pub colors: Vec<Color>,
+    /// Vertex indices of every triangle, counter-clockwise when seen from the front.
+    pub triangles: Vec<[usize; 3]>,
+    /// Shade with the geometric normal of every triangle, even when ``normals`` are given.
+    pub flat_shading: bool,
+}
+
+
+impl MeshData {
+    pub(crate) fn validate(&self) -> Result<()> {
+        let vertices = self.positions.len();
+        if !self.normals.is_empty() && self.normals.len() != vertices {
+            return Err(Error::AttributeCount { attribute: "normal", expected: vertices, found: self.normals.len() });
+        }
+        if !self.uvs.is_empty() && self.uvs.len() != vertices {
+            return Err(Error::AttributeCount { attribute: "uv", expected: vertices, found: self.uvs.len() });
+        }
+        if !self.colors.is_empty() && self.colors.len() != vertices {
+            return Err(Error::AttributeCount { attribute: "color", expected: vertices, found: self.colors.len() });
+        }
+        for &index in self.triangles.iter().flatten() {
+            if index >= vertices {
+                return Err(Error::IndexOutOfRange { index, len: vertices });
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Mesh data and material shared by all triangles of a mesh.
+struct Shared {
+    data: MeshData,
+    material: Material,
+}
+
+
+/// A single triangle of a mesh, referring to the shared vertex data.
+struct Triangle {
+    mesh: Arc<Shared>,
+    index: usize,
+    bbox: AABB,
+}
+
+
+impl Triangle {
+    fn vertices(&self) -> [usize; 3] {
+        self.mesh.data.triangles[self.index]
+    }
+}
+
+
+impl Hittable for Triangle {
+    /// Möller-Trumbore ray triangle intersection.
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let [i0, i1, i2] = self.vertices();
+        let positions = &self.mesh.data.positions;
+        let p0 = positions[i0];
+        let edge_1 = positions[i1] - p0;
+        let edge_2 = positions[i2] - p0;
+
+        let p = cross(&ray.direction, &edge_2);
+        let det = dot(&edge_1, &p);
+        if det.abs() < tolerance::parallel() { return None; }
+        let inv_det = 1.0 / det;
+
+        let s = ray.origin - p0;
+        let beta = dot(&s, &p) * inv_det;
+        if !(0.0..=1.0).contains(&beta) { return None; }
+
+        let q = cross(&s, &edge_1);
+        let gamma = dot(&ray.direction, &q) * inv_det;
+        if gamma < 0.0 || beta + gamma > 1.0 { return None; }
+
+        let t = dot(&edge_2, &q) * inv_det;
+        if !interval.contains(t) { return None; }
+
+        let alpha = 1.0 - beta - gamma;
+        let uvs = &self.mesh.data.uvs;
+        let (u, v) = if uvs.is_empty() {
+            (beta, gamma)
+        } else {
+            (
+                alpha * uvs[i0].0 + beta * uvs[i1].0 + gamma * uvs[i2].0,
+                alpha * uvs[i0].1 + beta * uvs[i1].1 + gamma * uvs[i2].1,
+            )
+        };
+
+        let mut rec = HitRecord::new(&self.mesh.material, t, u, v, ray.at(t));
+        rec.barycentric = Some((beta, gamma));
+        rec.set_face_normal(ray, cross(&edge_1, &edge_2).unit_vector());
+
+        // The geometric normal decides the side that was hit, the shading normal only bends
+        // the normal on that side.
+        let normals = &self.mesh.data.normals;
+        if !normals.is_empty() && !self.mesh.data.flat_shading {
+            let normal = normals[i0] * alpha + normals[i1] * beta + normals[i2] * gamma;
+            if !normal.near_zero() {
+                let normal = normal.unit_vector();
+                rec.normal = if rec.front_face { normal } else { -normal };
+            }
+        }
+
+        let colors = &self.mesh.data.colors;
+        if !colors.is_empty() {
+            rec.color = Some(colors[i0] * alpha + colors[i1] * beta + colors[i2] * gamma);
+        }
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+/// A triangle mesh with a single material, accelerated by its own BVH.
+/// # Examples
+/// ```
+/// use ray_tracing::object::{Hittable, Mesh, MeshData};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let data = MeshData {
+///     positions: vec![Point3d::zero(), Point3d::new(1.0, 0.0, 0.0), Point3d::new(0.0, 1.0, 0.0)],
+///     triangles: vec![[0, 1, 2]],
+///     ..Default::default()
+/// };
+/// let mesh = Mesh::new(data, Material::Empty(Empty {}));
+/// let ray = Ray::new(Point3d::new(0.25, 0.25, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// let rec = mesh.hit(&ray, &Interval::UNIVERSE).unwrap();
+/// assert_eq!(rec.t, 1.0);
+/// assert_eq!(rec.normal, Vec3d::new(0.0, 0.0, 1.0));
+/// ```
+pub struct Mesh {
+    shared: Arc<Shared>,
+    bvh: BVHNode,
+    area_cdf: Vec<Float>, // Running total of the triangle areas, to sample points uniformly.
+}
+
+
+impl Mesh {
+    /// Creates a mesh from its data.
+    /// # Panics
+    /// If a triangle refers to a missing vertex or a vertex attribute has the wrong number of
+    /// values, see ``try_new`` for a fallible version.
+    pub fn new(data: MeshData, material: Material) -> Self {
+        Self::try_new(data, material).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(data: MeshData, material: Material) -> Result<Self> {
+        data.validate()?;
+        let shared = Arc::new(Shared { data, material });
+
+        let mut triangles = HittableVec::new();
+        let mut area_cdf = Vec::with_capacity(shared.data.triangles.len());
+        let mut area = 0.0;
+        for (index, vertices) in shared.data.triangles.iter().enumerate() {
+            let positions = vertices.map(|i| shared.data.positions[i]);
+            area += 0.5 * cross(&(positions[1] - positions[0]), &(positions[2] - positions[0])).length();
+            area_cdf.push(area);
+
+            let min = positions[0].min(&positions[1]).min(&positions[2]);
+            let max = positions[0].max(&positions[1]).max(&positions[2]);
+            triangles.add(Arc::new(Triangle {
+                mesh: shared.clone(),
+                index,
+                bbox: AABB::from_points(&min, &max),
+            }));
+        }
+
+        Ok(Self { shared, bvh: BVHNode::from_hittable_vec(Arc::new(triangles)), area_cdf })
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.shared.data
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.shared.data.triangles.len()
+    }
+}
+
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        self.bvh.hit(ray, interval)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.bvh.hit_any(ray, interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bvh.bounding_box()
+    }
+}
+
+
+impl Surface for Mesh {
+    fn area(&self) -> Float {
+        self.area_cdf.last().copied().unwrap_or(0.0)
+    }
+
+    fn sample_point<R: Rng + ?Sized>(&self, rng: &mut R) -> (Point3d, Vec3d) {
+        let target = rng.random::<Float>() * self.area();
+        let index = self.area_cdf.partition_point(|&area| area < target).min(self.area_cdf.len() - 1);
+        let [p0, p1, p2] = self.shared.data.triangles[index].map(|i| self.shared.data.positions[i]);
+
+        // Fold points of the parallelogram back into the triangle.
+        let (mut beta, mut gamma) = (rng.random::<Float>(), rng.random::<Float>());
+        if beta + gamma > 1.0 {
+            (beta, gamma) = (1.0 - beta, 1.0 - gamma);
+        }
+        let point = p0 + (p1 - p0) * beta + (p2 - p0) * gamma;
+        (point, cross(&(p1 - p0), &(p2 - p0)).unit_vector())
+    }
+}
+
+
+#[cfg(test)]
+mod test_mesh {
+    use super::*;
+    use crate::object::material::Empty;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// A unit square in the xy plane made of two triangles.
+    fn square() -> MeshData {
+        MeshData {
+            positions: vec![
+                Point3d::zero(),
+                Point3d::new(1.0, 0.0, 0.0),
+                Point3d::new(1.0, 1.0, 0.0),
+                Point3d::new(0.0, 1.0, 0.0),
+            ],
+            uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mesh_hit_interpolates_uv() {
+        let mesh = Mesh::new(square(), Material::Empty(Empty {}));
+        assert_eq!(mesh.triangle_count(), 2);
+
+        for (x, y) in [(0.75, 0.25), (0.25, 0.75)] {
+            let ray = Ray::new(Point3d::new(x, y, 2.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+            let rec = mesh.hit(&ray, &Interval::UNIVERSE).unwrap();
+            assert_approx_eq!(rec.t, 2.0);
+            assert_approx_eq!(rec.u, x);
+            assert_approx_eq!(rec.v, y);
+            let (beta, gamma) = rec.barycentric.unwrap();
+            assert!(beta >= 0.0 && gamma >= 0.0 && beta + gamma <= 1.0);
+            assert!(rec.front_face);
+        }
+
+        let miss = Ray::new(Point3d::new(1.5, 0.5, 2.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert!(mesh.hit(&miss, &Interval::UNIVERSE).is_none());
+        assert!(!mesh.hit_any(&miss, &Interval::UNIVERSE));
+    }
+
+    #[test]
+    fn test_mesh_back_face() {
+        let mesh = Mesh::new(square(), Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(0.5, 0.2, -1.0), Vec3d::new(0.0, 0.0, 1.0), 0.0);
+        let rec = mesh.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3d::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_mesh_smooth_shading() {
+        let mut data = square();
+        // Normals tilted outwards along x, as if the square was part of a cylinder.
+        data.normals = vec![
+            Vec3d::new(-1.0, 0.0, 1.0),
+            Vec3d::new(1.0, 0.0, 1.0),
+            Vec3d::new(1.0, 0.0, 1.0),
+            Vec3d::new(-1.0, 0.0, 1.0),
+        ];
+        let center = Ray::new(Point3d::new(0.5, 0.25, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        let edge = Ray::new(Point3d::new(0.9, 0.5, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+
+        let mesh = Mesh::new(data.clone(), Material::Empty(Empty {}));
+        let rec = mesh.hit(&center, &Interval::UNIVERSE).unwrap();
+        assert_approx_eq!(rec.normal.x(), 0.0);
+        assert_approx_eq!(rec.normal.z(), 1.0);
+        let rec = mesh.hit(&edge, &Interval::UNIVERSE).unwrap();
+        assert!(rec.normal.x() > 0.5);
+
+        // Seen from behind, the interpolated normal is flipped too.
+        let behind = Ray::new(Point3d::new(0.9, 0.5, -1.0), Vec3d::new(0.0, 0.0, 1.0), 0.0);
+        let rec = mesh.hit(&behind, &Interval::UNIVERSE).unwrap();
+        assert!(!rec.front_face);
+        assert!(rec.normal.x() < -0.5 && rec.normal.z() < 0.0);
+
+        data.flat_shading = true;
+        let mesh = Mesh::new(data, Material::Empty(Empty {}));
+        let rec = mesh.hit(&edge, &Interval::UNIVERSE).unwrap();
+        assert_eq!(rec.normal, Vec3d::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_mesh_vertex_colors() {
+        let mut data = square();
+        let mesh = Mesh::new(data.clone(), Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(0.75, 0.25, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(mesh.hit(&ray, &Interval::UNIVERSE).unwrap().color, None);
+
+        data.colors = vec![Color::zero(), Color::new(1.0, 0.0, 0.0), Color::zero(), Color::zero()];
+        let mesh = Mesh::new(data.clone(), Material::Empty(Empty {}));
+        let color = mesh.hit(&ray, &Interval::UNIVERSE).unwrap().color.unwrap();
+        assert_approx_eq!(color.r(), 0.5);
+        assert_approx_eq!(color.g(), 0.0);
+
+        data.colors.pop();
+        assert!(matches!(
+            Mesh::try_new(data, Material::Empty(Empty {})),
+            Err(Error::AttributeCount { attribute: "color", expected: 4, found: 3 }),
+        ));
+    }
+
+    #[test]
+    fn test_mesh_surface_sampling() {
+        let mut data = square();
+        // A far away, larger triangle takes most of the samples.
+        data.positions.extend([Point3d::new(10.0, 0.0, 0.0), Point3d::new(13.0, 0.0, 0.0), Point3d::new(10.0, 2.0, 0.0)]);
+        data.uvs.extend([(0.0, 0.0); 3]);
+        data.triangles.push([4, 5, 6]);
+        let mesh = Mesh::new(data, Material::Empty(Empty {}));
+        assert_approx_eq!(mesh.area(), 4.0);
+
+        let mut rng = rand::rng();
+        let far = (0..1000).filter(|_| {
+            let (point, normal) = mesh.sample_point(&mut rng);
+            assert_eq!(normal, Vec3d::new(0.0, 0.0, 1.0));
+            point.x() >= 10.0
+        }).count();
+        assert!((650..850).contains(&far));
+    }
+
+    #[test]
+    fn test_mesh_invalid_data() {
+        let mut data = square();
+        data.triangles.push([0, 1, 4]);
+        assert!(matches!(
+            Mesh::try_new(data, Material::Empty(Empty {})),
+            Err(Error::IndexOutOfRange { index: 4, len: 4 }),
+        ));
+
+        let mut data = square();
+        data.uvs.pop();
+        assert!(matches!(
+            Mesh::try_new(data, Material::Empty(Empty {})),
+            Err(Error::AttributeCount { attribute: "uv", expected: 4, found: 3 }),
+        ));
+    }
+}
@@ -7,10 +7,35 @@ pub mod quad;
 mod r#box;
 mod instance;
 mod medium;
+mod curve;
+mod surface;
+mod mesh;
+mod boolean;
+mod displacement;
+mod subdivision;
+mod sdf;
+mod obj;
+mod ply;
+mod patch;
+mod portal;
+mod emitter;
+mod ies;
 
 pub use hit::{HitRecord, Hittable, HittableVec, BVHNode};
 pub use sphere::Sphere;
 pub use quad::Quad;
-pub use r#box::bbox;
-pub use instance::{Translate, RotateY};
-pub use medium::Medium;
+pub use r#box::{bbox, bbox_with, Box3d, BoxFaces, BoxUv};
+pub use aabb::AABB;
+pub use instance::{Named, Visibility, VisibilityFlags, LevelOfDetail, Translate, RotateY, Transform, TransformedEmitter, Animated, Clip, ClipRegion};
+pub use medium::{Atmosphere, DistanceSample, Medium};
+pub use curve::{Curve, CurveShape};
+pub use surface::{scatter_on_surface, Surface};
+pub use mesh::{Mesh, MeshData};
+pub use boolean::BooleanOp;
+pub use sdf::{DistanceField, Mandelbulb, MengerSponge, Sdf};
+pub use obj::{parse_obj, read_obj};
+pub use ply::{parse_ply, read_ply};
+pub use patch::BezierPatch;
+pub use portal::Portal;
+pub use emitter::{EmissionMap, Emitter, EmitterSample, LightPower, PointLight};
+pub use ies::IesProfile;
@@ -0,0 +1,185 @@
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::object::MeshData;
+use crate::error::{Error, Result};
+
+use std::collections::HashMap;
+
+
+/// Reads a Wavefront OBJ file into a ``MeshData``, see ``parse_obj``.
+pub fn read_obj(path: &str) -> Result<MeshData> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|source| Error::Io { file: path.to_string(), source })?;
+    parse_obj(&source)
+}
+
+
+/// Parses the geometry of a Wavefront OBJ model.
+///
+/// Positions (``v``), texture coordinates (``vt``), normals (``vn``) and faces (``f``) are
+/// read; polygons are split into triangle fans and everything else, e.g. groups and
+/// materials, is ignored. Every distinct combination of indices of a face corner becomes a
+/// vertex, so per-vertex normals are kept for smooth shading. Corners without a texture
+/// coordinate or normal get ``(0, 0)`` and a zero normal, which shades flat.
+/// # Examples
+/// ```
+/// use ray_tracing::object::parse_obj;
+/// let data = parse_obj("
+///     v 0 0 0
+///     v 1 0 0
+///     v 1 1 0
+///     v 0 1 0
+///     vn 0 0 1
+///     f 1//1 2//1 3//1 4//1
+/// ").unwrap();
+/// assert_eq!(data.positions.len(), 4);
+/// assert_eq!(data.normals.len(), 4);
+/// assert_eq!(data.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+/// ```
+pub fn parse_obj(source: &str) -> Result<MeshData> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut corners: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+    let mut vertices = HashMap::new();
+    let mut triangles = Vec::new();
+
+    for (number, text) in source.lines().enumerate() {
+        let line = number + 1;
+        let mut fields = text.split_whitespace();
+        match fields.next() {
+            Some("v") => {
+                let [x, y, z] = numbers(fields, line)?;
+                positions.push(Point3d::new(x, y, z));
+            }
+            Some("vt") => {
+                let [u, v] = numbers(fields, line)?;
+                uvs.push((u, v));
+            }
+            Some("vn") => {
+                let [x, y, z] = numbers(fields, line)?;
+                normals.push(Vec3d::new(x, y, z));
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                for corner in fields {
+                    let corner = parse_corner(corner, positions.len(), uvs.len(), normals.len(), line)?;
+                    let index = *vertices.entry(corner).or_insert_with(|| {
+                        corners.push(corner);
+                        corners.len() - 1
+                    });
+                    face.push(index);
+                }
+                if face.len() < 3 {
+                    return Err(Error::Parse { line, message: format!("face needs at least 3 vertices, got {}", face.len()) });
+                }
+                for i in 1..face.len() - 1 {
+                    triangles.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let has_uvs = corners.iter().any(|corner| corner.1.is_some());
+    let has_normals = corners.iter().any(|corner| corner.2.is_some());
+    Ok(MeshData {
+        positions: corners.iter().map(|corner| positions[corner.0]).collect(),
+        normals: if has_normals {
+            corners.iter().map(|corner| corner.2.map_or(Vec3d::zero(), |i| normals[i])).collect()
+        } else {
+            Vec::new()
+        },
+        uvs: if has_uvs {
+            corners.iter().map(|corner| corner.1.map_or((0.0, 0.0), |i| uvs[i])).collect()
+        } else {
+            Vec::new()
+        },
+        triangles,
+        ..Default::default()
+    })
+}
+
+
+/// Parses the first ``N`` numbers of a line, ignoring any extra ones such as a ``w``
+/// coordinate.
+fn numbers<'a, const N: usize>(mut fields: impl Iterator<Item = &'a str>, line: usize) -> Result<[Float; N]> {
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        let field = fields.next()
+            .ok_or_else(|| Error::Parse { line, message: format!("expected {} numbers", N) })?;
+        *value = field.parse()
+            .map_err(|_| Error::Parse { line, message: format!("invalid number {:?}", field) })?;
+    }
+    Ok(values)
+}
+
+
+/// Parses a face corner ``v``, ``v/vt``, ``v//vn`` or ``v/vt/vn`` into zero-based indices.
+fn parse_corner(corner: &str, positions: usize, uvs: usize, normals: usize, line: usize) -> Result<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = corner.split('/');
+    let position = resolve(parts.next(), positions, line)?
+        .ok_or_else(|| Error::Parse { line, message: format!("face corner {:?} has no position", corner) })?;
+    let uv = resolve(parts.next(), uvs, line)?;
+    let normal = resolve(parts.next(), normals, line)?;
+    Ok((position, uv, normal))
+}
+
+
+/// Turns a one-based or negative, i.e. relative to the end, OBJ index into a zero-based one.
+fn resolve(index: Option<&str>, len: usize, line: usize) -> Result<Option<usize>> {
+    let index = match index {
+        Some(index) if !index.is_empty() => index,
+        _ => return Ok(None),
+    };
+    let value: i64 = index.parse()
+        .map_err(|_| Error::Parse { line, message: format!("invalid index {:?}", index) })?;
+    let resolved = if value < 0 { len as i64 + value } else { value - 1 };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(Error::Parse { line, message: format!("index {} is out of range", value) });
+    }
+    Ok(Some(resolved as usize))
+}
+
+
+#[cfg(test)]
+mod test_obj {
+    use super::*;
+
+    #[test]
+    fn test_parse_obj_shares_vertices() {
+        let data = parse_obj("
+            # a unit square split in two, with a shared edge
+            o square
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            f 1/1 2/2 3/3
+            f -4/-4 -2/-2 -1/-1
+        ").unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.uvs[3], (0.0, 1.0));
+        assert!(data.normals.is_empty());
+        assert_eq!(data.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_parse_obj_errors() {
+        match parse_obj("v 0 0\n") {
+            Err(Error::Parse { line: 1, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n") {
+            Err(Error::Parse { line: 3, message }) => assert_eq!(message, "index 3 is out of range"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match read_obj("does/not/exist.obj") {
+            Err(Error::Io { file, .. }) => assert_eq!(file, "does/not/exist.obj"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::vec3d::{Point3d, Vec3d, Float, cross};
+use crate::object::material::Material;
+use crate::object::{Mesh, MeshData};
+
+
+/// A bicubic tensor-product Bézier patch, the building block of smooth models such as the
+/// Utah teapot.
+///
+/// Patches are rendered by tessellating them into a ``Mesh``, whose texture coordinates are
+/// the patch parameters. ``control[row][column]`` is the control point at ``v = row / 3`` and
+/// ``u = column / 3``; the front of the patch is where ``dP/du x dP/dv`` points.
+/// # Examples
+/// ```
+/// use ray_tracing::object::{BezierPatch, Hittable};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Ray, Interval};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// // A gentle bump over the unit square.
+/// let control = std::array::from_fn(|row| std::array::from_fn(|column| {
+///     let height = if (1..3).contains(&row) && (1..3).contains(&column) { 1.0 } else { 0.0 };
+///     Point3d::new(column as f64 / 3.0, row as f64 / 3.0, height)
+/// }));
+/// let bump = BezierPatch::new(control).to_mesh(16, Material::Empty(Empty {}));
+/// let ray = Ray::new(Point3d::new(0.5, 0.5, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// let rec = bump.hit(&ray, &Interval::UNIVERSE).unwrap();
+/// assert!((rec.point.z() - 0.5625).abs() < 1e-2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BezierPatch {
+    control: [[Point3d; 4]; 4],
+}
+
+
+impl BezierPatch {
+    pub fn new(control: [[Point3d; 4]; 4]) -> Self {
+        Self { control }
+    }
+
+    /// Returns the point of the patch at the parameters ``u`` and ``v`` in ``[0, 1]``.
+    pub fn point(&self, u: Float, v: Float) -> Point3d {
+        let (bu, bv) = (bernstein(u), bernstein(v));
+        let mut point = Point3d::zero();
+        for (row, weight_v) in self.control.iter().zip(bv) {
+            for (control, weight_u) in row.iter().zip(bu) {
+                point += *control * (weight_u * weight_v);
+            }
+        }
+        point
+    }
+
+    /// Returns the unit normal of the patch at the parameters ``u`` and ``v``, or zero where
+    /// the patch is degenerate, e.g. at a pole where a row of control points coincides.
+    pub fn normal(&self, u: Float, v: Float) -> Vec3d {
+        let (bu, bv) = (bernstein(u), bernstein(v));
+        let (du, dv) = (bernstein_derivative(u), bernstein_derivative(v));
+        let mut tangent_u = Vec3d::zero();
+        let mut tangent_v = Vec3d::zero();
+        for (j, row) in self.control.iter().enumerate() {
+            for (i, control) in row.iter().enumerate() {
+                tangent_u += *control * (du[i] * bv[j]);
+                tangent_v += *control * (bu[i] * dv[j]);
+            }
+        }
+        let normal = cross(&tangent_u, &tangent_v);
+        if normal.near_zero() { Vec3d::zero() } else { normal.unit_vector() }
+    }
+
+    /// Tessellates the patch into a grid of ``resolution x resolution`` quads, each split into
+    /// two triangles, with the exact normals of the patch at every vertex for smooth shading.
+    /// A resolution of ``0`` is treated as ``1``.
+    pub fn tessellate(&self, resolution: usize) -> MeshData {
+        let mut data = MeshData::default();
+        self.append_to(&mut data, resolution);
+        data
+    }
+
+    /// Tessellates several patches, e.g. all patches of a model, into a single mesh.
+    pub fn tessellate_all(patches: &[BezierPatch], resolution: usize) -> MeshData {
+        let mut data = MeshData::default();
+        for patch in patches {
+            patch.append_to(&mut data, resolution);
+        }
+        data
+    }
+
+    /// Shorthand for building a ``Mesh`` out of ``tessellate``.
+    pub fn to_mesh(&self, resolution: usize, material: Material) -> Mesh {
+        Mesh::new(self.tessellate(resolution), material)
+    }
+
+    fn append_to(&self, data: &mut MeshData, resolution: usize) {
+        let resolution = resolution.max(1);
+        let first = data.positions.len();
+        let index = |i: usize, j: usize| first + j * (resolution + 1) + i;
+
+        for j in 0..=resolution {
+            for i in 0..=resolution {
+                let (u, v) = (i as Float / resolution as Float, j as Float / resolution as Float);
+                data.positions.push(self.point(u, v));
+                data.normals.push(self.normal(u, v));
+                data.uvs.push((u, v));
+            }
+        }
+        for j in 0..resolution {
+            for i in 0..resolution {
+                let (a, b, c, d) = (index(i, j), index(i + 1, j), index(i + 1, j + 1), index(i, j + 1));
+                data.triangles.push([a, b, c]);
+                data.triangles.push([a, c, d]);
+            }
+        }
+    }
+}
+
+
+/// Derivatives of the cubic Bernstein polynomials at ``t``.
+fn bernstein_derivative(t: Float) -> [Float; 4] {
+    let s = 1.0 - t;
+    [-3.0 * s * s, 3.0 * s * s - 6.0 * s * t, 6.0 * s * t - 3.0 * t * t, 3.0 * t * t]
+}
+
+
+/// The cubic Bernstein polynomials at ``t``.
+fn bernstein(t: Float) -> [Float; 4] {
+    let s = 1.0 - t;
+    [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t]
+}
+
+
+#[cfg(test)]
+mod test_patch {
+    use super::*;
+    use crate::object::Hittable;
+    use crate::object::material::Empty;
+    use crate::ray::{Interval, Ray};
+    use assert_approx_eq::assert_approx_eq;
+
+    fn flat() -> BezierPatch {
+        BezierPatch::new(std::array::from_fn(|row| std::array::from_fn(|column| {
+            Point3d::new(2.0 * column as Float / 3.0, 0.0, -2.0 * row as Float / 3.0)
+        })))
+    }
+
+    #[test]
+    fn test_patch_corners() {
+        let patch = flat();
+        assert_eq!(patch.point(0.0, 0.0), Point3d::zero());
+        assert_eq!(patch.point(1.0, 1.0), Point3d::new(2.0, 0.0, -2.0));
+        assert_approx_eq!(patch.point(0.5, 0.25).x(), 1.0);
+        assert_approx_eq!(patch.point(0.5, 0.25).z(), -0.5);
+        assert_eq!(patch.normal(0.3, 0.6), Vec3d::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_patch_tessellation() {
+        let data = flat().tessellate(4);
+        assert_eq!(data.positions.len(), 25);
+        assert_eq!(data.triangles.len(), 32);
+
+        let two = BezierPatch::tessellate_all(&[flat(), flat()], 4);
+        assert_eq!(two.positions.len(), 50);
+        assert_eq!(two.triangles[32], [25, 26, 31]);
+    }
+
+    #[test]
+    fn test_patch_mesh_front_and_uv() {
+        let mesh = flat().to_mesh(3, Material::Empty(Empty {}));
+        // du points along +x and dv along -z, so the front faces +y.
+        let ray = Ray::new(Point3d::new(0.5, 3.0, -1.5), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        let rec = mesh.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert!(rec.front_face);
+        assert_approx_eq!(rec.u, 0.25);
+        assert_approx_eq!(rec.v, 0.75);
+    }
+}
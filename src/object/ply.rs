@@ -0,0 +1,261 @@
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::color::Color;
+use crate::object::MeshData;
+use crate::error::{Error, Result};
+
+
+/// Reads a Stanford PLY file into a ``MeshData``, see ``parse_ply``.
+pub fn read_ply(path: &str) -> Result<MeshData> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|source| Error::Io { file: path.to_string(), source })?;
+    parse_ply(&source)
+}
+
+
+/// Parses an ASCII Stanford PLY model, the usual output of 3D scanners.
+///
+/// Vertex positions (``x``, ``y``, ``z``), normals (``nx``, ``ny``, ``nz``), texture
+/// coordinates (``s``, ``t`` or ``u``, ``v``) and colors (``red``, ``green``, ``blue``) are
+/// read along with the faces, which are split into triangle fans. Colors are converted to
+/// linear, integer channels being scaled from ``0..=255``. Other elements and properties are
+/// skipped; binary PLY files are not supported.
+/// # Examples
+/// ```
+/// use ray_tracing::object::parse_ply;
+/// let data = parse_ply("ply
+/// format ascii 1.0
+/// element vertex 3
+/// property float x
+/// property float y
+/// property float z
+/// property uchar red
+/// property uchar green
+/// property uchar blue
+/// element face 1
+/// property list uchar int vertex_indices
+/// end_header
+/// 0 0 0 255 0 0
+/// 1 0 0 0 255 0
+/// 0 1 0 0 0 255
+/// 3 0 1 2
+/// ").unwrap();
+/// assert_eq!(data.triangles, vec![[0, 1, 2]]);
+/// assert_eq!(data.colors[0].r(), 1.0);
+/// ```
+pub fn parse_ply(source: &str) -> Result<MeshData> {
+    let mut lines = source.lines().enumerate().map(|(number, text)| (number + 1, text));
+    let elements = parse_header(&mut lines)?;
+
+    let mut data = MeshData::default();
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let (line, text) = lines.next()
+                .ok_or_else(|| Error::Parse { line: 0, message: format!("missing {} data", element.name) })?;
+            let mut fields = text.split_whitespace();
+            match element.name.as_str() {
+                "vertex" => read_vertex(element, &mut fields, &mut data, line)?,
+                "face" => read_face(element, &mut fields, &mut data, line)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(data)
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Integer,
+    Real,
+}
+
+
+enum Property {
+    Scalar { name: String, kind: Kind },
+    List { name: String },
+}
+
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+
+impl Element {
+    fn has(&self, name: &str) -> bool {
+        self.properties.iter().any(|property| matches!(property, Property::Scalar { name: n, .. } if n == name))
+    }
+}
+
+
+fn parse_header<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>) -> Result<Vec<Element>> {
+    match lines.next() {
+        Some((_, text)) if text.trim() == "ply" => {}
+        _ => return Err(Error::Parse { line: 1, message: "not a PLY file".to_string() }),
+    }
+
+    let mut elements: Vec<Element> = Vec::new();
+    for (line, text) in lines.by_ref() {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        match fields.as_slice() {
+            ["format", "ascii", ..] => {}
+            ["format", format, ..] => {
+                return Err(Error::Parse { line, message: format!("unsupported format {}, only ascii is supported", format) });
+            }
+            ["element", name, count] => {
+                let count = count.parse()
+                    .map_err(|_| Error::Parse { line, message: format!("invalid element count {:?}", count) })?;
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            ["property", "list", _, _, name] => match elements.last_mut() {
+                Some(element) => element.properties.push(Property::List { name: name.to_string() }),
+                None => return Err(Error::Parse { line, message: "property outside of an element".to_string() }),
+            },
+            ["property", kind, name] => match elements.last_mut() {
+                Some(element) => {
+                    let kind = match *kind {
+                        "float" | "double" | "float32" | "float64" => Kind::Real,
+                        _ => Kind::Integer,
+                    };
+                    element.properties.push(Property::Scalar { name: name.to_string(), kind });
+                }
+                None => return Err(Error::Parse { line, message: "property outside of an element".to_string() }),
+            },
+            ["end_header"] => return Ok(elements),
+            _ => {}
+        }
+    }
+    Err(Error::Parse { line: 0, message: "missing end_header".to_string() })
+}
+
+
+fn number(field: Option<&str>, line: usize) -> Result<Float> {
+    let field = field.ok_or_else(|| Error::Parse { line, message: "too few values".to_string() })?;
+    field.parse().map_err(|_| Error::Parse { line, message: format!("invalid number {:?}", field) })
+}
+
+
+fn read_vertex<'a>(element: &Element, fields: &mut impl Iterator<Item = &'a str>, data: &mut MeshData, line: usize) -> Result<()> {
+    let (mut position, mut normal, mut uv, mut color) = ([0.0; 3], [0.0; 3], [0.0; 2], [0.0; 3]);
+    for property in element.properties.iter() {
+        match property {
+            Property::Scalar { name, kind } => {
+                let value = number(fields.next(), line)?;
+                let channel = if *kind == Kind::Integer { value / 255.0 } else { value };
+                match name.as_str() {
+                    "x" => position[0] = value,
+                    "y" => position[1] = value,
+                    "z" => position[2] = value,
+                    "nx" => normal[0] = value,
+                    "ny" => normal[1] = value,
+                    "nz" => normal[2] = value,
+                    "s" | "u" | "texture_u" => uv[0] = value,
+                    "t" | "v" | "texture_v" => uv[1] = value,
+                    "red" => color[0] = channel,
+                    "green" => color[1] = channel,
+                    "blue" => color[2] = channel,
+                    _ => {}
+                }
+            }
+            Property::List { .. } => {
+                let len = number(fields.next(), line)? as usize;
+                fields.by_ref().take(len).for_each(drop);
+            }
+        }
+    }
+
+    data.positions.push(Point3d::new(position[0], position[1], position[2]));
+    if element.has("nx") {
+        data.normals.push(Vec3d::new(normal[0], normal[1], normal[2]));
+    }
+    if element.has("s") || element.has("u") || element.has("texture_u") {
+        data.uvs.push((uv[0], uv[1]));
+    }
+    if element.has("red") {
+        data.colors.push(Color::new(color[0], color[1], color[2]).to_linear(2.0));
+    }
+    Ok(())
+}
+
+
+fn read_face<'a>(element: &Element, fields: &mut impl Iterator<Item = &'a str>, data: &mut MeshData, line: usize) -> Result<()> {
+    for property in element.properties.iter() {
+        match property {
+            Property::List { name } if name == "vertex_indices" || name == "vertex_index" => {
+                let len = number(fields.next(), line)? as usize;
+                if len < 3 {
+                    return Err(Error::Parse { line, message: format!("face needs at least 3 vertices, got {}", len) });
+                }
+                let mut face = Vec::with_capacity(len);
+                for _ in 0..len {
+                    face.push(number(fields.next(), line)? as usize);
+                }
+                for i in 1..len - 1 {
+                    data.triangles.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            Property::List { .. } => {
+                let len = number(fields.next(), line)? as usize;
+                fields.by_ref().take(len).for_each(drop);
+            }
+            Property::Scalar { .. } => {
+                fields.next();
+            }
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test_ply {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_parse_ply_quad_with_normals() {
+        let data = parse_ply("ply
+format ascii 1.0
+comment a unit square
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float red
+property float green
+property float blue
+element face 1
+property uchar flags
+property list uchar int vertex_index
+end_header
+0 0 0 0 0 1 0.5 0.5 0.5
+1 0 0 0 0 1 0.5 0.5 0.5
+1 1 0 0 0 1 0.5 0.5 0.5
+0 1 0 0 0 1 0.5 0.5 0.5
+0 4 0 1 2 3
+").unwrap();
+        assert_eq!(data.positions[2], Point3d::new(1.0, 1.0, 0.0));
+        assert_eq!(data.normals[3], Vec3d::new(0.0, 0.0, 1.0));
+        assert!(data.uvs.is_empty());
+        assert_approx_eq!(data.colors[0].r(), 0.25);
+        assert_eq!(data.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_parse_ply_errors() {
+        assert!(matches!(parse_ply("obj\n"), Err(Error::Parse { line: 1, .. })));
+        assert!(matches!(
+            parse_ply("ply\nformat binary_little_endian 1.0\nend_header\n"),
+            Err(Error::Parse { line: 2, .. }),
+        ));
+        assert!(matches!(
+            parse_ply("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\nzero\n"),
+            Err(Error::Parse { line: 6, .. }),
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
+use crate::object::material::{Material, Empty};
+use crate::object::{Hittable, Quad};
+use crate::ray::{Interval, Ray};
+use crate::sampler;
+use rand::Rng;
+
+
+/// An opening, e.g. a window, through which the background lights the scene.
+///
+/// Portals are not part of the world and are never seen by rays. Registered on the camera
+/// with ``Camera::add_portal``, they make diffuse bounces send half of their rays through a
+/// random portal instead of all over the hemisphere. Interiors lit only by the background
+/// through small openings converge far faster, and the result stays unbiased as long as every
+/// opening the light comes through is covered.
+/// # Examples
+/// ```
+/// use ray_tracing::object::Portal;
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let window = Portal::new(
+///     Point3d::new(-1.0, 1.0, -5.0), Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0),
+/// );
+/// let origin = Point3d::zero();
+/// let direction = window.sample_direction(&origin);
+/// assert!(window.pdf(&origin, &direction) > 0.0);
+/// assert_eq!(window.pdf(&origin, &Vec3d::new(0.0, 0.0, 1.0)), 0.0);
+/// ```
+pub struct Portal {
+    point: Point3d,
+    vec_u: Vec3d,
+    vec_v: Vec3d,
+    area: Float,
+
+    quad: Quad,
+}
+
+
+impl Portal {
+    /// Creates a portal covering the parallelogram spanned by ``vec_u`` and ``vec_v`` from
+    /// ``point``, like a ``Quad``.
+    pub fn new(point: Point3d, vec_u: Vec3d, vec_v: Vec3d) -> Self {
+        Self {
+            point,
+            vec_u,
+            vec_v,
+            area: cross(&vec_u, &vec_v).length(),
+            quad: Quad::new(point, vec_u, vec_v, Material::Empty(Empty {})),
+        }
+    }
+
+    /// Returns a direction from ``origin`` towards a uniformly sampled point of the portal.
+    pub fn sample_direction(&self, origin: &Point3d) -> Vec3d {
+        let mut rng = sampler::rng();
+        let target = self.point + self.vec_u * rng.random::<Float>() + self.vec_v * rng.random::<Float>();
+        target - *origin
+    }
+
+    /// Solid angle density with which ``sample_direction`` returns ``direction`` from
+    /// ``origin``, zero if it misses the portal.
+    pub fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        let ray = Ray::new(*origin, *direction, 0.0);
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
+        match self.quad.hit(&ray, &interval) {
+            Some(hit_record) => {
+                let distance_squared = hit_record.t * hit_record.t * direction.length_squared();
+                let cosine = dot(direction, &hit_record.normal).abs() / direction.length();
+                distance_squared / (cosine * self.area)
+            }
+            None => 0.0,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test_portal {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_portal_pdf_small_window() {
+        // A small window straight ahead subtends about ``area / distance^2`` steradians.
+        let portal = Portal::new(
+            Point3d::new(-0.01, -0.01, -10.0), Vec3d::new(0.02, 0.0, 0.0), Vec3d::new(0.0, 0.02, 0.0),
+        );
+        let pdf = portal.pdf(&Point3d::zero(), &Vec3d::new(0.0, 0.0, -1.0));
+        assert_approx_eq!(pdf, 100.0 / 0.0004, 1e-3);
+    }
+
+    #[test]
+    fn test_portal_samples_hit_portal() {
+        let portal = Portal::new(
+            Point3d::new(0.0, 0.0, -2.0), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 1.0),
+        );
+        let origin = Point3d::new(0.5, 3.0, -1.5);
+        for _ in 0..16 {
+            let direction = portal.sample_direction(&origin);
+            assert!(direction.y() < 0.0);
+            assert!(portal.pdf(&origin, &direction) > 0.0);
+        }
+        assert_eq!(portal.pdf(&origin, &Vec3d::new(0.0, 1.0, 0.0)), 0.0);
+    }
+}
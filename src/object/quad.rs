@@ -1,10 +1,17 @@
-use crate::vec3d::{Vec3d, Point3d, cross, dot};
+use crate::vec3d::{Vec3d, Point3d, Float, cross, dot};
 
 use crate::object::aabb::AABB;
 use crate::object::HitRecord;
 use crate::object::material::Material;
 use crate::ray::{Interval, Ray};
 use crate::object::hit::Hittable;
+use crate::object::surface::Surface;
+use crate::object::{Emitter, EmitterSample};
+use crate::object::material::{BackFace, Scatterable};
+use crate::sampler;
+use crate::tolerance;
+use crate::color::Color;
+use rand::Rng;
 
 
 pub struct Quad {
@@ -14,10 +21,13 @@ pub struct Quad {
     vec_w: Vec3d,
 
     normal: Vec3d,
-    shift_d: f64,
+    shift_d: Float,
 
     material: Material,
     bbox: AABB,
+
+    uv_origin: (Float, Float),
+    uv_size: (Float, Float),
 }
 
 impl Quad {
@@ -38,6 +48,63 @@ impl Quad {
             shift_d,
             material,
             bbox,
+            uv_origin: (0.0, 0.0),
+            uv_size: (1.0, 1.0),
+        }
+    }
+
+    /// Maps the quad onto the texture rectangle starting at ``origin`` with the given ``size``
+    /// instead of the whole ``[0, 1]`` range, e.g. to place it in a texture atlas.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::object::{Quad, Hittable};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::ray::{Ray, Interval};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// let quad = Quad::new(
+    ///     Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0),
+    ///     Material::Empty(Empty {}),
+    /// ).with_uv_rect((0.5, 0.0), (0.5, 0.25));
+    /// let ray = Ray::new(Point3d::new(0.5, 0.5, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+    /// let rec = quad.hit(&ray, &Interval::UNIVERSE).unwrap();
+    /// assert_eq!((rec.u, rec.v), (0.75, 0.125));
+    /// ```
+    pub fn with_uv_rect(mut self, origin: (Float, Float), size: (Float, Float)) -> Self {
+        self.uv_origin = origin;
+        self.uv_size = size;
+        self
+    }
+
+    /// Picks a uniformly distributed point of the quad drawn from ``rng``, returning the
+    /// direction from ``origin`` towards it, whose length is the distance, and its solid angle
+    /// density. ``None`` if the material does not emit towards ``origin``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::color::Color;
+    /// use ray_tracing::object::Quad;
+    /// use ray_tracing::object::material::{Material, Light};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// // A ceiling panel, facing down.
+    /// let panel = Quad::new(
+    ///     Point3d::new(-0.5, 2.0, -0.5), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 1.0),
+    ///     Material::Light(Light::from_color(Color::splat(15.0))),
+    /// );
+    /// let origin = Point3d::zero();
+    /// let (direction, pdf) = panel.sample(&origin, &mut rand::rng()).unwrap();
+    /// assert!(direction.y() == 2.0);
+    /// assert!((panel.pdf_value(&origin, &direction) - pdf).abs() < 1e-9);
+    /// ```
+    pub fn sample<R: Rng + ?Sized>(&self, origin: &Point3d, rng: &mut R) -> Option<(Vec3d, Float)> {
+        self.emitter_sample(origin, rng.random(), rng.random(), 1.0 / self.area())
+            .map(|sample| (sample.direction, sample.pdf))
+    }
+
+    /// Solid angle density with which ``sample`` returns ``direction`` from ``origin``, zero if
+    /// it misses the quad or the quad does not emit towards ``origin``.
+    pub fn pdf_value(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        match self.locate_emission(origin, direction) {
+            Some((_, _, solid_angle_factor)) => solid_angle_factor / self.area(),
+            None => 0.0,
         }
     }
 
@@ -51,19 +118,97 @@ impl Quad {
         AABB::surrounding_box(&bbox_diagonal_1, &bbox_diagonal_2)
     }
 
-    fn is_interior(alpha: f64, beta: f64) -> bool {
+    fn is_interior(alpha: Float, beta: Float) -> bool {
         let unit_interval = Interval { min: 0.0, max: 1.0 };
         unit_interval.contains(alpha) && unit_interval.contains(beta)
     }
+
+    fn uv(&self, alpha: Float, beta: Float) -> (Float, Float) {
+        (self.uv_origin.0 + alpha * self.uv_size.0, self.uv_origin.1 + beta * self.uv_size.1)
+    }
+
+    /// Radiance emitted by the material at the fractions ``alpha`` along ``vec_u`` and
+    /// ``beta`` along ``vec_v``.
+    pub(crate) fn emission_at(&self, alpha: Float, beta: Float) -> Color {
+        let (u, v) = self.uv(alpha, beta);
+        self.material.emitted(u, v, &(self.point + self.vec_u * alpha + self.vec_v * beta))
+    }
+
+    /// Whether the material emits towards a point on the given side of the quad.
+    fn emits_towards(&self, front: bool) -> bool {
+        front || self.material.back_face() == BackFace::TwoSided
+    }
+
+    /// Light sample of the point at ``alpha`` and ``beta`` picked with the area density
+    /// ``area_pdf``, as seen from ``origin``.
+    pub(crate) fn emitter_sample(&self, origin: &Point3d, alpha: Float, beta: Float, area_pdf: Float) -> Option<EmitterSample> {
+        let direction = self.point + self.vec_u * alpha + self.vec_v * beta - *origin;
+        let distance_squared = direction.length_squared();
+        let cosine = dot(&direction, &self.normal) / distance_squared.sqrt();
+        if cosine.abs() < tolerance::parallel() || !self.emits_towards(cosine < 0.0) { return None; }
+
+        Some(EmitterSample {
+            direction,
+            radiance: self.emission_at(alpha, beta),
+            pdf: area_pdf * distance_squared / cosine.abs(),
+        })
+    }
+
+    /// Where the ray from ``origin`` along ``direction`` meets an emitting side of the quad, as
+    /// ``alpha``, ``beta`` and the factor turning area densities there into solid angle ones.
+    pub(crate) fn locate_emission(&self, origin: &Point3d, direction: &Vec3d) -> Option<(Float, Float, Float)> {
+        let denom = dot(&self.normal, direction);
+        if denom.abs() < tolerance::parallel() || !self.emits_towards(denom < 0.0) { return None; }
+        let t = (self.shift_d - dot(&self.normal, origin)) / denom;
+        if t <= 0.0 { return None; }
+
+        let planar_hit_point_vector = *origin + *direction * t - self.point;
+        let alpha = dot(&self.vec_w, &cross(&planar_hit_point_vector, &self.vec_v));
+        let beta = dot(&self.vec_w, &cross(&self.vec_u, &planar_hit_point_vector));
+        if !Self::is_interior(alpha, beta) { return None; }
+
+        let distance_squared = t * t * direction.length_squared();
+        let cosine = denom.abs() / direction.length();
+        Some((alpha, beta, distance_squared / cosine))
+    }
+}
+
+
+/// Samples the quad uniformly by area.
+impl Emitter for Quad {
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample> {
+        let mut rng = sampler::rng();
+        self.emitter_sample(origin, rng.random(), rng.random(), 1.0 / self.area())
+    }
+
+    fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        self.pdf_value(origin, direction)
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.material.light_group()
+    }
 }
 
+impl Surface for Quad {
+    fn area(&self) -> Float {
+        cross(&self.vec_u, &self.vec_v).length()
+    }
+
+    fn sample_point<R: Rng + ?Sized>(&self, rng: &mut R) -> (Point3d, Vec3d) {
+        let point = self.point + self.vec_u * rng.random::<Float>() + self.vec_v * rng.random::<Float>();
+        (point, self.normal)
+    }
+}
+
+
 impl Hittable for Quad {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
         let denom = dot(&self.normal, &ray.direction);
 
         // Return None if ray is parallel to the plane, or the hit point parameter t
         // is outside the ray.
-        if denom.abs() < f64::EPSILON { return None; };
+        if denom.abs() < tolerance::parallel() { return None; };
 
         let t = (self.shift_d - dot(&self.normal, &ray.origin)) / denom;
         if !interval.contains(t) { return None; };
@@ -76,13 +221,8 @@ impl Hittable for Quad {
         let beta = dot(&self.vec_w, &cross(&self.vec_u, &planar_hit_point_vector));
         if !Self::is_interior(alpha, beta) { return None; };
 
-        let mut rec = HitRecord::new(
-            &self.material,
-            t,
-            alpha,
-            beta,
-            intersection,
-        );
+        let (u, v) = self.uv(alpha, beta);
+        let mut rec = HitRecord::new(&self.material, t, u, v, intersection);
         rec.set_face_normal(ray, self.normal.clone());
         Some(rec)
     }
@@ -97,6 +237,7 @@ impl Hittable for Quad {
 mod test_quad {
     use super::*;
     use crate::object::material::Lambertian;
+    use crate::color::Color;
 
     use assert_approx_eq::assert_approx_eq;
 
@@ -144,7 +285,7 @@ mod test_quad {
             Vec3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         assert_eq!(quad.point, Vec3d::new(0.0, 0.0, 0.0));
@@ -161,7 +302,7 @@ mod test_quad {
             Point3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -170,7 +311,7 @@ mod test_quad {
             0.0,
         );
 
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = quad.hit(&ray, &interval).unwrap();
 
         assert_eq!(hit_record.t, 5.0);
@@ -185,7 +326,7 @@ mod test_quad {
             Point3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -194,7 +335,7 @@ mod test_quad {
             0.0,
         );
 
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = quad.hit(&ray, &interval).unwrap();
 
         assert_eq!(hit_record.t, 7.5);
@@ -209,7 +350,7 @@ mod test_quad {
             Point3d::new(-0.5, -0.5, 0.0),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -218,10 +359,10 @@ mod test_quad {
             0.0,
         );
 
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = quad.hit(&ray, &interval).unwrap();
 
-        assert_approx_eq!(hit_record.t, (0.5_f64.powi(2) * 3.0).sqrt());
+        assert_approx_eq!(hit_record.t, (Float::powi(0.5, 2) * 3.0).sqrt());
         assert_eq!(hit_record.point, Point3d::new(0.0, 0.0, 0.0));
         assert_eq!(hit_record.normal, Vec3d::new(0.0, 0.0, -1.0));
         assert_eq!(hit_record.front_face, false);
@@ -233,7 +374,7 @@ mod test_quad {
             Point3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -254,7 +395,7 @@ mod test_quad {
             Point3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -263,7 +404,7 @@ mod test_quad {
             0.0,
         );
 
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = quad.hit(&ray, &interval);
 
         assert!(hit_record.is_none());
@@ -275,7 +416,7 @@ mod test_quad {
             Point3d::zero(),
             Vec3d::new(1.0, 0.0, 0.0),
             Vec3d::new(0.0, 1.0, 0.0),
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
 
         let ray = Ray::new(
@@ -284,7 +425,7 @@ mod test_quad {
             0.0,
         );
 
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = quad.hit(&ray, &interval);
 
         assert!(hit_record.is_none());
@@ -0,0 +1,279 @@
+//! Objects given by signed distance fields, rendered by sphere tracing: a ray steps ahead by
+//! the distance to the nearest surface until it is close enough to count as a hit. This makes
+//! shapes with infinite detail possible, like the built-in ``Mandelbulb`` and
+//! ``MengerSponge``, which no mesh could hold.
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::object::{HitRecord, Hittable, AABB};
+use crate::object::material::Material;
+use crate::ray::{Interval, Ray};
+
+use std::sync::Arc;
+
+
+/// A signed distance field: the distance from any point to the nearest surface of a shape,
+/// negative inside it.
+///
+/// Distance estimates of fractals may be smaller than the true distance, but never larger,
+/// or the ray would step over the surface.
+pub trait DistanceField: Send + Sync {
+    fn distance(&self, point: &Point3d) -> Float;
+
+    /// A box containing the whole shape, where rays start to march.
+    fn bounding_box(&self) -> AABB;
+}
+
+
+/// Distance at which a ray counts as hitting the surface of an ``Sdf``, by default.
+const HIT_DISTANCE: Float = 1e-5;
+
+
+/// An object shaped by a ``DistanceField``, see the module documentation.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Hittable, MengerSponge, Sdf};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::ray::{Interval, Ray};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let sponge = Sdf::new(Arc::new(MengerSponge::new(3)), Material::Empty(Empty {}));
+/// // Through the hole in the middle of a face, or onto its solid corner.
+/// let through = Ray::new(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// assert!(sponge.hit(&through, &Interval::UNIVERSE).is_none());
+/// let corner = Ray::new(Point3d::new(0.95, 0.95, 5.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+/// let hit = sponge.hit(&corner, &Interval::UNIVERSE).unwrap();
+/// assert!((hit.t - 4.0).abs() < 1e-4);
+/// assert!((hit.normal.z() - 1.0).abs() < 1e-3);
+/// ```
+pub struct Sdf {
+    field: Arc<dyn DistanceField>,
+    material: Material,
+    max_steps: u32,
+    hit_distance: Float,
+    bbox: AABB,
+}
+
+
+impl Sdf {
+    pub fn new(field: Arc<dyn DistanceField>, material: Material) -> Self {
+        let bbox = field.bounding_box();
+        Self { field, material, max_steps: 512, hit_distance: HIT_DISTANCE, bbox }
+    }
+
+    /// Sets how many steps a ray takes at most before it counts as missing, ``512`` by
+    /// default. Rays grazing a surface need many.
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets the distance at which a ray counts as hitting the surface, ``1e-5`` by default.
+    /// Larger values render faster and smooth over fine detail. Keep it below the hit epsilon
+    /// of the camera, or bounces start inside the surface.
+    pub fn with_hit_distance(mut self, hit_distance: Float) -> Self {
+        self.hit_distance = hit_distance;
+        self
+    }
+
+    /// The outward normal at ``point``, as the gradient of the field by central differences on
+    /// the corners of a tetrahedron.
+    fn normal(&self, point: &Point3d) -> Vec3d {
+        let h = self.hit_distance;
+        let corners = [Vec3d::new(1.0, -1.0, -1.0), Vec3d::new(-1.0, -1.0, 1.0), Vec3d::new(-1.0, 1.0, -1.0), Vec3d::new(1.0, 1.0, 1.0)];
+        let gradient = corners.iter()
+            .fold(Vec3d::zero(), |sum, &corner| sum + corner * self.field.distance(&(*point + corner * h)));
+        if gradient.length_squared() > 0.0 { gradient.unit_vector() } else { Vec3d::new(0.0, 1.0, 0.0) }
+    }
+}
+
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        let (enter, leave) = box_span(&self.bbox, ray)?;
+        let speed = ray.direction.length();
+        // Surfaces may lie on the box, which the last step can only reach within the hit distance.
+        let (mut t, max) = (interval.min.max(enter), interval.max.min(leave + self.hit_distance / speed));
+        let mut steps = 0;
+        while t < max && steps < self.max_steps {
+            let distance = self.field.distance(&ray.at(t)).abs();
+            if distance < self.hit_distance {
+                let point = ray.at(t);
+                let mut hit_record = HitRecord::new(&self.material, t, 0.0, 0.0, point);
+                hit_record.set_face_normal(ray, self.normal(&point));
+                return Some(hit_record);
+            }
+            t += distance / speed;
+            steps += 1;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+
+/// The span of ``ray`` inside ``bbox``, ``None`` if it misses it.
+fn box_span(bbox: &AABB, ray: &Ray) -> Option<(Float, Float)> {
+    let (mut enter, mut leave) = (Float::NEG_INFINITY, Float::INFINITY);
+    for axis in 0..3 {
+        let bounds = bbox.axis_interval(axis);
+        if ray.direction[axis] == 0.0 {
+            if !bounds.contains(ray.origin[axis]) { return None; }
+            continue;
+        }
+        let t0 = (bounds.min - ray.origin[axis]) / ray.direction[axis];
+        let t1 = (bounds.max - ray.origin[axis]) / ray.direction[axis];
+        enter = enter.max(t0.min(t1));
+        leave = leave.min(t0.max(t1));
+    }
+    (enter < leave).then_some((enter, leave))
+}
+
+
+/// The Mandelbulb, the three-dimensional Mandelbrot set of White and Nylander, in the sphere of
+/// radius ``1.2`` around the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mandelbulb {
+    power: Float,
+    iterations: u32,
+}
+
+
+impl Mandelbulb {
+    /// The bulb of the given ``power``, ``8.0`` for the classic one, iterated ``iterations``
+    /// times, which sets how fine its detail gets.
+    pub fn new(power: Float, iterations: u32) -> Self {
+        Self { power, iterations }
+    }
+}
+
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self::new(8.0, 12)
+    }
+}
+
+
+impl DistanceField for Mandelbulb {
+    fn distance(&self, point: &Point3d) -> Float {
+        let mut z = *point;
+        // Derivative of the orbit, for the distance estimate.
+        let mut derivative = 1.0;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2.0 || r == 0.0 { break; }
+            let theta = (z.z() / r).acos() * self.power;
+            let phi = z.y().atan2(z.x()) * self.power;
+            derivative = r.powf(self.power - 1.0) * self.power * derivative + 1.0;
+            z = Vec3d::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) * r.powf(self.power) + *point;
+            r = z.length();
+        }
+        if r == 0.0 { return 0.0; }
+        0.5 * r.ln() * r / derivative
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::from_points(&Point3d::new(-1.2, -1.2, -1.2), &Point3d::new(1.2, 1.2, 1.2))
+    }
+}
+
+
+/// The Menger sponge filling the cube from ``-1`` to ``1``: the cube with the middle of every
+/// face drilled through, again and again in each of the remaining smaller cubes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MengerSponge {
+    iterations: u32,
+}
+
+
+impl MengerSponge {
+    /// The sponge drilled through ``iterations`` times.
+    pub fn new(iterations: u32) -> Self {
+        Self { iterations }
+    }
+}
+
+
+impl DistanceField for MengerSponge {
+    fn distance(&self, point: &Point3d) -> Float {
+        // Distance to the cube, then to the crosses cut out of it at every scale.
+        let q = Vec3d::new(point.x().abs() - 1.0, point.y().abs() - 1.0, point.z().abs() - 1.0);
+        let outside = Vec3d::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+        let mut distance = outside + q.x().max(q.y()).max(q.z()).min(0.0);
+
+        let mut scale = 1.0;
+        for _ in 0..self.iterations {
+            let r = |x: Float| (1.0 - 3.0 * ((x * scale).rem_euclid(2.0) - 1.0).abs()).abs();
+            let (rx, ry, rz) = (r(point.x()), r(point.y()), r(point.z()));
+            let cross = rx.max(ry).min(ry.max(rz)).min(rz.max(rx));
+            scale *= 3.0;
+            distance = distance.max((cross - 1.0) / scale);
+        }
+        distance
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::from_points(&Point3d::new(-1.0, -1.0, -1.0), &Point3d::new(1.0, 1.0, 1.0))
+    }
+}
+
+
+#[cfg(test)]
+mod test_sdf {
+    use super::*;
+    use crate::object::material::Empty;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// The unit sphere.
+    struct Ball;
+
+    impl DistanceField for Ball {
+        fn distance(&self, point: &Point3d) -> Float {
+            point.length() - 1.0
+        }
+
+        fn bounding_box(&self) -> AABB {
+            AABB::from_points(&Point3d::new(-1.0, -1.0, -1.0), &Point3d::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn test_sphere_tracing() {
+        let ball = Sdf::new(Arc::new(Ball), Material::Empty(Empty {}));
+        let ray = Ray::new(Point3d::new(0.6, 0.0, 4.0), Vec3d::new(0.0, 0.0, -2.0), 0.0);
+        let hit = ball.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert_approx_eq!(hit.t, (4.0 - 0.8) / 2.0, 1e-5);
+        assert_approx_eq!(hit.normal.x(), 0.6, 1e-4);
+        assert_approx_eq!(hit.normal.z(), 0.8, 1e-4);
+        assert!(hit.front_face);
+
+        // From inside, the far side is a back face, and the interval is respected.
+        let inside = Ray::new(Point3d::zero(), Vec3d::new(1.0, 0.0, 0.0), 0.0);
+        let hit = ball.hit(&inside, &Interval { min: 0.001, max: Float::INFINITY }).unwrap();
+        assert_approx_eq!(hit.t, 1.0, 1e-5);
+        assert!(!hit.front_face);
+        assert!(ball.hit(&inside, &Interval { min: 0.001, max: 0.5 }).is_none());
+        assert!(ball.hit(&Ray::new(Point3d::new(0.0, 2.0, 4.0), Vec3d::new(0.0, 0.0, -1.0), 0.0), &Interval::UNIVERSE).is_none());
+    }
+
+    #[test]
+    fn test_fractal_distances() {
+        let sponge = MengerSponge::new(2);
+        // Outside the cube, the distance is to the cube.
+        assert_approx_eq!(sponge.distance(&Point3d::new(3.0, 0.9, 0.9)), 2.0);
+        // The middle of a face is drilled through, a third of the cube wide.
+        assert!(sponge.distance(&Point3d::new(0.0, 0.0, 0.95)) > 0.3);
+        assert!(sponge.distance(&Point3d::new(0.9, 0.9, 0.0)) < 0.0);
+
+        let bulb = Mandelbulb::default();
+        assert!(bulb.distance(&Point3d::zero()) <= 0.0);
+        let far = bulb.distance(&Point3d::new(0.0, 0.0, 3.0));
+        assert!(far > 0.5 && far < 3.0, "{}", far);
+        let ray = Ray::new(Point3d::new(0.0, 0.0, 3.0), Vec3d::new(0.0, 0.0, -1.0), 0.0);
+        let bulb = Sdf::new(Arc::new(bulb), Material::Empty(Empty {}));
+        let hit = bulb.hit(&ray, &Interval::UNIVERSE).unwrap();
+        assert!(hit.t > 1.8 && hit.t < 2.5, "{}", hit.t);
+    }
+}
@@ -1,12 +1,17 @@
 use crate::ray::{Interval, Ray};
 use super::hit::*;
-use crate::vec3d::{Vec3d, Point3d, dot};
-use crate::object::material::Material;
+use crate::vec3d::{Vec3d, Point3d, Float, consts, cross, dot};
+use crate::object::material::{Material, Scatterable};
 use crate::object::aabb::AABB;
+use crate::error::{Error, Result};
+use crate::object::surface::{self, Surface};
+use crate::object::emitter::{Emitter, EmitterSample};
+use crate::sampler;
+use rand::Rng;
 
 pub struct Sphere {
     center: Point3d,
-    radius: f64,
+    radius: Float,
     material: Material,
 
     center_vec: Vec3d,
@@ -14,70 +19,166 @@ pub struct Sphere {
 }
 
 impl Sphere {
+    /// Creates a sphere that stays at ``center``.
+    /// # Panics
+    /// If ``radius`` is not greater than 0, see ``try_static_sphere`` for a fallible version.
     pub fn static_sphere(
         center: Point3d,
-        radius: f64,
+        radius: Float,
         material: Material,
     ) -> Self {
+        Self::try_static_sphere(center, radius, material).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_static_sphere(
+        center: Point3d,
+        radius: Float,
+        material: Material,
+    ) -> Result<Self> {
         let bbox = AABB::from_points(
             &(center - Vec3d::new(radius, radius, radius)),
             &(center + Vec3d::new(radius, radius, radius)),
         );
-        Self::new(center, center, radius, material, bbox)
+        Self::try_new(center, center, radius, material, bbox)
     }
 
+    /// Creates a sphere moving linearly from ``center`` to ``center1`` over the ray time.
+    /// # Panics
+    /// If ``radius`` is not greater than 0, see ``try_moving_sphere`` for a fallible version.
+    #[deprecated(note = "wrap a static sphere in an ``Animated``, which moves any object")]
     pub fn moving_sphere(
         center: Point3d,
         center1: Point3d,
-        radius: f64,
+        radius: Float,
         material: Material,
     ) -> Self {
+        #[allow(deprecated)]
+        Self::try_moving_sphere(center, center1, radius, material).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[deprecated(note = "wrap a static sphere in an ``Animated``, which moves any object")]
+    pub fn try_moving_sphere(
+        center: Point3d,
+        center1: Point3d,
+        radius: Float,
+        material: Material,
+    ) -> Result<Self> {
         let rvec = Vec3d::new(radius, radius, radius);
         let bbox = AABB::surrounding_box(
             &AABB::from_points(&(center - rvec), &(center + rvec)),
             &AABB::from_points(&(center1 - rvec), &(center1 + rvec)),
         );
-        Self::new(center, center1, radius, material, bbox)
+        Self::try_new(center, center1, radius, material, bbox)
     }
 
-    fn new(
+    fn try_new(
         center: Point3d,
         center1: Point3d,
-        radius: f64,
+        radius: Float,
         material: Material,
         bbox: AABB,
-    ) -> Self {
-        if radius <= 0.0 {
-            panic!("Radius must be greater than 0, but was {} instead.", radius);
+    ) -> Result<Self> {
+        if radius.is_nan() || radius <= 0.0 {
+            return Err(Error::InvalidRadius(radius));
         }
-        Self {
+        Ok(Self {
             center,
             radius,
             material,
             center_vec: center1 - center,
             bbox,
-        }
+        })
     }
 
     pub fn is_moving(&self) -> bool {
         self.center_vec.x() != 0.0 || self.center_vec.y() != 0.0 || self.center_vec.z() != 0.0
     }
 
-    pub fn sphere_center(&self, time: f64) -> Point3d {
+    pub fn sphere_center(&self, time: Float) -> Point3d {
         // If the sphere is not moving, the center is the same.
         self.center + self.center_vec * time
     }
 
-    fn get_sphere_uv(point: &Vec3d) -> (f64, f64) {
+    fn get_sphere_uv(point: &Vec3d) -> (Float, Float) {
         let theta = (-point.y()).acos();
-        let phi = -point.z().atan2(point.x()) + std::f64::consts::PI;
+        let phi = -point.z().atan2(point.x()) + consts::PI;
 
-        let u = phi / (2.0 * std::f64::consts::PI);
-        let v = theta / std::f64::consts::PI;
+        let u = phi / (2.0 * consts::PI);
+        let v = theta / consts::PI;
         (u, v)
     }
+
+    /// Vector from ``origin`` to the center at time ``0.0`` and the cosine of the half angle of
+    /// the cone the sphere covers, ``None`` from inside the sphere.
+    fn cone(&self, origin: &Point3d) -> Option<(Vec3d, Float)> {
+        let to_center = self.center - *origin;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared { return None; }
+        Some((to_center, (1.0 - radius_squared / distance_squared).sqrt()))
+    }
+
+    /// Distance from ``origin`` to the near side of the sphere along the unit ``direction``,
+    /// clamped to the tangent point for directions grazing the cone's edge.
+    fn distance_along(&self, to_center: &Vec3d, direction: &Vec3d) -> Float {
+        let h = dot(direction, to_center);
+        let discriminant = h * h - to_center.length_squared() + self.radius * self.radius;
+        h - discriminant.max(0.0).sqrt()
+    }
+}
+
+/// The surface of the sphere at time ``0.0``.
+impl Surface for Sphere {
+    fn area(&self) -> Float {
+        4.0 * consts::PI * self.radius * self.radius
+    }
+
+    fn sample_point<R: Rng + ?Sized>(&self, rng: &mut R) -> (Point3d, Vec3d) {
+        let normal = surface::random_unit_vector(rng);
+        (self.center + normal * self.radius, normal)
+    }
+}
+
+
+/// Samples the cone of directions the sphere covers uniformly, at time ``0.0``.
+///
+/// Unlike picking points on the surface, every sample lands on the side facing ``origin``, so
+/// noise no longer grows with the size of the sphere.
+impl Emitter for Sphere {
+    fn sample(&self, origin: &Point3d) -> Option<EmitterSample> {
+        let (to_center, cos_max) = self.cone(origin)?;
+        let mut rng = sampler::rng();
+        let cos_theta = 1.0 + rng.random::<Float>() * (cos_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * consts::PI * rng.random::<Float>();
+
+        let axis = to_center.unit_vector();
+        let helper = if axis.x().abs() > 0.9 { Vec3d::new(0.0, 1.0, 0.0) } else { Vec3d::new(1.0, 0.0, 0.0) };
+        let a = cross(&helper, &axis).unit_vector();
+        let b = cross(&axis, &a);
+        let direction = (a * phi.cos() + b * phi.sin()) * sin_theta + axis * cos_theta;
+
+        let direction = direction * self.distance_along(&to_center, &direction);
+        let (u, v) = Sphere::get_sphere_uv(&((*origin + direction - self.center) / self.radius));
+        Some(EmitterSample {
+            direction,
+            radiance: self.material.emitted(u, v, &(*origin + direction)),
+            pdf: 1.0 / (2.0 * consts::PI * (1.0 - cos_max)),
+        })
+    }
+
+    fn pdf(&self, origin: &Point3d, direction: &Vec3d) -> Float {
+        let Some((to_center, cos_max)) = self.cone(origin) else { return 0.0; };
+        if dot(&direction.unit_vector(), &to_center.unit_vector()) < cos_max { return 0.0; }
+        1.0 / (2.0 * consts::PI * (1.0 - cos_max))
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.material.light_group()
+    }
 }
 
+
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
         let center = if self.is_moving() {
@@ -126,6 +227,7 @@ mod test_hittable {
     use super::*;
 
     use super::super::material::*;
+    use crate::color::Color;
     use assert_approx_eq::assert_approx_eq;
 
     #[test]
@@ -133,14 +235,14 @@ mod test_hittable {
         let sphere = Sphere::static_sphere(
             Point3d::new(0.0, 0.0, 0.0),
             2.0,
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
         let ray = Ray::new(
             Vec3d::new(0.0, 0.0, -5.0),
             Vec3d::new(0.0, 0.0, 1.0),
             0.0,
         );
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = sphere.hit(&ray, &interval).unwrap();
 
         assert_eq!(hit_record.t, 3.0);
@@ -155,14 +257,14 @@ mod test_hittable {
             let sphere = Sphere::static_sphere(
                 Vec3d::new(0.0, 0.0, 0.0),
                 2.0,
-                Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+                Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
             );
             let ray = Ray::new(
                 Vec3d::new(0.0, 0.0, 0.0),
                 Vec3d::new(0.0, 0.0, 1.0),
                 0.0,
             );
-            let interval = Interval { min: 0.0, max: f64::INFINITY };
+            let interval = Interval { min: 0.0, max: Float::INFINITY };
             let hit_record = sphere.hit(&ray, &interval).unwrap();
 
             assert_eq!(hit_record.t, 2.0);
@@ -172,19 +274,69 @@ mod test_hittable {
         }
     }
 
+    #[test]
+    fn test_sphere_invalid_radius() {
+        let sphere = Sphere::try_static_sphere(
+            Point3d::zero(),
+            0.0,
+            Material::Empty(Empty {}),
+        );
+        assert!(matches!(sphere, Err(Error::InvalidRadius(r)) if r == 0.0));
+
+        #[allow(deprecated)]
+        let sphere = Sphere::try_moving_sphere(
+            Point3d::zero(),
+            Point3d::new(1.0, 0.0, 0.0),
+            -1.0,
+            Material::Empty(Empty {}),
+        );
+        assert!(matches!(sphere, Err(Error::InvalidRadius(r)) if r == -1.0));
+
+        let sphere = Sphere::try_static_sphere(Point3d::zero(), Float::NAN, Material::Empty(Empty {}));
+        assert!(matches!(sphere, Err(Error::InvalidRadius(r)) if r.is_nan()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Radius must be greater than 0")]
+    fn test_sphere_invalid_radius_panics() {
+        Sphere::static_sphere(Point3d::zero(), 0.0, Material::Empty(Empty {}));
+    }
+
+    #[test]
+    fn test_sphere_emitter_cone() {
+        let sphere = Sphere::static_sphere(
+            Point3d::new(0.0, 0.0, -4.0),
+            2.0,
+            Material::Light(Light::from_color(Color::splat(3.0))),
+        );
+        // The sphere covers a cone of half angle 30 degrees seen from the origin.
+        let pdf = 1.0 / (2.0 * consts::PI * (1.0 - (3.0 as Float).sqrt() / 2.0));
+        for _ in 0..100 {
+            let sample = sphere.sample(&Point3d::zero()).unwrap();
+            let distance = sample.direction.length();
+            assert!((2.0 - 1e-9..=(12.0 as Float).sqrt() + 1e-9).contains(&distance));
+            assert_approx_eq!((sample.direction - Point3d::new(0.0, 0.0, -4.0)).length(), 2.0);
+            assert_eq!(sample.radiance, Color::splat(3.0));
+            assert_approx_eq!(sample.pdf, pdf);
+            assert_approx_eq!(sphere.pdf(&Point3d::zero(), &sample.direction), pdf);
+        }
+        assert_eq!(sphere.pdf(&Point3d::zero(), &Vec3d::new(0.0, 1.0, -1.0)), 0.0);
+        assert!(sphere.sample(&Point3d::new(0.0, 0.0, -3.0)).is_none());
+    }
+
     #[test]
     fn test_sphere_no_hit_1() {
         let sphere = Sphere::static_sphere(
             Vec3d::new(0.0, 0.0, 0.0),
             2.0,
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
         let ray = Ray::new(
             Vec3d::new(0.0, 0.0, -5.0),
             Vec3d::new(0.0, 0.0, -1.0),
             0.0,
         );
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = sphere.hit(&ray, &interval);
 
         assert!(hit_record.is_none());
@@ -195,14 +347,14 @@ mod test_hittable {
         let sphere = Sphere::static_sphere(
             Vec3d::new(0.0, 0.0, 0.0),
             2.0,
-            Material::Lambertian(Lambertian::new(Vec3d::new(0.1, 0.2, 0.5))),
+            Material::Lambertian(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
         );
         let ray = Ray::new(
             Vec3d::new(2.0, 2.0, -1.0),
             Vec3d::new(2.0, 0.0, -1.0),
             0.0,
         );
-        let interval = Interval { min: 0.0, max: f64::INFINITY };
+        let interval = Interval { min: 0.0, max: Float::INFINITY };
         let hit_record = sphere.hit(&ray, &interval);
 
         assert!(hit_record.is_none());
@@ -210,11 +362,11 @@ mod test_hittable {
 
     // This function is directly copied from
     // https://github.com/fralken/ray-tracing-the-next-week/blob/ea3f3b5e2bb4e5967b7f6e1da415d5feffc4416a/src/sphere.rs#L9
-    fn get_sphere_uv(p: &Vec3d) -> (f64, f64) {
+    fn get_sphere_uv(p: &Vec3d) -> (Float, Float) {
         let phi = p.z().atan2(p.x());
         let theta = p.y().asin();
-        let u = 1.0 - (phi + std::f64::consts::PI) / (2.0 * std::f64::consts::PI);
-        let v = (theta + std::f64::consts::FRAC_PI_2) / std::f64::consts::PI;
+        let u = 1.0 - (phi + consts::PI) / (2.0 * consts::PI);
+        let v = (theta + consts::FRAC_PI_2) / consts::PI;
         (u, v)
     }
 
@@ -0,0 +1,194 @@
+//! Loop subdivision surfaces, smoothing coarse cage meshes into organic shapes while loading a
+//! scene instead of modelling them with huge source meshes.
+//!
+//! Every level splits each triangle into four and moves the vertices towards the smooth
+//! surface the cage stands for. Edges of only one triangle are kept as creases, subdivided
+//! as curves of their own, so open meshes keep their outline and vertices split at texture
+//! seams stay together.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::object::MeshData;
+//! use ray_tracing::vec3d::Point3d;
+//! let tetrahedron = MeshData {
+//!     positions: vec![
+//!         Point3d::new(1.0, 1.0, 1.0), Point3d::new(1.0, -1.0, -1.0),
+//!         Point3d::new(-1.0, 1.0, -1.0), Point3d::new(-1.0, -1.0, 1.0),
+//!     ],
+//!     triangles: vec![[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]],
+//!     ..Default::default()
+//! };
+//! let smooth = tetrahedron.subdivide(2);
+//! assert_eq!(smooth.triangles.len(), 4 * 16);
+//! assert_eq!(smooth.normals.len(), smooth.positions.len());
+//! ```
+use crate::vec3d::{Point3d, Float};
+use crate::color::Color;
+use crate::object::MeshData;
+use crate::object::displacement::vertex_normals;
+
+use std::collections::HashMap;
+
+
+impl MeshData {
+    /// Applies ``levels`` steps of Loop subdivision, multiplying the triangles by ``4^levels``.
+    ///
+    /// Texture coordinates and colors are smoothed like the positions. Normals are recomputed
+    /// from the subdivided surface, unless the mesh has ``flat_shading``.
+    pub fn subdivide(&self, levels: u32) -> MeshData {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = mesh.subdivide_once();
+        }
+        if !mesh.flat_shading {
+            mesh.normals = vertex_normals(&mesh.positions, &mesh.triangles);
+        }
+        mesh
+    }
+
+    fn subdivide_once(&self) -> MeshData {
+        let vertices = self.positions.len();
+
+        // Every edge with the vertex it gets and the corners of its triangles opposite to it.
+        let mut edges: HashMap<(usize, usize), (usize, Vec<usize>)> = HashMap::new();
+        let mut edge_order = Vec::new();
+        for &[a, b, c] in &self.triangles {
+            for (from, to, opposite) in [(a, b, c), (b, c, a), (c, a, b)] {
+                let key = (from.min(to), from.max(to));
+                edges.entry(key)
+                    .or_insert_with(|| {
+                        edge_order.push(key);
+                        (vertices + edge_order.len() - 1, Vec::new())
+                    })
+                    .1.push(opposite);
+            }
+        }
+
+        let mut neighbours = vec![Vec::new(); vertices];
+        let mut crease_neighbours = vec![Vec::new(); vertices];
+        for &(a, b) in &edge_order {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+            if edges[&(a, b)].1.len() != 2 {
+                crease_neighbours[a].push(b);
+                crease_neighbours[b].push(a);
+            }
+        }
+
+        // How every vertex of the result mixes the vertices of this mesh.
+        let mut stencils: Vec<Vec<(usize, Float)>> = Vec::with_capacity(vertices + edge_order.len());
+        for vertex in 0..vertices {
+            let stencil = match (neighbours[vertex].len(), crease_neighbours[vertex].as_slice()) {
+                (0, _) => vec![(vertex, 1.0)],
+                (valence, []) => {
+                    let k = valence as Float;
+                    let beta = if valence == 3 { 3.0 / 16.0 } else { 3.0 / (8.0 * k) };
+                    let mut stencil = vec![(vertex, 1.0 - k * beta)];
+                    stencil.extend(neighbours[vertex].iter().map(|&n| (n, beta)));
+                    stencil
+                }
+                (_, &[first, second]) => vec![(vertex, 0.75), (first, 0.125), (second, 0.125)],
+                // Where creases meet or branch the vertex is a corner, and stays put.
+                _ => vec![(vertex, 1.0)],
+            };
+            stencils.push(stencil);
+        }
+        for &(a, b) in &edge_order {
+            stencils.push(match edges[&(a, b)].1.as_slice() {
+                &[c, d] => vec![(a, 0.375), (b, 0.375), (c, 0.125), (d, 0.125)],
+                _ => vec![(a, 0.5), (b, 0.5)],
+            });
+        }
+
+        let mix = |stencil: &[(usize, Float)]| {
+            let mut point = Point3d::zero();
+            let mut uv = (0.0, 0.0);
+            let mut color = Color::zero();
+            for &(index, weight) in stencil {
+                point += self.positions[index] * weight;
+                if !self.uvs.is_empty() {
+                    uv = (uv.0 + self.uvs[index].0 * weight, uv.1 + self.uvs[index].1 * weight);
+                }
+                if !self.colors.is_empty() {
+                    color += self.colors[index] * weight;
+                }
+            }
+            (point, uv, color)
+        };
+
+        let mut result = MeshData { flat_shading: self.flat_shading, ..Default::default() };
+        for stencil in &stencils {
+            let (point, uv, color) = mix(stencil);
+            result.positions.push(point);
+            if !self.uvs.is_empty() { result.uvs.push(uv); }
+            if !self.colors.is_empty() { result.colors.push(color); }
+        }
+        let middle = |a: usize, b: usize| edges[&(a.min(b), a.max(b))].0;
+        for &[a, b, c] in &self.triangles {
+            let (ab, bc, ca) = (middle(a, b), middle(b, c), middle(c, a));
+            result.triangles.extend([[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]);
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod test_subdivision {
+    use super::*;
+    use crate::vec3d::{Vec3d, dot};
+    use assert_approx_eq::assert_approx_eq;
+
+    /// A cube around the origin with sides of ``2``, facing outwards.
+    fn cube() -> MeshData {
+        let positions = (0..8)
+            .map(|i| Point3d::new((i & 1) as Float, ((i >> 1) & 1) as Float, ((i >> 2) & 1) as Float) * 2.0 - Vec3d::new(1.0, 1.0, 1.0))
+            .collect();
+        let faces = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        let triangles = faces.iter().flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]]).collect();
+        MeshData { positions, triangles, ..Default::default() }
+    }
+
+    #[test]
+    fn test_closed_mesh_rounds_off() {
+        let cube = cube();
+        let smooth = cube.subdivide(3);
+        // Every level adds a vertex per edge, and a closed mesh has 1.5 edges per triangle.
+        assert_eq!(smooth.triangles.len(), 12 * 64);
+        assert_eq!(smooth.positions.len(), 8 + 18 + 72 + 288);
+
+        let radii: Vec<Float> = smooth.positions.iter().map(|p| p.length()).collect();
+        let (min, max) = radii.iter().fold((Float::INFINITY, 0.0), |(min, max): (Float, Float), &r| (min.min(r), max.max(r)));
+        assert!(max < 1.7 && min > 0.5, "{} {}", min, max);
+        // The corners of the cage sink in, and normals point outwards.
+        assert!(radii[0] < 1.2, "{}", radii[0]);
+        for (point, normal) in smooth.positions.iter().zip(&smooth.normals) {
+            assert!(dot(point, normal) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_open_mesh_keeps_outline() {
+        // A flat square with a vertex in the middle, its border all creases.
+        let square = MeshData {
+            positions: vec![
+                Point3d::new(0.0, 0.0, 0.0), Point3d::new(2.0, 0.0, 0.0), Point3d::new(2.0, 0.0, 2.0),
+                Point3d::new(0.0, 0.0, 2.0), Point3d::new(1.0, 0.0, 1.0),
+            ],
+            uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.5, 0.5)],
+            triangles: vec![[4, 1, 0], [4, 2, 1], [4, 3, 2], [4, 0, 3]],
+            ..Default::default()
+        };
+        let smooth = square.subdivide(2);
+        assert_eq!(smooth.uvs.len(), smooth.positions.len());
+        for (point, &(u, v)) in smooth.positions.iter().zip(&smooth.uvs) {
+            assert_approx_eq!(point.y(), 0.0);
+            // Texture coordinates are smoothed like the positions, which they are proportional to.
+            assert_approx_eq!(point.x(), 2.0 * u);
+            assert_approx_eq!(point.z(), 2.0 * v);
+        }
+        // The border is subdivided along its own edges, pulling the corners inwards.
+        assert!(smooth.positions.iter().all(|p| p.x() >= -1e-12 && p.z() >= -1e-12));
+        assert_approx_eq!(smooth.positions[1].x(), 1.6875);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::vec3d::{Vec3d, Point3d, Float, consts, cross};
+use crate::object::{Hittable, HittableVec, Transform};
+use rand::Rng;
+
+use std::sync::Arc;
+
+
+/// A shape points can be sampled uniformly on, e.g. to scatter objects over it.
+pub trait Surface {
+    /// Total area of the surface.
+    fn area(&self) -> Float;
+
+    /// Returns a uniformly distributed point of the surface and its outward unit normal.
+    fn sample_point<R: Rng + ?Sized>(&self, rng: &mut R) -> (Point3d, Vec3d);
+}
+
+
+/// Returns ``count`` copies of ``object`` placed at random points of ``surface``, e.g. to
+/// cover a field with grass blades or a planet with rocks.
+///
+/// Every copy stands on the surface with its ``+y`` axis along the surface normal and a
+/// random rotation around it, so ``object`` should be modelled upright with its base at the
+/// origin. All copies share ``object`` and only add a transform each.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{scatter_on_surface, Hittable, Quad, Sphere};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let ground = Quad::new(
+///     Point3d::zero(), Vec3d::new(10.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 10.0),
+///     Material::Empty(Empty {}),
+/// );
+/// let rock: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
+///     Point3d::zero(), 0.1, Material::Empty(Empty {}),
+/// ));
+/// let rocks = scatter_on_surface(&ground, rock, 100, &mut rand::rng());
+/// assert_eq!(rocks.objects.len(), 100);
+/// ```
+pub fn scatter_on_surface<S, R>(surface: &S, object: Arc<dyn Hittable>, count: usize, rng: &mut R) -> HittableVec
+where
+    S: Surface + ?Sized,
+    R: Rng + ?Sized,
+{
+    let mut copies = HittableVec::new();
+    for _ in 0..count {
+        let (point, normal) = surface.sample_point(rng);
+
+        // Any axis perpendicular to the normal, turned by a random angle around it.
+        let helper = if normal.x().abs() > 0.9 { Vec3d::new(0.0, 1.0, 0.0) } else { Vec3d::new(1.0, 0.0, 0.0) };
+        let a = cross(&helper, &normal).unit_vector();
+        let b = cross(&normal, &a);
+        let angle = rng.random_range(0.0..(2.0 * consts::PI));
+        let x_axis = a * angle.cos() + b * angle.sin();
+        let z_axis = cross(&x_axis, &normal);
+
+        copies.add(Arc::new(Transform::from_columns(object.clone(), [
+            [x_axis.x(), x_axis.y(), x_axis.z(), 0.0],
+            [normal.x(), normal.y(), normal.z(), 0.0],
+            [z_axis.x(), z_axis.y(), z_axis.z(), 0.0],
+            [point.x(), point.y(), point.z(), 1.0],
+        ])));
+    }
+    copies
+}
+
+
+/// Returns a uniformly distributed unit vector drawn from ``rng``.
+pub(crate) fn random_unit_vector<R: Rng + ?Sized>(rng: &mut R) -> Vec3d {
+    loop {
+        let p = Vec3d::new(
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        );
+        let length_squared = p.length_squared();
+        if 1e-12 < length_squared && length_squared <= 1.0 {
+            return p / length_squared.sqrt();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test_surface {
+    use super::*;
+    use crate::object::{Quad, Sphere};
+    use crate::object::material::{Material, Empty};
+    use assert_approx_eq::assert_approx_eq;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// A thin upright stick, taller than wide, to check the copies' orientation.
+    fn stick() -> Arc<dyn Hittable> {
+        Arc::new(Quad::new(
+            Point3d::new(-0.01, 0.0, 0.0), Vec3d::new(0.02, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0),
+            Material::Empty(Empty {}),
+        ))
+    }
+
+    #[test]
+    fn test_scatter_on_sphere_stands_along_normal() {
+        let planet = Sphere::static_sphere(Point3d::zero(), 2.0, Material::Empty(Empty {}));
+        let mut rng = StdRng::seed_from_u64(7);
+        let sticks = scatter_on_surface(&planet, stick(), 20, &mut rng);
+        assert_eq!(sticks.objects.len(), 20);
+
+        for copy in sticks.objects.iter() {
+            // Every stick reaches from the surface to one unit above it.
+            let center = copy.bounding_box().center();
+            assert_approx_eq!(center.length(), 2.5, 0.05);
+        }
+    }
+
+    #[test]
+    fn test_scatter_on_quad_stays_inside() {
+        let ground = Quad::new(
+            Point3d::zero(), Vec3d::new(4.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 2.0),
+            Material::Empty(Empty {}),
+        );
+        assert_approx_eq!(ground.area(), 8.0);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..50 {
+            let (point, normal) = ground.sample_point(&mut rng);
+            assert!((0.0..=4.0).contains(&point.x()) && (0.0..=2.0).contains(&point.z()));
+            assert_eq!(point.y(), 0.0);
+            assert_approx_eq!(normal.y().abs(), 1.0);
+        }
+    }
+}
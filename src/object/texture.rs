@@ -1,16 +1,25 @@
-use crate::vec3d::{Vec3d, Color, dot};
+use crate::vec3d::{Vec3d, Float, dot};
+use crate::color::{Color, ColorSpace};
 use std::sync::Arc;
 use image;
 
 use std::fmt::{Debug, Formatter};
-use image::{GenericImageView, Pixel};
 use crate::ray::Interval;
+use crate::object::HitRecord;
+use crate::error::{Error, Result};
+use crate::sampler::{self, Pcg32};
 
 use rand::Rng;
 
 
 pub trait Texture: Send + Sync + Debug {
-    fn value(&self, u: f64, v: f64, p: &Vec3d) -> Color;
+    fn value(&self, u: Float, v: Float, p: &Vec3d) -> Color;
+
+    /// Looks the texture up at a hit, which by default only depends on its ``u``, ``v`` and
+    /// point.
+    fn value_at(&self, hit_record: &HitRecord) -> Color {
+        self.value(hit_record.u, hit_record.v, &hit_record.point)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -19,7 +28,7 @@ pub struct SolidColor {
 }
 
 impl SolidColor {
-    pub fn new(color: Vec3d) -> Self {
+    pub fn new(color: Color) -> Self {
         Self { color }
     }
 }
@@ -31,7 +40,7 @@ impl Debug for SolidColor {
 }
 
 impl Texture for SolidColor {
-    fn value(&self, _u: f64, _v: f64, _p: &Vec3d) -> Color {
+    fn value(&self, _u: Float, _v: Float, _p: &Vec3d) -> Color {
         self.color
     }
 }
@@ -39,13 +48,13 @@ impl Texture for SolidColor {
 
 #[derive(Clone)]
 pub struct Checker {
-    inv_scale: f64,
-    even: Arc<Box<dyn Texture>>,
-    odd: Arc<Box<dyn Texture>>,
+    inv_scale: Float,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
 }
 
 impl Checker {
-    pub fn new(even: Arc<Box<dyn Texture>>, odd: Arc<Box<dyn Texture>>, scale: f64) -> Self {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: Float) -> Self {
         Self {
             inv_scale: 1.0 / scale,
             even,
@@ -53,9 +62,9 @@ impl Checker {
         }
     }
 
-    pub fn from_color(color1: Vec3d, color2: Vec3d, scale: f64) -> Self {
-        let even: Arc<Box<dyn Texture>> = Arc::new(Box::new(SolidColor::new(color1)));
-        let odd: Arc<Box<dyn Texture>> = Arc::new(Box::new(SolidColor::new(color2)));
+    pub fn from_color(color1: Color, color2: Color, scale: Float) -> Self {
+        let even: Arc<dyn Texture> = Arc::new(SolidColor::new(color1));
+        let odd: Arc<dyn Texture> = Arc::new(SolidColor::new(color2));
         Self::new(
             even,
             odd,
@@ -73,7 +82,7 @@ impl Debug for Checker {
 
 
 impl Texture for Checker {
-    fn value(&self, u: f64, v: f64, p: &Vec3d) -> Color {
+    fn value(&self, u: Float, v: Float, p: &Vec3d) -> Color {
         let p_val = *p * self.inv_scale;
 
         let x_int = p_val.x().floor() as i32;
@@ -89,19 +98,210 @@ impl Texture for Checker {
 }
 
 
+/// The vertex colors of a ``Mesh``, e.g. the captured colors of a scanned model, so it
+/// renders without a texture atlas.
+///
+/// Surfaces without vertex colors show ``fallback`` instead.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::material::{Material, Lambertian};
+/// use ray_tracing::object::texture::{SolidColor, VertexColor};
+/// let grey = Arc::new(SolidColor::new(Color::new(0.5, 0.5, 0.5)));
+/// let scan = Material::Lambertian(Lambertian::from_texture(Arc::new(VertexColor::new(grey))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VertexColor {
+    fallback: Arc<dyn Texture>,
+}
+
+
+impl VertexColor {
+    pub fn new(fallback: Arc<dyn Texture>) -> Self {
+        Self { fallback }
+    }
+}
+
+
+impl Texture for VertexColor {
+    fn value(&self, u: Float, v: Float, p: &Vec3d) -> Color {
+        self.fallback.value(u, v, p)
+    }
+
+    fn value_at(&self, hit_record: &HitRecord) -> Color {
+        hit_record.color.unwrap_or_else(|| self.fallback.value_at(hit_record))
+    }
+}
+
+
+/// A debugging texture of ``cells x cells`` colored squares numbered from ``0`` in the corner
+/// at ``u = v = 0``, to check the orientation and stretch of texture coordinates without an
+/// image file.
+///
+/// The hue goes around the color wheel along ``u`` and the squares get lighter along ``v``, so
+/// flipped or swapped coordinates show at a glance, and the numbers read upright when ``v``
+/// points up.
+/// # Examples
+/// ```
+/// use ray_tracing::object::texture::{Texture, UvGrid};
+/// use ray_tracing::vec3d::Vec3d;
+/// let grid = UvGrid::new(8);
+/// // The corners of a square are on its dark border.
+/// assert!(grid.value(0.125, 0.0, &Vec3d::zero()).max_component() < 0.1);
+/// assert_ne!(grid.value(0.1, 0.1, &Vec3d::zero()), grid.value(0.9, 0.1, &Vec3d::zero()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UvGrid {
+    cells: u32,
+}
+
+
+/// Digits of the labels of ``UvGrid``, 3 pixels wide and 5 high, from the top row down.
+const GLYPHS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+
+impl UvGrid {
+    pub fn new(cells: u32) -> Self {
+        Self { cells: cells.max(1) }
+    }
+
+    /// Whether ``x`` and ``y``, from the top left of a square, are on the digits of ``label``.
+    fn on_label(label: u32, x: Float, y: Float) -> bool {
+        let digits: Vec<usize> = label.to_string().bytes().map(|digit| usize::from(digit - b'0')).collect();
+        // Glyphs and the gaps between them span the middle 60% of the square at most.
+        let columns = 4 * digits.len() - 1;
+        let pixel = (0.6 / columns as Float).min(0.1);
+        let (left, top) = (0.5 - 0.5 * pixel * columns as Float, 0.5 - 2.5 * pixel);
+        let (column, row) = ((x - left) / pixel, (y - top) / pixel);
+        if !(0.0..columns as Float).contains(&column) || !(0.0..5.0).contains(&row) {
+            return false;
+        }
+        let (column, row) = (column as usize, row as usize);
+        column % 4 != 3 && GLYPHS[digits[column / 4]][row] & (0b100 >> (column % 4)) != 0
+    }
+}
+
+
+impl Texture for UvGrid {
+    fn value(&self, u: Float, v: Float, _p: &Vec3d) -> Color {
+        let cells = self.cells as Float;
+        let (x, y) = (u.clamp(0.0, 1.0) * cells, v.clamp(0.0, 1.0) * cells);
+        let (column, row) = ((x as u32).min(self.cells - 1), (y as u32).min(self.cells - 1));
+        let (x, y) = (x - column as Float, y - row as Float);
+
+        if x.min(1.0 - x).min(y).min(1.0 - y) < 0.03 {
+            return Color::splat(0.02);
+        }
+        if Self::on_label(row * self.cells + column, x, 1.0 - y) {
+            return Color::zero();
+        }
+        let lightness = 0.5 + 0.5 * row as Float / cells;
+        hue(column as Float / cells) * (0.6 * lightness) + Color::splat(0.4 * lightness)
+    }
+}
+
+
+/// The fully saturated color at ``hue`` around the color wheel, from red at ``0`` through
+/// green at ``1/3`` and blue at ``2/3``.
+fn hue(hue: Float) -> Color {
+    let channel = |offset: Float| {
+        let k = (hue * 6.0 + offset).rem_euclid(6.0);
+        1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    Color::new(channel(5.0), channel(3.0), channel(1.0))
+}
+
+
+/// A texture looked up in an 8-bit image, e.g. a PNG or JPEG.
+///
+/// Texels are decoded from gamma ``2.0``, the encoding ``image::write_image`` uses, into linear
+/// sRGB once when loading, and into the working color space on lookup, see
+/// ``with_color_space``. They are kept in a flat buffer behind an ``Arc``, so lookups from
+/// any number of threads only index into it, and clones share it.
+#[derive(Clone)]
 pub struct ImageTexture {
     file: String,
-    image: image::DynamicImage,
+    width: u32,
+    height: u32,
+    /// Linear sRGB texels, row by row from the top.
+    texels: Arc<Vec<[f32; 3]>>,
+    color_space: ColorSpace,
 }
 
 
+/// Gamma the 8-bit texels of ``ImageTexture`` are encoded with.
+const IMAGE_GAMMA: f32 = 2.0;
+
+
 impl ImageTexture {
-    pub fn new(file: &String) -> Self {
-        let image = image::open(file);
-        match image {
-            Ok(image) => Self { file: file.clone(), image },
-            Err(e) => panic!("Could not open image file {}: {}", file, e),
-        }
+    /// Loads an image texture from ``file``.
+    /// # Panics
+    /// If the file can't be opened or decoded, see ``try_new`` for a fallible version.
+    pub fn new(file: &str) -> Self {
+        Self::try_new(file).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(file: &str) -> Result<Self> {
+        let image = image::open(file).map_err(|source| Error::Image { file: file.to_string(), source })?.to_rgb8();
+        let texels = image.pixels().map(|pixel| pixel.0.map(|c| (c as f32 / 255.0).powf(IMAGE_GAMMA))).collect();
+        Ok(Self {
+            file: file.to_string(), width: image.width(), height: image.height(), texels: Arc::new(texels),
+            color_space: ColorSpace::LinearSrgb,
+        })
+    }
+
+    /// Converts the texels into the working color space ``color_space`` of the render.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Downsamples the image, keeping its aspect ratio, until neither side is larger than
+    /// ``max_size`` pixels, to bound the memory of scenes with many large textures.
+    pub fn with_max_size(self, max_size: u32) -> Self {
+        let (width, height) = self.dimensions();
+        let largest = width.max(height);
+        if largest <= max_size.max(1) { return self; }
+        let scale = max_size.max(1) as Float / largest as Float;
+        let resize = |side: u32| ((side as Float * scale).round() as u32).max(1);
+        self.resized(resize(width), resize(height))
+    }
+
+    /// Halves the resolution of the image ``bias`` times, like skipping the ``bias`` most
+    /// detailed levels of its mipmap, down to a single pixel at most.
+    pub fn with_lod_bias(self, bias: u32) -> Self {
+        if bias == 0 { return self; }
+        let (width, height) = self.dimensions();
+        let halve = |side: u32| side.checked_shr(bias).unwrap_or(0).max(1);
+        self.resized(halve(width), halve(height))
+    }
+
+    /// Width and height of the image in pixels, after any downsampling.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn resized(mut self, width: u32, height: u32) -> Self {
+        // Filtered as encoded, like the 8-bit image, and decoded again.
+        let encoded = self.texels.iter().flat_map(|texel| texel.map(|c| c.powf(1.0 / IMAGE_GAMMA))).collect();
+        let image = image::Rgb32FImage::from_raw(self.width, self.height, encoded).unwrap();
+        // The triangle filter widens with the reduction, averaging every texel it drops.
+        let resized = image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle);
+        self.texels = Arc::new(resized.pixels().map(|pixel| pixel.0.map(|c| c.max(0.0).powf(IMAGE_GAMMA))).collect());
+        (self.width, self.height) = (width, height);
+        self
     }
 }
 
@@ -114,87 +314,134 @@ impl Debug for ImageTexture {
 
 
 impl Texture for ImageTexture {
-    fn value(&self, u: f64, v: f64, _p: &Vec3d) -> Color {
-        if self.image.height() <= 0 || self.image.width() <= 0 {
-            return Vec3d::new(0.0, 1.0, 1.0);
+    #[allow(clippy::unnecessary_cast)]
+    fn value(&self, u: Float, v: Float, _p: &Vec3d) -> Color {
+        if self.height == 0 || self.width == 0 {
+            return Color::new(0.0, 1.0, 1.0);
         }
 
         let interval = Interval { min: 0.0, max: 1.0 };
         let u = interval.clamp(u);
         let v = 1.0 - interval.clamp(v);
 
-        let i = (u * self.image.width() as f64) as i32;
-        let j = (v * self.image.height() as f64) as i32;
-        let pixel = self.image.get_pixel(i as u32, j as u32).to_rgb();
-
-        Vec3d::new(
-            pixel[0] as f64 / 255.0,
-            pixel[1] as f64 / 255.0,
-            pixel[2] as f64 / 255.0,
-        )
+        // The far edges at ``1`` belong to the last texels.
+        let i = ((u * self.width as Float) as usize).min(self.width as usize - 1);
+        let j = ((v * self.height as Float) as usize).min(self.height as usize - 1);
+        let texel = self.texels[j * self.width as usize + i];
+        self.color_space.from_linear_srgb(Color::new(texel[0] as Float, texel[1] as Float, texel[2] as Float))
     }
 }
 
 
+/// Marble-like stripes along ``z``, ``scale`` apart, distorted by the turbulence of Perlin
+/// noise.
+///
+/// The turbulence sums ``octaves`` layers of noise, starting at ``frequency`` and each
+/// ``lacunarity`` times finer and ``gain`` times weaker than the last.
+/// # Examples
+/// ```
+/// use ray_tracing::object::texture::{PerlinTexture, Texture};
+/// use ray_tracing::vec3d::Vec3d;
+/// let p = Vec3d::new(0.3, 1.7, -2.1);
+/// let marble = PerlinTexture::from_seed(4.0, 42).with_octaves(5);
+/// assert_eq!(marble.value(0.0, 0.0, &p), PerlinTexture::from_seed(4.0, 42).with_octaves(5).value(0.0, 0.0, &p));
+/// ```
 #[derive(Debug)]
 pub struct PerlinTexture {
-    point_count: usize,
-    rand_vec3d: Vec<Vec3d>,
-
-    perm_x: Vec<i32>,
-    perm_y: Vec<i32>,
-    perm_z: Vec<i32>,
-
-    scale: f64,
+    /// Unit gradients at the lattice points, picked by hashing their coordinates.
+    gradients: [Vec3d; PERLIN_POINT_COUNT],
+
+    perm_x: [u8; PERLIN_POINT_COUNT],
+    perm_y: [u8; PERLIN_POINT_COUNT],
+    perm_z: [u8; PERLIN_POINT_COUNT],
+
+    scale: Float,
+    frequency: Float,
+    octaves: i32,
+    lacunarity: Float,
+    gain: Float,
 }
 
 
-impl PerlinTexture {
-    pub fn new(scale: f64) -> Self {
-        let mut rng = rand::thread_rng();
+/// Gradients of ``PerlinTexture``, after which the lattice repeats.
+const PERLIN_POINT_COUNT: usize = 256;
 
-        let point_count = 256;
-        // let rand_float: Vec<f64> = (0..point_count).map(|_| rng.gen_range(0.0..1.0)).collect();
-        let rand_vec3d: Vec<Vec3d> = (0..point_count).map(|_| Vec3d::gen_range(-1.0, 1.0).unit_vector()).collect();
 
-        let perm_x: Vec<i32> = (0..point_count).collect();
-        let perm_y: Vec<i32> = perm_x.clone();
-        let perm_z: Vec<i32> = perm_x.clone();
+impl PerlinTexture {
+    /// Creates the texture with different noise on every call, see ``from_seed`` for noise
+    /// that can be reproduced.
+    pub fn new(scale: Float) -> Self {
+        Self::from_seed(scale, rand::random())
+    }
 
-        let count = point_count as usize;
+    /// Creates the texture with noise only depending on ``seed``, the same on every run and
+    /// machine.
+    pub fn from_seed(scale: Float, seed: u64) -> Self {
+        let mut stream = Pcg32::new(seed, 0);
+        let (gradients, perm_x, perm_y, perm_z) = sampler::with_stream(&mut stream, || {
+            let gradients = std::array::from_fn(|_| Vec3d::gen_range(-1.0, 1.0).unit_vector());
+            let identity = std::array::from_fn(|i| i as u8);
+            (gradients, Self::permute(identity), Self::permute(identity), Self::permute(identity))
+        });
 
         Self {
-            point_count: count,
-            rand_vec3d,
-            perm_x: Self::permute(perm_x, point_count),
-            perm_y: Self::permute(perm_y, point_count),
-            perm_z: Self::permute(perm_z, point_count),
+            gradients,
+            perm_x,
+            perm_y,
+            perm_z,
             scale,
+            frequency: 1.0,
+            octaves: 7,
+            lacunarity: 2.0,
+            gain: 0.5,
         }
     }
 
-    pub fn noise(&self, point: &Vec3d) -> f64 {
-        let new_p = point.map(|x| x - x.floor());
+    /// Sets the frequency of the coarsest layer of turbulence, ``1.0`` by default.
+    pub fn with_frequency(mut self, frequency: Float) -> Self {
+        self.frequency = frequency;
+        self
+    }
 
-        let i = point.x().floor() as i32;
-        let j = point.y().floor() as i32;
-        let k = point.z().floor() as i32;
+    /// Sets the number of layers of turbulence, ``7`` by default.
+    pub fn with_octaves(mut self, octaves: i32) -> Self {
+        self.octaves = octaves;
+        self
+    }
 
-        let mut c: Vec<Vec<Vec<Vec3d>>> = vec![vec![vec![Vec3d::zero(); 2]; 2]; 2];
+    /// Sets how much finer every layer of turbulence is than the last, ``2.0`` by default.
+    pub fn with_lacunarity(mut self, lacunarity: Float) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
 
-        for di in 0..2 {
-            for dj in 0..2 {
-                for dk in 0..2 {
-                    c[di as usize][dj as usize][dk as usize] =
-                        self.rand_vec3d[(self.perm_x[((i + di) & 255) as usize] ^ self.perm_y[((j + dj) & 255) as usize] ^ self.perm_z[((k + dk) & 255) as usize]) as usize];
-                }
-            }
-        }
+    /// Sets how much weaker every layer of turbulence is than the last, ``0.5`` by default.
+    pub fn with_gain(mut self, gain: Float) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    pub fn noise(&self, point: &Vec3d) -> Float {
+        let floor = point.floor();
+        let new_p = *point - floor;
+
+        let i = floor.x() as i32;
+        let j = floor.y() as i32;
+        let k = floor.z() as i32;
 
-        Self::perlin_interpolate(c, new_p)
+        // Evaluated millions of times per render, so the corners stay on the stack.
+        let lattice = |perm: &[u8; PERLIN_POINT_COUNT], base: i32, offset: usize| {
+            perm[(base.wrapping_add(offset as i32) & (PERLIN_POINT_COUNT as i32 - 1)) as usize]
+        };
+        let c: [[[Vec3d; 2]; 2]; 2] = std::array::from_fn(|di| std::array::from_fn(|dj| std::array::from_fn(|dk| {
+            let hash = lattice(&self.perm_x, i, di) ^ lattice(&self.perm_y, j, dj) ^ lattice(&self.perm_z, k, dk);
+            self.gradients[hash as usize]
+        })));
+
+        Self::perlin_interpolate(&c, new_p)
     }
 
-    fn perlin_interpolate(c: Vec<Vec<Vec<Vec3d>>>, u: Vec3d) -> f64 {
+    fn perlin_interpolate(c: &[[[Vec3d; 2]; 2]; 2], u: Vec3d) -> Float {
 
         let new_u = u * u * (3.0 - 2.0 * u);
 
@@ -205,8 +452,8 @@ impl PerlinTexture {
         for i in 0..2 {
             for j in 0..2 {
                 for k in 0..2 {
-                    let weight_v = u - Vec3d::new(i as f64, j as f64, k as f64);
-                    let coord = Vec3d::new(i as f64, j as f64, k as f64);
+                    let weight_v = u - Vec3d::new(i as Float, j as Float, k as Float);
+                    let coord = Vec3d::new(i as Float, j as Float, k as Float);
                     let vec = coord * u + (ones - coord) * inv_u;
 
                     accum += dot(&c[i][j][k], &weight_v) * vec.x() * vec.y() * vec.z();
@@ -216,28 +463,24 @@ impl PerlinTexture {
         accum
     }
 
-    fn permute(mut p: Vec<i32>, n: i32) -> Vec<i32> {
-        let mut rng = rand::thread_rng();
-        for i in (1..n).rev() {
-            let target = rng.gen_range(0..i) as usize;
-            let i = i as usize;
-
-            let tmp = p[i];
-            p[i] = p[target];
-            p[target] = tmp;
+    fn permute(mut p: [u8; PERLIN_POINT_COUNT]) -> [u8; PERLIN_POINT_COUNT] {
+        let mut rng = sampler::rng();
+        for i in (1..PERLIN_POINT_COUNT as i32).rev() {
+            let target = rng.random_range(0..i) as usize;
+            p.swap(i as usize, target);
         }
         p
     }
 
-    fn turbulence(&self, point: &Vec3d, depth: i32) -> f64 {
+    fn turbulence(&self, point: &Vec3d) -> Float {
         let mut accum = 0.0;
-        let mut temp_p = *point;
+        let mut temp_p = *point * self.frequency;
         let mut weight = 1.0;
 
-        for _ in 0..depth {
+        for _ in 0..self.octaves {
             accum += weight * self.noise(&temp_p);
-            weight *= 0.5;
-            temp_p *= 2.0;
+            weight *= self.gain;
+            temp_p *= self.lacunarity;
         }
         accum.abs()
     }
@@ -245,8 +488,295 @@ impl PerlinTexture {
 
 
 impl Texture for PerlinTexture {
-    fn value(&self, _u: f64, _v: f64, p: &Vec3d) -> Color {
-        Vec3d::new(0.5, 0.5, 0.5) * (1.0 + (self.scale * p.z() + 10.0 * self.turbulence(p, 7)).sin())
+    fn value(&self, _u: Float, _v: Float, p: &Vec3d) -> Color {
+        Color::splat(0.5) * (1.0 + (self.scale * p.z() + 10.0 * self.turbulence(p)).sin())
+    }
+}
+
+
+/// Fractal simplex noise, with fewer directional artifacts than ``PerlinTexture`` and a 4D
+/// variant for noise evolving over time.
+///
+/// Like the turbulence of ``PerlinTexture`` it sums ``octaves`` layers, starting at
+/// ``frequency`` and each ``lacunarity`` times finer and ``gain`` times weaker than the last,
+/// shown as grey from ``0`` to ``1``.
+///
+/// The fourth dimension animates the noise: ``with_time`` sets the time of a frame, so the
+/// frames of a sequence show smoothly changing noise, and ``with_time_scale`` how far the noise
+/// moves on over the shutter of each frame, following ``Ray::time`` like moving objects. Lights
+/// sampled directly, see ``Camera::add_light``, show their emission at the start of the shutter,
+/// like the positions of moving spheres.
+/// # Examples
+/// ```
+/// use ray_tracing::object::texture::{SimplexTexture, Texture};
+/// use ray_tracing::vec3d::Vec3d;
+/// let p = Vec3d::new(0.2, 0.4, 0.6);
+/// let clouds = SimplexTexture::from_seed(3).with_frequency(2.0).with_octaves(4);
+/// let grey = clouds.value(0.0, 0.0, &p);
+/// assert!((0.0..=1.0).contains(&grey.r()));
+/// assert_eq!(grey, SimplexTexture::from_seed(3).with_frequency(2.0).with_octaves(4).value(0.0, 0.0, &p));
+///
+/// let frame = |time| clouds.clone().with_time(time).value(0.0, 0.0, &p);
+/// assert_ne!(frame(0.0), frame(0.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimplexTexture {
+    /// A permutation of ``0..256``, twice in a row so lookups don't wrap.
+    perm: Vec<usize>,
+    frequency: Float,
+    octaves: i32,
+    lacunarity: Float,
+    gain: Float,
+    turbulence: bool,
+    time: Option<Float>,
+    time_scale: Float,
+}
+
+
+/// Gradients of the 3D noise, towards the midpoints of the edges of a cube.
+const SIMPLEX_GRADIENTS: [[Float; 3]; 12] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+];
+
+
+impl SimplexTexture {
+    /// Creates the texture with different noise on every call, see ``from_seed`` for noise
+    /// that can be reproduced.
+    pub fn new() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// Creates the texture with noise only depending on ``seed``, the same on every run and
+    /// machine.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut stream = Pcg32::new(seed, 0);
+        let mut perm: Vec<usize> = (0..256).collect();
+        for i in (1..perm.len()).rev() {
+            perm.swap(i, stream.random_range(0..=i));
+        }
+        perm.extend_from_within(..);
+        Self { perm, frequency: 1.0, octaves: 1, lacunarity: 2.0, gain: 0.5, turbulence: false, time: None, time_scale: 0.0 }
+    }
+
+    /// Sets the frequency of the coarsest layer, ``1.0`` by default.
+    pub fn with_frequency(mut self, frequency: Float) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the number of layers, ``1`` by default.
+    pub fn with_octaves(mut self, octaves: i32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    /// Sets how much finer every layer is than the last, ``2.0`` by default.
+    pub fn with_lacunarity(mut self, lacunarity: Float) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Sets how much weaker every layer is than the last, ``0.5`` by default.
+    pub fn with_gain(mut self, gain: Float) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Sums the magnitude of every layer, for billowy turbulence instead of smooth clouds.
+    pub fn with_turbulence(mut self, turbulence: bool) -> Self {
+        self.turbulence = turbulence;
+        self
+    }
+
+    /// Samples the 4D noise at ``time``, scaled by the ``frequency`` of each layer like the
+    /// position.
+    pub fn with_time(mut self, time: Float) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Moves the 4D noise on by ``time_scale`` times the ``Ray::time`` of each hit, ``0.0`` by
+    /// default, on top of the time of the frame.
+    pub fn with_time_scale(mut self, time_scale: Float) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    fn hash(&self, i: i64) -> usize {
+        self.perm[(i & 255) as usize]
+    }
+
+    /// 3D simplex noise at ``point``, in about ``[-1, 1]``.
+    pub fn noise(&self, point: &Vec3d) -> Float {
+        const SKEW: Float = 1.0 / 3.0;
+        const UNSKEW: Float = 1.0 / 6.0;
+        let p = [point.x(), point.y(), point.z()];
+
+        // The simplex containing the point, found in the skewed lattice of cubes.
+        let s = (p[0] + p[1] + p[2]) * SKEW;
+        let cell = p.map(|x| (x + s).floor());
+        let t = (cell[0] + cell[1] + cell[2]) * UNSKEW;
+        let offset = [p[0] - cell[0] + t, p[1] - cell[1] + t, p[2] - cell[2] + t];
+        // Walking from the first corner to the last along the axes by decreasing offset.
+        let mut axes = [0, 1, 2];
+        axes.sort_by(|&a, &b| offset[b].total_cmp(&offset[a]));
+
+        let cell = cell.map(|x| x as i64);
+        let mut step = [0; 3];
+        let mut total = 0.0;
+        for corner in 0..4 {
+            if corner > 0 { step[axes[corner - 1]] = 1; }
+            let d: [Float; 3] = std::array::from_fn(|a| offset[a] - step[a] as Float + corner as Float * UNSKEW);
+            let falloff = 0.6 - d[0] * d[0] - d[1] * d[1] - d[2] * d[2];
+            if falloff <= 0.0 { continue; }
+            let hash = self.hash(cell[0] + step[0]
+                + self.hash(cell[1] + step[1] + self.hash(cell[2] + step[2]) as i64) as i64);
+            let gradient = SIMPLEX_GRADIENTS[hash % 12];
+            total += falloff.powi(4) * (gradient[0] * d[0] + gradient[1] * d[1] + gradient[2] * d[2]);
+        }
+        32.0 * total
+    }
+
+    /// 4D simplex noise at ``point`` and ``w``, in about ``[-1, 1]``.
+    pub fn noise4(&self, point: &Vec3d, w: Float) -> Float {
+        let sqrt5 = (5.0 as Float).sqrt();
+        let (skew, unskew) = ((sqrt5 - 1.0) / 4.0, (5.0 - sqrt5) / 20.0);
+        let p = [point.x(), point.y(), point.z(), w];
+
+        let s = (p[0] + p[1] + p[2] + p[3]) * skew;
+        let cell = p.map(|x| (x + s).floor());
+        let t = (cell[0] + cell[1] + cell[2] + cell[3]) * unskew;
+        let offset: [Float; 4] = std::array::from_fn(|a| p[a] - cell[a] + t);
+        let mut axes = [0, 1, 2, 3];
+        axes.sort_by(|&a, &b| offset[b].total_cmp(&offset[a]));
+
+        let cell = cell.map(|x| x as i64);
+        let mut step = [0; 4];
+        let mut total = 0.0;
+        for corner in 0..5 {
+            if corner > 0 { step[axes[corner - 1]] = 1; }
+            let d: [Float; 4] = std::array::from_fn(|a| offset[a] - step[a] as Float + corner as Float * unskew);
+            let falloff = 0.6 - d.iter().map(|x| x * x).sum::<Float>();
+            if falloff <= 0.0 { continue; }
+            let hash = self.hash(cell[0] + step[0] + self.hash(cell[1] + step[1]
+                + self.hash(cell[2] + step[2] + self.hash(cell[3] + step[3]) as i64) as i64) as i64);
+            // One of the 32 midpoints of the edges of a tesseract, zero along one axis.
+            let zero = (hash >> 3) % 4;
+            let dot: Float = (0..4).filter(|&a| a != zero)
+                .enumerate()
+                .map(|(bit, a)| if hash & (1 << bit) == 0 { d[a] } else { -d[a] })
+                .sum();
+            total += falloff.powi(4) * dot;
+        }
+        27.0 * total
+    }
+
+    /// The layered noise at ``point`` for a ray at ``ray_time``, normalized to about
+    /// ``[-1, 1]``, or ``[0, 1]`` for turbulence.
+    fn fractal(&self, point: &Vec3d, ray_time: Float) -> Float {
+        let animated = self.time.is_some() || self.time_scale != 0.0;
+        let time = self.time.unwrap_or(0.0) + self.time_scale * ray_time;
+        let (mut total, mut weights) = (0.0, 0.0);
+        let (mut frequency, mut weight) = (self.frequency, 1.0);
+        for _ in 0..self.octaves {
+            let noise = if animated {
+                self.noise4(&(*point * frequency), time * frequency)
+            } else {
+                self.noise(&(*point * frequency))
+            };
+            total += weight * if self.turbulence { noise.abs() } else { noise };
+            weights += weight;
+            frequency *= self.lacunarity;
+            weight *= self.gain;
+        }
+        if weights > 0.0 { total / weights } else { 0.0 }
+    }
+}
+
+
+impl Default for SimplexTexture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl SimplexTexture {
+    fn color(&self, noise: Float) -> Color {
+        let grey = if self.turbulence { noise } else { 0.5 * (1.0 + noise) };
+        Color::splat(grey.clamp(0.0, 1.0))
+    }
+}
+
+
+impl Texture for SimplexTexture {
+    fn value(&self, _u: Float, _v: Float, p: &Vec3d) -> Color {
+        self.color(self.fractal(p, 0.0))
+    }
+
+    fn value_at(&self, hit_record: &HitRecord) -> Color {
+        self.color(self.fractal(&hit_record.point, hit_record.time))
+    }
+}
+
+
+/// Maps the luminance of an ``input`` texture, such as the ``[0, 1]`` turbulence of a
+/// ``PerlinTexture``, through a gradient of colors, e.g. to build lava or plasma for a ``Light``.
+///
+/// The gradient blends linearly between its ``(position, color)`` stops and keeps the color of
+/// the first and last stop beyond them. Stops can be brighter than white for emitters.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::material::{Light, Material};
+/// use ray_tracing::object::texture::{ColorRamp, PerlinTexture, SolidColor, Texture};
+/// use ray_tracing::vec3d::Vec3d;
+/// let stops = vec![(0.3, Color::zero()), (0.7, Color::new(8.0, 2.0, 0.0)), (1.0, Color::new(12.0, 10.0, 4.0))];
+/// let lava = ColorRamp::new(Arc::new(PerlinTexture::new(2.0)), stops.clone());
+/// let glowing_rock = Material::Light(Light::new(Arc::new(lava)));
+///
+/// let hottest = ColorRamp::new(Arc::new(SolidColor::new(Color::splat(1.0))), stops);
+/// assert_eq!(hottest.value(0.0, 0.0, &Vec3d::zero()), Color::new(12.0, 10.0, 4.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColorRamp {
+    input: Arc<dyn Texture>,
+    stops: Vec<(Float, Color)>,
+}
+
+
+impl ColorRamp {
+    /// Creates the ramp through ``stops`` in any order, black without any.
+    pub fn new(input: Arc<dyn Texture>, mut stops: Vec<(Float, Color)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { input, stops }
+    }
+
+    /// The color of the gradient at ``t``.
+    pub fn color(&self, t: Float) -> Color {
+        let (Some(&(first, low)), Some(&(last, high))) = (self.stops.first(), self.stops.last()) else {
+            return Color::zero();
+        };
+        if t <= first { return low; }
+        if t >= last { return high; }
+        let above = self.stops.partition_point(|&(position, _)| position <= t);
+        let ((start, from), (end, to)) = (self.stops[above - 1], self.stops[above]);
+        let blend = (t - start) / (end - start);
+        from * (1.0 - blend) + to * blend
+    }
+}
+
+
+impl Texture for ColorRamp {
+    fn value(&self, u: Float, v: Float, p: &Vec3d) -> Color {
+        self.color(self.input.value(u, v, p).luminance())
+    }
+
+    fn value_at(&self, hit_record: &HitRecord) -> Color {
+        self.color(self.input.value_at(hit_record).luminance())
     }
 }
 
@@ -255,6 +785,99 @@ impl Texture for PerlinTexture {
 mod test_texture{
     use super::*;
 
+    #[test]
+    fn test_vertex_color() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let texture = VertexColor::new(Arc::new(SolidColor::new(grey)));
+        let material = crate::object::material::Material::Empty(crate::object::material::Empty {});
+        let mut rec = HitRecord::new(&material, 1.0, 0.0, 0.0, Vec3d::zero());
+        assert_eq!(texture.value_at(&rec), grey);
+
+        rec.color = Some(Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.value_at(&rec), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_uv_grid_labels() {
+        assert_eq!(hue(0.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(hue(1.0 / 3.0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(hue(2.0 / 3.0), Color::new(0.0, 0.0, 1.0));
+
+        // The middle column of a lone "1" is set from the top to the bottom row, its right
+        // column only at the bottom.
+        let pixel = 0.1;
+        let center = |column: Float, row: Float| (0.35 + (column + 0.5) * pixel, 0.25 + (row + 0.5) * pixel);
+        for row in 0..5 {
+            let (x, y) = center(1.0, row as Float);
+            assert!(UvGrid::on_label(1, x, y));
+        }
+        let (x, y) = center(2.0, 0.0);
+        assert!(!UvGrid::on_label(1, x, y));
+        let (x, y) = center(2.0, 4.0);
+        assert!(UvGrid::on_label(1, x, y));
+
+        // Square 5 of a 4 x 4 grid is in the second row and column, numbered in its middle.
+        let grid = UvGrid::new(4);
+        assert_eq!(grid.value(0.25 + 0.125, 0.25 + 0.7 * 0.25, &Vec3d::zero()), Color::zero());
+        assert!(grid.value(0.25 + 0.02, 0.25 + 0.125, &Vec3d::zero()).max_component() > 0.3);
+    }
+
+    #[test]
+    fn test_color_ramp() {
+        let black = Arc::new(SolidColor::new(Color::zero()));
+        let ramp = ColorRamp::new(black, vec![(1.0, Color::splat(1.0)), (0.0, Color::zero()), (0.5, Color::new(1.0, 0.0, 0.0))]);
+        assert_eq!(ramp.color(-1.0), Color::zero());
+        assert_eq!(ramp.color(0.25), Color::new(0.5, 0.0, 0.0));
+        assert_eq!(ramp.color(0.5), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(ramp.color(0.75), Color::new(1.0, 0.5, 0.5));
+        assert_eq!(ramp.color(2.0), Color::splat(1.0));
+
+        let empty = ColorRamp::new(Arc::new(SolidColor::new(Color::splat(0.5))), Vec::new());
+        assert_eq!(empty.value(0.0, 0.0, &Vec3d::zero()), Color::zero());
+    }
+
+    #[test]
+    fn test_perlin_seed_reproduces_noise() {
+        let points = [Vec3d::new(0.5, 0.25, 0.1), Vec3d::new(-3.2, 7.9, 1.4), Vec3d::new(10.5, -0.3, 4.4)];
+        let sample = |texture: &PerlinTexture| points.map(|p| texture.noise(&p));
+        assert_eq!(sample(&PerlinTexture::from_seed(1.0, 7)), sample(&PerlinTexture::from_seed(4.0, 7)));
+        assert_ne!(sample(&PerlinTexture::from_seed(1.0, 7)), sample(&PerlinTexture::from_seed(1.0, 8)));
+
+        // A single layer of turbulence is the magnitude of the noise itself.
+        let single = PerlinTexture::from_seed(1.0, 7).with_octaves(1).with_frequency(2.0);
+        assert_eq!(single.turbulence(&points[0]), single.noise(&(points[0] * 2.0)).abs());
+    }
+
+    #[test]
+    fn test_simplex_noise_is_bounded_and_smooth() {
+        let noise = SimplexTexture::from_seed(11);
+        let (mut low, mut high) = (Float::MAX, Float::MIN);
+        for i in 0..2000 {
+            let i = i as Float;
+            let p = Vec3d::new(i * 0.173, i * 0.091 - 40.0, (i * 0.37).sin() * 9.0);
+            for value in [noise.noise(&p), noise.noise4(&p, i * 0.05)] {
+                assert!(value.abs() <= 1.0, "{} at {:?}", value, p);
+                (low, high) = (low.min(value), high.max(value));
+            }
+            let step = Vec3d::new(1e-4, 0.0, 0.0);
+            assert!((noise.noise(&(p + step)) - noise.noise(&p)).abs() < 1e-2);
+            assert!((noise.noise4(&p, 1e-4) - noise.noise4(&p, 0.0)).abs() < 1e-2);
+        }
+        // The noise spans most of its range.
+        assert!(low < -0.6 && high > 0.6, "{} {}", low, high);
+
+        let p = Vec3d::new(0.4, 0.8, 1.6);
+        assert_ne!(noise.noise(&p), SimplexTexture::from_seed(12).noise(&p));
+        assert_ne!(noise.clone().with_time(0.5).fractal(&p, 0.0), noise.clone().with_time(0.0).fractal(&p, 0.0));
+
+        // Over the shutter the noise moves on from the time of the frame.
+        let animated = noise.with_time(2.0).with_time_scale(0.5);
+        let material = crate::object::material::Material::Empty(crate::object::material::Empty {});
+        let mut rec = HitRecord::new(&material, 1.0, 0.0, 0.0, p);
+        rec.time = 1.0;
+        assert_eq!(animated.value_at(&rec), Color::splat(0.5 * (1.0 + animated.noise4(&p, 2.5))));
+    }
+
     #[test]
     fn test_solid_color_1() {
         let color = Color::new(1.0, 0.0, 0.0);
@@ -271,6 +894,60 @@ mod test_texture{
         assert_eq!(result, color);
     }
 
+    #[test]
+    fn test_image_texture_downsampling() {
+        let path = std::env::temp_dir().join("ray_tracing_test_downsample.png");
+        let path = path.to_str().unwrap();
+        // Columns alternating black and white, four times as wide as high.
+        let pixels: Vec<Color> = (0..64).map(|i| Color::splat((i % 2) as Float)).collect();
+        crate::image::write_image(path, &pixels, 16, 4);
+
+        let texture = ImageTexture::new(path).with_max_size(4);
+        assert_eq!(texture.dimensions(), (4, 1));
+        // Averaged to gray, which is brighter than half once gamma encoded.
+        let gray = texture.value(0.5, 0.5, &Vec3d::zero());
+        assert!(gray.r() > 0.15 && gray.r() < 0.35, "{}", gray);
+
+        assert_eq!(ImageTexture::new(path).with_max_size(32).dimensions(), (16, 4));
+        assert_eq!(ImageTexture::new(path).with_lod_bias(1).dimensions(), (8, 2));
+        assert_eq!(ImageTexture::new(path).with_lod_bias(3).dimensions(), (2, 1));
+        assert_eq!(ImageTexture::new(path).with_lod_bias(40).dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn test_image_texture_shared_across_threads() {
+        let path = std::env::temp_dir().join("ray_tracing_test_shared_texture.png");
+        let path = path.to_str().unwrap();
+        let pixels: Vec<Color> = (0..16).map(|i| Color::new(i as Float / 15.0, 0.5, 1.0)).collect();
+        crate::image::write_image(path, &pixels, 4, 4);
+
+        let texture = Arc::new(ImageTexture::new(path));
+        let lookup = |texture: &ImageTexture| -> Vec<Color> {
+            (0..=8).flat_map(|i| (0..=8).map(move |j| (i as Float / 8.0, j as Float / 8.0)))
+                .map(|(u, v)| texture.value(u, v, &Vec3d::zero()))
+                .collect()
+        };
+        let expected = lookup(&texture);
+        // The top left texel, back in linear sRGB.
+        let top_left = expected[8];
+        assert!(top_left.r() == 0.0 && (top_left.g() - 0.5).abs() < 0.01 && top_left.b() == 1.0, "{}", top_left);
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..4).map(|_| scope.spawn(|| lookup(&texture))).collect();
+            for thread in threads {
+                assert_eq!(thread.join().unwrap(), expected);
+            }
+        });
+
+        let clone = (*texture).clone();
+        assert!(Arc::ptr_eq(&clone.texels, &texture.texels));
+    }
+
+    #[test]
+    fn test_image_texture_missing_file() {
+        let texture = ImageTexture::try_new("./misc/does_not_exist.png");
+        assert!(matches!(texture, Err(Error::Image { .. })));
+    }
+
     #[test]
     fn test_checker_1() {
         let color1 = Color::new(1.0, 0.0, 0.0);
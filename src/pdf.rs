@@ -0,0 +1,223 @@
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::object::{Emitter, HitRecord, Portal};
+use crate::object::material::Scatterable;
+use crate::ray::Ray;
+use crate::sampler;
+use rand::Rng;
+
+use std::sync::Arc;
+
+
+/// A distribution of directions leaving a point, which can be both sampled and evaluated.
+///
+/// This is what integrators combine for multiple importance sampling: a direction drawn from
+/// one strategy is weighted by the densities every strategy would have given it, see
+/// ``MixturePdf``.
+pub trait Pdf {
+    /// Solid angle density of ``direction``, zero where the distribution never goes.
+    fn value(&self, direction: &Vec3d) -> Float;
+
+    /// Draws a direction, ``None`` if the distribution has none to offer, e.g. an absorbed
+    /// scattering or no light visible.
+    fn generate(&self) -> Option<Vec3d>;
+}
+
+
+/// The directions a material scatters ``ray_in`` into at ``hit_record``.
+///
+/// Specular materials have no density, so their ``value`` is zero everywhere.
+pub struct MaterialPdf<'a> {
+    ray_in: &'a Ray,
+    hit_record: &'a HitRecord<'a>,
+}
+
+
+impl<'a> MaterialPdf<'a> {
+    pub fn new(ray_in: &'a Ray, hit_record: &'a HitRecord<'a>) -> Self {
+        Self { ray_in, hit_record }
+    }
+}
+
+
+impl Pdf for MaterialPdf<'_> {
+    fn value(&self, direction: &Vec3d) -> Float {
+        let scattered = Ray::new(self.hit_record.point, *direction, self.ray_in.time);
+        self.hit_record.material.scattering_pdf(self.ray_in, self.hit_record, &scattered).unwrap_or(0.0)
+    }
+
+    fn generate(&self) -> Option<Vec3d> {
+        self.hit_record.material.scatter(self.ray_in, self.hit_record).map(|(scattered, _)| scattered.direction)
+    }
+}
+
+
+/// Directions from ``origin`` towards a light picked uniformly out of ``lights``.
+pub struct LightPdf<'a> {
+    lights: &'a [Arc<dyn Emitter>],
+    origin: Point3d,
+}
+
+
+impl<'a> LightPdf<'a> {
+    pub fn new(lights: &'a [Arc<dyn Emitter>], origin: Point3d) -> Self {
+        Self { lights, origin }
+    }
+}
+
+
+impl Pdf for LightPdf<'_> {
+    fn value(&self, direction: &Vec3d) -> Float {
+        if self.lights.is_empty() { return 0.0; }
+        self.lights.iter()
+            .map(|light| light.pdf(&self.origin, direction))
+            .sum::<Float>() / self.lights.len() as Float
+    }
+
+    fn generate(&self) -> Option<Vec3d> {
+        if self.lights.is_empty() { return None; }
+        let light = &self.lights[sampler::rng().random_range(0..self.lights.len())];
+        light.sample(&self.origin).map(|sample| sample.direction)
+    }
+}
+
+
+/// Directions from ``origin`` through a portal picked uniformly out of ``portals``.
+pub struct PortalPdf<'a> {
+    portals: &'a [Arc<Portal>],
+    origin: Point3d,
+}
+
+
+impl<'a> PortalPdf<'a> {
+    pub fn new(portals: &'a [Arc<Portal>], origin: Point3d) -> Self {
+        Self { portals, origin }
+    }
+}
+
+
+impl Pdf for PortalPdf<'_> {
+    fn value(&self, direction: &Vec3d) -> Float {
+        if self.portals.is_empty() { return 0.0; }
+        self.portals.iter()
+            .map(|portal| portal.pdf(&self.origin, direction))
+            .sum::<Float>() / self.portals.len() as Float
+    }
+
+    fn generate(&self) -> Option<Vec3d> {
+        if self.portals.is_empty() { return None; }
+        let portal = &self.portals[sampler::rng().random_range(0..self.portals.len())];
+        Some(portal.sample_direction(&self.origin))
+    }
+}
+
+
+/// A blend of distributions, each picked with a probability proportional to its weight.
+///
+/// Its ``value`` is the weighted mean of the densities of all components, which is what a
+/// direction drawn by ``generate`` has to be divided by to stay unbiased.
+/// # Examples
+/// ```
+/// use ray_tracing::pdf::{MixturePdf, Pdf};
+/// use ray_tracing::vec3d::{Vec3d, Float};
+/// // Sends every direction straight up with the given density.
+/// struct Up(Float);
+/// impl Pdf for Up {
+///     fn value(&self, _direction: &Vec3d) -> Float { self.0 }
+///     fn generate(&self) -> Option<Vec3d> { Some(Vec3d::new(0.0, 1.0, 0.0)) }
+/// }
+/// let (narrow, wide) = (Up(4.0), Up(1.0));
+/// let mixture = MixturePdf::new().with(3.0, &narrow).with(1.0, &wide);
+/// assert_eq!(mixture.value(&Vec3d::new(0.0, 1.0, 0.0)), 3.25);
+/// assert_eq!(mixture.generate(), Some(Vec3d::new(0.0, 1.0, 0.0)));
+/// ```
+#[derive(Default)]
+pub struct MixturePdf<'a> {
+    components: Vec<(Float, &'a dyn Pdf)>,
+    total_weight: Float,
+}
+
+
+impl<'a> MixturePdf<'a> {
+    /// Creates an empty mixture, whose density is zero everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds ``pdf`` with the given ``weight``. Components with a weight that is not positive
+    /// are left out.
+    pub fn with(mut self, weight: Float, pdf: &'a dyn Pdf) -> Self {
+        if weight > 0.0 {
+            self.components.push((weight, pdf));
+            self.total_weight += weight;
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: &Vec3d) -> Float {
+        if self.components.is_empty() { return 0.0; }
+        self.components.iter()
+            .map(|(weight, pdf)| weight * pdf.value(direction))
+            .sum::<Float>() / self.total_weight
+    }
+
+    fn generate(&self) -> Option<Vec3d> {
+        let mut pick = sampler::rng().random::<Float>() * self.total_weight;
+        for (weight, pdf) in self.components.iter() {
+            if pick < *weight { return pdf.generate(); }
+            pick -= weight;
+        }
+        self.components.last().and_then(|(_, pdf)| pdf.generate())
+    }
+}
+
+
+#[cfg(test)]
+mod test_pdf {
+    use super::*;
+    use crate::color::Color;
+    use crate::object::Quad;
+    use crate::object::material::{Lambertian, Light, Material};
+    use crate::vec3d::consts;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_mixture_of_material_and_lights() {
+        let floor = Material::Lambertian(Lambertian::new(Color::splat(0.5)));
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 1.0), Vec3d::new(0.0, -1.0, -1.0), 0.0);
+        let mut hit_record = HitRecord::new(&floor, 1.0, 0.0, 0.0, Point3d::zero());
+        hit_record.set_face_normal(&ray_in, Vec3d::new(0.0, 1.0, 0.0));
+
+        let light: Arc<dyn Emitter> = Arc::new(Quad::new(
+            Point3d::new(-0.5, 2.0, -0.5), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 1.0),
+            Material::Light(Light::from_color(Color::splat(1.0))),
+        ));
+        let lights = [light];
+        let material = MaterialPdf::new(&ray_in, &hit_record);
+        let towards_lights = LightPdf::new(&lights, hit_record.point);
+        let mixture = MixturePdf::new().with(1.0, &material).with(1.0, &towards_lights).with(0.0, &material);
+
+        let up = Vec3d::new(0.0, 1.0, 0.0);
+        let light_density = towards_lights.value(&up);
+        // A unit square two units away, seen head-on.
+        assert_approx_eq!(light_density, 4.0);
+        assert_approx_eq!(material.value(&up), 1.0 / consts::PI);
+        assert_approx_eq!(mixture.value(&up), 0.5 * (4.0 + 1.0 / consts::PI));
+        // Below the floor only the lights could have sent rays, but they don't.
+        assert_eq!(mixture.value(&-up), 0.0);
+
+        for _ in 0..100 {
+            let direction = mixture.generate().unwrap();
+            assert!(direction.y() > 0.0);
+            assert!(mixture.value(&direction) > 0.0);
+        }
+        assert!(MixturePdf::new().generate().is_none());
+        assert!(LightPdf::new(&[], Point3d::zero()).generate().is_none());
+    }
+}
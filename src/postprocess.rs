@@ -0,0 +1,284 @@
+//! Effects applied to the linear HDR pixels of a render before writing them out.
+//!
+//! Every function takes the pixels in row-major order, as returned by ``Camera::render``,
+//! along with the width of the image for effects looking at neighbors or the position, and
+//! returns the processed pixels.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::color::Color;
+//! use ray_tracing::postprocess;
+//! // A single light far brighter than white, in the middle of a dark image.
+//! let mut pixels = vec![Color::zero(); 25];
+//! pixels[12] = Color::splat(15.0);
+//! let glowing = postprocess::bloom(&pixels, 5, 1.0, 2, 0.5);
+//! assert!(glowing[11].r() > 0.0 && glowing[0].r() > 0.0);
+//! assert!(glowing[12].r() > 15.0);
+//! ```
+use crate::color::Color;
+use crate::vec3d::Float;
+
+
+/// Colors of the exposure zones of ``false_color``, from six stops below middle grey to six
+/// stops above.
+const ZONES: [Color; 13] = [
+    Color::new(0.0, 0.0, 0.0),
+    Color::new(0.25, 0.0, 0.35),
+    Color::new(0.4, 0.1, 0.7),
+    Color::new(0.1, 0.2, 0.85),
+    Color::new(0.0, 0.55, 0.75),
+    Color::new(0.1, 0.6, 0.3),
+    Color::new(0.5, 0.5, 0.5),
+    Color::new(0.55, 0.8, 0.2),
+    Color::new(0.95, 0.9, 0.1),
+    Color::new(1.0, 0.6, 0.0),
+    Color::new(1.0, 0.25, 0.1),
+    Color::new(1.0, 0.6, 0.8),
+    Color::new(1.0, 1.0, 1.0),
+];
+
+
+/// Width in pixels of the stripes ``false_color`` marks clipping pixels with.
+const ZEBRA_WIDTH: usize = 4;
+
+
+/// Makes pixels brighter than white bleed into their surroundings, like the glare of bright
+/// lights in a camera lens.
+///
+/// The part of each pixel's luminance above ``threshold`` is blurred with a gaussian reaching
+/// ``radius`` pixels and added back on top of the image, scaled by ``strength``. Pixels at or
+/// below the threshold contribute nothing, so the rest of the image stays untouched.
+pub fn bloom(pixels: &[Color], width: usize, threshold: Float, radius: usize, strength: Float) -> Vec<Color> {
+    if pixels.is_empty() || width == 0 { return pixels.to_vec(); }
+
+    let bright: Vec<Color> = pixels.iter()
+        .map(|pixel| {
+            let luminance = pixel.luminance();
+            if luminance <= threshold { Color::zero() } else { *pixel * ((luminance - threshold) / luminance) }
+        })
+        .collect();
+    let glow = blur(&bright, width, radius);
+    pixels.iter().zip(glow).map(|(pixel, glow)| *pixel + glow * strength).collect()
+}
+
+
+/// Darkens the image towards its corners, like the light falloff of a real lens.
+///
+/// Pixels are scaled by ``1 - strength * r^falloff``, where ``r`` is the distance from the
+/// center relative to the corners, so the corners lose ``strength`` of their light. A larger
+/// ``falloff`` keeps more of the center at full brightness.
+pub fn vignette(pixels: &[Color], width: usize, strength: Float, falloff: Float) -> Vec<Color> {
+    if pixels.is_empty() || width == 0 { return pixels.to_vec(); }
+    let height = pixels.len() / width;
+    let (cx, cy) = (width as Float / 2.0, height as Float / 2.0);
+    let corner = (cx * cx + cy * cy).sqrt();
+
+    pixels.iter().enumerate().map(|(index, pixel)| {
+        let x = (index % width) as Float + 0.5 - cx;
+        let y = (index / width) as Float + 0.5 - cy;
+        let r = (x * x + y * y).sqrt() / corner;
+        *pixel * (1.0 - strength * r.powf(falloff)).max(0.0)
+    }).collect()
+}
+
+
+/// Shifts the red and blue channels radially against green, like the color fringes of a lens
+/// focusing each wavelength slightly differently.
+///
+/// The red image is magnified by ``1 + shift`` around the center and the blue one shrunk by
+/// ``1 - shift``, so fringes grow towards the edges; a ``shift`` of ``0.005`` is subtle.
+/// Channels are resampled bilinearly, repeating the pixels at the edges.
+pub fn chromatic_aberration(pixels: &[Color], width: usize, shift: Float) -> Vec<Color> {
+    if pixels.is_empty() || width == 0 { return pixels.to_vec(); }
+    let height = pixels.len() / width;
+    let (cx, cy) = (width as Float / 2.0, height as Float / 2.0);
+
+    // Bilinear lookup of one channel at continuous pixel coordinates.
+    let channel = |x: Float, y: Float, c: usize| -> Float {
+        let (x, y) = ((x - 0.5).clamp(0.0, (width - 1) as Float), (y - 0.5).clamp(0.0, (height - 1) as Float));
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+        let (tx, ty) = (x - x0 as Float, y - y0 as Float);
+        let at = |x: usize, y: usize| pixels[y * width + x][c];
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    };
+
+    pixels.iter().enumerate().map(|(index, pixel)| {
+        let x = (index % width) as Float + 0.5 - cx;
+        let y = (index / width) as Float + 0.5 - cy;
+        let red = channel(cx + x / (1.0 + shift), cy + y / (1.0 + shift), 0);
+        let blue = channel(cx + x / (1.0 - shift), cy + y / (1.0 - shift), 2);
+        Color::new(red, pixel.g(), blue)
+    }).collect()
+}
+
+
+/// Color temperature white balance maps to neutral, the white point of sRGB.
+const NEUTRAL_TEMPERATURE: Float = 6504.0;
+
+
+/// Corrects the colors for a scene lit at ``kelvin``, so light of that temperature, e.g.
+/// ``Color::from_temperature(kelvin)``, turns neutral gray.
+///
+/// Like the white balance setting of a camera, lower temperatures than the light's make the
+/// image cooler and higher ones warmer. ``6504`` kelvin leaves the image unchanged.
+pub fn white_balance(pixels: &[Color], kelvin: Float) -> Vec<Color> {
+    let (neutral, light) = (Color::from_temperature(NEUTRAL_TEMPERATURE), Color::from_temperature(kelvin));
+    // The reddest lights have next to no blue, which is not boosted beyond recovery.
+    let channel = |i: usize| neutral[i] / light[i].max(1e-3);
+    let scale = Color::new(channel(0), channel(1), channel(2));
+    pixels.iter().map(|pixel| *pixel * scale).collect()
+}
+
+
+/// Replaces every pixel by the color of its exposure zone, to judge the exposure of a render
+/// at a glance, like the false color view of a cinema camera.
+///
+/// Each stop of luminance around ``MIDDLE_GREY`` has its own color: grey for middle grey,
+/// greens just around it, blues and purples for the shadows down to black at six stops below,
+/// and yellow, orange, red and pink for the highlights up to white at six stops above. Pixels
+/// clipping in any channel are striped with black like the zebras of a viewfinder, see
+/// ``LuminanceHistogram`` for the same in numbers.
+/// # Examples
+/// ```
+/// use ray_tracing::color::{Color, MIDDLE_GREY};
+/// use ray_tracing::postprocess;
+/// let zones = postprocess::false_color(&[Color::splat(MIDDLE_GREY), Color::zero()], 2);
+/// assert_eq!(zones, vec![Color::splat(0.5), Color::zero()]);
+/// ```
+pub fn false_color(pixels: &[Color], width: usize) -> Vec<Color> {
+    let half = (ZONES.len() / 2) as Float;
+    pixels.iter().enumerate().map(|(index, pixel)| {
+        let (x, y) = (index % width.max(1), index / width.max(1));
+        if pixel.max_component() > 1.0 && ((x + y) / ZEBRA_WIDTH).is_multiple_of(2) { return Color::zero(); }
+        // Black and NaN pixels count as the darkest zone.
+        let zone = (pixel.stops().round() + half).max(0.0).min(2.0 * half);
+        ZONES[if zone.is_nan() { 0 } else { zone as usize }]
+    }).collect()
+}
+
+
+/// Separable gaussian blur reaching ``radius`` pixels, repeating the pixels at the edges.
+fn blur(pixels: &[Color], width: usize, radius: usize) -> Vec<Color> {
+    let height = pixels.len() / width;
+    let kernel = gaussian_kernel(radius);
+    let radius = radius as isize;
+
+    let pass = |source: &[Color], horizontal: bool| -> Vec<Color> {
+        (0..pixels.len()).map(|index| {
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            kernel.iter().enumerate().fold(Color::zero(), |sum, (i, weight)| {
+                let offset = i as isize - radius;
+                let (sx, sy) = if horizontal {
+                    ((x + offset).clamp(0, width as isize - 1), y)
+                } else {
+                    (x, (y + offset).clamp(0, height as isize - 1))
+                };
+                sum + source[sy as usize * width + sx as usize] * *weight
+            })
+        }).collect()
+    };
+    pass(&pass(pixels, true), false)
+}
+
+
+/// Normalized gaussian weights for offsets ``-radius..=radius``, with the radius at three
+/// standard deviations.
+fn gaussian_kernel(radius: usize) -> Vec<Float> {
+    let sigma = (radius as Float / 3.0).max(1e-3);
+    let weights: Vec<Float> = (0..=2 * radius)
+        .map(|i| {
+            let offset = i as Float - radius as Float;
+            (-offset * offset / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let total: Float = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+
+#[cfg(test)]
+mod test_postprocess {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_gaussian_kernel() {
+        assert_eq!(gaussian_kernel(0), vec![1.0]);
+        let kernel = gaussian_kernel(3);
+        assert_eq!(kernel.len(), 7);
+        assert_approx_eq!(kernel.iter().sum::<Float>(), 1.0);
+        assert_eq!(kernel[1], kernel[5]);
+        assert!(kernel[3] > kernel[2]);
+    }
+
+    #[test]
+    fn test_white_balance() {
+        let neutral = Color::from_temperature(NEUTRAL_TEMPERATURE);
+        let pixels = vec![Color::from_temperature(3200.0) * 0.5, Color::new(0.1, 0.2, 0.3)];
+        let balanced = white_balance(&pixels, 3200.0);
+        for i in 0..3 {
+            assert_approx_eq!(balanced[0][i], 0.5 * neutral[i]);
+        }
+        // Balancing for a warm light cools everything else down.
+        assert!(balanced[1].b() / balanced[1].r() > 3.0);
+        assert_eq!(white_balance(&pixels, NEUTRAL_TEMPERATURE), pixels);
+    }
+
+    #[test]
+    fn test_false_color_zones_and_zebras() {
+        let pixels = [Color::splat(0.36), Color::splat(0.18 / 16.0), Color::splat(0.9), Color::splat(Float::NAN), Color::splat(1e3)];
+        let zones = false_color(&pixels, 8);
+        assert_eq!(zones, vec![ZONES[7], ZONES[2], ZONES[8], ZONES[0], ZONES[12]]);
+
+        // Clipping pixels are striped diagonally, everything else is not.
+        let bright = vec![Color::splat(1.5); 64];
+        let striped = false_color(&bright, 8);
+        assert_eq!(striped[0], Color::zero());
+        assert_eq!(striped[4], ZONES[9]);
+        assert_eq!(striped[8 + 2], Color::zero());
+        assert_eq!(striped[8 + 3], ZONES[9]);
+        assert!(false_color(&[Color::splat(1.0); 64], 8).iter().all(|&zone| zone == ZONES[8]));
+    }
+
+    #[test]
+    fn test_vignette() {
+        let pixels = vec![Color::splat(1.0); 16];
+        let result = vignette(&pixels, 4, 0.5, 2.0);
+        // The four central pixels are equally close to the center, the corners the farthest.
+        assert_eq!(result[5], result[10]);
+        assert_approx_eq!(result[5].r(), 1.0 - 0.5 * 0.0625);
+        assert_approx_eq!(result[0].r(), 1.0 - 0.5 * 0.5625);
+        assert_eq!(vignette(&pixels, 4, 0.0, 2.0), pixels);
+    }
+
+    #[test]
+    fn test_chromatic_aberration() {
+        // A white vertical line, right of the center of a 9 x 1 image.
+        let mut pixels = vec![Color::zero(); 9];
+        pixels[6] = Color::splat(1.0);
+        let result = chromatic_aberration(&pixels, 9, 0.5);
+
+        assert_eq!(result[6].g(), 1.0);
+        // Red is pushed outwards and blue inwards, splitting the line into colored fringes.
+        assert_eq!(result[7], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(result[5], Color::new(0.0, 0.0, 1.0));
+        assert!(result[6].r() < 1.0 && result[6].b() == 0.0);
+        assert_eq!(chromatic_aberration(&pixels, 9, 0.0), pixels);
+    }
+
+    #[test]
+    fn test_bloom_keeps_energy_and_dim_pixels() {
+        let mut pixels = vec![Color::splat(0.5); 49];
+        pixels[24] = Color::splat(9.0);
+        let result = bloom(&pixels, 7, 1.0, 2, 1.0);
+
+        // Away from the light nothing changes, and the glow adds up to what exceeded the threshold.
+        assert_eq!(result[0], Color::splat(0.5));
+        let added: Float = result.iter().zip(pixels.iter()).map(|(a, b)| a.r() - b.r()).sum();
+        assert_approx_eq!(added, 8.0);
+        assert!(result[23].r() > 0.5 && result[23].r() < result[24].r());
+    }
+}
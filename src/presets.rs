@@ -0,0 +1,202 @@
+//! Ready-made materials and textures with measured or typical constants, so scenes can say
+//! ``presets::gold()`` instead of guessing an albedo.
+//!
+//! Colors are linear sRGB. The metals use their reflectance head on, which is what the albedo
+//! of ``Metal`` stands for, computed from the complex indices of refraction in ``ior``, where
+//! the glasses take theirs from too.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::object::material::{Dielectric, Material};
+//! use ray_tracing::presets::{self, ior};
+//! let water = presets::glass(ior::WATER);
+//! assert_eq!(water, Material::Dielectric(Dielectric::new(1.333)));
+//! ```
+use crate::color::Color;
+use crate::object::material::{Coat, Dielectric, Lambertian, Material, Metal};
+use crate::object::texture::{Checker, PerlinTexture};
+use crate::vec3d::Float;
+
+
+/// Indices of refraction of common materials, looked up by name or used as constants.
+///
+/// # Examples
+/// ```
+/// use ray_tracing::presets::ior;
+/// assert_eq!(ior::dielectric("Diamond"), Some(ior::DIAMOND));
+/// let gold = ior::conductor("gold").unwrap().reflectance();
+/// assert!(gold.r() > gold.b());
+/// assert!(ior::conductor("glass").is_none());
+/// ```
+pub mod ior {
+    use crate::color::Color;
+    use crate::vec3d::Float;
+
+    // Transparent materials, for yellow light of 589 nm.
+
+    pub const AIR: Float = 1.000293;
+    pub const ICE: Float = 1.31;
+    pub const WATER: Float = 1.333;
+    /// Ordinary window and bottle glass.
+    pub const GLASS: Float = 1.52;
+    pub const QUARTZ: Float = 1.544;
+    /// Dense, sparkly glass used for lenses and cut crystal.
+    pub const FLINT_GLASS: Float = 1.62;
+    pub const SAPPHIRE: Float = 1.77;
+    pub const DIAMOND: Float = 2.417;
+
+    const DIELECTRICS: [(&str, Float); 8] = [
+        ("air", AIR), ("ice", ICE), ("water", WATER), ("glass", GLASS), ("quartz", QUARTZ),
+        ("flint_glass", FLINT_GLASS), ("sapphire", SAPPHIRE), ("diamond", DIAMOND),
+    ];
+
+    /// Index of refraction of the transparent material ``name``, e.g. ``"flint_glass"``,
+    /// ignoring case. ``None`` for unknown names.
+    pub fn dielectric(name: &str) -> Option<Float> {
+        DIELECTRICS.iter().find(|(known, _)| known.eq_ignore_ascii_case(name)).map(|&(_, ior)| ior)
+    }
+
+
+    /// Complex index of refraction ``n + ik`` of a conductor in the red, green and blue
+    /// channels, ``k`` being how strongly it absorbs light entering it.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct ComplexIor {
+        pub n: Color,
+        pub k: Color,
+    }
+
+
+    impl ComplexIor {
+        pub fn new(n: Color, k: Color) -> Self {
+            Self { n, k }
+        }
+
+        /// Share of light reflected head on from air, by the Fresnel equations.
+        pub fn reflectance(&self) -> Color {
+            let channel = |i: usize| {
+                let (n, k) = (self.n[i], self.k[i]);
+                ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k)
+            };
+            Color::new(channel(0), channel(1), channel(2))
+        }
+    }
+
+
+    /// Measured at 650, 550 and 450 nm, as ``(name, n, k)``.
+    const CONDUCTORS: [(&str, [Float; 3], [Float; 3]); 8] = [
+        ("gold", [0.18299, 0.42108, 1.37340], [3.42420, 2.34590, 1.77040]),
+        ("silver", [0.15943, 0.14512, 0.13547], [3.92910, 3.19000, 2.38080]),
+        ("copper", [0.27105, 0.67693, 1.31640], [3.60920, 2.62480, 2.29210]),
+        ("aluminium", [1.65746, 0.88037, 0.52123], [9.22387, 6.26952, 4.83700]),
+        ("iron", [2.91140, 2.94970, 2.58450], [3.08930, 2.93180, 2.76700]),
+        ("chromium", [3.10710, 3.18120, 2.32300], [3.33140, 3.32910, 3.13500]),
+        ("titanium", [2.74070, 2.54180, 2.26370], [3.81430, 3.43450, 3.03850]),
+        ("platinum", [2.37570, 2.08470, 1.84530], [4.26550, 3.71530, 3.13650]),
+    ];
+
+    /// Complex index of refraction of the metal ``name``, e.g. ``"copper"``, ignoring case.
+    /// ``None`` for unknown names.
+    pub fn conductor(name: &str) -> Option<ComplexIor> {
+        CONDUCTORS.iter()
+            .find(|(known, _, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(_, n, k)| ComplexIor::new(Color::from(n), Color::from(k)))
+    }
+}
+
+
+/// Fuzz of the metal presets, polished but not quite mirrors.
+const METAL_FUZZ: Float = 0.02;
+
+
+/// The polished metal ``name`` from ``ior::conductor``, ``None`` for unknown names.
+pub fn metal(name: &str) -> Option<Material> {
+    let albedo = ior::conductor(name)?.reflectance();
+    Some(Material::Metal(Metal::new(albedo, METAL_FUZZ)))
+}
+
+
+pub fn gold() -> Material {
+    metal("gold").unwrap()
+}
+
+
+pub fn silver() -> Material {
+    metal("silver").unwrap()
+}
+
+
+pub fn copper() -> Material {
+    metal("copper").unwrap()
+}
+
+
+pub fn aluminium() -> Material {
+    metal("aluminium").unwrap()
+}
+
+
+/// Clear glass with the given index of refraction, see ``ior``.
+pub fn glass(refraction_index: Float) -> Material {
+    Material::Dielectric(Dielectric::new(refraction_index))
+}
+
+
+/// Sandblasted window glass, blurring what is seen through it.
+pub fn frosted_glass() -> Material {
+    Material::Dielectric(Dielectric::new(ior::GLASS).with_roughness(0.3))
+}
+
+
+/// Glossy paint of the given color, under a clear coat mirroring the surroundings at grazing
+/// angles.
+pub fn car_paint(color: Color) -> Material {
+    Material::Coat(Coat::new(Material::Lambertian(Lambertian::new(color))))
+}
+
+
+/// A grey and white checkerboard with squares of ``size``, for floors and test scenes.
+pub fn checkerboard(size: Float) -> Checker {
+    Checker::from_color(Color::splat(0.2), Color::splat(0.9), size)
+}
+
+
+/// White marble with grey veins, from Perlin noise.
+pub fn marble() -> PerlinTexture {
+    PerlinTexture::new(4.0)
+}
+
+
+#[cfg(test)]
+mod test_presets {
+    use super::*;
+    use crate::object::HitRecord;
+    use crate::object::material::Scatterable;
+    use crate::ray::Ray;
+    use crate::vec3d::{Point3d, Vec3d};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_metal_tints() {
+        let mut hit_record = HitRecord::empty();
+        hit_record.normal = Vec3d::new(0.0, 1.0, 0.0);
+        let ray_in = Ray::new(Point3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0), 0.0);
+        let tint = |metal: Material| metal.scatter(&ray_in, &hit_record).unwrap().1;
+
+        let (gold, copper, silver) = (tint(gold()), tint(copper()), tint(silver()));
+        assert!(gold.r() > gold.g() && gold.g() > gold.b());
+        assert!(copper.r() > copper.b() && copper.g() < gold.g());
+        assert!(silver.b() > 0.9 && silver.max_component() <= 1.0);
+    }
+
+    #[test]
+    fn test_lookup_by_name() {
+        assert_eq!(ior::dielectric("FLINT_GLASS"), Some(ior::FLINT_GLASS));
+        assert_eq!(ior::dielectric("unobtainium"), None);
+        assert!(metal("Platinum").is_some());
+        assert!(metal("diamond").is_none());
+
+        // A perfect dielectric with ``k = 0`` reflects like Schlick's ``r0``.
+        let glass = ior::ComplexIor::new(Color::splat(1.5), Color::zero());
+        assert_approx_eq!(glass.reflectance().g(), 0.04);
+    }
+}
@@ -1,15 +1,36 @@
-use crate::vec3d::{Vec3d, Point3d};
-use std::ops::{Add, Sub};
+use crate::vec3d::{Vec3d, Point3d, Float};
+use std::ops::{Add, Sub, RangeInclusive};
+
+
+/// What a ray is traced for, so objects can be hidden from some kinds of rays only, see
+/// ``object::Visibility``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RayKind {
+    /// Sent from the camera through a pixel.
+    #[default]
+    Camera,
+    /// Scattered off a surface or inside a medium.
+    Reflection,
+    /// Only testing whether a point is visible, e.g. from a light.
+    Shadow,
+}
 
 
 /// A ray is a line that starts at a point and goes in a direction.
+///
+/// The component-wise inverse of the direction and its sign bits are cached on construction
+/// for the bounding box slab test, so build a new ray with ``Ray::new`` rather than mutating
+/// ``direction`` in place.
 /// # Fields
 /// * `origin` - The starting point of the ray.
 /// * `direction` - The direction of the ray.
+/// * `time` - The shutter time the ray is traced at.
+/// * `kind` - What the ray is traced for, ``RayKind::Camera`` unless set with ``with_kind``.
+/// * `depth` - Bounces along the path before this ray, ``0`` unless set with ``with_depth``.
 /// # Examples
 /// ```
 /// use ray_tracing::ray::Ray;
-/// use ray_tracing::vec3d::Vec3d;
+/// use ray_tracing::vec3d::{Float, Vec3d};
 /// let ray = Ray::new(
 ///     Vec3d::new(1.0, 2.0, 3.0),
 ///     Vec3d::new(4.0, 5.0, 6.0),
@@ -18,7 +39,7 @@ use std::ops::{Add, Sub};
 /// assert_eq!(ray.origin, Vec3d::new(1.0, 2.0, 3.0));
 /// assert_eq!(ray.direction, Vec3d::new(4.0, 5.0, 6.0));
 ///
-/// let t: f64 = 0.5;
+/// let t: Float = 0.5;
 /// let result = ray.at(t);
 /// assert_eq!(result, Vec3d::new(3.0, 4.5, 6.0));
 /// ```
@@ -26,25 +47,85 @@ use std::ops::{Add, Sub};
 pub struct Ray {
     pub origin: Point3d,
     pub direction: Vec3d,
-    pub time: f64,
+    pub time: Float,
+    pub kind: RayKind,
+    pub depth: u32,
+
+    inv_direction: Vec3d,
+    sign: [usize; 3],
 }
 
 impl Ray {
 
     pub fn default() -> Self {
-        Self { origin: Point3d::zero(), direction: Vec3d::zero(), time: 0.0}
+        Self::new(Point3d::zero(), Vec3d::zero(), 0.0)
+    }
+
+    pub fn new(origin: Point3d, direction: Vec3d, time: Float) -> Self {
+        let inv_direction = Vec3d::new(1.0 / direction.x(), 1.0 / direction.y(), 1.0 / direction.z());
+        let sign = [0, 1, 2].map(|axis| (inv_direction[axis] < 0.0) as usize);
+        Self { origin, direction, time, kind: RayKind::Camera, depth: 0, inv_direction, sign }
     }
 
-    pub fn new(origin: Point3d, direction: Vec3d, time: f64) -> Self {
-        Self { origin, direction, time }
+    /// Returns the ray traced for the given purpose.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::ray::{Ray, RayKind};
+    /// use ray_tracing::vec3d::Vec3d;
+    /// let ray = Ray::new(Vec3d::zero(), Vec3d::new(0.0, 1.0, 0.0), 0.0).with_kind(RayKind::Shadow);
+    /// assert_eq!(ray.kind, RayKind::Shadow);
+    /// ```
+    pub fn with_kind(mut self, kind: RayKind) -> Self {
+        self.kind = kind;
+        self
     }
 
-    pub fn at(&self, t: f64) -> Point3d {
+    /// Returns the ray as traced after ``depth`` bounces.
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Returns the component-wise inverse of the direction. Axes with a zero direction
+    /// component hold a signed infinity.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::ray::Ray;
+    /// use ray_tracing::vec3d::{Float, Vec3d};
+    /// let ray = Ray::new(Vec3d::zero(), Vec3d::new(2.0, -4.0, 0.0), 0.0);
+    /// assert_eq!(ray.inv_direction(), Vec3d::new(0.5, -0.25, Float::INFINITY));
+    /// ```
+    pub fn inv_direction(&self) -> Vec3d { self.inv_direction }
+
+    /// Returns, per axis, ``1`` if the ray travels towards negative values and ``0`` otherwise.
+    pub fn sign(&self) -> [usize; 3] { self.sign }
+
+    pub fn at(&self, t: Float) -> Point3d {
         self.origin + self.direction * t
     }
 }
 
 
+/// Conversion from an ``(origin, direction)`` pair, with the ray time set to ``0.0``.
+///
+/// Anything convertible into a ``Vec3d`` is accepted, e.g. plain ``[x, y, z]`` arrays as
+/// produced by other math libraries.
+/// # Examples
+/// ```
+/// use ray_tracing::ray::Ray;
+/// use ray_tracing::vec3d::Vec3d;
+/// let ray = Ray::from(([1.0, 2.0, 3.0], [0.0, 0.0, -1.0]));
+/// assert_eq!(ray.origin, Vec3d::new(1.0, 2.0, 3.0));
+/// assert_eq!(ray.direction, Vec3d::new(0.0, 0.0, -1.0));
+/// assert_eq!(ray.time, 0.0);
+/// ```
+impl<O: Into<Point3d>, D: Into<Vec3d>> From<(O, D)> for Ray {
+    fn from((origin, direction): (O, D)) -> Self {
+        Self::new(origin.into(), direction.into(), 0.0)
+    }
+}
+
+
 #[cfg(test)]
 mod test_ray {
     use super::*;
@@ -68,18 +149,33 @@ mod test_ray {
             0.0,
         );
 
-        let t: f64 = 0.5;
+        let t: Float = 0.5;
         let result = ray.at(t);
         assert_eq!(result, Point3d::new(3.0, 4.5, 6.0));
 
-        let t: f64 = 2.0;
+        let t: Float = 2.0;
         let result = ray.at(t);
         assert_eq!(result, Point3d::new(9.0, 12.0, 15.0));
 
-        let t: f64 = 3.0;
+        let t: Float = 3.0;
         let result = ray.at(t);
         assert_eq!(result, Point3d::new(13.0, 17.0, 21.0));
     }
+
+    #[test]
+    fn test_ray_from_tuple() {
+        let ray = Ray::from(([1.0, 2.0, 3.0], Vec3d::new(4.0, 5.0, 6.0)));
+        assert_eq!(ray.origin, Point3d::new(1.0, 2.0, 3.0));
+        assert_eq!(ray.direction, Vec3d::new(4.0, 5.0, 6.0));
+        assert_eq!(ray.time, 0.0);
+    }
+
+    #[test]
+    fn test_ray_inv_direction() {
+        let ray = Ray::new(Point3d::zero(), Vec3d::new(-2.0, 0.5, -0.0), 0.0);
+        assert_eq!(ray.inv_direction(), Vec3d::new(-0.5, 2.0, Float::NEG_INFINITY));
+        assert_eq!(ray.sign(), [1, 0, 1]);
+    }
 }
 
 
@@ -96,12 +192,17 @@ mod test_ray {
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Interval {
-    pub min: f64,
-    pub max: f64,
+    pub min: Float,
+    pub max: Float,
 }
 
 impl Interval {
 
+    /// Creates a new interval from its bounds.
+    pub const fn new(min: Float, max: Float) -> Self {
+        Self { min, max }
+    }
+
     /// Creates a new interval with the given two interval values.
     /// # Arguments
     /// * `interval_1` - The first interval value.
@@ -124,11 +225,53 @@ impl Interval {
         }
     }
 
-    pub fn contains(&self, t: f64) -> bool {
+    /// Returns the smallest interval covering both intervals, same as ``Interval::interval``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::ray::Interval;
+    /// let result = Interval::new(1.0, 2.0).union(&Interval::new(3.0, 4.0));
+    /// assert_eq!(result, Interval::new(1.0, 4.0));
+    /// assert_eq!(Interval::EMPTY.union(&result), result);
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        Self::interval(self, other)
+    }
+
+    /// Returns the overlap of both intervals, which is empty if they are disjoint.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::ray::Interval;
+    /// let result = Interval::new(1.0, 3.0).intersection(&Interval::new(2.0, 4.0));
+    /// assert_eq!(result, Interval::new(2.0, 3.0));
+    /// assert!(Interval::new(1.0, 2.0).intersection(&Interval::new(3.0, 4.0)).is_empty());
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        }
+    }
+
+    /// Whether the interval contains no value, i.e. ``min > max``.
+    pub fn is_empty(&self) -> bool {
+        self.min > self.max
+    }
+
+    /// Returns the value halfway between both bounds.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::ray::Interval;
+    /// assert_eq!(Interval::new(1.0, 2.0).midpoint(), 1.5);
+    /// ```
+    pub fn midpoint(&self) -> Float {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn contains(&self, t: Float) -> bool {
         self.min <= t && t <= self.max
     }
 
-    pub fn surrounds(&self, t: f64) -> bool {
+    pub fn surrounds(&self, t: Float) -> bool {
         self.min < t && t < self.max
     }
 
@@ -137,7 +280,7 @@ impl Interval {
     /// * `t` - The value to clamp.
     /// # Returns
     /// The clamped value.
-    pub fn clamp(&self, t: f64) -> f64 {
+    pub fn clamp(&self, t: Float) -> Float {
         if t < self.min { self.min } else if t > self.max { self.max } else { t }
     }
 
@@ -148,7 +291,7 @@ impl Interval {
     /// let interval = Interval { min: 1.0, max: 2.0 };
     /// assert_eq!(interval.size(), 1.0);
     /// ```
-    pub fn size(&self) -> f64 {
+    pub fn size(&self) -> Float {
         self.max - self.min
     }
 
@@ -165,20 +308,67 @@ impl Interval {
     /// assert_eq!(result.min, 0.75);
     /// assert_eq!(result.max, 2.25);
     /// ```
-    pub fn expand(&self, t: f64) -> Interval {
+    pub fn expand(&self, t: Float) -> Interval {
         let delta = t * 0.5;
         Interval { min: self.min - delta, max: self.max + delta }
     }
 
-    pub const EMPTY: Interval = Interval { min: f64::INFINITY, max: f64::NEG_INFINITY };
+    pub const EMPTY: Interval = Interval { min: Float::INFINITY, max: Float::NEG_INFINITY };
+
+    pub const UNIVERSE: Interval = Interval { min: Float::NEG_INFINITY, max: Float::INFINITY };
+}
+
+/// Conversion from a ``min..=max`` range.
+/// # Examples
+/// ```
+/// use ray_tracing::ray::Interval;
+/// assert_eq!(Interval::from(1.0..=2.0), Interval::new(1.0, 2.0));
+/// ```
+impl From<RangeInclusive<Float>> for Interval {
+    fn from(range: RangeInclusive<Float>) -> Self {
+        let (min, max) = range.into_inner();
+        Self { min, max }
+    }
+}
+
+
+/// Conversion from a ``(min, max)`` pair.
+impl From<(Float, Float)> for Interval {
+    fn from((min, max): (Float, Float)) -> Self {
+        Self { min, max }
+    }
+}
+
 
-    pub const UNIVERSE: Interval = Interval { min: f64::NEG_INFINITY, max: f64::INFINITY };
+/// Collects the smallest interval covering every value, or ``Interval::EMPTY`` if there is none.
+/// # Examples
+/// ```
+/// use ray_tracing::ray::Interval;
+/// let interval: Interval = [3.0, -1.0, 2.0].into_iter().collect();
+/// assert_eq!(interval, Interval::new(-1.0, 3.0));
+/// ```
+impl FromIterator<Float> for Interval {
+    fn from_iter<I: IntoIterator<Item = Float>>(iter: I) -> Self {
+        iter.into_iter().fold(Interval::EMPTY, |acc, t| Self {
+            min: acc.min.min(t),
+            max: acc.max.max(t),
+        })
+    }
 }
 
-impl Add<f64> for Interval {
+
+/// Collects the union of all intervals, or ``Interval::EMPTY`` if there is none.
+impl FromIterator<Interval> for Interval {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        iter.into_iter().fold(Interval::EMPTY, |acc, other| acc.union(&other))
+    }
+}
+
+
+impl Add<Float> for Interval {
     type Output = Self;
 
-    fn add(self, t: f64) -> Self {
+    fn add(self, t: Float) -> Self {
         Self { min: self.min + t, max: self.max + t }
     }
 }
@@ -202,10 +392,10 @@ impl Add<&Interval> for Interval {
 }
 
 
-impl Sub<f64> for Interval {
+impl Sub<Float> for Interval {
     type Output = Self;
 
-    fn sub(self, t: f64) -> Self {
+    fn sub(self, t: Float) -> Self {
         Self { min: self.min - t, max: self.max - t }
     }
 }
@@ -270,14 +460,14 @@ mod test_interval {
 
     #[test]
     fn test_interval_empty() {
-        assert_eq!(Interval::EMPTY.min, f64::INFINITY);
-        assert_eq!(Interval::EMPTY.max, f64::NEG_INFINITY);
+        assert_eq!(Interval::EMPTY.min, Float::INFINITY);
+        assert_eq!(Interval::EMPTY.max, Float::NEG_INFINITY);
     }
 
     #[test]
     fn test_interval_universe() {
-        assert_eq!(Interval::UNIVERSE.min, f64::NEG_INFINITY);
-        assert_eq!(Interval::UNIVERSE.max, f64::INFINITY);
+        assert_eq!(Interval::UNIVERSE.min, Float::NEG_INFINITY);
+        assert_eq!(Interval::UNIVERSE.max, Float::INFINITY);
     }
 
     #[test]
@@ -296,6 +486,33 @@ mod test_interval {
         assert_eq!(result.max, 3.4);
     }
 
+    #[test]
+    fn test_interval_intersection() {
+        let interval_1 = Interval::new(1.0, 3.0);
+        let interval_2 = Interval::new(2.0, 4.0);
+        assert_eq!(interval_1.intersection(&interval_2), Interval::new(2.0, 3.0));
+        assert_eq!(interval_1.intersection(&Interval::UNIVERSE), interval_1);
+        assert!(interval_1.intersection(&Interval::EMPTY).is_empty());
+    }
+
+    #[test]
+    fn test_interval_is_empty() {
+        assert!(Interval::EMPTY.is_empty());
+        assert!(!Interval::UNIVERSE.is_empty());
+        assert!(!Interval::new(1.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_interval_from_iter() {
+        let empty: Interval = std::iter::empty::<Float>().collect();
+        assert_eq!(empty, Interval::EMPTY);
+
+        let intervals = [Interval::new(1.0, 2.0), Interval::new(-1.0, 0.0)];
+        let union: Interval = intervals.into_iter().collect();
+        assert_eq!(union, Interval::new(-1.0, 2.0));
+        assert_eq!(Interval::from((0.5, 1.5)), Interval::new(0.5, 1.5));
+    }
+
     #[test]
     fn test_interval_add() {
         let interval = Interval { min: 1.0, max: 2.0 };
@@ -0,0 +1,176 @@
+//! Recorded light paths for debugging light transport, see ``Camera::record_paths``.
+//!
+//! Every path is kept as the points it bounced at, from the camera to where it ended, and
+//! exported as a set of lines to look at in any 3D viewer next to the scene: ``to_obj`` writes
+//! polylines, ``to_ply`` edges colored by how each path ended.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::raylog::{RayLog, RecordedPath, Termination};
+//! use ray_tracing::vec3d::{Point3d, Vec3d};
+//! let bounce = RecordedPath {
+//!     pixel: (0, 0),
+//!     vertices: vec![Point3d::zero(), Point3d::new(0.0, 0.0, -2.0)],
+//!     termination: Termination::Escaped(Vec3d::new(0.0, 1.0, 0.0)),
+//! };
+//! let log = RayLog::new(vec![bounce]).with_escape_length(0.5);
+//! assert_eq!(log.to_obj(), "v 0 0 0\nv 0 0 -2\nv 0 0.5 -2\nl 1 2 3\n");
+//! ```
+use crate::error::{Error, Result};
+use crate::vec3d::{Point3d, Vec3d, Float};
+
+use std::fmt::Write;
+
+
+/// Why a recorded path ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Termination {
+    /// Left the scene along the unit direction, lit by the background.
+    Escaped(Vec3d),
+    /// Stopped on a surface not scattering it, e.g. a light, a black back face or a holdout,
+    /// or by a material absorbing it.
+    Absorbed,
+    /// Ran out of bounces, see ``Camera::set_depth``.
+    MaxDepth,
+}
+
+
+impl Termination {
+    /// Color of the paths ending this way in ``RayLog::to_ply``: blue for escaped, red for
+    /// absorbed and yellow for exhausted paths.
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            Termination::Escaped(_) => [60, 120, 255],
+            Termination::Absorbed => [255, 60, 60],
+            Termination::MaxDepth => [255, 220, 40],
+        }
+    }
+}
+
+
+/// A light path traced from the camera, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPath {
+    /// Column and row of the pixel the path was traced for.
+    pub pixel: (usize, usize),
+    /// Points the path went through, starting at the camera, including every hit.
+    pub vertices: Vec<Point3d>,
+    pub termination: Termination,
+}
+
+
+/// Paths recorded by ``Camera::record_paths``, to export as lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayLog {
+    paths: Vec<RecordedPath>,
+    escape_length: Float,
+}
+
+
+impl RayLog {
+    pub fn new(paths: Vec<RecordedPath>) -> Self {
+        Self { paths, escape_length: 1.0 }
+    }
+
+    /// Sets how long the last segment of escaped paths is drawn, ``1.0`` by default.
+    pub fn with_escape_length(mut self, escape_length: Float) -> Self {
+        self.escape_length = escape_length;
+        self
+    }
+
+    pub fn paths(&self) -> &[RecordedPath] {
+        &self.paths
+    }
+
+    /// The points to draw of every path, ending escaped paths ``escape_length`` away from
+    /// their last point.
+    fn polylines(&self) -> impl Iterator<Item = (Vec<Point3d>, Termination)> + '_ {
+        self.paths.iter().map(|path| {
+            let mut points = path.vertices.clone();
+            if let (Termination::Escaped(direction), Some(&last)) = (path.termination, points.last()) {
+                points.push(last + direction * self.escape_length);
+            }
+            (points, path.termination)
+        })
+    }
+
+    /// The paths as a Wavefront OBJ file of one polyline each.
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        let mut lines = String::new();
+        let mut count = 0;
+        for (points, _) in self.polylines() {
+            if points.len() < 2 { continue; }
+            for point in &points {
+                writeln!(obj, "v {} {} {}", point.x(), point.y(), point.z()).unwrap();
+            }
+            let indices: Vec<String> = (count + 1..=count + points.len()).map(|i| i.to_string()).collect();
+            writeln!(lines, "l {}", indices.join(" ")).unwrap();
+            count += points.len();
+        }
+        obj + &lines
+    }
+
+    /// The paths as an ASCII PLY file of edges, colored by ``Termination::color``.
+    pub fn to_ply(&self) -> String {
+        let polylines: Vec<_> = self.polylines().filter(|(points, _)| points.len() >= 2).collect();
+        let vertex_count: usize = polylines.iter().map(|(points, _)| points.len()).sum();
+        let edge_count = vertex_count - polylines.len();
+
+        let mut ply = String::new();
+        writeln!(ply, "ply\nformat ascii 1.0").unwrap();
+        writeln!(ply, "element vertex {}\nproperty float x\nproperty float y\nproperty float z", vertex_count).unwrap();
+        writeln!(ply, "element edge {}\nproperty int vertex1\nproperty int vertex2", edge_count).unwrap();
+        writeln!(ply, "property uchar red\nproperty uchar green\nproperty uchar blue\nend_header").unwrap();
+        for (points, _) in &polylines {
+            for point in points {
+                writeln!(ply, "{} {} {}", point.x(), point.y(), point.z()).unwrap();
+            }
+        }
+        let mut first = 0;
+        for (points, termination) in &polylines {
+            let [r, g, b] = termination.color();
+            for i in first..first + points.len() - 1 {
+                writeln!(ply, "{} {} {} {} {}", i, i + 1, r, g, b).unwrap();
+            }
+            first += points.len();
+        }
+        ply
+    }
+
+    /// Writes ``to_obj`` to ``path``.
+    pub fn write_obj(&self, path: &str) -> Result<()> {
+        std::fs::write(path, self.to_obj()).map_err(|source| Error::Io { file: path.to_string(), source })
+    }
+
+    /// Writes ``to_ply`` to ``path``.
+    pub fn write_ply(&self, path: &str) -> Result<()> {
+        std::fs::write(path, self.to_ply()).map_err(|source| Error::Io { file: path.to_string(), source })
+    }
+}
+
+
+#[cfg(test)]
+mod test_raylog {
+    use super::*;
+
+    #[test]
+    fn test_ply_edges_per_path() {
+        let log = RayLog::new(vec![
+            RecordedPath {
+                pixel: (0, 0),
+                vertices: vec![Point3d::zero(), Point3d::new(1.0, 0.0, 0.0), Point3d::new(1.0, 1.0, 0.0)],
+                termination: Termination::MaxDepth,
+            },
+            // Paths without a segment are left out.
+            RecordedPath { pixel: (1, 0), vertices: vec![Point3d::zero()], termination: Termination::Absorbed },
+            RecordedPath { pixel: (2, 0), vertices: vec![Point3d::zero(), Point3d::new(0.0, 0.0, 1.0)], termination: Termination::Absorbed },
+        ]);
+        let ply = log.to_ply();
+        assert!(ply.contains("element vertex 5\n"));
+        assert!(ply.contains("element edge 3\n"));
+        let edges: Vec<&str> = ply.lines().rev().take(3).collect();
+        assert_eq!(edges, ["3 4 255 60 60", "1 2 255 220 40", "0 1 255 220 40"]);
+        assert_eq!(log.to_obj().lines().filter(|line| line.starts_with("l ")).count(), 2);
+    }
+}
@@ -0,0 +1,348 @@
+//! Random numbers of a render, drawn from a separate stream for every sample of every pixel.
+//!
+//! Each stream is a ``Pcg32`` derived only from the seed of the render, the index of the pixel
+//! and the index of the sample, never from what was drawn before. A pixel therefore gets the
+//! same samples however the image is split into tiles, in which order the tiles are rendered,
+//! on which thread or machine, and whether the render was resumed from a checkpoint.
+//!
+//! Code drawing random numbers while tracing uses ``rng`` or ``random``, which take from the
+//! stream entered with ``with_stream`` on the current thread, and from ``rand::rng`` outside
+//! of one, e.g. while building a scene.
+//!
+//! The positions of the samples within a pixel may instead follow a Sobol sequence, see
+//! ``PixelSampler``, spreading them out more evenly than independent random numbers do.
+//! Every pixel gets its own Owen scrambling of the sequence, which keeps that evenness while
+//! breaking up the patterns an unscrambled sequence repeats from pixel to pixel.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::sampler::{self, Pcg32};
+//! use ray_tracing::vec3d::Float;
+//! let mut stream = Pcg32::for_sample(7, 1234, 0);
+//! let first: Float = sampler::with_stream(&mut stream, || sampler::random());
+//! let mut again = Pcg32::for_sample(7, 1234, 0);
+//! assert_eq!(sampler::with_stream(&mut again, || sampler::random::<Float>()), first);
+//! ```
+use rand::distr::{Distribution, StandardUniform};
+use rand::{Rng, RngCore};
+
+use crate::vec3d::Float;
+
+use std::cell::Cell;
+
+
+/// The PCG32 generator by O'Neill, with 64 bits of state, a selectable stream and 32 bits of
+/// output per step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+
+impl Pcg32 {
+    /// Starts the generator at ``state`` on stream ``stream``, like ``pcg32_srandom``.
+    pub fn new(state: u64, stream: u64) -> Self {
+        let mut pcg = Self { state: 0, increment: (stream << 1) | 1 };
+        pcg.step();
+        pcg.state = pcg.state.wrapping_add(state);
+        pcg.step();
+        pcg
+    }
+
+    /// The generator of sample ``sample`` of the pixel with index ``pixel`` in a render seeded
+    /// with ``seed``. Neighboring pixels and samples get unrelated streams.
+    pub fn for_sample(seed: u64, pixel: u64, sample: u64) -> Self {
+        let key = mix(seed ^ mix(pixel ^ mix(sample)));
+        Self::new(key, mix(key ^ sample.rotate_left(32) ^ pixel))
+    }
+
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.increment);
+    }
+}
+
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (u64::from(self.next_u32()) << 32) | u64::from(self.next_u32())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            chunk.copy_from_slice(&self.next_u32().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+
+/// The finalizer of SplitMix64, scrambling the bits of ``x`` so close inputs end up far apart.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+
+/// How the positions of the samples within each pixel are chosen, set with
+/// ``Camera::set_pixel_sampler``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelSampler {
+    /// Independently at random from the stream of each sample.
+    #[default]
+    Random,
+    /// From ``sobol_2d``, stratified over the samples of the pixel. Converges faster on edges
+    /// and soft shadows, most of all at powers of two samples per pixel.
+    Sobol,
+}
+
+
+/// Dimensions ``sobol`` provides.
+pub const SOBOL_DIMENSIONS: usize = 2;
+
+
+/// Point ``index`` of the Sobol sequence in ``dimension``, as a fixed point fraction of
+/// ``2^32``. The first two dimensions are the van der Corput sequence and the one of the
+/// polynomial ``x + 1``, which together make every ``2^m`` consecutive points from a multiple
+/// of ``2^m`` a (0, m, 2)-net: one point in every box of area ``2^-m`` the unit square splits
+/// into along powers of two.
+/// # Panics
+/// If ``dimension`` is not below ``SOBOL_DIMENSIONS``.
+pub fn sobol(index: u32, dimension: usize) -> u32 {
+    assert!(dimension < SOBOL_DIMENSIONS, "The Sobol sequence only has {} dimensions", SOBOL_DIMENSIONS);
+    if dimension == 0 {
+        return index.reverse_bits();
+    }
+    let mut direction = 1 << 31;
+    let mut value = 0;
+    let mut index = index;
+    while index != 0 {
+        if index & 1 == 1 {
+            value ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+    value
+}
+
+
+/// The nested uniform, or Owen, scrambling of the fixed point fraction ``x`` by ``seed``:
+/// every bit is flipped or not depending on the seed and on all the bits above it only. Points
+/// in the same box of a power of two grid stay together, so nets stay nets.
+///
+/// Hashes in place of a random flip per node of the tree of bits, after "Practical Hash-based
+/// Owen Scrambling" by Burley.
+pub fn owen_scramble(x: u32, seed: u32) -> u32 {
+    // Reversed, the bits above become the ones below, which multiplication only carries from.
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
+
+
+/// Point ``index`` of the first two dimensions of the Sobol sequence, Owen scrambled for the
+/// pixel with index ``pixel`` in a render seeded with ``seed``, within ``[0, 1)``.
+///
+/// The order of the points is scrambled too, so the first ``2^m`` points of every pixel are a
+/// (0, m, 2)-net but different ones, however many samples are taken.
+/// # Examples
+/// ```
+/// use ray_tracing::sampler::sobol_2d;
+/// let points: Vec<_> = (0..4).map(|index| sobol_2d(1, 42, index)).collect();
+/// // One point in every quarter of the pixel.
+/// for (x, y) in [(0.0, 0.0), (0.5, 0.0), (0.0, 0.5), (0.5, 0.5)] {
+///     assert_eq!(points.iter().filter(|p| p.0 >= x && p.0 < x + 0.5 && p.1 >= y && p.1 < y + 0.5).count(), 1);
+/// }
+/// ```
+pub fn sobol_2d(seed: u64, pixel: u64, index: u32) -> (Float, Float) {
+    let key = mix(seed ^ mix(pixel));
+    let index = owen_scramble(index, key as u32);
+    let x = owen_scramble(sobol(index, 0), (key >> 32) as u32);
+    let y = owen_scramble(sobol(index, 1), mix(key) as u32);
+    (unit_fraction(x), unit_fraction(y))
+}
+
+
+/// The fixed point fraction ``x`` of ``2^32`` as a float below ``1`` in any precision.
+fn unit_fraction(x: u32) -> Float {
+    (x >> 8) as Float / (1 << 24) as Float
+}
+
+
+thread_local! {
+    static STREAM: Cell<Option<Pcg32>> = const { Cell::new(None) };
+}
+
+
+/// Runs ``f`` with ``rng`` and ``random`` drawing from ``stream`` on this thread, leaving
+/// ``stream`` where ``f`` stopped so the next call carries on from there.
+pub fn with_stream<T>(stream: &mut Pcg32, f: impl FnOnce() -> T) -> T {
+    let outer = STREAM.with(|current| current.replace(Some(*stream)));
+    let result = f();
+    *stream = STREAM.with(|current| current.replace(outer)).unwrap();
+    result
+}
+
+
+/// Handle to the random numbers of the current thread, see ``rng``.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SampleRng;
+
+
+impl SampleRng {
+    fn draw<T>(f: impl Fn(&mut dyn RngCore) -> T) -> T {
+        STREAM.with(|current| match current.get() {
+            Some(mut stream) => {
+                let value = f(&mut stream);
+                current.set(Some(stream));
+                value
+            }
+            None => f(&mut rand::rng()),
+        })
+    }
+}
+
+
+impl RngCore for SampleRng {
+    fn next_u32(&mut self) -> u32 {
+        Self::draw(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Self::draw(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        STREAM.with(|current| match current.get() {
+            Some(mut stream) => {
+                stream.fill_bytes(dest);
+                current.set(Some(stream));
+            }
+            None => rand::rng().fill_bytes(dest),
+        })
+    }
+}
+
+
+/// The random numbers of the current sample while rendering, a ``rand::rng`` otherwise.
+pub fn rng() -> SampleRng {
+    SampleRng
+}
+
+
+/// A random value from ``rng``, like ``rand::random``.
+pub fn random<T>() -> T
+where
+    StandardUniform: Distribution<T>,
+{
+    rng().random()
+}
+
+
+#[cfg(test)]
+mod test_sampler {
+    use super::*;
+
+    #[test]
+    fn test_pcg32_reference_output() {
+        // First outputs of the reference implementation's demo, seeded with 42 on stream 54.
+        let mut pcg = Pcg32::new(42, 54);
+        let outputs: Vec<u32> = (0..6).map(|_| pcg.next_u32()).collect();
+        assert_eq!(outputs, vec![0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]);
+    }
+
+    /// Whether ``points`` are a (0, m, 2)-net: one point in every box of every shape with an
+    /// area of ``1 / points.len()``, for a power of two number of points.
+    fn is_net(points: &[(Float, Float)]) -> bool {
+        let m = points.len().trailing_zeros();
+        (0..=m).all(|a| {
+            let (columns, rows) = (1 << a, 1 << (m - a));
+            let mut counts = vec![0; points.len()];
+            for &(x, y) in points {
+                counts[(y * rows as Float) as usize * columns + (x * columns as Float) as usize] += 1;
+            }
+            counts.iter().all(|&count| count == 1)
+        })
+    }
+
+    #[test]
+    fn test_sobol_stratification() {
+        let unscrambled: Vec<(Float, Float)> = (0..8)
+            .map(|index| (unit_fraction(sobol(index, 0)), unit_fraction(sobol(index, 1))))
+            .collect();
+        assert_eq!(&unscrambled[..4], &[(0.0, 0.0), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25)]);
+
+        for pixel in 0..16 {
+            let points: Vec<_> = (0..256).map(|index| sobol_2d(3, pixel, index)).collect();
+            // Every prefix of a power of two points is a net, and so is every aligned block.
+            for m in 0..=8 {
+                assert!(is_net(&points[..1 << m]), "pixel {} with {} points", pixel, 1 << m);
+            }
+            assert!(points[64..128].len() == 64 && is_net(&points[64..128]));
+            assert!(points.iter().all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)));
+        }
+        // Pixels and seeds scramble differently.
+        assert_ne!(sobol_2d(3, 0, 0), sobol_2d(3, 1, 0));
+        assert_ne!(sobol_2d(3, 0, 0), sobol_2d(4, 0, 0));
+    }
+
+    #[test]
+    fn test_owen_scramble_keeps_the_bits_above() {
+        // Values sharing their top bits still share them, scrambled.
+        for seed in [0, 1, 0xdeadbeef] {
+            let scrambled: Vec<u32> = (0..256).map(|low| owen_scramble(0xabcd_1200 | low, seed)).collect();
+            assert!(scrambled.iter().all(|&x| x >> 8 == scrambled[0] >> 8));
+            // And the bits below are permuted.
+            let mut low: Vec<u32> = scrambled.iter().map(|&x| x & 0xff).collect();
+            low.sort();
+            assert_eq!(low, (0..256).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_streams_depend_only_on_their_key() {
+        let draw = |seed, pixel, sample| {
+            let mut stream = Pcg32::for_sample(seed, pixel, sample);
+            with_stream(&mut stream, || (0..4).map(|_| rng().next_u64()).collect::<Vec<_>>())
+        };
+        assert_eq!(draw(1, 10, 3), draw(1, 10, 3));
+        assert_ne!(draw(1, 10, 3), draw(1, 10, 4));
+        assert_ne!(draw(1, 10, 3), draw(1, 11, 3));
+        assert_ne!(draw(1, 10, 3), draw(2, 10, 3));
+    }
+
+    #[test]
+    fn test_with_stream_carries_on_and_nests() {
+        let mut stream = Pcg32::for_sample(0, 0, 0);
+        let mut reference = stream;
+        let expected: Vec<u32> = (0..3).map(|_| reference.next_u32()).collect();
+
+        let first = with_stream(&mut stream, || rng().next_u32());
+        let mut other = Pcg32::new(5, 5);
+        let rest = with_stream(&mut stream, || {
+            let second = rng().next_u32();
+            // A nested stream leaves the outer one untouched.
+            with_stream(&mut other, || rng().next_u32());
+            vec![second, rng().next_u32()]
+        });
+        assert_eq!(vec![first, rest[0], rest[1]], expected);
+        assert_eq!(stream, reference);
+        assert!(STREAM.with(|current| current.get()).is_none());
+    }
+}
@@ -1,87 +1,450 @@
 #[forbid(unsafe_code)]
 
 use std::sync::Arc;
-use crate::object::{BVHNode, HittableVec, Sphere, Quad, bbox, Hittable, Translate, RotateY, Medium};
-use crate::object::material::{Dielectric, Lambertian, Material, Metal, Light};
+use crate::object::{Animated, BVHNode, HitRecord, HittableVec, Sphere, Quad, bbox, Hittable, Translate, RotateY, Transform, Medium, Curve, CurveShape};
+use crate::object::material::{Dielectric, Lambertian, Material, Metal, Light, Hair};
 use crate::object::texture::{Texture, Checker, ImageTexture, PerlinTexture, SolidColor};
-use crate::vec3d::{Vec3d, Color, Point3d};
+use crate::vec3d::{Vec3d, Point3d, Float};
+use crate::color::Color;
 use rand::Rng;
 use crate::camera::Camera;
+use crate::trace;
+use crate::ray::{Interval, Ray};
+use crate::sampler::Pcg32;
+use crate::object::{AABB, Emitter, TransformedEmitter, Mandelbulb, MengerSponge, Sdf};
+use crate::error::{Error, Result};
+
+
+/// Result of a ``Scene::raycast`` query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    /// Name of the hit object, see ``object::Named``.
+    pub name: Option<String>,
+    /// Distance from the ray origin to the hit point.
+    pub distance: Float,
+    pub point: Point3d,
+    /// Unit surface normal, facing against the ray.
+    pub normal: Vec3d,
+    /// Whether the ray hit the outside of the surface.
+    pub front_face: bool,
+}
+
+
+/// A renderable world, wrapping the BVH built over its objects.
+///
+/// Besides rendering, a scene answers ray queries against the same BVH, which lets
+/// applications embedding the renderer implement mouse picking.
+///
+/// An ``animated`` scene is rebuilt for every ``set_time``, so each frame of an animation is a
+/// snapshot at any time of its sequence: the builder places moving spheres, transforms and
+/// time-varying textures like ``SimplexTexture::with_time`` for the frame. Motion within the
+/// frame, such as motion blur, still follows ``Ray::time`` over the shutter from ``0`` to ``1``.
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use ray_tracing::object::{Named, Sphere, HittableVec, BVHNode};
+/// use ray_tracing::object::material::{Material, Empty};
+/// use ray_tracing::scene::Scene;
+/// use ray_tracing::vec3d::{Point3d, Vec3d};
+/// let mut world = HittableVec::new();
+/// let sphere = Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}));
+/// world.add(Arc::new(Named::new(Arc::new(sphere), "ball")));
+/// let scene = Scene::new(BVHNode::from_hittable_vec(Arc::new(world)));
+///
+/// let hit = scene.raycast(Point3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -2.0)).unwrap();
+/// assert_eq!(hit.name.as_deref(), Some("ball"));
+/// assert_eq!(hit.distance, 4.0);
+/// assert_eq!(hit.normal, Vec3d::new(0.0, 0.0, 1.0));
+/// ```
+pub struct Scene {
+    world: Arc<BVHNode>,
+    time: Float,
+    origin: WorldPoint,
+    build: Option<Arc<SceneBuilder>>,
+    /// The lights moved to ``origin``.
+    lights: Vec<Arc<dyn Emitter>>,
+    /// The lights as they were added, with the origin they were added at.
+    added_lights: Vec<(Arc<dyn Emitter>, WorldPoint)>,
+}
+
+
+/// Builds the world of a scene around an origin at a time, see ``Scene::relative``.
+type SceneBuilder = dyn Fn(WorldPoint, Float) -> Arc<BVHNode> + Send + Sync;
+
+
+/// A position in double precision whatever the precision of ``Float``, for worlds too large
+/// to hold in it, see ``Scene::relative``.
+pub type WorldPoint = [f64; 3];
+
+
+/// ``point`` relative to ``origin``, subtracted in double precision before it is rounded to
+/// ``Float``, so positions near the origin keep their precision however far out both are.
+#[allow(clippy::unnecessary_cast)]
+pub fn relative_to(point: WorldPoint, origin: WorldPoint) -> Point3d {
+    let [x, y, z] = [0, 1, 2].map(|axis| (point[axis] - origin[axis]) as Float);
+    Point3d::new(x, y, z)
+}
+
+
+/// Row-major matrix moving by ``offset``, see ``Transform::new``.
+fn translation(offset: Vec3d) -> [[Float; 4]; 4] {
+    [
+        [1.0, 0.0, 0.0, offset.x()],
+        [0.0, 1.0, 0.0, offset.y()],
+        [0.0, 0.0, 1.0, offset.z()],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+
+/// ``object`` built around ``built_at`` moved to be around ``origin`` instead, keeping its
+/// precision.
+fn moved(object: Arc<dyn Hittable>, built_at: WorldPoint, origin: WorldPoint) -> Arc<dyn Hittable> {
+    if built_at == origin { return object; }
+    Arc::new(Translate::new(object, relative_to(built_at, origin)))
+}
+
+
+/// ``light`` added around ``built_at`` moved to be around ``origin`` instead, like ``moved``.
+fn moved_light(light: &Arc<dyn Emitter>, built_at: WorldPoint, origin: WorldPoint) -> Arc<dyn Emitter> {
+    if built_at == origin { return light.clone(); }
+    Arc::new(TransformedEmitter::new(light.clone(), translation(relative_to(built_at, origin))))
+}
+
+
+impl Scene {
+    pub fn new(world: BVHNode) -> Self {
+        Self { world: Arc::new(world), time: 0.0, origin: [0.0; 3], build: None, lights: Vec::new(), added_lights: Vec::new() }
+    }
+
+    /// Creates a scene whose world ``build`` creates for the time of the frame, starting at
+    /// time ``0.0``.
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::scene::Scene;
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// // A ball rolling along x at one unit per second.
+    /// let mut scene = Scene::animated(|time| {
+    ///     let mut world = HittableVec::new();
+    ///     let ball = Sphere::static_sphere(Point3d::new(time, 0.0, 0.0), 0.5, Material::Empty(Empty {}));
+    ///     world.add(Arc::new(ball));
+    ///     BVHNode::from_hittable_vec(Arc::new(world))
+    /// });
+    /// let looking_down = |scene: &Scene, x| scene.raycast(Point3d::new(x, 5.0, 0.0), Vec3d::new(0.0, -1.0, 0.0));
+    /// assert!(looking_down(&scene, 0.0).is_some());
+    /// scene.set_time(3.0);
+    /// assert!(looking_down(&scene, 0.0).is_none());
+    /// assert!(looking_down(&scene, 3.0).is_some());
+    /// ```
+    pub fn animated(build: impl Fn(Float) -> BVHNode + Send + Sync + 'static) -> Self {
+        let world = Arc::new(build(0.0));
+        let build = move |origin, time| {
+            if origin == [0.0; 3] { return Arc::new(build(time)); }
+            let mut objects = HittableVec::new();
+            objects.add(moved(Arc::new(build(time)), [0.0; 3], origin));
+            Arc::new(BVHNode::from_hittable_vec(Arc::new(objects)))
+        };
+        Self { world, time: 0.0, origin: [0.0; 3], build: Some(Arc::new(build)), lights: Vec::new(), added_lights: Vec::new() }
+    }
+
+    /// Creates a scene whose world ``build`` creates around an origin for the time of the
+    /// frame, placing every object ``relative_to`` the origin, starting out at ``[0.0; 3]``
+    /// and time ``0.0``.
+    ///
+    /// Far from the origin, the spacing of ``Float`` grows coarse enough for hit points to
+    /// snap to a visible grid: rays leak through seams and leave surfaces at the wrong spot,
+    /// long before ``Tolerance::scaled`` can make up for it. Worlds built relative to their
+    /// origin can be moved to the camera with ``center_on`` instead, where the intersection
+    /// math only ever sees the small coordinates of nearby objects.
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::scene::{relative_to, Scene};
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// // A ball on the surface of the earth, seen from a metre above it.
+    /// let mut scene = Scene::relative(|origin, _| {
+    ///     let mut world = HittableVec::new();
+    ///     let ball = Sphere::static_sphere(relative_to([6.371e6, 0.0, 0.0], origin), 0.5, Material::Empty(Empty {}));
+    ///     world.add(Arc::new(ball));
+    ///     BVHNode::from_hittable_vec(Arc::new(world))
+    /// });
+    /// scene.set_origin([6.371e6, 1.0, 0.0]);
+    /// let hit = scene.raycast(Point3d::zero(), Vec3d::new(0.0, -1.0, 0.0)).unwrap();
+    /// assert_eq!(hit.point, Point3d::new(0.0, -0.5, 0.0));
+    /// ```
+    pub fn relative(build: impl Fn(WorldPoint, Float) -> BVHNode + Send + Sync + 'static) -> Self {
+        let world = Arc::new(build([0.0; 3], 0.0));
+        let build = move |origin, time| Arc::new(build(origin, time));
+        Self { world, time: 0.0, origin: [0.0; 3], build: Some(Arc::new(build)), lights: Vec::new(), added_lights: Vec::new() }
+    }
+
+    pub fn time(&self) -> Float {
+        self.time
+    }
+
+    /// Moves the scene to ``time``, rebuilding the world of an ``animated`` scene for it.
+    /// Static scenes look the same at any time.
+    pub fn set_time(&mut self, time: Float) {
+        self.time = time;
+        if let Some(build) = &self.build {
+            self.world = build(self.origin, time);
+        }
+    }
+
+    /// The point of the world at the coordinates ``0.0``, see ``set_origin``.
+    pub fn origin(&self) -> WorldPoint {
+        self.origin
+    }
+
+    /// Moves the coordinates of the scene to be ``relative_to`` the world point ``origin``,
+    /// including its lights.
+    ///
+    /// Scenes built ``relative`` are rebuilt around it, which keeps the full precision of
+    /// their objects near the origin. Other scenes are only moved there, with the precision
+    /// they were built with. Lights are moved from where they were added each time, so
+    /// following a camera around does not pile up translations.
+    pub fn set_origin(&mut self, origin: WorldPoint) {
+        self.lights = self.added_lights.iter()
+            .map(|(light, built_at)| moved_light(light, *built_at, origin))
+            .collect();
+        let build = self.build.get_or_insert_with(|| {
+            let (world, built_at) = (self.world.clone(), self.origin);
+            Arc::new(move |origin, _| {
+                let mut objects = HittableVec::new();
+                objects.add(moved(world.clone(), built_at, origin));
+                Arc::new(BVHNode::from_hittable_vec(Arc::new(objects)))
+            })
+        });
+        self.world = build(origin, self.time);
+        self.origin = origin;
+    }
+
+    /// Renders the scene camera-relative: moves its origin to where ``camera`` looks from
+    /// and the camera along, to look from ``0.0``, see ``relative``. Lights handed to the
+    /// camera before are not moved, so add the scene's ``lights`` afterwards.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::camera::Camera;
+    /// use ray_tracing::object::{BVHNode, HittableVec};
+    /// use ray_tracing::scene::Scene;
+    /// use ray_tracing::vec3d::Point3d;
+    /// let mut scene = Scene::relative(|_, _| BVHNode::from_hittable_vec(std::sync::Arc::new(HittableVec::new())));
+    /// scene.set_origin([1e9, 0.0, 0.0]);
+    /// let mut camera = Camera::new();
+    /// camera.set_look_from(Point3d::new(2.0, 3.0, 0.0));
+    /// camera.set_look_at(Point3d::new(2.0, 3.0, -1.0));
+    /// scene.center_on(&mut camera);
+    /// assert_eq!(scene.origin(), [1e9 + 2.0, 3.0, 0.0]);
+    /// assert_eq!(camera.look_from(), Point3d::zero());
+    /// assert_eq!(camera.look_at(), Point3d::new(0.0, 0.0, -1.0));
+    /// ```
+    #[allow(clippy::unnecessary_cast)]
+    pub fn center_on(&mut self, camera: &mut Camera) {
+        let eye = camera.look_from();
+        let origin = [0, 1, 2].map(|axis| self.origin[axis] + eye[axis] as f64);
+        self.set_origin(origin);
+        camera.set_look_at(camera.look_at() - eye);
+        camera.set_look_from(Point3d::zero());
+    }
+
+    pub fn world(&self) -> &BVHNode {
+        &self.world
+    }
+
+    /// The box around all objects of the world, e.g. to place the camera with
+    /// ``Camera::frame``.
+    pub fn bounds(&self) -> AABB {
+        self.world.bounding_box()
+    }
+
+    /// Lights of the world to sample directly, handed to ``Camera::add_light`` for rendering.
+    /// Scenes only keep them to carry them along in ``merge``.
+    pub fn lights(&self) -> &[Arc<dyn Emitter>] {
+        &self.lights
+    }
+
+    pub fn add_light(&mut self, light: Arc<dyn Emitter>) {
+        self.added_lights.push((light.clone(), self.origin));
+        self.lights.push(light);
+    }
+
+    /// Adds the objects and lights of ``other`` to this scene, moved by a row-major
+    /// ``transform`` like ``Transform::new``, to assemble scenes from reusable pieces.
+    ///
+    /// ``other`` is shared, not copied, so inserting a piece many times costs little memory.
+    /// An animated ``other`` is added as it is at its current time, while an animated scene
+    /// keeps its merged pieces through ``set_time``. Lights must only be rotated, moved and
+    /// scaled uniformly, see ``TransformedEmitter``.
+    /// # Panics
+    /// If the transform is not invertible, or ``other`` has lights and the transform does more
+    /// than rotate, move and scale uniformly, see ``try_merge`` for a fallible version.
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use ray_tracing::object::{BVHNode, HittableVec, Named, Sphere};
+    /// use ray_tracing::object::material::{Material, Empty};
+    /// use ray_tracing::scene::Scene;
+    /// use ray_tracing::vec3d::{Point3d, Vec3d};
+    /// let mut stool = HittableVec::new();
+    /// let seat = Sphere::static_sphere(Point3d::new(0.0, 1.0, 0.0), 0.5, Material::Empty(Empty {}));
+    /// stool.add(Arc::new(Named::new(Arc::new(seat), "stool")));
+    /// let stool = Scene::new(BVHNode::from_hittable_vec(Arc::new(stool)));
+    ///
+    /// let mut room = Scene::new(BVHNode::from_hittable_vec(Arc::new(HittableVec::new())));
+    /// for x in [-2.0, 2.0] {
+    ///     room.merge(&stool, [
+    ///         [1.0, 0.0, 0.0, x],
+    ///         [0.0, 1.0, 0.0, 0.0],
+    ///         [0.0, 0.0, 1.0, 0.0],
+    ///         [0.0, 0.0, 0.0, 1.0],
+    ///     ]);
+    /// }
+    /// let down = Vec3d::new(0.0, -1.0, 0.0);
+    /// assert_eq!(room.raycast(Point3d::new(2.0, 5.0, 0.0), down).unwrap().name.as_deref(), Some("stool"));
+    /// assert!(room.raycast(Point3d::new(0.0, 5.0, 0.0), down).is_none());
+    /// ```
+    pub fn merge(&mut self, other: &Scene, transform: [[Float; 4]; 4]) {
+        self.try_merge(other, transform).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_merge(&mut self, other: &Scene, transform: [[Float; 4]; 4]) -> Result<()> {
+        let piece: Arc<dyn Hittable> = Arc::new(Transform::try_new(other.world.clone(), transform)?);
+        for light in &other.lights {
+            self.add_light(Arc::new(TransformedEmitter::try_new(light.clone(), transform)?));
+        }
+
+        let combine = move |world: Arc<BVHNode>, piece: Arc<dyn Hittable>| {
+            let mut objects = HittableVec::new();
+            objects.add(world);
+            objects.add(piece);
+            BVHNode::from_hittable_vec(Arc::new(objects))
+        };
+        self.world = Arc::new(combine(self.world.clone(), piece.clone()));
+        if let Some(build) = self.build.take() {
+            let built_at = self.origin;
+            self.build = Some(Arc::new(move |origin, time| {
+                Arc::new(combine(build(origin, time), moved(piece.clone(), built_at, origin)))
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns the closest hit along the ray from ``origin`` in ``direction``, if any.
+    /// ``direction`` does not need to be normalized.
+    pub fn raycast(&self, origin: Point3d, direction: Vec3d) -> Option<RayHit> {
+        let ray = Ray::new(origin, direction, 0.0);
+        let rec = self.world.hit(&ray, &Interval { min: 0.0, max: Float::INFINITY })?;
+        Some(RayHit {
+            name: rec.name.map(str::to_string),
+            distance: rec.t * direction.length(),
+            point: rec.point,
+            normal: rec.normal,
+            front_face: rec.front_face,
+        })
+    }
+}
+
+impl Hittable for Scene {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        self.world.hit(ray, interval)
+    }
+
+    fn hit_any(&self, ray: &Ray, interval: &Interval) -> bool {
+        self.world.hit_any(ray, interval)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.world.bounding_box()
+    }
+}
+
+impl From<BVHNode> for Scene {
+    fn from(world: BVHNode) -> Self {
+        Self::new(world)
+    }
+}
+
 
 pub fn bouncing_balls() -> BVHNode {
+    let _span = trace::span("scene::bouncing_balls");
     let mut rng = rand::thread_rng();
     let mut world = HittableVec::new();
 
-    let checker: Arc<Box<dyn Texture>> = Arc::new(Box::new(Checker::from_color(
-        Vec3d::new(0.2, 0.3, 0.1),
-        Vec3d::new(0.9, 0.9, 0.9),
+    let checker: Arc<dyn Texture> = Arc::new(Checker::from_color(
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
         0.32,
-    )));
+    ));
 
     let ground = Material::Lambertian(Lambertian::from_texture(checker.clone()));
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(Vec3d::new(0.0, -1000.0, 0.0), 1000.0, ground))));
+        Arc::new(Sphere::static_sphere(Vec3d::new(0.0, -1000.0, 0.0), 1000.0, ground)));
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rand::random::<f64>();
-            let center = Vec3d::new(a as f64 + 0.9 * rand::random::<f64>(), 0.2, b as f64 + 0.9 * rand::random::<f64>());
+            let choose_mat = rand::random::<Float>();
+            let center = Vec3d::new(a as Float + 0.9 * rand::random::<Float>(), 0.2, b as Float + 0.9 * rand::random::<Float>());
             if (center - Vec3d::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 let sphere_material: Material;
                 if choose_mat < 0.8 {
-                    let albedo = Vec3d::random() * Vec3d::random();
+                    let albedo = rand::random::<Color>() * rand::random::<Color>();
                     sphere_material = Material::Lambertian(Lambertian::new(albedo));
-                    let center2 = center + Vec3d::new(0.0, rng.gen_range(0.0..0.5), 0.0);
-                    world.add(Arc::new(Box::new(Sphere::moving_sphere(center, center2, 0.2, sphere_material))));
+                    let bounce = Vec3d::new(0.0, rng.random_range(0.0..0.5), 0.0);
+                    let sphere = Sphere::static_sphere(center, 0.2, sphere_material);
+                    world.add(Arc::new(Animated::translating(Arc::new(sphere), bounce)));
                 } else if choose_mat < 0.95 {
-                    let albedo = Vec3d::gen_range(0.5, 1.0);
-                    let fuzz = rand::random::<f64>() * 0.5;
+                    let albedo = Color::from(Vec3d::gen_range(0.5, 1.0));
+                    let fuzz = rand::random::<Float>() * 0.5;
                     sphere_material = Material::Metal(Metal::new(albedo, fuzz));
-                    world.add(Arc::new(Box::new(Sphere::static_sphere(center, 0.2, sphere_material))));
+                    world.add(Arc::new(Sphere::static_sphere(center, 0.2, sphere_material)));
                 } else {
                     sphere_material = Material::Dielectric(Dielectric::new(1.5));
-                    world.add(Arc::new(Box::new(Sphere::static_sphere(center, 0.2, sphere_material))));
+                    world.add(Arc::new(Sphere::static_sphere(center, 0.2, sphere_material)));
                 }
             }
         }
     }
 
     let material1 = Material::Dielectric(Dielectric::new(1.5));
-    world.add(Arc::new(Box::new(Sphere::static_sphere(Vec3d::new(0.0, 1.0, 0.0), 1.0, material1))));
+    world.add(Arc::new(Sphere::static_sphere(Vec3d::new(0.0, 1.0, 0.0), 1.0, material1)));
 
-    let material2 = Material::Lambertian(Lambertian::new(Vec3d::new(0.4, 0.2, 0.1)));
-    world.add(Arc::new(Box::new(Sphere::static_sphere(Vec3d::new(-4.0, 1.0, 0.0), 1.0, material2))));
+    let material2 = Material::Lambertian(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    world.add(Arc::new(Sphere::static_sphere(Vec3d::new(-4.0, 1.0, 0.0), 1.0, material2)));
 
-    let material3 = Material::Metal(Metal::new(Vec3d::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Arc::new(Box::new(Sphere::static_sphere(Vec3d::new(4.0, 1.0, 0.0), 1.0, material3))));
+    let material3 = Material::Metal(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(Arc::new(Sphere::static_sphere(Vec3d::new(4.0, 1.0, 0.0), 1.0, material3)));
 
     BVHNode::from_hittable_vec(Arc::new(world))
 }
 
 
 pub fn checkered_spheres() -> BVHNode {
+    let _span = trace::span("scene::checkered_spheres");
     let mut world = HittableVec::new();
 
-    let checker: Box<dyn Texture> = Box::new(Checker::from_color(
-        Vec3d::new(0.2, 0.3, 0.1),
-        Vec3d::new(0.9, 0.9, 0.9),
+    let checker: Arc<dyn Texture> = Arc::new(Checker::from_color(
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
         0.32,
     ));
 
-    let arc_checker = Arc::new(checker);
-
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, -10.0, 0.0),
             10.0,
-            Material::Lambertian(Lambertian::from_texture(arc_checker.clone())))))
+            Material::Lambertian(Lambertian::from_texture(checker.clone()))))
     );
 
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, 10.0, 0.0),
             10.0,
-            Material::Lambertian(Lambertian::from_texture(arc_checker.clone())))))
+            Material::Lambertian(Lambertian::from_texture(checker.clone()))))
     );
 
     BVHNode::from_hittable_vec(Arc::new(world))
@@ -89,22 +452,24 @@ pub fn checkered_spheres() -> BVHNode {
 
 
 pub fn earth() -> BVHNode {
+    let _span = trace::span("scene::earth");
     let mut world = HittableVec::new();
 
     let image_file = "./misc/earthmap.png".to_string();
-    let earth_texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(ImageTexture::new(&image_file)));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&image_file));
 
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, 0.0, 0.0),
             2.0,
             Material::Lambertian(Lambertian::from_texture(earth_texture)))
-        )));
+        ));
     BVHNode::from_hittable_vec(Arc::new(world))
 }
 
 
 pub fn perlin_sphere() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::perlin_sphere");
     let mut camera = Camera::new();
     camera.set_aspect_ratio(16.0 / 9.0);
     camera.set_resolution_width(400);
@@ -123,20 +488,20 @@ pub fn perlin_sphere() -> (Camera, BVHNode) {
 
     let mut world = HittableVec::new();
 
-    let perlin_texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(PerlinTexture::new(4.0)));
+    let perlin_texture: Arc<dyn Texture> = Arc::new(PerlinTexture::new(4.0));
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, -1000.0, 0.0),
             1000.0,
             Material::Lambertian(Lambertian::from_texture(perlin_texture.clone())),
-        )))
+        ))
     );
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, 2.0, 0.0),
             2.0,
             Material::Lambertian(Lambertian::from_texture(perlin_texture.clone())),
-        )))
+        ))
     );
 
     (camera, BVHNode::from_hittable_vec(Arc::new(world)))
@@ -144,6 +509,7 @@ pub fn perlin_sphere() -> (Camera, BVHNode) {
 
 
 pub fn quads() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::quads");
     let mut camera = Camera::new();
 
     camera.set_depth(50);
@@ -168,92 +534,93 @@ pub fn quads() -> (Camera, BVHNode) {
     // Material
     let left_red = Material::Lambertian(
         Lambertian::from_texture(
-            Arc::new(Box::new(SolidColor::new(
-                Vec3d::new(1.0, 0.2, 0.2))))
+            Arc::new(SolidColor::new(
+                Color::new(1.0, 0.2, 0.2)))
         )
     );
 
     let back_green = Material::Lambertian(
         Lambertian::from_texture(
-            Arc::new(Box::new(SolidColor::new(
-                Vec3d::new(0.2, 1.0, 0.2)
-            )))
+            Arc::new(SolidColor::new(
+                Color::new(0.2, 1.0, 0.2)
+            ))
         )
     );
 
     let right_blue = Material::Lambertian(
         Lambertian::from_texture(
-            Arc::new(Box::new(SolidColor::new(
-                Vec3d::new(0.2, 0.2, 1.0)
-            )))
+            Arc::new(SolidColor::new(
+                Color::new(0.2, 0.2, 1.0)
+            ))
         )
     );
 
     let upper_orange = Material::Lambertian(
         Lambertian::from_texture(
-            Arc::new(Box::new(SolidColor::new(
-                Vec3d::new(1.0, 0.5, 0.0)
-            )))
+            Arc::new(SolidColor::new(
+                Color::new(1.0, 0.5, 0.0)
+            ))
         )
     );
 
     let lower_teal = Material::Lambertian(
         Lambertian::from_texture(
-            Arc::new(Box::new(SolidColor::new(
-                Vec3d::new(0.2, 0.8, 0.8)
-            )))
+            Arc::new(SolidColor::new(
+                Color::new(0.2, 0.8, 0.8)
+            ))
         )
     );
 
     // Quads
-    world.add(Arc::new(Box::new(
+    world.add(Arc::new(
         Quad::new(
             Vec3d::new(-3.0, -2.0, 5.0),
             Vec3d::new(0.0, 0.0, -4.0),
             Vec3d::new(0.0, 4.0, 0.0),
             left_red,
         )
-    )));
+    ));
 
-    world.add(Arc::new(Box::new(
+    world.add(Arc::new(
         Quad::new(
             Vec3d::new(-2.0, -2.0, 0.0),
             Vec3d::new(4.0, 0.0, 0.0),
             Vec3d::new(0.0, 4.0, 0.0),
             back_green,
         )
-    )));
+    ));
 
-    world.add(Arc::new(Box::new(
+    world.add(Arc::new(
         Quad::new(
             Vec3d::new(3.0, -2.0, 1.0),
             Vec3d::new(0.0, 0.0, 4.0),
             Vec3d::new(0.0, 4.0, 0.0),
             right_blue,
         )
-    )));
-    world.add(Arc::new(Box::new(
+    ));
+    world.add(Arc::new(
         Quad::new(
             Vec3d::new(-2.0, 3.0, 1.0),
             Vec3d::new(4.0, 0.0, 0.0),
             Vec3d::new(0.0, 0.0, 4.0),
             upper_orange,
         )
-    )));
+    ));
 
-    world.add(Arc::new(Box::new(
+    world.add(Arc::new(
         Quad::new(
             Vec3d::new(-2.0, -3.0, 5.0),
             Vec3d::new(4.0, 0.0, 0.0),
             Vec3d::new(0.0, 0.0, -4.0),
             lower_teal,
         )
-    )));
+    ));
     (camera, BVHNode::from_hittable_vec(Arc::new(world)))
 }
 
 
 pub fn simple_light() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::simple_light");
     let mut camera = Camera::new();
 
     camera.set_depth(50);
@@ -266,108 +633,244 @@ pub fn simple_light() -> (Camera, BVHNode) {
     camera.set_look_at(Vec3d::new(0.0, 2.0, 0.0));
     camera.set_v_up(Vec3d::new(0.0, 1.0, 0.0));
 
-    camera.set_background_color(Vec3d::new(0.0, 0.0, 0.0));
+    camera.set_background_color(Color::new(0.0, 0.0, 0.0));
     camera.set_defocus_angle(0.0);
 
     let mut world = HittableVec::new();
-    let perlin_texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(
+    let perlin_texture: Arc<dyn Texture> = Arc::new(
         PerlinTexture::new(4.0)
-    ));
+    );
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, -1000.0, 0.0),
             1000.0,
             Material::Lambertian(Lambertian::from_texture(perlin_texture.clone())),
-        )))
+        ))
     );
     world.add(
-        Arc::new(Box::new(Sphere::static_sphere(
+        Arc::new(Sphere::static_sphere(
             Vec3d::new(0.0, 2.0, 0.0),
             2.0,
             Material::Lambertian(Lambertian::from_texture(perlin_texture.clone())),
-        )))
-    );
-
-    let light = Material::Light(Light::from_color(Vec3d::new(4.0, 4.0, 4.0)));
-    world.add(
-        Arc::new(Box::new(Quad::new(
-            Vec3d::new(3.0, 1.0, -2.0),
-            Vec3d::new(2.0, 0.0, 0.0),
-            Vec3d::new(0.0, 2.0, 0.0),
-            light.clone(),
-        )))
-    );
-    world.add(
-        Arc::new(Box::new(
-            Sphere::static_sphere(
-                Vec3d::new(0.0, 7.0, 0.0),
-                2.0,
-                light.clone(),
-            )
         ))
     );
+
+    let light = Material::Light(Light::from_color(Color::new(4.0, 4.0, 4.0)));
+    let quad_light = Arc::new(Quad::new(
+        Vec3d::new(3.0, 1.0, -2.0),
+        Vec3d::new(2.0, 0.0, 0.0),
+        Vec3d::new(0.0, 2.0, 0.0),
+        light.clone(),
+    ));
+    let sphere_light = Arc::new(Sphere::static_sphere(
+        Vec3d::new(0.0, 7.0, 0.0),
+        2.0,
+        light.clone(),
+    ));
+    world.add(quad_light.clone());
+    world.add(sphere_light.clone());
+    camera.add_light(quad_light);
+    camera.add_light(sphere_light);
     (camera, BVHNode::from_hittable_vec(Arc::new(world)))
 }
 
 
-pub fn cornell_box() -> (Camera, BVHNode) {
+/// A ball covered in curly fur, lit by a soft sky.
+pub fn fur_ball() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::fur_ball");
+    let mut camera = Camera::new();
+
+    camera.set_depth(20);
+    camera.set_aspect_ratio(1.0);
+    camera.set_resolution_width(400);
+    camera.set_samples_per_pixel(100);
+
+    camera.set_v_fov(30.0);
+    camera.set_look_from(Vec3d::new(0.0, 1.0, 6.0));
+    camera.set_look_at(Vec3d::new(0.0, 0.0, 0.0));
+    camera.set_v_up(Vec3d::new(0.0, 1.0, 0.0));
+
+    camera.set_background_color(Color::new(0.7, 0.8, 1.0));
+    camera.set_defocus_angle(0.0);
+
     let mut world = HittableVec::new();
-    let red = Material::Lambertian(Lambertian::new(Vec3d::new(0.65, 0.05, 0.05)));
-    let white = Material::Lambertian(Lambertian::new(Vec3d::new(0.73, 0.73, 0.73)));
-    let green = Material::Lambertian(Lambertian::new(Vec3d::new(0.12, 0.45, 0.15)));
-    let light = Material::Light(Light::from_color(Vec3d::new(15.0, 15.0, 15.0)));
-
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 555.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        green.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::zero(),
-        Vec3d::new(0.0, 555.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        red.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(343.0, 554.0, 332.0),
-        Vec3d::new(-130.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, -105.0),
-        light.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::zero(),
-        Vec3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        white.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(555.0, 555.0, 555.0),
-        Vec3d::new(-555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, -555.0),
-        white.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(0.0, 0.0, 555.0),
-        Vec3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 555.0, 0.0),
-        white.clone(),
-    ))));
+    let skin = Material::Lambertian(Lambertian::new(Color::new(0.3, 0.15, 0.05)));
+    world.add(Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, skin)));
+
+    let hair = Material::Hair(Hair::new(Color::new(0.6, 0.35, 0.1), 0.2));
+    let mut rng = rand::rng();
+    for _ in 0..2000 {
+        let root = Vec3d::random_unit_vector();
+        let curl = Vec3d::random_unit_vector() * 0.15;
+        let points = [
+            root,
+            root * 1.2 + curl,
+            root * 1.4 - curl * 0.5,
+            root * (1.5 + rng.random_range(0.0..0.15)) + curl,
+        ];
+        world.add(Arc::new(Curve::catmull_rom(&points, (0.01, 0.002), CurveShape::Ribbon, hair.clone())));
+    }
+    (camera, BVHNode::from_hittable_vec(Arc::new(world)))
+}
+
+
+/// A golden Mandelbulb next to a Menger sponge on a checkered floor, both ray-marched by
+/// ``Sdf``.
+pub fn fractals() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::fractals");
+    let mut camera = Camera::new();
+
+    camera.set_depth(20);
+    camera.set_aspect_ratio(16.0 / 9.0);
+    camera.set_resolution_width(400);
+    camera.set_samples_per_pixel(100);
+
+    camera.set_v_fov(30.0);
+    camera.set_look_from(Vec3d::new(0.0, 2.5, 9.0));
+    camera.set_look_at(Vec3d::new(0.0, 0.5, 0.0));
+    camera.set_v_up(Vec3d::new(0.0, 1.0, 0.0));
+
+    camera.set_background_color(Color::new(0.7, 0.8, 1.0));
+    camera.set_defocus_angle(0.0);
+
+    let mut world = HittableVec::new();
+    let checker = Checker::from_color(Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9), 0.5);
+    let floor = Material::Lambertian(Lambertian::from_texture(Arc::new(checker)));
+    world.add(Arc::new(Sphere::static_sphere(Point3d::new(0.0, -1000.0, 0.0), 1000.0, floor)));
+
+    let gold = Material::Metal(Metal::new(Color::new(0.8, 0.6, 0.2), 0.1));
+    let bulb = Sdf::new(Arc::new(Mandelbulb::default()), gold);
+    world.add(Arc::new(Translate::new(Arc::new(bulb), Vec3d::new(-1.5, 1.2, 0.0))));
+
+    let stone = Material::Lambertian(Lambertian::new(Color::new(0.7, 0.7, 0.75)));
+    let sponge = Sdf::new(Arc::new(MengerSponge::new(4)), stone);
+    world.add(Arc::new(Translate::new(Arc::new(sponge), Vec3d::new(1.5, 1.0, 0.0))));
+
+    (camera, BVHNode::from_hittable_vec(Arc::new(world)))
+}
+
+
+/// Materials of the walls of ``cornell_shell``, as seen from the open front; ``None`` leaves
+/// a wall out.
+#[derive(Debug, Clone)]
+pub struct CornellWalls {
+    /// The wall at ``x = width``, on the left from the front.
+    pub left: Option<Material>,
+    /// The wall at ``x = 0``.
+    pub right: Option<Material>,
+    pub floor: Option<Material>,
+    pub ceiling: Option<Material>,
+    /// The wall at ``z = depth``, facing the front.
+    pub back: Option<Material>,
+    /// The wall at ``z = 0``, which the camera usually looks through.
+    pub front: Option<Material>,
+}
+
+
+impl CornellWalls {
+    /// The walls of the original Cornell box: green on the left, red on the right, white
+    /// elsewhere, and open at the front.
+    pub fn classic() -> Self {
+        let white = Material::Lambertian(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+        Self {
+            left: Some(Material::Lambertian(Lambertian::new(Color::new(0.12, 0.45, 0.15)))),
+            right: Some(Material::Lambertian(Lambertian::new(Color::new(0.65, 0.05, 0.05)))),
+            floor: Some(white.clone()),
+            ceiling: Some(white.clone()),
+            back: Some(white),
+            front: None,
+        }
+    }
+}
+
+
+/// The rectangular light of ``cornell_shell``, just below its ceiling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CornellLight {
+    /// The ``x`` and ``z`` of the corner of the light nearest to the origin.
+    pub corner: (Float, Float),
+    /// Extent of the light along ``x`` and ``z``.
+    pub size: (Float, Float),
+    pub emission: Color,
+}
+
+
+/// Builds the walls of a Cornell box of ``dimensions``, from the origin to its opposite corner,
+/// and the light in its ceiling, if any.
+///
+/// The light is also among the walls, and is returned again to be given to
+/// ``Camera::add_light``.
+/// # Examples
+/// ```
+/// use ray_tracing::color::Color;
+/// use ray_tracing::object::material::Material;
+/// use ray_tracing::scene::{cornell_shell, CornellLight, CornellWalls};
+/// use ray_tracing::vec3d::Vec3d;
+/// // A wide box without a left wall, lit by a strip along the whole ceiling.
+/// let walls = CornellWalls { left: None, ..CornellWalls::classic() };
+/// let light = CornellLight { corner: (0.0, 200.0), size: (800.0, 100.0), emission: Color::splat(10.0) };
+/// let (shell, light) = cornell_shell(Vec3d::new(800.0, 500.0, 500.0), walls, Some(light));
+/// assert_eq!(shell.len(), 5);
+/// assert!(light.is_some());
+/// ```
+pub fn cornell_shell(
+    dimensions: Vec3d,
+    walls: CornellWalls,
+    light: Option<CornellLight>,
+) -> (HittableVec, Option<Arc<Quad>>) {
+    let (width, height, depth) = (dimensions.x(), dimensions.y(), dimensions.z());
+    let planes = [
+        (walls.left, Point3d::new(width, 0.0, 0.0), Vec3d::new(0.0, height, 0.0), Vec3d::new(0.0, 0.0, depth)),
+        (walls.right, Point3d::zero(), Vec3d::new(0.0, height, 0.0), Vec3d::new(0.0, 0.0, depth)),
+        (walls.floor, Point3d::zero(), Vec3d::new(width, 0.0, 0.0), Vec3d::new(0.0, 0.0, depth)),
+        (walls.ceiling, dimensions, Vec3d::new(-width, 0.0, 0.0), Vec3d::new(0.0, 0.0, -depth)),
+        (walls.back, Point3d::new(0.0, 0.0, depth), Vec3d::new(width, 0.0, 0.0), Vec3d::new(0.0, height, 0.0)),
+        (walls.front, Point3d::zero(), Vec3d::new(width, 0.0, 0.0), Vec3d::new(0.0, height, 0.0)),
+    ];
+
+    let mut shell = HittableVec::new();
+    for (material, q, u, v) in planes {
+        if let Some(material) = material {
+            shell.add(Arc::new(Quad::new(q, u, v, material)));
+        }
+    }
+
+    let light = light.map(|light| {
+        let ((x, z), (size_x, size_z)) = (light.corner, light.size);
+        // Facing down, a unit below the ceiling so it is not hidden in it.
+        let quad = Arc::new(Quad::new(
+            Point3d::new(x + size_x, height - 1.0, z + size_z),
+            Vec3d::new(-size_x, 0.0, 0.0),
+            Vec3d::new(0.0, 0.0, -size_z),
+            Material::Light(Light::from_color(light.emission)),
+        ));
+        shell.add(quad.clone());
+        quad
+    });
+    (shell, light)
+}
+
+
+pub fn cornell_box() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::cornell_box");
+    let white = Material::Lambertian(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let ceiling_light = CornellLight { corner: (213.0, 227.0), size: (130.0, 105.0), emission: Color::new(15.0, 15.0, 15.0) };
+    let (mut world, ceiling_light) = cornell_shell(Vec3d::new(555.0, 555.0, 555.0), CornellWalls::classic(), Some(ceiling_light));
 
     let box1 = bbox(
         Point3d::zero(),
         Point3d::new(165.0, 330.0, 165.0),
         white.clone(),
     );
-    let box1: Arc<Box<dyn Hittable>> = Arc::new(Box::new(RotateY::new(
-        Arc::new(Box::new(box1)),
+    let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(
+        Arc::new(box1),
         15.0,
-    )));
+    ));
 
-    let box1: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Translate::new(
+    let box1: Arc<dyn Hittable> = Arc::new(Translate::new(
         box1,
         Vec3d::new(265.0, 0.0, 295.0),
-    )));
+    ));
 
     world.add(box1);
 
@@ -377,15 +880,15 @@ pub fn cornell_box() -> (Camera, BVHNode) {
         white.clone(),
     );
 
-    let box2: Arc<Box<dyn Hittable>> = Arc::new(Box::new(RotateY::new(
-        Arc::new(Box::new(box2)),
+    let box2: Arc<dyn Hittable> = Arc::new(RotateY::new(
+        Arc::new(box2),
         -18.0,
-    )));
+    ));
 
-    let box2: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Translate::new(
+    let box2: Arc<dyn Hittable> = Arc::new(Translate::new(
         box2,
         Vec3d::new(130.0, 0.0, 65.0),
-    )));
+    ));
 
     world.add(box2);
 
@@ -401,68 +904,30 @@ pub fn cornell_box() -> (Camera, BVHNode) {
     camera.set_look_at(Point3d::new(278.0, 278.0, 0.0));
     camera.set_v_up(Vec3d::new(0.0, 1.0, 0.0));
     camera.set_defocus_angle(0.0);
+    if let Some(light) = ceiling_light { camera.add_light(light); }
     (camera, BVHNode::from_hittable_vec(Arc::new(world)))
 }
 
 pub fn cornell_smoke() -> (Camera, BVHNode) {
-    let mut world = HittableVec::new();
-
-    let red = Material::Lambertian(Lambertian::new(Vec3d::new(0.65, 0.05, 0.05)));
-    let white = Material::Lambertian(Lambertian::new(Vec3d::new(0.73, 0.73, 0.73)));
-    let green = Material::Lambertian(Lambertian::new(Vec3d::new(0.12, 0.45, 0.15)));
-    let light = Material::Light(Light::from_color(Vec3d::new(7.0, 7.0, 7.0)));
-
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 555.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        green.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::zero(),
-        Vec3d::new(0.0, 555.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        red.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(113.0, 554.0, 127.0),
-        Vec3d::new(330.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, 305.0),
-        light.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(0.0, 555.0, 0.0),
-        Vec3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        white.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::zero(),
-        Vec3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 0.0, 555.0),
-        white.clone(),
-    ))));
-    world.add(Arc::new(Box::new(Quad::new(
-        Point3d::new(0.0, 0.0, 555.0),
-        Vec3d::new(555.0, 0.0, 0.0),
-        Vec3d::new(0.0, 555.0, 0.0),
-        white.clone(),
-    ))));
+    let _span = trace::span("scene::cornell_smoke");
+    let white = Material::Lambertian(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let light = CornellLight { corner: (113.0, 127.0), size: (330.0, 305.0), emission: Color::new(7.0, 7.0, 7.0) };
+    let (mut world, _) = cornell_shell(Vec3d::new(555.0, 555.0, 555.0), CornellWalls::classic(), Some(light));
 
     let box1 = bbox(
         Point3d::zero(),
         Point3d::new(165.0, 330.0, 165.0),
         white.clone(),
     );
-    let box1: Arc<Box<dyn Hittable>> = Arc::new(Box::new(RotateY::new(
-        Arc::new(Box::new(box1)),
+    let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(
+        Arc::new(box1),
         15.0,
-    )));
+    ));
 
-    let box1: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Translate::new(
+    let box1: Arc<dyn Hittable> = Arc::new(Translate::new(
         box1,
         Vec3d::new(265.0, 0.0, 295.0),
-    )));
+    ));
 
     let box2 = bbox(
         Point3d::zero(),
@@ -470,29 +935,29 @@ pub fn cornell_smoke() -> (Camera, BVHNode) {
         white.clone(),
     );
 
-    let box2: Arc<Box<dyn Hittable>> = Arc::new(Box::new(RotateY::new(
-        Arc::new(Box::new(box2)),
+    let box2: Arc<dyn Hittable> = Arc::new(RotateY::new(
+        Arc::new(box2),
         -18.0,
-    )));
+    ));
 
-    let box2: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Translate::new(
+    let box2: Arc<dyn Hittable> = Arc::new(Translate::new(
         box2,
         Vec3d::new(130.0, 0.0, 65.0),
-    )));
+    ));
 
     world.add(
-        Arc::new(Box::new(Medium::from_color(
+        Arc::new(Medium::from_color(
             box1,
             0.01,
             Color::zero(),
-        )))
+        ))
     );
     world.add(
-        Arc::new(Box::new(Medium::from_color(
+        Arc::new(Medium::from_color(
             box2,
             0.01,
             Color::new(1.0, 1.0, 1.0),
-        )))
+        ))
     );
 
 
@@ -513,6 +978,7 @@ pub fn cornell_smoke() -> (Camera, BVHNode) {
 }
 
 pub fn final_scene() -> (Camera, BVHNode) {
+    let _span = trace::span("scene::final_scene");
     let mut boxes1 = HittableVec::new();
 
     let ground = Material::Lambertian(Lambertian::new(Color::new(0.48, 0.83, 0.53)));
@@ -522,8 +988,8 @@ pub fn final_scene() -> (Camera, BVHNode) {
     for i in 0..boxes_per_side {
         for j in 0..boxes_per_side {
             let w = 100.0;
-            let x0 = -1000.0 + i as f64 * w;
-            let z0 = -1000.0 + j as f64 * w;
+            let x0 = -1000.0 + i as Float * w;
+            let z0 = -1000.0 + j as Float * w;
             let y0 = 0.0;
             let x1 = x0 + w;
             let y1 = rand::thread_rng().gen_range(1.0..101.0);
@@ -533,97 +999,97 @@ pub fn final_scene() -> (Camera, BVHNode) {
                 Point3d::new(x1, y1, z1),
                 ground.clone(),
             );
-            boxes1.add(Arc::new(Box::new(box_)))
+            boxes1.add(Arc::new(box_))
         }
     }
     let mut world = HittableVec::new();
-    world.add(Arc::new(Box::new(BVHNode::from_hittable_vec(Arc::new(boxes1)))));
+    world.add(Arc::new(BVHNode::from_hittable_vec(Arc::new(boxes1))));
 
     let light = Material::Light(Light::from_color(Color::new(7.0, 7.0, 7.0)));
-    world.add(Arc::new(Box::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point3d::new(123.0, 554.0, 147.0),
         Vec3d::new(300.0, 0.0, 0.0),
         Vec3d::new(0.0, 0.0, 265.0),
         light.clone(),
-    ))));
+    )));
 
     let center1 = Point3d::new(400.0, 400.0, 200.0);
-    let center2 = center1 + Vec3d::new(30.0, 0.0, 0.0);
 
     let sphere_material = Material::Lambertian(Lambertian::new(Color::new(0.7, 0.3, 0.1)));
-    world.add(Arc::new(Box::new(Sphere::moving_sphere(center1, center2, 50.0, sphere_material.clone()))));
+    let moving = Sphere::static_sphere(center1, 50.0, sphere_material.clone());
+    world.add(Arc::new(Animated::translating(Arc::new(moving), Vec3d::new(30.0, 0.0, 0.0))));
 
-    world.add(Arc::new(Box::new(Sphere::static_sphere(
+    world.add(Arc::new(Sphere::static_sphere(
         Point3d::new(260.0, 150.0, 45.0),
         50.0,
         Material::Dielectric(Dielectric::new(1.5)),
-    ))));
+    )));
 
-    world.add(Arc::new(Box::new(Sphere::static_sphere(
+    world.add(Arc::new(Sphere::static_sphere(
         Point3d::new(0.0, 150.0, 145.0),
         50.0,
         Material::Metal(Metal::new(Color::new(0.8, 0.8, 0.9), 1.0)),
-    ))));
+    )));
 
-    let boundary: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Sphere::static_sphere(
+    let boundary: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
         Point3d::new(360.0, 150.0, 145.0),
         70.0,
         Material::Dielectric(Dielectric::new(1.5)),
-    )));
+    ));
 
     world.add(boundary.clone());
 
-    world.add(Arc::new(Box::new(Medium::from_color(
+    world.add(Arc::new(Medium::from_color(
         boundary,
         0.2,
         Color::new(0.2, 0.4, 0.9),
-    ))));
+    )));
 
-    let boundary: Arc<Box<dyn Hittable>> = Arc::new(Box::new(Sphere::static_sphere(
+    let boundary: Arc<dyn Hittable> = Arc::new(Sphere::static_sphere(
         Point3d::zero(),
         5000.0,
         Material::Dielectric(Dielectric::new(1.5)),
-    )));
-    world.add(Arc::new(Box::new(Medium::from_color(
+    ));
+    world.add(Arc::new(Medium::from_color(
         boundary,
         0.0001,
         Color::new(1.0, 1.0, 1.0),
-    ))));
+    )));
 
     let image_file = "./misc/earthmap.png".to_string();
-    let earth_texture: Arc<Box<dyn Texture>> = Arc::new(Box::new(ImageTexture::new(&image_file)));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&image_file));
     let emat = Material::Lambertian(Lambertian::from_texture(earth_texture));
-    world.add(Arc::new(Box::new(Sphere::static_sphere(
+    world.add(Arc::new(Sphere::static_sphere(
         Point3d::new(400.0, 200.0, 400.0),
         100.0,
         emat,
-    ))));
+    )));
 
     let pertext = PerlinTexture::new(0.2);
-    world.add(Arc::new(Box::new(Sphere::static_sphere(
+    world.add(Arc::new(Sphere::static_sphere(
         Point3d::new(220.0, 280.0, 300.0),
         80.0,
-        Material::Lambertian(Lambertian::from_texture(Arc::new(Box::new(pertext)))),
-    ))));
+        Material::Lambertian(Lambertian::from_texture(Arc::new(pertext))),
+    )));
 
     let mut boxes2 = HittableVec::new();
     let white = Material::Lambertian(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
     let ns = 1000;
     for _ in 0..ns {
-        boxes2.add(Arc::new(Box::new(Sphere::static_sphere(
+        boxes2.add(Arc::new(Sphere::static_sphere(
             Vec3d::gen_range(0.0, 165.0),
             10.0,
             white.clone(),
-        ))));
+        )));
     }
 
-    world.add(Arc::new(Box::new(Translate::new(
-        Arc::new(Box::new(RotateY::new(
-            Arc::new(Box::new(BVHNode::from_hittable_vec(Arc::new(boxes2)))),
+    world.add(Arc::new(Translate::new(
+        Arc::new(RotateY::new(
+            Arc::new(BVHNode::from_hittable_vec(Arc::new(boxes2))),
             15.0,
-        ))),
+        )),
         Vec3d::new(-100.0, 270.0, 395.0),
-    ))));
+    )));
 
     let mut camera = Camera::new();
     camera.set_aspect_ratio(1.0);
@@ -638,4 +1104,285 @@ pub fn final_scene() -> (Camera, BVHNode) {
     camera.set_v_up(Vec3d::new(0.0, 1.0, 0.0));
     camera.set_defocus_angle(0.0);
     (camera, BVHNode::from_hittable_vec(Arc::new(world)))
-}
\ No newline at end of file
+}
+
+
+/// Side of a block of ``generate_grid_city``, without the street around it.
+const CITY_BLOCK: Float = 10.0;
+/// Width of the streets between blocks.
+const CITY_STREET: Float = 2.0;
+
+
+/// Scales ``object`` by ``scale``, turns it by ``angle`` degrees around ``y`` and moves it to
+/// ``position``, as an instance sharing the object with others.
+fn instance(object: &Arc<dyn Hittable>, scale: Vec3d, angle: Float, position: Point3d) -> Arc<dyn Hittable> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    Arc::new(Transform::new(object.clone(), [
+        [cos * scale.x(), 0.0, sin * scale.z(), position.x()],
+        [0.0, scale.y(), 0.0, position.y()],
+        [-sin * scale.x(), 0.0, cos * scale.z(), position.z()],
+        [0.0, 0.0, 0.0, 1.0],
+    ]))
+}
+
+
+/// Generates a city of ``blocks x blocks`` blocks on ``y = 0`` around the origin, for stress
+/// tests far bigger than ``final_scene``.
+///
+/// Every block has four lots, of which ``density`` in ``[0, 1]`` get a building, growing taller
+/// towards the center. Buildings are instances of a few shared boxes, and where they go and
+/// how tall they are follows from ``seed``.
+pub fn generate_grid_city(blocks: usize, density: Float, seed: u64) -> BVHNode {
+    let _span = trace::span("scene::generate_grid_city");
+    let mut rng = Pcg32::new(seed, 0);
+    let facades: Vec<Arc<dyn Hittable>> = [Color::new(0.7, 0.68, 0.62), Color::new(0.35, 0.38, 0.42), Color::new(0.6, 0.4, 0.3)]
+        .into_iter()
+        .map(|color| {
+            let material = Material::Lambertian(Lambertian::new(color));
+            Arc::new(bbox(Point3d::new(-0.5, 0.0, -0.5), Point3d::new(0.5, 1.0, 0.5), material)) as Arc<dyn Hittable>
+        })
+        .collect();
+
+    let pitch = CITY_BLOCK + CITY_STREET;
+    let half = blocks as Float * pitch / 2.0;
+    let mut world = HittableVec::new();
+    let asphalt = Material::Lambertian(Lambertian::new(Color::splat(0.2)));
+    world.add(Arc::new(Quad::new(
+        Point3d::new(-half, 0.0, -half), Vec3d::new(2.0 * half, 0.0, 0.0), Vec3d::new(0.0, 0.0, 2.0 * half), asphalt,
+    )));
+
+    let lot = CITY_BLOCK / 2.0;
+    let mut buildings = HittableVec::new();
+    for i in 0..blocks * 2 {
+        for j in 0..blocks * 2 {
+            if rng.random::<Float>() >= density { continue; }
+            // Lots are in pairs, with a street after every second one.
+            let corner = |k: usize| -half + CITY_STREET / 2.0 + (k / 2) as Float * pitch + (k % 2) as Float * lot;
+            let center = Point3d::new(corner(i) + lot / 2.0, 0.0, corner(j) + lot / 2.0);
+            let closeness = 1.0 - (center.length() / (half * Float::sqrt(2.0))).min(1.0);
+            let height = rng.random_range(2.0..6.0) * (1.0 + 4.0 * closeness * closeness);
+            let footprint = Vec3d::new(rng.random_range(0.6..0.9) * lot, height, rng.random_range(0.6..0.9) * lot);
+            let facade = &facades[rng.random_range(0..facades.len())];
+            buildings.add(instance(facade, footprint, 0.0, center));
+        }
+    }
+    if !buildings.objects.is_empty() {
+        world.add(Arc::new(BVHNode::from_hittable_vec(Arc::new(buildings))));
+    }
+    BVHNode::from_hittable_vec(Arc::new(world))
+}
+
+
+/// Generates a forest of ``density`` trees per unit of area on a square ground of ``size``
+/// around the origin at ``y = 0``, for stress tests far bigger than ``final_scene``.
+///
+/// Trees are broadleaves and conifers shared between instances, randomly turned, scaled and
+/// scattered as ``seed`` says. Fails unless ``size`` is positive and ``density`` is not
+/// negative, both finite.
+pub fn generate_forest(size: Float, density: Float, seed: u64) -> Result<BVHNode> {
+    let invalid = |name: &str, value: Float| Error::InvalidSetting { name: name.to_string(), value: value.to_string() };
+    if !size.is_finite() || size <= 0.0 { return Err(invalid("size", size)); }
+    if !density.is_finite() || density < 0.0 { return Err(invalid("density", density)); }
+    let _span = trace::span("scene::generate_forest");
+    let mut rng = Pcg32::new(seed, 0);
+    let bark = Material::Lambertian(Lambertian::new(Color::new(0.3, 0.2, 0.12)));
+    let trunk = || Arc::new(bbox(Point3d::new(-0.1, 0.0, -0.1), Point3d::new(0.1, 1.6, 0.1), bark.clone())) as Arc<dyn Hittable>;
+    let tree = |crowns: &[(Float, Float)], color: Color| {
+        let leaves = Material::Lambertian(Lambertian::new(color));
+        let mut parts = HittableVec::new();
+        parts.add(trunk());
+        for &(height, radius) in crowns {
+            parts.add(Arc::new(Sphere::static_sphere(Point3d::new(0.0, height, 0.0), radius, leaves.clone())));
+        }
+        Arc::new(BVHNode::from_hittable_vec(Arc::new(parts))) as Arc<dyn Hittable>
+    };
+    let trees = [
+        tree(&[(2.2, 0.9)], Color::new(0.25, 0.5, 0.15)),
+        tree(&[(2.0, 0.7), (2.6, 0.6)], Color::new(0.4, 0.55, 0.15)),
+        tree(&[(1.6, 0.8), (2.4, 0.6), (3.1, 0.4)], Color::new(0.1, 0.3, 0.15)),
+    ];
+
+    let half = size / 2.0;
+    let mut world = HittableVec::new();
+    let soil = Material::Lambertian(Lambertian::new(Color::new(0.3, 0.35, 0.15)));
+    world.add(Arc::new(Quad::new(
+        Point3d::new(-half, 0.0, -half), Vec3d::new(size, 0.0, 0.0), Vec3d::new(0.0, 0.0, size), soil,
+    )));
+
+    let mut forest = HittableVec::new();
+    for _ in 0..(density * size * size).round() as usize {
+        let position = Point3d::new(rng.random_range(-half..half), 0.0, rng.random_range(-half..half));
+        let scale = rng.random_range(0.7..1.3);
+        let tree = &trees[rng.random_range(0..trees.len())];
+        forest.add(instance(tree, Vec3d::new(scale, scale, scale), rng.random_range(0.0..360.0), position));
+    }
+    if !forest.objects.is_empty() {
+        world.add(Arc::new(BVHNode::from_hittable_vec(Arc::new(forest))));
+    }
+    Ok(BVHNode::from_hittable_vec(Arc::new(world)))
+}
+
+
+/// Names of the scenes ``by_name`` builds.
+pub const SCENE_NAMES: [&str; 8] = [
+    "perlin_sphere", "quads", "simple_light", "fur_ball", "fractals", "cornell_box", "cornell_smoke", "final_scene",
+];
+
+
+/// Builds the scene with its camera from the name of its function, e.g. ``"cornell_box"``, to
+/// let a scene be picked at run time. ``None`` for names not in ``SCENE_NAMES``.
+pub fn by_name(name: &str) -> Option<(Camera, BVHNode)> {
+    match name {
+        "perlin_sphere" => Some(perlin_sphere()),
+        "quads" => Some(quads()),
+        "simple_light" => Some(simple_light()),
+        "fur_ball" => Some(fur_ball()),
+        "fractals" => Some(fractals()),
+        "cornell_box" => Some(cornell_box()),
+        "cornell_smoke" => Some(cornell_smoke()),
+        "final_scene" => Some(final_scene()),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test_scene {
+    use super::*;
+    use crate::object::Named;
+    use crate::object::material::Empty;
+
+    #[test]
+    fn test_by_name() {
+        assert!(by_name("cornell_box").is_some());
+        assert!(by_name("quads").is_some());
+        assert!(by_name("teapot").is_none());
+    }
+
+    #[test]
+    fn test_raycast_miss() {
+        let scene = Scene::new(BVHNode::from_hittable_vec(Arc::new(HittableVec::new())));
+        assert!(scene.raycast(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn test_raycast_closest_named() {
+        let mut world = HittableVec::new();
+        let material = Material::Lambertian(Lambertian::new(Color::splat(0.5)));
+        for (z, name) in [(-2.0, "near"), (-6.0, "far")] {
+            let sphere = Sphere::static_sphere(Point3d::new(0.0, 0.0, z), 1.0, material.clone());
+            world.add(Arc::new(Named::new(Arc::new(sphere), name)));
+        }
+        world.add(Arc::new(Sphere::static_sphere(Point3d::new(5.0, 0.0, -2.0), 1.0, material)));
+        let scene = Scene::from(BVHNode::from_hittable_vec(Arc::new(world)));
+
+        let hit = scene.raycast(Point3d::zero(), Vec3d::new(0.0, 0.0, -1.0)).unwrap();
+        assert_eq!(hit.name.as_deref(), Some("near"));
+        assert_eq!(hit.distance, 1.0);
+        assert_eq!(hit.point, Point3d::new(0.0, 0.0, -1.0));
+        assert!(hit.front_face);
+
+        let hit = scene.raycast(Point3d::new(5.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, -1.0)).unwrap();
+        assert_eq!(hit.name, None);
+    }
+
+    #[test]
+    fn test_merge_lights_and_animation() {
+        // Only the light of an empty shell, at y = 1.
+        let (shell, light) = cornell_shell(
+            Vec3d::new(2.0, 2.0, 2.0),
+            CornellWalls { left: None, right: None, floor: None, ceiling: None, back: None, front: None },
+            Some(CornellLight { corner: (0.5, 0.5), size: (1.0, 1.0), emission: Color::splat(1.0) }),
+        );
+        let mut lamp = Scene::new(BVHNode::from_hittable_vec(Arc::new(shell)));
+        lamp.add_light(light.unwrap());
+
+        let mut room = Scene::animated(|_| BVHNode::from_hittable_vec(Arc::new(HittableVec::new())));
+        let raised = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 3.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        room.merge(&lamp, raised);
+        assert_eq!(room.lights().len(), 1);
+        let sample = room.lights()[0].sample(&Point3d::new(1.0, 0.0, 1.0)).unwrap();
+        assert!((sample.direction.y() - 4.0).abs() < 1e-9);
+
+        let mut singular = raised;
+        singular[1][1] = 0.0;
+        assert!(matches!(room.try_merge(&lamp, singular), Err(crate::error::Error::SingularMatrix(_))));
+        room.set_time(2.0);
+        let height = room.world().bounding_box().axis_interval(1);
+        assert!(height.min < 4.0 && height.max > 4.0 && height.size() < 1.0);
+    }
+
+    #[test]
+    fn test_relative_precision() {
+        let center = [1e15 + 0.5, 0.0, 0.0];
+        let mut scene = Scene::relative(move |origin, _| {
+            let mut world = HittableVec::new();
+            let ball = Sphere::static_sphere(relative_to(center, origin), 1.0, Material::Empty(Empty {}));
+            world.add(Arc::new(ball));
+            BVHNode::from_hittable_vec(Arc::new(world))
+        });
+        scene.add_light(Arc::new(crate::object::PointLight::new(Point3d::new(1.0, 2.0, 3.0), Color::splat(1.0))));
+        // Near the ball, the ray starts closer to it than the spacing of doubles out there.
+        let hit_from = |scene: &Scene, x: Float| scene.raycast(Point3d::new(x, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0)).unwrap();
+        #[cfg(not(feature = "f32"))]
+        assert!((hit_from(&scene, 1e15 + 0.8).normal.x() - 0.3).abs() > 1e-3);
+
+        scene.set_origin(center);
+        assert!((hit_from(&scene, 0.3).normal.x() - 0.3).abs() < 1e-6);
+        let sample = scene.lights()[0].sample(&Point3d::zero()).unwrap();
+        assert!((sample.direction.x() - (1.0 - 1e15 - 0.5) as Float).abs() < 1.0);
+        // Lights are moved once from where they were added, however often the origin moves.
+        let light = scene.added_lights[0].0.clone();
+        for step in 0..100 {
+            scene.set_origin([step as f64, 0.0, 0.0]);
+        }
+        scene.set_origin([0.0; 3]);
+        assert!(Arc::ptr_eq(&scene.lights()[0], &light));
+
+        // Static scenes are moved along, also through later changes of time and origin.
+        let mut world = HittableVec::new();
+        world.add(Arc::new(Sphere::static_sphere(Point3d::zero(), 1.0, Material::Empty(Empty {}))));
+        let mut plain = Scene::new(BVHNode::from_hittable_vec(Arc::new(world)));
+        plain.set_origin([0.0, 0.0, 1.0]);
+        plain.set_time(1.0);
+        plain.set_origin([0.0, 0.0, 2.0]);
+        let down = Vec3d::new(0.0, 0.0, -1.0);
+        assert!((plain.raycast(Point3d::zero(), down).unwrap().distance - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_generate_grid_city() {
+        let down = Vec3d::new(0.0, -1.0, 0.0);
+        let empty = Scene::new(generate_grid_city(3, 0.0, 1));
+        assert_eq!(empty.raycast(Point3d::new(0.0, 100.0, 0.0), down).unwrap().distance, 100.0);
+
+        // Every lot is built on, and buildings cover the center of their lot.
+        let city = Scene::new(generate_grid_city(3, 1.0, 1));
+        let half = 1.5 * (CITY_BLOCK + CITY_STREET);
+        let lot_center = -half + CITY_STREET / 2.0 + CITY_BLOCK / 4.0;
+        let roof = city.raycast(Point3d::new(lot_center, 100.0, lot_center), down).unwrap();
+        assert!(roof.distance < 98.0);
+        // Buildings keep apart, and the middle of the city is between four of them.
+        assert_eq!(city.raycast(Point3d::new(0.0, 100.0, 0.0), down).unwrap().distance, 100.0);
+
+        let same = generate_grid_city(3, 1.0, 1).bounding_box();
+        assert_eq!(city.bounding_box(), same);
+        assert_ne!(generate_grid_city(3, 1.0, 2).bounding_box(), same);
+    }
+
+    #[test]
+    fn test_generate_forest() {
+        let forest = generate_forest(40.0, 0.5, 7).unwrap();
+        let bbox = forest.bounding_box();
+        assert!(bbox.axis_interval(1).max > 3.0 && bbox.axis_interval(1).max < 5.0);
+        assert!(bbox.axis_interval(0).max < 20.0 + 1.5);
+        assert_eq!(generate_forest(40.0, 0.5, 7).unwrap().bounding_box(), bbox);
+
+        let clearing = Scene::new(generate_forest(10.0, 0.0, 7).unwrap());
+        assert_eq!(clearing.raycast(Point3d::new(1.0, 10.0, 1.0), Vec3d::new(0.0, -1.0, 0.0)).unwrap().distance, 10.0);
+
+        for (size, density) in [(0.0, 0.5), (-4.0, 0.5), (Float::NAN, 0.5), (10.0, -1.0), (10.0, Float::NAN), (10.0, Float::INFINITY)] {
+            assert!(matches!(generate_forest(size, density, 7), Err(Error::InvalidSetting { .. })), "{} {}", size, density);
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! A small HTTP service rendering the scenes of the ``scene`` module, so the renderer can run
+//! as a backend. Only built with the ``server`` feature, which also builds the ``render_server``
+//! binary serving it.
+//!
+//! Renders are jobs, queued and run one at a time in the order they were requested:
+//!
+//! - ``POST /render?scene=cornell_box&width=300&samples=64&depth=20&seed=1`` queues a render
+//!   of one of the ``scene::SCENE_NAMES`` and answers ``202`` with the job, e.g. ``{"id":0}``.
+//!   Settings left out keep the scene's own, see ``RenderSettings``.
+//! - ``GET /jobs/<id>`` answers its progress in pixels, e.g.
+//!   ``{"id":0,"done":1200,"total":90000,"finished":false}``.
+//! - ``GET /jobs/<id>/events`` streams the same progress as server-sent events, ending with a
+//!   ``finished`` event, or an ``error`` event if the render failed.
+//! - ``GET /jobs/<id>/image`` returns the finished image as a PNG, ``409`` while it renders or
+//!   ``500`` if it failed.
+//!
+//! A failed render, e.g. one panicking, only fails its own job: the status of the job then
+//! carries the ``error``, and the jobs after it render as usual.
+//!
+//! To bound what a client can ask of the server, ``width``, ``samples`` and ``depth`` are
+//! clamped to ``MAX_WIDTH``, ``MAX_SAMPLES`` and ``MAX_DEPTH``, at most ``MAX_QUEUED_JOBS``
+//! renders wait or run at once, further ones answering ``503``, and only the last
+//! ``MAX_FINISHED_JOBS`` finished jobs are kept, older ones answering ``404``. Connections
+//! are bounded the same way: at most ``MAX_CONNECTIONS`` are answered at once, further ones
+//! answering ``503``, a request line and headers longer than ``MAX_HEADER_BYTES`` answer
+//! ``431``, and a client not sending or receiving for ``CONNECTION_TIMEOUT`` is dropped.
+use crate::error::{Error, Result};
+use crate::image::encode_png;
+use crate::scene;
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+
+/// How often ``/jobs/<id>/events`` checks the progress of a job.
+const EVENT_INTERVAL: Duration = Duration::from_millis(200);
+
+
+/// Largest image width a request may ask for.
+const MAX_WIDTH: i32 = 8192;
+
+
+/// Most samples per pixel a request may ask for.
+const MAX_SAMPLES: i32 = 4096;
+
+
+/// Deepest bounce a request may ask for.
+const MAX_DEPTH: i32 = 256;
+
+
+/// Most renders waiting or running at once.
+const MAX_QUEUED_JOBS: usize = 16;
+
+
+/// Most finished jobs kept for their status and image, the oldest are dropped first.
+const MAX_FINISHED_JOBS: usize = 64;
+
+
+/// Most connections answered at once, each taking a thread.
+const MAX_CONNECTIONS: usize = 64;
+
+
+/// Most bytes read of the request line and headers together.
+const MAX_HEADER_BYTES: u64 = 8192;
+
+
+/// How long a connection may wait for its client to send or receive anything.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+
+/// What to render, parsed from the query of a ``/render`` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderSettings {
+    /// One of the ``scene::SCENE_NAMES``.
+    pub scene: String,
+    pub width: Option<i32>,
+    pub samples_per_pixel: Option<i32>,
+    pub max_depth: Option<i32>,
+    pub seed: u64,
+}
+
+
+impl RenderSettings {
+    /// Parses ``scene``, ``width``, ``samples``, ``depth`` and ``seed`` from a query string
+    /// like ``scene=quads&samples=16``. Only ``scene`` is required.
+    pub fn from_query(query: &str) -> Result<Self> {
+        let mut scene = None;
+        let (mut width, mut samples_per_pixel, mut max_depth, mut seed) = (None, None, None, 0);
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let invalid = || Error::InvalidSetting { name: name.to_string(), value: value.to_string() };
+            let positive = || value.parse::<i32>().ok().filter(|&n| n > 0).ok_or_else(invalid);
+            match name {
+                "scene" if scene::SCENE_NAMES.contains(&value) => scene = Some(value.to_string()),
+                "width" => width = Some(positive()?.min(MAX_WIDTH)),
+                "samples" => samples_per_pixel = Some(positive()?.min(MAX_SAMPLES)),
+                "depth" => max_depth = Some(positive()?.min(MAX_DEPTH)),
+                "seed" => seed = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        let scene = scene.ok_or_else(|| Error::InvalidSetting { name: "scene".to_string(), value: String::new() })?;
+        Ok(Self { scene, width, samples_per_pixel, max_depth, seed })
+    }
+}
+
+
+/// A queued, running or finished render.
+struct Job {
+    done: AtomicU64,
+    total: AtomicU64,
+    /// The PNG of the render once it finished, or why it failed.
+    outcome: Mutex<Option<std::result::Result<Vec<u8>, String>>>,
+}
+
+
+impl Job {
+    fn new() -> Self {
+        Self { done: AtomicU64::new(0), total: AtomicU64::new(0), outcome: Mutex::new(None) }
+    }
+
+    fn status(&self, id: usize) -> String {
+        let error = match self.outcome.lock().unwrap().as_ref() {
+            Some(Err(error)) => format!(",\"error\":\"{}\"", error.replace('\\', "\\\\").replace('"', "\\\"")),
+            _ => String::new(),
+        };
+        format!(
+            "{{\"id\":{},\"done\":{},\"total\":{},\"finished\":{}{}}}",
+            id, self.done.load(Ordering::Relaxed), self.total.load(Ordering::Relaxed), self.is_finished(), error,
+        )
+    }
+
+    /// Whether the render is over, successfully or not.
+    fn is_finished(&self) -> bool {
+        self.outcome.lock().unwrap().is_some()
+    }
+
+    fn is_failed(&self) -> bool {
+        matches!(self.outcome.lock().unwrap().as_ref(), Some(Err(_)))
+    }
+}
+
+
+/// The jobs by their id, ids counting up from ``0`` and never reused.
+#[derive(Default)]
+struct Jobs {
+    by_id: BTreeMap<usize, Arc<Job>>,
+    next_id: usize,
+}
+
+
+impl Jobs {
+    /// Adds ``job``, dropping the oldest finished jobs beyond ``MAX_FINISHED_JOBS``, and
+    /// returns its id, failing when ``MAX_QUEUED_JOBS`` jobs already wait or run. Checking and
+    /// adding with the same borrow keeps requests arriving at once from passing the limit.
+    fn try_insert(&mut self, job: Arc<Job>) -> Result<usize> {
+        if self.queued() >= MAX_QUEUED_JOBS {
+            return Err(Error::QueueFull(MAX_QUEUED_JOBS));
+        }
+        let finished: Vec<usize> = self.by_id.iter()
+            .filter(|(_, job)| job.is_finished())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &finished[..finished.len().saturating_sub(MAX_FINISHED_JOBS)] {
+            self.by_id.remove(id);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.by_id.insert(id, job);
+        Ok(id)
+    }
+
+    /// How many jobs are waiting or running.
+    fn queued(&self) -> usize {
+        self.by_id.values().filter(|job| !job.is_finished()).count()
+    }
+}
+
+
+/// The render service, see the module documentation for its endpoints.
+#[derive(Default)]
+pub struct RenderServer {
+    jobs: Mutex<Jobs>,
+    /// Held by the running job, so renders take turns instead of sharing the cores.
+    render_lock: Arc<Mutex<()>>,
+    /// How many connections are being answered.
+    connections: AtomicUsize,
+}
+
+
+/// A connection counted in ``RenderServer::connections`` until dropped, also when its thread
+/// panics.
+struct ConnectionSlot<'a>(&'a AtomicUsize);
+
+
+impl Drop for ConnectionSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+
+impl RenderServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts connections on ``address`` forever, answering each on its own thread, up to
+    /// ``MAX_CONNECTIONS`` at once. Only failing to bind returns, a connection failing to be
+    /// accepted is skipped.
+    pub fn serve(self: Arc<Self>, address: impl ToSocketAddrs) -> std::io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Render server could not accept a connection: {}", error);
+                    continue;
+                }
+            };
+            if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                self.connections.fetch_sub(1, Ordering::SeqCst);
+                let _ = stream.set_write_timeout(Some(CONNECTION_TIMEOUT));
+                let _ = write_response(&mut stream, "503 Service Unavailable", "text/plain", b"Too many connections");
+                continue;
+            }
+            let server = self.clone();
+            thread::spawn(move || {
+                let _slot = ConnectionSlot(&server.connections);
+                if let Err(error) = server.handle_connection(stream) {
+                    eprintln!("Render server connection failed: {}", error);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?.take(MAX_HEADER_BYTES));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Skip the headers, requests carry everything in the target.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+        if reader.get_ref().limit() == 0 {
+            return write_response(&mut stream, "431 Request Header Fields Too Large", "text/plain", b"Request too long");
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        self.respond(method, target, &mut stream)
+    }
+
+    /// Answers the request for ``target`` with ``method`` on ``out``.
+    fn respond<W: Write>(&self, method: &str, target: &str, out: &mut W) -> std::io::Result<()> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            ("POST", ["render"]) => match RenderSettings::from_query(query).and_then(|settings| self.start(settings)) {
+                Ok(id) => write_response(out, "202 Accepted", "application/json", format!("{{\"id\":{}}}", id).as_bytes()),
+                Err(error @ Error::QueueFull(_)) => {
+                    write_response(out, "503 Service Unavailable", "text/plain", error.to_string().as_bytes())
+                }
+                Err(error) => write_response(out, "400 Bad Request", "text/plain", error.to_string().as_bytes()),
+            },
+            ("GET", ["jobs", id, rest @ ..]) => {
+                let Some((id, job)) = id.parse::<usize>().ok().and_then(|id| Some((id, self.job(id)?))) else {
+                    return write_response(out, "404 Not Found", "text/plain", b"No such job");
+                };
+                match rest {
+                    [] => write_response(out, "200 OK", "application/json", job.status(id).as_bytes()),
+                    ["events"] => stream_events(out, id, &job),
+                    ["image"] => match job.outcome.lock().unwrap().as_ref() {
+                        Some(Ok(png)) => write_response(out, "200 OK", "image/png", png),
+                        Some(Err(error)) => write_response(out, "500 Internal Server Error", "text/plain", error.as_bytes()),
+                        None => write_response(out, "409 Conflict", "text/plain", b"Still rendering"),
+                    },
+                    _ => write_response(out, "404 Not Found", "text/plain", b"Not found"),
+                }
+            }
+            _ => write_response(out, "404 Not Found", "text/plain", b"Not found"),
+        }
+    }
+
+    fn job(&self, id: usize) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().by_id.get(&id).cloned()
+    }
+
+    /// Queues a render and returns the id of its job, failing for scenes there are none of or
+    /// with the queue full.
+    fn start(&self, settings: RenderSettings) -> Result<usize> {
+        if !scene::SCENE_NAMES.contains(&settings.scene.as_str()) {
+            return Err(Error::InvalidSetting { name: "scene".to_string(), value: settings.scene });
+        }
+        let job = Arc::new(Job::new());
+        let id = self.jobs.lock().unwrap().try_insert(job.clone())?;
+
+        let render_lock = self.render_lock.clone();
+        thread::spawn(move || {
+            // A job panicking while holding the lock leaves nothing behind it to protect.
+            let _turn = render_lock.lock().unwrap_or_else(PoisonError::into_inner);
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| render(&settings, &job)))
+                .unwrap_or_else(|panic| {
+                    let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "The render panicked".to_string());
+                    Err(message)
+                });
+            *job.outcome.lock().unwrap() = Some(outcome);
+        });
+        Ok(id)
+    }
+}
+
+
+/// Renders the job with ``settings``, reporting its progress on ``job``, and returns the PNG.
+fn render(settings: &RenderSettings, job: &Arc<Job>) -> std::result::Result<Vec<u8>, String> {
+    let (mut camera, world) = scene::by_name(&settings.scene).ok_or_else(|| format!("No scene {}", settings.scene))?;
+    if let Some(width) = settings.width { camera.set_resolution_width(width); }
+    if let Some(samples) = settings.samples_per_pixel { camera.set_samples_per_pixel(samples); }
+    if let Some(depth) = settings.max_depth { camera.set_depth(depth); }
+    camera.set_seed(settings.seed);
+
+    let progress = job.clone();
+    camera.set_progress(move |done, total| {
+        progress.total.store(total, Ordering::Relaxed);
+        progress.done.store(done, Ordering::Relaxed);
+    });
+    let image = camera.render(&world);
+    Ok(encode_png(&image, camera.resolution_width(), camera.resolution_height()))
+}
+
+
+fn write_response<W: Write>(out: &mut W, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    write!(
+        out, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len(),
+    )?;
+    out.write_all(body)?;
+    out.flush()
+}
+
+
+/// Sends the status of ``job`` as a server-sent event whenever it changes, until it finishes
+/// or fails.
+fn stream_events<W: Write>(out: &mut W, id: usize, job: &Job) -> std::io::Result<()> {
+    write!(out, "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")?;
+    let mut last = String::new();
+    loop {
+        let finished = job.is_finished();
+        let status = job.status(id);
+        if finished {
+            let event = if job.is_failed() { "error" } else { "finished" };
+            write!(out, "event: {}\ndata: {}\n\n", event, status)?;
+            return out.flush();
+        }
+        if status != last {
+            write!(out, "data: {}\n\n", status)?;
+            out.flush()?;
+            last = status;
+        }
+        thread::sleep(EVENT_INTERVAL);
+    }
+}
+
+
+#[cfg(test)]
+mod test_server {
+    use super::*;
+
+    fn request(server: &RenderServer, method: &str, target: &str) -> (String, Vec<u8>) {
+        let mut out = Vec::new();
+        server.respond(method, target, &mut out).unwrap();
+        let split = out.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(out[..split].to_vec()).unwrap();
+        (head.lines().next().unwrap().to_string(), out[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn test_settings_from_query() {
+        let settings = RenderSettings::from_query("scene=quads&width=64&samples=4&seed=9").unwrap();
+        assert_eq!(settings, RenderSettings {
+            scene: "quads".to_string(), width: Some(64), samples_per_pixel: Some(4), max_depth: None, seed: 9,
+        });
+        assert!(matches!(
+            RenderSettings::from_query("scene=teapot"),
+            Err(Error::InvalidSetting { name, value }) if name == "scene" && value == "teapot",
+        ));
+        assert!(RenderSettings::from_query("scene=quads&samples=0").is_err());
+        assert!(RenderSettings::from_query("scene=quads&exposure=2").is_err());
+        assert!(RenderSettings::from_query("width=64").is_err());
+    }
+
+    #[test]
+    fn test_render_job() {
+        let server = RenderServer::new();
+        let (status, body) = request(&server, "POST", "/render?scene=cornell_box&width=8&samples=1&depth=2");
+        assert_eq!(status, "HTTP/1.1 202 Accepted");
+        assert_eq!(body, b"{\"id\":0}");
+
+        // The events only end once the job is done.
+        let (status, events) = request(&server, "GET", "/jobs/0/events");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let events = String::from_utf8(events).unwrap();
+        assert!(events.ends_with("event: finished\ndata: {\"id\":0,\"done\":64,\"total\":64,\"finished\":true}\n\n"), "{}", events);
+
+        let (_, body) = request(&server, "GET", "/jobs/0");
+        assert_eq!(body, b"{\"id\":0,\"done\":64,\"total\":64,\"finished\":true}");
+        let (status, png) = request(&server, "GET", "/jobs/0/image");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[test]
+    fn test_failed_job() {
+        let server = RenderServer::new();
+        let job = Arc::new(Job::new());
+        server.jobs.lock().unwrap().try_insert(job.clone()).unwrap();
+        *job.outcome.lock().unwrap() = Some(Err("Out of \"memory\"".to_string()));
+
+        let (_, events) = request(&server, "GET", "/jobs/0/events");
+        let events = String::from_utf8(events).unwrap();
+        assert!(events.ends_with("event: error\ndata: {\"id\":0,\"done\":0,\"total\":0,\"finished\":true,\"error\":\"Out of \\\"memory\\\"\"}\n\n"), "{}", events);
+        assert_eq!(request(&server, "GET", "/jobs/0/image").0, "HTTP/1.1 500 Internal Server Error");
+
+        // A job panicking with the render lock leaves it usable for the next ones.
+        let lock = server.render_lock.clone();
+        let _ = thread::spawn(move || {
+            let _turn = lock.lock().unwrap();
+            panic!("Render failed");
+        }).join();
+        assert!(server.render_lock.is_poisoned());
+        request(&server, "POST", "/render?scene=cornell_box&width=8&samples=1&depth=1");
+        let (_, events) = request(&server, "GET", "/jobs/1/events");
+        assert!(String::from_utf8(events).unwrap().contains("event: finished\n"));
+    }
+
+    #[test]
+    fn test_job_limits() {
+        let settings = RenderSettings::from_query("scene=quads&samples=100000&depth=100000").unwrap();
+        assert_eq!((settings.samples_per_pixel, settings.max_depth), (Some(MAX_SAMPLES), Some(MAX_DEPTH)));
+
+        let server = RenderServer::new();
+        let jobs: Vec<Arc<Job>> = (0..MAX_QUEUED_JOBS).map(|_| Arc::new(Job::new())).collect();
+        for job in &jobs {
+            server.jobs.lock().unwrap().try_insert(job.clone()).unwrap();
+        }
+        assert_eq!(request(&server, "POST", "/render?scene=quads").0, "HTTP/1.1 503 Service Unavailable");
+
+        for job in &jobs {
+            *job.outcome.lock().unwrap() = Some(Ok(Vec::new()));
+        }
+        for _ in 0..MAX_FINISHED_JOBS {
+            let job = Arc::new(Job::new());
+            *job.outcome.lock().unwrap() = Some(Ok(Vec::new()));
+            server.jobs.lock().unwrap().try_insert(job).unwrap();
+        }
+        let total = MAX_QUEUED_JOBS + MAX_FINISHED_JOBS;
+        server.jobs.lock().unwrap().try_insert(Arc::new(Job::new())).unwrap();
+        assert_eq!(server.jobs.lock().unwrap().by_id.len(), MAX_FINISHED_JOBS + 1);
+        assert_eq!(request(&server, "GET", "/jobs/0").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(request(&server, "GET", &format!("/jobs/{}", total - 1)).0, "HTTP/1.1 200 OK");
+        assert_eq!(request(&server, "GET", &format!("/jobs/{}", total)).0, "HTTP/1.1 200 OK");
+
+        // Requests arriving at once, with the renders held back, queue no more than the limit.
+        let server = RenderServer::new();
+        let turn = server.render_lock.lock().unwrap();
+        thread::scope(|scope| {
+            for _ in 0..2 * MAX_QUEUED_JOBS {
+                scope.spawn(|| request(&server, "POST", "/render?scene=cornell_box&width=8&samples=1&depth=1"));
+            }
+        });
+        assert_eq!(server.jobs.lock().unwrap().queued(), MAX_QUEUED_JOBS);
+        drop(turn);
+    }
+
+    #[test]
+    fn test_long_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let answer = thread::spawn(move || RenderServer::new().handle_connection(stream));
+
+        // A request line without an end is answered once it reaches the limit, rather than read
+        // for as long as the client sends it.
+        let line = vec![b'a'; MAX_HEADER_BYTES as usize];
+        client.write_all(&line).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"), "{}", response);
+        answer.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let server = RenderServer::new();
+        assert_eq!(request(&server, "POST", "/render?scene=teapot").0, "HTTP/1.1 400 Bad Request");
+        assert_eq!(request(&server, "GET", "/jobs/3").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(request(&server, "GET", "/jobs/zero/image").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(request(&server, "DELETE", "/render").0, "HTTP/1.1 404 Not Found");
+    }
+}
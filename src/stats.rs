@@ -0,0 +1,209 @@
+//! Opt-in render statistics.
+//!
+//! With the ``stats`` crate feature enabled, the renderer counts traced rays, visited BVH
+//! nodes and texture lookups, and times BVH construction and rendering. Counters are kept per
+//! thread and only flushed into the process wide totals once per rendered tile, so the cost
+//! in the traversal hot path is a thread local increment. Without the feature every hook
+//! compiles to nothing and all statistics read as zero.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::stats;
+//! stats::reset();
+//! // ... build a scene and render it ...
+//! println!("{}", stats::snapshot());
+//! ```
+use std::fmt::Formatter;
+use std::ops::Sub;
+use std::time::Duration;
+
+
+/// Statistics gathered while building and rendering scenes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    /// Camera rays, one per pixel sample.
+    pub primary_rays: u64,
+    /// Rays scattered off surfaces and media.
+    pub secondary_rays: u64,
+    /// Rays only testing visibility, e.g. towards lights.
+    pub shadow_rays: u64,
+    pub bvh_nodes_visited: u64,
+    pub texture_lookups: u64,
+    pub bvh_build_time: Duration,
+    pub render_time: Duration,
+}
+
+
+impl RenderStats {
+    /// Total number of rays traced.
+    pub fn total_rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+}
+
+
+/// Difference between two snapshots, e.g. the statistics of a single render.
+impl Sub for RenderStats {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            primary_rays: self.primary_rays.saturating_sub(rhs.primary_rays),
+            secondary_rays: self.secondary_rays.saturating_sub(rhs.secondary_rays),
+            shadow_rays: self.shadow_rays.saturating_sub(rhs.shadow_rays),
+            bvh_nodes_visited: self.bvh_nodes_visited.saturating_sub(rhs.bvh_nodes_visited),
+            texture_lookups: self.texture_lookups.saturating_sub(rhs.texture_lookups),
+            bvh_build_time: self.bvh_build_time.saturating_sub(rhs.bvh_build_time),
+            render_time: self.render_time.saturating_sub(rhs.render_time),
+        }
+    }
+}
+
+
+impl std::fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Rays traced:       {}", self.total_rays())?;
+        writeln!(f, "  primary:         {}", self.primary_rays)?;
+        writeln!(f, "  secondary:       {}", self.secondary_rays)?;
+        writeln!(f, "  shadow:          {}", self.shadow_rays)?;
+        writeln!(f, "BVH nodes visited: {}", self.bvh_nodes_visited)?;
+        writeln!(f, "Texture lookups:   {}", self.texture_lookups)?;
+        writeln!(f, "BVH build time:    {:?}", self.bvh_build_time)?;
+        write!(f, "Render time:       {:?}", self.render_time)
+    }
+}
+
+
+/// Statistic updated by the renderer hooks.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Counter {
+    PrimaryRays,
+    SecondaryRays,
+    ShadowRays,
+    BvhNodesVisited,
+    TextureLookups,
+    BvhBuildNanos,
+    RenderNanos,
+}
+
+#[cfg(feature = "stats")]
+const COUNTER_COUNT: usize = 7;
+
+
+#[cfg(feature = "stats")]
+mod counters {
+    use super::COUNTER_COUNT;
+    use std::cell::RefCell;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static GLOBAL: [AtomicU64; COUNTER_COUNT] = [const { AtomicU64::new(0) }; COUNTER_COUNT];
+
+    thread_local! {
+        pub static LOCAL: RefCell<[u64; COUNTER_COUNT]> = const { RefCell::new([0; COUNTER_COUNT]) };
+    }
+
+    pub fn flush() {
+        LOCAL.with(|local| {
+            for (global, value) in GLOBAL.iter().zip(local.borrow_mut().iter_mut()) {
+                if *value > 0 {
+                    global.fetch_add(*value, Ordering::Relaxed);
+                    *value = 0;
+                }
+            }
+        });
+    }
+}
+
+
+/// Adds ``amount`` to the calling thread's pending value of ``counter``.
+#[inline]
+pub(crate) fn add(_counter: Counter, _amount: u64) {
+    #[cfg(feature = "stats")]
+    counters::LOCAL.with(|local| local.borrow_mut()[_counter as usize] += _amount);
+}
+
+
+#[inline]
+pub(crate) fn count(counter: Counter) {
+    add(counter, 1);
+}
+
+
+#[inline]
+pub(crate) fn add_time(counter: Counter, duration: Duration) {
+    add(counter, duration.as_nanos() as u64);
+}
+
+
+/// Publishes the calling thread's pending counts to the process wide totals.
+#[inline]
+pub(crate) fn flush() {
+    #[cfg(feature = "stats")]
+    counters::flush();
+}
+
+
+/// Returns the statistics accumulated since the start of the process or the last ``reset``.
+///
+/// Always zero unless the ``stats`` feature is enabled.
+pub fn snapshot() -> RenderStats {
+    #[cfg(feature = "stats")]
+    {
+        use std::sync::atomic::Ordering;
+
+        flush();
+        let value = |counter: Counter| counters::GLOBAL[counter as usize].load(Ordering::Relaxed);
+        RenderStats {
+            primary_rays: value(Counter::PrimaryRays),
+            secondary_rays: value(Counter::SecondaryRays),
+            shadow_rays: value(Counter::ShadowRays),
+            bvh_nodes_visited: value(Counter::BvhNodesVisited),
+            texture_lookups: value(Counter::TextureLookups),
+            bvh_build_time: Duration::from_nanos(value(Counter::BvhBuildNanos)),
+            render_time: Duration::from_nanos(value(Counter::RenderNanos)),
+        }
+    }
+    #[cfg(not(feature = "stats"))]
+    RenderStats::default()
+}
+
+
+/// Clears the accumulated statistics.
+pub fn reset() {
+    #[cfg(feature = "stats")]
+    {
+        use std::sync::atomic::Ordering;
+
+        counters::LOCAL.with(|local| *local.borrow_mut() = [0; COUNTER_COUNT]);
+        for global in counters::GLOBAL.iter() {
+            global.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test_stats {
+    use super::*;
+
+    #[test]
+    fn test_render_stats_sub() {
+        let before = RenderStats { primary_rays: 2, render_time: Duration::from_secs(1), ..Default::default() };
+        let after = RenderStats { primary_rays: 5, secondary_rays: 3, render_time: Duration::from_secs(3), ..Default::default() };
+        let diff = after - before;
+        assert_eq!(diff.primary_rays, 3);
+        assert_eq!(diff.secondary_rays, 3);
+        assert_eq!(diff.total_rays(), 6);
+        assert_eq!(diff.render_time, Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_counters_flushed_on_snapshot() {
+        // Other tests may render concurrently, so only check the counts grow.
+        let before = snapshot();
+        add(Counter::TextureLookups, 3);
+        let after = snapshot();
+        assert!(after.texture_lookups >= before.texture_lookups + 3);
+    }
+}
@@ -0,0 +1,165 @@
+//! Splitting the image into tiles rendered as independent tasks, and the order they are
+//! scheduled in.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::tile::{tiles, TileOrder};
+//! let order = tiles(100, 60, 16, TileOrder::Spiral);
+//! assert_eq!(order.len(), 7 * 4);
+//! // The tile holding the center of the image comes first.
+//! assert!(order[0].contains(50, 30));
+//! ```
+
+
+/// Order tiles are scheduled in, set with ``Camera::set_tile_order``.
+///
+/// Tiles are picked up by the worker threads roughly in this order, so it decides which part
+/// of the image finishes first, which matters for progressive previews.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    /// Row by row from the top left, like reading.
+    #[default]
+    Scanline,
+    /// Ring by ring outwards from the center, so the subject usually shows first.
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive tiles next to each other and the scene
+    /// data they touch in the caches.
+    Hilbert,
+}
+
+
+/// A rectangle of pixels, starting at ``x`` and ``y`` from the top left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+
+impl Tile {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Whether any pixel is in both tiles.
+    pub fn overlaps(&self, other: &Tile) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width
+            && self.y < other.y + other.height && other.y < self.y + self.height
+    }
+
+    /// The pixels of the tile row by row, as ``(x, y)``.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height).flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+
+/// Covers a ``width x height`` image with tiles of ``size x size`` pixels, smaller along the
+/// right and bottom edges, listed in the given ``order``. A size of ``0`` is treated as ``1``.
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+    let mut grid: Vec<(usize, usize)> = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect();
+
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            // Ring around the center tile first, then clockwise from straight up.
+            let (cx, cy) = ((columns as f64 - 1.0) / 2.0, (rows as f64 - 1.0) / 2.0);
+            let key = |&(column, row): &(usize, usize)| {
+                let (dx, dy) = (column as f64 - cx, row as f64 - cy);
+                let ring = dx.abs().max(dy.abs()).floor();
+                let angle = dx.atan2(-dy).rem_euclid(std::f64::consts::TAU);
+                (ring, angle)
+            };
+            grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(column, row)| Tile {
+            x: column * size,
+            y: row * size,
+            width: size.min(width - column * size),
+            height: size.min(height - row * size),
+        })
+        .collect()
+}
+
+
+/// Distance along the Hilbert curve filling a ``side x side`` grid, ``side`` being a power of
+/// two, to the cell at ``x`` and ``y``.
+fn hilbert_index(side: usize, mut x: usize, mut y: usize) -> usize {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = usize::from(x & s > 0);
+        let ry = usize::from(y & s > 0);
+        index += s * s * ((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve inside it starts where the last one ended.
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= s - 1;
+        y &= s - 1;
+        s /= 2;
+    }
+    index
+}
+
+
+#[cfg(test)]
+mod test_tile {
+    use super::*;
+
+    fn assert_covers_once(tiles: &[Tile], width: usize, height: usize) {
+        let mut covered = vec![0; width * height];
+        for tile in tiles {
+            for (x, y) in tile.pixels() {
+                covered[y * width + x] += 1;
+            }
+        }
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_tiles_cover_image() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let list = tiles(37, 21, 8, order);
+            assert_eq!(list.len(), 5 * 3);
+            assert_covers_once(&list, 37, 21);
+        }
+        assert_eq!(tiles(3, 2, 0, TileOrder::Scanline).len(), 6);
+        assert_eq!(tiles(37, 21, 8, TileOrder::Scanline)[4], Tile { x: 32, y: 0, width: 5, height: 8 });
+    }
+
+    #[test]
+    fn test_spiral_grows_from_center() {
+        let list = tiles(50, 50, 10, TileOrder::Spiral);
+        assert_eq!(list[0], Tile { x: 20, y: 20, width: 10, height: 10 });
+        // The first ring around the center tile comes next.
+        for tile in &list[1..9] {
+            assert!(tile.x.abs_diff(20) <= 10 && tile.y.abs_diff(20) <= 10, "{:?}", tile);
+        }
+    }
+
+    #[test]
+    fn test_hilbert_steps_to_neighbors() {
+        let list = tiles(64, 64, 8, TileOrder::Hilbert);
+        assert_eq!((list[0].x, list[0].y), (0, 0));
+        for pair in list.windows(2) {
+            let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(distance, 8, "{:?}", pair);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! The tolerances geometry is tested against, shared by all primitives and tunable while
+//! setting up a scene.
+//!
+//! The defaults suit scenes of roughly unit scale. Far larger coordinates, e.g. a city in
+//! millimetres, lose precision faster than the tolerances grow: rays then slip through the
+//! edges of quads and triangles or scatter into degenerate directions, showing up as speckles.
+//! Raising all tolerances together with ``Tolerance::scaled`` fixes this consistently,
+//! along with ``Camera::set_hit_epsilon``.
+//!
+//! # Examples
+//! ```
+//! use ray_tracing::tolerance::{self, Tolerance};
+//! use ray_tracing::vec3d::Vec3d;
+//! assert!(Vec3d::new(1e-20, 0.0, 0.0).near_zero());
+//! assert!(!Vec3d::new(1e-6, 0.0, 0.0).near_zero());
+//! tolerance::set(Tolerance::DEFAULT.scaled(1e12));
+//! assert!(Vec3d::new(1e-6, 0.0, 0.0).near_zero());
+//! tolerance::set(Tolerance::DEFAULT);
+//! ```
+use crate::vec3d::Float;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+
+/// Tolerances of degenerate geometry, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Largest component of a vector counting as zero, see ``Vec3d::near_zero``, e.g. for
+    /// scattered directions to replace.
+    pub near_zero: Float,
+    /// Largest denominator of a ray intersection counting as the ray running parallel to the
+    /// surface, e.g. to the plane of a quad or triangle, which it then misses.
+    pub parallel: Float,
+    /// Minimum extent of a bounding box along any axis. Flat primitives such as quads get
+    /// padded to this size so the slab test can still hit them.
+    pub min_padding: Float,
+}
+
+
+impl Tolerance {
+    /// The tolerances of unit sized scenes. Single precision needs a coarser padding.
+    pub const DEFAULT: Self = Self {
+        near_zero: Float::EPSILON,
+        parallel: Float::EPSILON,
+        #[cfg(not(feature = "f32"))]
+        min_padding: f32::EPSILON as Float,
+        #[cfg(feature = "f32")]
+        min_padding: 1e-4,
+    };
+
+    /// All tolerances multiplied by ``scale``, e.g. the extent of the scene.
+    pub fn scaled(self, scale: Float) -> Self {
+        Self { near_zero: self.near_zero * scale, parallel: self.parallel * scale, min_padding: self.min_padding * scale }
+    }
+}
+
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+
+// Stored as the bits of an ``f64`` whatever the precision of ``Float``.
+static NEAR_ZERO: AtomicU64 = AtomicU64::new(to_bits(Tolerance::DEFAULT.near_zero));
+static PARALLEL: AtomicU64 = AtomicU64::new(to_bits(Tolerance::DEFAULT.parallel));
+static MIN_PADDING: AtomicU64 = AtomicU64::new(to_bits(Tolerance::DEFAULT.min_padding));
+
+
+#[allow(clippy::unnecessary_cast)]
+const fn to_bits(value: Float) -> u64 {
+    (value as f64).to_bits()
+}
+
+
+#[allow(clippy::unnecessary_cast)]
+fn load(value: &AtomicU64) -> Float {
+    f64::from_bits(value.load(Ordering::Relaxed)) as Float
+}
+
+
+/// Sets the tolerances for the whole process. Bounding boxes are padded when they are made,
+/// so set them before building the scene.
+pub fn set(tolerance: Tolerance) {
+    NEAR_ZERO.store(to_bits(tolerance.near_zero), Ordering::Relaxed);
+    PARALLEL.store(to_bits(tolerance.parallel), Ordering::Relaxed);
+    MIN_PADDING.store(to_bits(tolerance.min_padding), Ordering::Relaxed);
+}
+
+
+/// The tolerances in use, ``Tolerance::DEFAULT`` unless ``set``.
+pub fn current() -> Tolerance {
+    Tolerance { near_zero: near_zero(), parallel: parallel(), min_padding: min_padding() }
+}
+
+
+/// See ``Tolerance::near_zero``.
+pub fn near_zero() -> Float {
+    load(&NEAR_ZERO)
+}
+
+
+/// See ``Tolerance::parallel``.
+pub fn parallel() -> Float {
+    load(&PARALLEL)
+}
+
+
+/// See ``Tolerance::min_padding``.
+pub fn min_padding() -> Float {
+    load(&MIN_PADDING)
+}
+
+
+#[cfg(test)]
+mod test_tolerance {
+    use super::*;
+
+    #[test]
+    fn test_scaled() {
+        let coarse = Tolerance::DEFAULT.scaled(1000.0);
+        assert_eq!(coarse.near_zero, Float::EPSILON * 1000.0);
+        assert_eq!(coarse.min_padding, Tolerance::DEFAULT.min_padding * 1000.0);
+        // Tests run in parallel, so only the defaults are checked, never changed.
+        assert_eq!(current(), Tolerance::default());
+    }
+}
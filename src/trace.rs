@@ -0,0 +1,50 @@
+//! Feature gated structured diagnostics.
+//!
+//! With the ``log`` crate feature enabled, scene building, BVH construction and rendering emit
+//! records through the [``log``](https://docs.rs/log) facade under the ``ray_tracing`` target:
+//! a span logs when it is entered and, on drop, how long it took. Any ``log`` backend can
+//! consume them, including ``tracing`` subscribers through ``tracing-log``. Without the feature
+//! spans and events compile to nothing.
+use std::time::Instant;
+
+
+/// Logs an event at the given ``log`` level macro, e.g. ``event!(info, "{} objects", n)``.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::$level!(target: "ray_tracing", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false { let _ = format_args!($($arg)+); }
+    }};
+}
+
+pub(crate) use event;
+
+
+/// A timed section of work, reported when created and when dropped.
+#[must_use = "the span ends as soon as it is dropped"]
+pub(crate) struct Span {
+    name: &'static str,
+    start: Instant,
+}
+
+
+impl Span {
+    pub(crate) fn enter(name: &'static str) -> Self {
+        event!(debug, "{}: started", name);
+        Self { name, start: Instant::now() }
+    }
+}
+
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        event!(debug, "{}: finished in {:?}", self.name, self.start.elapsed());
+    }
+}
+
+
+/// Enters a span named ``name`` that lasts until the returned guard is dropped.
+pub(crate) fn span(name: &'static str) -> Span {
+    Span::enter(name)
+}
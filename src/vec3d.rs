@@ -13,22 +13,42 @@ use std::ops::{
     IndexMut,
 };
 use rand::Rng;
-use rand::distr::{Distribution, Standard};
+use rand::distr::{Distribution, StandardUniform};
+
+use crate::sampler;
+use crate::tolerance;
+
+
+/// Floating point type used by every geometric and color computation in the crate.
+///
+/// Defaults to ``f64``. Enabling the ``f32`` crate feature switches the whole renderer to
+/// single precision, which roughly halves the memory used by BVH and scene data and is
+/// usually accurate enough for preview renders.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+/// Mathematical constants matching the selected ``Float`` precision.
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3d {
-    vector: [f64; 3],
+    vector: [Float; 3],
 }
 
 pub use Vec3d as Point3d;
-pub use Vec3d as Color;
 
 /// Implementation of ``Vec3d``
 ///
 /// This is a struct that represents a 3D vector with x, y, and z components.
-/// Normally, this struct is used to represent points in 3D space, but it can also be used to
-/// represent colors. In this particular library, it's used to represent vectors or points in
-/// 3D space, and can be used for various calculations.
+/// In this particular library, it's used to represent vectors or points in 3D space, and can
+/// be used for various calculations. Colors have their own ``color::Color`` type.
 /// Most of this struct's implementation follows the same pattern in the website
 /// [Ray Tracing in One Weekend](https://raytracing.github.io/books/RayTracingInOneWeekend.html)
 ///
@@ -41,7 +61,7 @@ pub use Vec3d as Color;
 /// assert_eq!(vec.z(), 3.0);
 /// ```
 impl Vec3d {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: Float, y: Float, z: Float) -> Self {
         Self {
             vector: [x, y, z],
         }
@@ -58,11 +78,11 @@ impl Vec3d {
         Self::new(0.0, 0.0, 0.0)
     }
 
-    pub fn x(&self) -> f64 { self.vector[0] }
+    pub fn x(&self) -> Float { self.vector[0] }
 
-    pub fn y(&self) -> f64 { self.vector[1] }
+    pub fn y(&self) -> Float { self.vector[1] }
 
-    pub fn z(&self) -> f64 { self.vector[2] }
+    pub fn z(&self) -> Float { self.vector[2] }
 
     /// Returns the length of the vector
     /// # Examples
@@ -72,7 +92,7 @@ impl Vec3d {
     /// assert_eq!(vec.length(), 3.7416573867739413);
     /// ```
     #[inline]
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> Float {
         self.length_squared().sqrt()
     }
 
@@ -84,7 +104,7 @@ impl Vec3d {
     /// assert_eq!(vec.length_squared(), 14.0);
     /// ```
     #[inline]
-    pub fn length_squared(&self) -> f64 {
+    pub fn length_squared(&self) -> Float {
         self.x().powi(2) + self.y().powi(2) + self.z().powi(2)
     }
 
@@ -102,16 +122,16 @@ impl Vec3d {
     }
 
     pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = sampler::rng();
         rng.random()
     }
 
-    pub fn gen_range(min: f64, max: f64) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn gen_range(min: Float, max: Float) -> Self {
+        let mut rng = sampler::rng();
         Vec3d::new(
-            rng.gen_range(min..max),
-            rng.gen_range(min..max),
-            rng.gen_range(min..max),
+            rng.random_range(min..max),
+            rng.random_range(min..max),
+            rng.random_range(min..max),
         )
     }
 
@@ -153,17 +173,19 @@ impl Vec3d {
         }
     }
 
+    /// Whether every component is within ``tolerance::near_zero`` of zero.
     pub fn near_zero(&self) -> bool {
-        self.x().abs() < f64::EPSILON &&
-            self.y().abs() < f64::EPSILON &&
-            self.z().abs() < f64::EPSILON
+        let epsilon = tolerance::near_zero();
+        self.x().abs() < epsilon &&
+            self.y().abs() < epsilon &&
+            self.z().abs() < epsilon
     }
 
     #[inline]
     fn zip_with(
         &self,
         other: &Vec3d,
-        mut f: impl FnMut(f64, f64) -> f64,
+        mut f: impl FnMut(Float, Float) -> Float,
     ) -> Self {
         Vec3d::new(
             f(self.x(), other.x()),
@@ -172,13 +194,67 @@ impl Vec3d {
         )
     }
 
+    /// Component-wise minimum of two vectors.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::vec3d::Vec3d;
+    /// let vec = Vec3d::new(1.0, 5.0, 3.0).min(&Vec3d::new(4.0, 2.0, 3.0));
+    /// assert_eq!(vec, Vec3d::new(1.0, 2.0, 3.0));
+    /// ```
     #[inline]
-    pub fn reduce(&self, f: impl Fn(f64, f64) -> f64) -> f64 {
+    pub fn min(&self, other: &Vec3d) -> Self {
+        self.zip_with(other, Float::min)
+    }
+
+    /// Component-wise maximum of two vectors.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::vec3d::Vec3d;
+    /// let vec = Vec3d::new(1.0, 5.0, 3.0).max(&Vec3d::new(4.0, 2.0, 3.0));
+    /// assert_eq!(vec, Vec3d::new(4.0, 5.0, 3.0));
+    /// ```
+    #[inline]
+    pub fn max(&self, other: &Vec3d) -> Self {
+        self.zip_with(other, Float::max)
+    }
+
+    /// Clamps every component between the matching components of ``min`` and ``max``.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::vec3d::Vec3d;
+    /// let vec = Vec3d::new(-1.0, 0.5, 3.0).clamp(&Vec3d::zero(), &Vec3d::new(1.0, 1.0, 1.0));
+    /// assert_eq!(vec, Vec3d::new(0.0, 0.5, 1.0));
+    /// ```
+    #[inline]
+    pub fn clamp(&self, min: &Vec3d, max: &Vec3d) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// Component-wise absolute value.
+    #[inline]
+    pub fn abs(&self) -> Self {
+        self.map(Float::abs)
+    }
+
+    /// Component-wise floor.
+    /// # Examples
+    /// ```
+    /// use ray_tracing::vec3d::Vec3d;
+    /// let vec = Vec3d::new(-1.5, 0.5, 3.0).floor();
+    /// assert_eq!(vec, Vec3d::new(-2.0, 0.0, 3.0));
+    /// ```
+    #[inline]
+    pub fn floor(&self) -> Self {
+        self.map(Float::floor)
+    }
+
+    #[inline]
+    pub fn reduce(&self, f: impl Fn(Float, Float) -> Float) -> Float {
         f(f(self.x(), self.y()), self.z())
     }
 
     #[inline]
-    pub fn map(&self, f: impl Fn(f64) -> f64) -> Self {
+    pub fn map(&self, f: impl Fn(Float) -> Float) -> Self {
         Vec3d::new(f(self.x()), f(self.y()), f(self.z()))
     }
 }
@@ -193,9 +269,9 @@ impl Vec3d {
 /// let mut rng = rand::thread_rng();
 /// let vec: Vec3d = rng.random();
 /// ```
-impl Distribution<Vec3d> for Standard {
+impl Distribution<Vec3d> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec3d {
-        let (x, y, z) = rng.random::<(f64, f64, f64)>();
+        let (x, y, z) = rng.random::<(Float, Float, Float)>();
         Vec3d::new(x, y, z)
     }
 }
@@ -225,7 +301,7 @@ impl std::fmt::Display for Vec3d {
 /// assert_eq!(result, 32.0);
 /// ```
 #[inline]
-pub fn dot(v1: &Vec3d, v2: &Vec3d) -> f64 {
+pub fn dot(v1: &Vec3d, v2: &Vec3d) -> Float {
     v1.zip_with(v2, Mul::mul).reduce(Add::add)
 }
 
@@ -240,11 +316,64 @@ pub fn dot(v1: &Vec3d, v2: &Vec3d) -> f64 {
 /// assert_eq!(result, 5.0);
 /// ```
 #[inline]
-pub fn distance<'a>(v1: &'a Vec3d, v2: &'a Vec3d) -> f64 {
+pub fn distance<'a>(v1: &'a Vec3d, v2: &'a Vec3d) -> Float {
     (*v1 - *v2).length()
 }
 
 
+/// Reflects a vector about a normal, as a mirror would.
+/// # Arguments
+/// * `v_in` - The incoming direction.
+/// * `normal` - The unit surface normal.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::{Vec3d, reflect};
+/// let v_in = Vec3d::new(1.0, -1.0, 0.0);
+/// let normal = Vec3d::new(0.0, 1.0, 0.0);
+/// assert_eq!(reflect(&v_in, &normal), Vec3d::new(1.0, 1.0, 0.0));
+/// ```
+#[inline]
+pub fn reflect(v_in: &Vec3d, normal: &Vec3d) -> Vec3d {
+    *v_in - *normal * dot(v_in, normal) * 2.0
+}
+
+
+/// Refracts a unit vector through a surface following Snell's law.
+/// # Arguments
+/// * `v_in` - The unit incoming direction.
+/// * `normal` - The unit surface normal, facing against ``v_in``.
+/// * `etai_over_etat` - The ratio of the refractive indices on the incoming and outgoing side.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::{Vec3d, refract};
+/// let v_in = Vec3d::new(0.0, -1.0, 0.0);
+/// let normal = Vec3d::new(0.0, 1.0, 0.0);
+/// assert_eq!(refract(&v_in, &normal, 1.5), Vec3d::new(0.0, -1.0, 0.0));
+/// ```
+#[inline]
+pub fn refract(v_in: &Vec3d, normal: &Vec3d, etai_over_etat: Float) -> Vec3d {
+    let cos_theta = dot(&-*v_in, normal).min(1.0);
+    let r_out_perp = (*v_in + *normal * cos_theta) * etai_over_etat;
+    let r_out_parallel = *normal * -1.0 * (1.0 - r_out_perp.length_squared()).abs().sqrt();
+    r_out_perp + r_out_parallel
+}
+
+
+/// Linear interpolation between two vectors, returning ``a`` at ``t = 0`` and ``b`` at ``t = 1``.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::{Vec3d, lerp};
+/// let vec = Vec3d::new(0.0, 2.0, 4.0);
+/// let vec2 = Vec3d::new(2.0, 2.0, 0.0);
+/// let result = lerp(&vec, &vec2, 0.25);
+/// assert_eq!(result, Vec3d::new(0.5, 2.0, 3.0));
+/// ```
+#[inline]
+pub fn lerp(a: &Vec3d, b: &Vec3d, t: Float) -> Vec3d {
+    *a + (*b - *a) * t
+}
+
+
 /// The cross product of two Vec3d vectors
 /// # Examples
 /// ```
@@ -314,11 +443,11 @@ impl Add<Vec3d> for Vec3d {
 /// let result = vec + 2.0;
 /// assert_eq!(result, Vec3d::new(3.0, 4.0, 5.0));
 /// ```
-impl Add<f64> for Vec3d {
+impl Add<Float> for Vec3d {
     type Output = Self;
 
     #[inline]
-    fn add(self, rhs: f64) -> Self::Output {
+    fn add(self, rhs: Float) -> Self::Output {
         self.map(|x| x + rhs)
     }
 }
@@ -331,7 +460,7 @@ impl Add<f64> for Vec3d {
 /// let result = 2.0 + vec;
 /// assert_eq!(result, Vec3d::new(3.0, 4.0, 5.0));
 /// ```
-impl Add<Vec3d> for f64 {
+impl Add<Vec3d> for Float {
     type Output = Vec3d;
 
     #[inline]
@@ -363,8 +492,8 @@ impl AddAssign<Vec3d> for Vec3d {
     }
 }
 
-impl AddAssign<f64> for Vec3d {
-    fn add_assign(&mut self, rhs: f64) {
+impl AddAssign<Float> for Vec3d {
+    fn add_assign(&mut self, rhs: Float) {
         self.vector[0] += rhs;
         self.vector[1] += rhs;
         self.vector[2] += rhs;
@@ -405,10 +534,10 @@ impl Sub<Vec3d> for Vec3d {
 /// let result = vec - 2.0;
 /// assert_eq!(result, Vec3d::new(-1.0, 0.0, 1.0));
 /// ```
-impl Sub<f64> for Vec3d {
+impl Sub<Float> for Vec3d {
     type Output = Self;
     #[inline]
-    fn sub(self, rhs: f64) -> Self::Output {
+    fn sub(self, rhs: Float) -> Self::Output {
         self.map(|x| x - rhs)
     }
 }
@@ -422,7 +551,7 @@ impl Sub<f64> for Vec3d {
 /// let result = 2.0 - vec;
 /// assert_eq!(result, Vec3d::new(1.0, 0.0, -1.0));
 /// ```
-impl Sub<Vec3d> for f64 {
+impl Sub<Vec3d> for Float {
     type Output = Vec3d;
     #[inline]
     fn sub(self, rhs: Vec3d) -> Self::Output {
@@ -453,8 +582,8 @@ impl SubAssign<Vec3d> for Vec3d {
     }
 }
 
-impl SubAssign<f64> for Vec3d {
-    fn sub_assign(&mut self, rhs: f64) {
+impl SubAssign<Float> for Vec3d {
+    fn sub_assign(&mut self, rhs: Float) {
         self.vector[0] -= rhs;
         self.vector[1] -= rhs;
         self.vector[2] -= rhs;
@@ -493,11 +622,11 @@ impl Mul<Vec3d> for Vec3d {
 /// let result = vec * 2.0;
 /// assert_eq!(result, Vec3d::new(2.0, 4.0, 6.0));
 /// ```
-impl Mul<f64> for Vec3d {
+impl Mul<Float> for Vec3d {
     type Output = Self;
 
     #[inline]
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         self.map(|x| x * rhs)
     }
 }
@@ -511,7 +640,7 @@ impl Mul<f64> for Vec3d {
 /// let result = 2.0 * vec;
 /// assert_eq!(result, Vec3d::new(2.0, 4.0, 6.0));
 /// ```
-impl Mul<Vec3d> for f64 {
+impl Mul<Vec3d> for Float {
     type Output = Vec3d;
 
     #[inline]
@@ -542,8 +671,8 @@ impl MulAssign<Vec3d> for Vec3d {
     }
 }
 
-impl MulAssign<f64> for Vec3d {
-    fn mul_assign(&mut self, rhs: f64) {
+impl MulAssign<Float> for Vec3d {
+    fn mul_assign(&mut self, rhs: Float) {
         self.vector[0] *= rhs;
         self.vector[1] *= rhs;
         self.vector[2] *= rhs;
@@ -574,11 +703,11 @@ impl Div<Vec3d> for Vec3d {
     }
 }
 
-impl Div<f64> for Vec3d {
+impl Div<Float> for Vec3d {
     type Output = Self;
 
     #[inline]
-    fn div(self, rhs: f64) -> Self::Output { self * (1.0 / rhs) }
+    fn div(self, rhs: Float) -> Self::Output { self * (1.0 / rhs) }
 }
 
 
@@ -605,8 +734,8 @@ impl DivAssign<Vec3d> for Vec3d {
     }
 }
 
-impl DivAssign<f64> for Vec3d {
-    fn div_assign(&mut self, rhs: f64) {
+impl DivAssign<Float> for Vec3d {
+    fn div_assign(&mut self, rhs: Float) {
         *self *= 1.0 / rhs;
     }
 }
@@ -622,7 +751,7 @@ impl DivAssign<f64> for Vec3d {
 /// assert_eq!(vec[2], 3.0);
 /// ```
 impl Index<usize> for Vec3d {
-    type Output = f64;
+    type Output = Float;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.vector[index]
@@ -646,9 +775,63 @@ impl IndexMut<usize> for Vec3d {
 }
 
 
+/// Conversion from a ``[x, y, z]`` array.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::Vec3d;
+/// let vec = Vec3d::from([1.0, 2.0, 3.0]);
+/// assert_eq!(vec, Vec3d::new(1.0, 2.0, 3.0));
+/// ```
+impl From<[Float; 3]> for Vec3d {
+    fn from(vector: [Float; 3]) -> Self {
+        Self { vector }
+    }
+}
+
+/// Conversion into a ``[x, y, z]`` array.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::{Float, Vec3d};
+/// let array: [Float; 3] = Vec3d::new(1.0, 2.0, 3.0).into();
+/// assert_eq!(array, [1.0, 2.0, 3.0]);
+/// ```
+impl From<Vec3d> for [Float; 3] {
+    fn from(vec: Vec3d) -> Self {
+        vec.vector
+    }
+}
+
+/// Conversion from a ``(x, y, z)`` tuple.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::Vec3d;
+/// let vec = Vec3d::from((1.0, 2.0, 3.0));
+/// assert_eq!(vec, Vec3d::new(1.0, 2.0, 3.0));
+/// ```
+impl From<(Float, Float, Float)> for Vec3d {
+    fn from((x, y, z): (Float, Float, Float)) -> Self {
+        Vec3d::new(x, y, z)
+    }
+}
+
+/// Conversion into a ``(x, y, z)`` tuple.
+/// # Examples
+/// ```
+/// use ray_tracing::vec3d::{Float, Vec3d};
+/// let tuple: (Float, Float, Float) = Vec3d::new(1.0, 2.0, 3.0).into();
+/// assert_eq!(tuple, (1.0, 2.0, 3.0));
+/// ```
+impl From<Vec3d> for (Float, Float, Float) {
+    fn from(vec: Vec3d) -> Self {
+        (vec.x(), vec.y(), vec.z())
+    }
+}
+
+
 #[cfg(test)]
 mod vec3d_tests {
     use super::*;
+    use assert_approx_eq::assert_approx_eq;
 
     #[test]
     fn test_vec3d_new() {
@@ -705,6 +888,125 @@ mod vec3d_tests {
         assert_eq!(result, 5.0);
     }
 
+    #[test]
+    fn test_vec3d_component_wise() {
+        let vec = Vec3d::new(-1.5, 2.0, 0.25);
+        let vec2 = Vec3d::new(1.0, -3.0, 0.5);
+        assert_eq!(vec.min(&vec2), Vec3d::new(-1.5, -3.0, 0.25));
+        assert_eq!(vec.max(&vec2), Vec3d::new(1.0, 2.0, 0.5));
+        assert_eq!(vec.abs(), Vec3d::new(1.5, 2.0, 0.25));
+        assert_eq!(vec.floor(), Vec3d::new(-2.0, 2.0, 0.0));
+        assert_eq!(
+            vec.clamp(&Vec3d::new(-1.0, -1.0, -1.0), &Vec3d::new(1.0, 1.0, 1.0)),
+            Vec3d::new(-1.0, 1.0, 0.25),
+        );
+    }
+
+    #[test]
+    fn test_vec3d_lerp() {
+        let vec = Vec3d::new(1.0, 2.0, 3.0);
+        let vec2 = Vec3d::new(3.0, 6.0, -1.0);
+        assert_eq!(lerp(&vec, &vec2, 0.0), vec);
+        assert_eq!(lerp(&vec, &vec2, 1.0), vec2);
+        assert_eq!(lerp(&vec, &vec2, 0.5), Vec3d::new(2.0, 4.0, 1.0));
+    }
+
+    #[test]
+    fn test_reflect_output_1() {
+        let v_in = Vec3d::new(1.0, 1.0, 0.0);
+        let normal = Vec3d::new(-1.0, 0.0, 0.0);
+        let expected = Vec3d::new(-1.0, 1.0, 0.0);
+        let result = reflect(&v_in, &normal);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_reflect_output_2() {
+        let v_in = Vec3d::new(1.0, 1.0, 0.0);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+        let expected = Vec3d::new(1.0, -1.0, 0.0);
+        let result = reflect(&v_in, &normal);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_reflect_output_3() {
+        let v_in = Vec3d::new(1.0, 1.0, 0.0);
+        let normal = Vec3d::new(-1.0, -1.0, 0.0);
+        let expected = Vec3d::new(-1.0, -1.0, 0.0);
+        let result = reflect(&v_in, &normal.unit_vector());
+        assert_approx_eq!(result.x(), expected.x(), f32::EPSILON as Float);
+        assert_approx_eq!(result.y(), expected.y(), f32::EPSILON as Float);
+        assert_eq!(result.z(), expected.z());
+    }
+
+
+    #[test]
+    fn test_refract_perp_1() {
+        let v_in = Vec3d::new(0.0, -1.0, 0.0);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+        let etai_over_etat = 1.0;
+
+        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_refract_perp_2() {
+        let v_in = Vec3d::new(0.0, -1.0, 0.0);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+        let etai_over_etat = 1.5;
+
+        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_refract_perp_3() {
+        let v_in = Vec3d::new(0.0, -1.0, 0.0);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+        let etai_over_etat = 0.5;
+
+        let expected = Vec3d::new(0.0, -1.0, 0.0); // No refraction, same vector
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_refract_1() {
+        let v_in = Vec3d::new(1.0, 1.0, 0.0);
+        let normal = Vec3d::new(-1.0, 0.0, 0.0);
+        let etai_over_etat = 1.0;
+
+        let expected = Vec3d::new(0.0, 1.0, 0.0); // Corrected expected result
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_refract_2() {
+        let v_in = Vec3d::new(1.0, 1.0, 0.0);
+        let normal = Vec3d::new(-1.0, 0.0, 0.0);
+        let etai_over_etat = 0.5;
+
+        let expected = Vec3d::new(0.8660254037844386, 0.5, 0.0); // Corrected expected result
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_refract_3() {
+        let v_in = Vec3d::new(0.0, -1.0, 0.0);
+        let normal = Vec3d::new(0.0, 1.0, 0.0);
+        let etai_over_etat = 1.5;
+
+        let expected = Vec3d::new(0.0, -1.0, 0.0); // Corrected expected result
+        let result = refract(&v_in, &normal, etai_over_etat);
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn test_vec3d_cross() {
         let vec = Vec3d::new(1.0, 0.0, 0.0);
@@ -887,4 +1189,22 @@ mod vec3d_tests {
         assert_eq!(vec.y() >= 5.0 && vec.y() <= 10.0, true);
         assert_eq!(vec.z() >= 5.0 && vec.z() <= 10.0, true);
     }
+
+    #[test]
+    fn test_vec3d_array_conversion() {
+        let vec = Vec3d::from([1.0, 2.0, 3.0]);
+        assert_eq!(vec, Vec3d::new(1.0, 2.0, 3.0));
+
+        let array: [Float; 3] = vec.into();
+        assert_eq!(array, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_vec3d_tuple_conversion() {
+        let vec = Vec3d::from((1.0, 2.0, 3.0));
+        assert_eq!(vec, Vec3d::new(1.0, 2.0, 3.0));
+
+        let tuple: (Float, Float, Float) = vec.into();
+        assert_eq!(tuple, (1.0, 2.0, 3.0));
+    }
 }